@@ -0,0 +1,320 @@
+//! Códigos de trapaça (Game Genie e Pro Action Replay)
+//!
+//! - Game Genie (`ABCD-EFGH`): substitui uma palavra da ROM. O patch é
+//!   aplicado nas leituras do cartucho através do mapper, sem alterar os
+//!   dados da `Rom` — desligar o código restaura o valor original.
+//! - Pro Action Replay (`FFxxxx:yyyy`): endereço de 24 bits e valor de 8
+//!   ou 16 bits. Na faixa da RAM (0xE00000–0xFFFFFF) o valor é escrito a
+//!   cada quadro; na faixa do cartucho funciona como um patch de ROM.
+//!   Na RAM do Z80 (0xA00000–0xA01FFF, vista pelo 68000) só valores de 8 bits.
+//!
+//! As trapaças de cada jogo ficam em um arquivo RON ao lado da ROM
+//! (`jogo.cheats.ron`):
+//!
+//! ```ron
+//! (cheats: [
+//!     (name: "Vidas infinitas", codes: ["FF0F24:0009"], enabled: true),
+//!     (name: "Pulo alto", codes: ["SCRA-BJX0"]),
+//! ])
+//! ```
+//!
+//! O estado ligado/desligado não faz parte da memória emulada: ao carregar
+//! um save state os patches de ROM continuam valendo e as escritas em RAM
+//! são refeitas no quadro seguinte.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Alfabeto dos códigos Game Genie (cada caractere vale 5 bits)
+const GAME_GENIE_CHARS: &str = "ABCDEFGHJKLMNPRSTVWXYZ0123456789";
+
+/// Faixa da RAM principal (com espelhos)
+const RAM_START: u32 = 0xE00000;
+/// Fim da área do cartucho
+const CART_END: u32 = 0x3FFFFF;
+/// RAM do Z80 vista pelo 68000 (8 KB, espelhada até 0xA03FFF)
+pub const Z80_RAM_START: u32 = 0xA00000;
+const Z80_RAM_END: u32 = 0xA03FFF;
+
+/// Erros ao decodificar ou carregar trapaças
+#[derive(Debug, Error)]
+pub enum CheatError {
+    #[error("Código inválido \"{0}\" (esperado ABCD-EFGH ou AAAAAA:VVVV)")]
+    InvalidCode(String),
+
+    #[error("O código \"{0}\" aponta para 0x{1:06X}, fora da ROM e da RAM")]
+    UnsupportedAddress(String, u32),
+
+    #[error("Falha ao ler {0}: {1}")]
+    Io(PathBuf, #[source] std::io::Error),
+
+    #[error("Erro de sintaxe RON: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+
+    #[error("Falha ao gravar as trapaças: {0}")]
+    Serialize(#[from] ron::Error),
+}
+
+/// Efeito de um código já decodificado
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheatCode {
+    /// Substitui a palavra da ROM em `addr` (par)
+    RomPatch { addr: u32, value: u16 },
+    /// Escreve um byte na RAM a cada quadro
+    RamWrite8 { addr: u32, value: u8 },
+    /// Escreve uma palavra na RAM a cada quadro
+    RamWrite16 { addr: u32, value: u16 },
+    /// Escreve um byte na RAM do Z80 a cada quadro (endereço do Z80)
+    Z80Write8 { addr: u16, value: u8 },
+}
+
+impl CheatCode {
+    /// Decodifica um código Game Genie ou Pro Action Replay
+    pub fn parse(code: &str) -> Result<Self, CheatError> {
+        let code = code.trim().to_ascii_uppercase();
+        let invalid = || CheatError::InvalidCode(code.clone());
+
+        if let Some((head, tail)) = code.split_once('-').filter(|(h, t)| h.len() == 4 && t.len() == 4) {
+            let (addr, value) = decode_game_genie(&format!("{}{}", head, tail)).ok_or_else(invalid)?;
+            return Ok(Self::RomPatch { addr: addr & !1, value });
+        }
+
+        // Action Replay: "AAAAAA:VVVV", "AAAAAA:VV" ou "AAAAAAVVVV"
+        let (addr, value) = match code.split_once(':') {
+            Some(parts) => parts,
+            None if code.len() == 10 => code.split_at(6),
+            None => return Err(invalid()),
+        };
+        if addr.len() != 6 || !matches!(value.len(), 2 | 4) {
+            return Err(invalid());
+        }
+        let addr = u32::from_str_radix(addr, 16).map_err(|_| invalid())?;
+        let word = u16::from_str_radix(value, 16).map_err(|_| invalid())?;
+
+        match addr {
+            RAM_START.. if value.len() == 2 => Ok(Self::RamWrite8 { addr, value: word as u8 }),
+            RAM_START.. => Ok(Self::RamWrite16 { addr, value: word }),
+            0..=CART_END if value.len() == 4 => Ok(Self::RomPatch { addr: addr & !1, value: word }),
+            Z80_RAM_START..=Z80_RAM_END if value.len() == 2 => Ok(Self::Z80Write8 {
+                addr: (addr & 0x1FFF) as u16,
+                value: word as u8,
+            }),
+            _ => Err(CheatError::UnsupportedAddress(code.clone(), addr)),
+        }
+    }
+}
+
+/// Decodifica os 8 caracteres de um código Game Genie em (endereço, valor).
+/// Os 40 bits do código ficam embaralhados entre endereço e dado.
+fn decode_game_genie(code: &str) -> Option<(u32, u16)> {
+    let mut addr = 0u32;
+    let mut data = 0u32;
+    for (i, c) in code.chars().enumerate() {
+        let n = GAME_GENIE_CHARS.find(c)? as u32;
+        match i {
+            0 => data |= n << 3,
+            1 => {
+                data |= n >> 2;
+                addr |= (n & 3) << 14;
+            }
+            2 => addr |= n << 9,
+            3 => addr |= (n & 0xF) << 20 | (n >> 4) << 8,
+            4 => {
+                data |= (n & 1) << 12;
+                addr |= (n >> 1) << 16;
+            }
+            5 => data |= (n & 1) << 15 | (n >> 1) << 8,
+            6 => {
+                data |= (n >> 3) << 13;
+                addr |= (n & 7) << 5;
+            }
+            _ => addr |= n,
+        }
+    }
+    Some((addr, data as u16))
+}
+
+// =====================================================
+// ARQUIVO DE TRAPAÇAS
+// =====================================================
+
+/// Uma trapaça do arquivo: um nome e um ou mais códigos
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Cheat {
+    pub name: String,
+    pub codes: Vec<String>,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CheatFile {
+    cheats: Vec<Cheat>,
+}
+
+/// Trapaças do jogo carregado, com os códigos já decodificados
+#[derive(Debug, Default)]
+pub struct CheatEngine {
+    cheats: Vec<Cheat>,
+    decoded: Vec<Vec<CheatCode>>,
+}
+
+impl CheatEngine {
+    /// Arquivo de trapaças correspondente a uma ROM (`jogo.md` -> `jogo.cheats.ron`)
+    pub fn path_for(rom_path: &Path) -> PathBuf {
+        rom_path.with_extension("cheats.ron")
+    }
+
+    /// Monta o conjunto a partir de uma lista de trapaças, validando os códigos
+    pub fn new(cheats: Vec<Cheat>) -> Result<Self, CheatError> {
+        let decoded = cheats
+            .iter()
+            .map(|cheat| cheat.codes.iter().map(|c| CheatCode::parse(c)).collect())
+            .collect::<Result<_, _>>()?;
+        Ok(Self { cheats, decoded })
+    }
+
+    /// Lê um arquivo RON de trapaças
+    pub fn load(path: &Path) -> Result<Self, CheatError> {
+        let text = std::fs::read_to_string(path).map_err(|e| CheatError::Io(path.to_path_buf(), e))?;
+        Self::from_ron(&text)
+    }
+
+    /// Lê trapaças de um texto RON
+    pub fn from_ron(text: &str) -> Result<Self, CheatError> {
+        let file: CheatFile = ron::from_str(text)?;
+        Self::new(file.cheats)
+    }
+
+    /// Grava o arquivo de trapaças (preserva o estado ligado/desligado)
+    pub fn save(&self, path: &Path) -> Result<(), CheatError> {
+        let file = CheatFile { cheats: self.cheats.clone() };
+        let text = ron::ser::to_string_pretty(&file, ron::ser::PrettyConfig::default())?;
+        std::fs::write(path, text).map_err(|e| CheatError::Io(path.to_path_buf(), e))
+    }
+
+    pub fn cheats(&self) -> &[Cheat] {
+        &self.cheats
+    }
+
+    pub fn len(&self) -> usize {
+        self.cheats.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cheats.is_empty()
+    }
+
+    /// Adiciona uma trapaça (validando os códigos) e retorna o índice dela
+    pub fn push(&mut self, cheat: Cheat) -> Result<usize, CheatError> {
+        let codes = cheat.codes.iter().map(|c| CheatCode::parse(c)).collect::<Result<_, _>>()?;
+        self.cheats.push(cheat);
+        self.decoded.push(codes);
+        Ok(self.cheats.len() - 1)
+    }
+
+    /// Liga ou desliga uma trapaça. Retorna false se o índice não existe.
+    pub fn set_enabled(&mut self, index: usize, enabled: bool) -> bool {
+        match self.cheats.get_mut(index) {
+            Some(cheat) => {
+                cheat.enabled = enabled;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Inverte o estado de uma trapaça e retorna o novo estado
+    pub fn toggle(&mut self, index: usize) -> Option<bool> {
+        let enabled = !self.cheats.get(index)?.enabled;
+        self.set_enabled(index, enabled);
+        Some(enabled)
+    }
+
+    /// Códigos das trapaças ligadas
+    fn active_codes(&self) -> impl Iterator<Item = &CheatCode> {
+        self.cheats
+            .iter()
+            .zip(&self.decoded)
+            .filter(|(cheat, _)| cheat.enabled)
+            .flat_map(|(_, codes)| codes)
+    }
+
+    /// Patches de ROM ativos (endereço par -> palavra)
+    pub fn rom_patches(&self) -> HashMap<u32, u16> {
+        self.active_codes()
+            .filter_map(|code| match *code {
+                CheatCode::RomPatch { addr, value } => Some((addr, value)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Escritas em RAM ativas, refeitas a cada quadro
+    pub fn ram_writes(&self) -> impl Iterator<Item = CheatCode> + '_ {
+        self.active_codes()
+            .copied()
+            .filter(|code| !matches!(code, CheatCode::RomPatch { .. }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_game_genie_decoding() {
+        assert_eq!(
+            CheatCode::parse("SCRA-BJX0").unwrap(),
+            CheatCode::RomPatch { addr: 0x009C76, value: 0x5478 }
+        );
+        assert!(CheatCode::parse("SCRA-BJXI").is_err()); // "I" não faz parte do alfabeto
+    }
+
+    #[test]
+    fn test_action_replay_decoding() {
+        assert_eq!(
+            CheatCode::parse("ff0f24:0009").unwrap(),
+            CheatCode::RamWrite16 { addr: 0xFF0F24, value: 0x0009 }
+        );
+        assert_eq!(
+            CheatCode::parse("FFA5C4:63").unwrap(),
+            CheatCode::RamWrite8 { addr: 0xFFA5C4, value: 0x63 }
+        );
+        assert_eq!(
+            CheatCode::parse("0012344E71").unwrap(),
+            CheatCode::RomPatch { addr: 0x001234, value: 0x4E71 }
+        );
+        assert_eq!(
+            CheatCode::parse("A01F00:01").unwrap(),
+            CheatCode::Z80Write8 { addr: 0x1F00, value: 0x01 }
+        );
+        assert!(matches!(
+            CheatCode::parse("C00000:0000"),
+            Err(CheatError::UnsupportedAddress(_, 0xC00000))
+        ));
+    }
+
+    #[test]
+    fn test_engine_toggles_from_ron() {
+        let mut engine = CheatEngine::from_ron(
+            r#"(cheats: [
+                (name: "Vidas", codes: ["FF0F24:0009"], enabled: true),
+                (name: "Pulo", codes: ["SCRA-BJX0"]),
+            ])"#,
+        )
+        .unwrap();
+
+        assert_eq!(engine.ram_writes().count(), 1);
+        assert!(engine.rom_patches().is_empty());
+
+        assert_eq!(engine.toggle(1), Some(true));
+        assert_eq!(engine.rom_patches().get(&0x009C76), Some(&0x5478));
+
+        engine.set_enabled(0, false);
+        assert_eq!(engine.ram_writes().count(), 0);
+        assert_eq!(engine.toggle(5), None);
+    }
+}
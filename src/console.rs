@@ -0,0 +1,279 @@
+//! Modelo do console (região e revisão de hardware)
+//!
+//! Define o que o jogo enxerga no registrador de versão (0xA10001),
+//! o padrão de vídeo do VDP (NTSC/PAL), o clock principal usado pelo
+//! som e o número de linhas por quadro.
+//!
+//! O modelo é escolhido automaticamente a partir do campo de região do
+//! cabeçalho da ROM (códigos antigos "JUE" ou novos em dígito hexadecimal),
+//! podendo ser sobrescrito manualmente.
+
+use crate::memory::rom::RomHeader;
+use crate::memory::tmss::HardwareRevision;
+use crate::sound::{NTSC_MASTER_CLOCK, PAL_MASTER_CLOCK};
+use bitflags::bitflags;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+bitflags! {
+    /// Regiões suportadas por um cartucho (formato do código hexadecimal novo)
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct RegionSupport: u8 {
+        /// Japão (doméstico, 60 Hz)
+        const JAPAN_NTSC  = 1 << 0;
+        /// Ásia (doméstico, 50 Hz)
+        const ASIA_PAL    = 1 << 1;
+        /// Américas (exportação, 60 Hz)
+        const USA_NTSC    = 1 << 2;
+        /// Europa (exportação, 50 Hz)
+        const EUROPE_PAL  = 1 << 3;
+    }
+}
+
+/// Região do console
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Region {
+    /// Japão, NTSC (doméstico)
+    JapanNtsc,
+    /// Ásia, PAL (doméstico)
+    AsiaPal,
+    /// EUA, NTSC (exportação)
+    #[default]
+    UsaNtsc,
+    /// Europa, PAL (exportação)
+    EuropePal,
+}
+
+impl Region {
+    /// Retorna true para consoles PAL (50 Hz)
+    pub fn is_pal(&self) -> bool {
+        matches!(self, Region::AsiaPal | Region::EuropePal)
+    }
+
+    /// Retorna true para consoles de exportação (fora do Japão)
+    pub fn is_overseas(&self) -> bool {
+        matches!(self, Region::UsaNtsc | Region::EuropePal)
+    }
+
+    /// Flag correspondente no conjunto de regiões do cartucho
+    pub fn support_flag(&self) -> RegionSupport {
+        match self {
+            Region::JapanNtsc => RegionSupport::JAPAN_NTSC,
+            Region::AsiaPal => RegionSupport::ASIA_PAL,
+            Region::UsaNtsc => RegionSupport::USA_NTSC,
+            Region::EuropePal => RegionSupport::EUROPE_PAL,
+        }
+    }
+}
+
+impl FromStr for Region {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "jp" | "jpn" | "japan" => Ok(Region::JapanNtsc),
+            "asia" => Ok(Region::AsiaPal),
+            "us" | "usa" => Ok(Region::UsaNtsc),
+            "eu" | "eur" | "europe" => Ok(Region::EuropePal),
+            _ => Err(format!("Região desconhecida: {}", s)),
+        }
+    }
+}
+
+impl FromStr for HardwareRevision {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "model1" | "model1-va0" => Ok(HardwareRevision::Model1Va0),
+            "model1-va6" => Ok(HardwareRevision::Model1Va6),
+            "model2" => Ok(HardwareRevision::Model2),
+            "model3" => Ok(HardwareRevision::Model3),
+            _ => Err(format!("Modelo de console desconhecido: {}", s)),
+        }
+    }
+}
+
+/// Ordem de preferência quando o cartucho suporta várias regiões
+const REGION_PREFERENCE: [Region; 4] = [
+    Region::UsaNtsc,
+    Region::EuropePal,
+    Region::JapanNtsc,
+    Region::AsiaPal,
+];
+
+/// Modelo completo do console emulado
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ConsoleModel {
+    pub region: Region,
+    pub revision: HardwareRevision,
+    /// Unidade de expansão (Mega-CD) conectada
+    pub expansion_unit: bool,
+}
+
+impl ConsoleModel {
+    /// Cria um modelo com região e revisão explícitas
+    pub fn new(region: Region, revision: HardwareRevision) -> Self {
+        Self {
+            region,
+            revision,
+            expansion_unit: false,
+        }
+    }
+
+    /// Escolhe o modelo a partir do cabeçalho da ROM. `region_override`
+    /// força uma região independente do que o cartucho declara.
+    pub fn detect(header: &RomHeader, revision: HardwareRevision, region_override: Option<Region>) -> Self {
+        let region = region_override.unwrap_or_else(|| {
+            let supported = parse_region_codes(&header.region);
+            REGION_PREFERENCE
+                .iter()
+                .copied()
+                .find(|r| supported.contains(r.support_flag()))
+                .unwrap_or_default()
+        });
+        Self::new(region, revision)
+    }
+
+    /// Retorna true para consoles PAL
+    pub fn is_pal(&self) -> bool {
+        self.region.is_pal()
+    }
+
+    /// Clock principal (Hz) do console
+    pub fn master_clock(&self) -> u32 {
+        if self.is_pal() {
+            PAL_MASTER_CLOCK
+        } else {
+            NTSC_MASTER_CLOCK
+        }
+    }
+
+    /// Número total de linhas por quadro do VDP
+    pub fn lines_per_frame(&self) -> u16 {
+        if self.is_pal() {
+            313
+        } else {
+            262
+        }
+    }
+
+    /// Ciclos do 68000 (clock principal / 7) em um quadro: 3420 ciclos do
+    /// clock principal por linha
+    pub fn m68k_cycles_per_frame(&self) -> u32 {
+        self.lines_per_frame() as u32 * 3420 / 7
+    }
+
+    /// Valor do registrador de versão (0xA10001):
+    /// - bit 7: 1 = exportação, 0 = doméstico
+    /// - bit 6: 1 = PAL, 0 = NTSC
+    /// - bit 5: 0 = unidade de expansão conectada
+    /// - bits 0–3: versão do hardware (0 = sem TMSS)
+    pub fn version_register(&self) -> u8 {
+        let mut value = 0u8;
+        if self.region.is_overseas() {
+            value |= 0x80;
+        }
+        if self.is_pal() {
+            value |= 0x40;
+        }
+        if !self.expansion_unit {
+            value |= 0x20;
+        }
+        if self.revision.has_tmss() {
+            value |= 0x01;
+        }
+        value
+    }
+}
+
+/// Interpreta o campo de região do cabeçalho (0x1F0).
+///
+/// Aceita o formato antigo com letras ("J", "U", "E", "A", "JUE") e o novo
+/// formato de um dígito hexadecimal (bit 0 = Japão, bit 1 = Ásia,
+/// bit 2 = EUA, bit 3 = Europa). Um "E" isolado é tratado como Europa.
+pub fn parse_region_codes(region: &str) -> RegionSupport {
+    let codes: Vec<char> = region
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+
+    // Formato novo: um único dígito hexadecimal (exceto "E", ambíguo)
+    if let [c] = codes.as_slice() {
+        if *c != 'E' {
+            if let Some(bits) = c.to_digit(16) {
+                return RegionSupport::from_bits_truncate(bits as u8);
+            }
+        }
+    }
+
+    let mut support = RegionSupport::empty();
+    for c in codes {
+        match c {
+            'J' => support |= RegionSupport::JAPAN_NTSC,
+            'U' => support |= RegionSupport::USA_NTSC,
+            'E' => support |= RegionSupport::EUROPE_PAL,
+            'A' => support |= RegionSupport::ASIA_PAL,
+            _ => {}
+        }
+    }
+    support
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::rom::Rom;
+
+    fn header_with_region(region: &[u8]) -> RomHeader {
+        let mut data = vec![0x20; 0x200];
+        data[0x1F0..0x1F0 + region.len()].copy_from_slice(region);
+        Rom::new(data).header().clone()
+    }
+
+    #[test]
+    fn test_parse_old_letter_codes() {
+        assert_eq!(
+            parse_region_codes("JUE"),
+            RegionSupport::JAPAN_NTSC | RegionSupport::USA_NTSC | RegionSupport::EUROPE_PAL
+        );
+        assert_eq!(parse_region_codes("E"), RegionSupport::EUROPE_PAL);
+        assert_eq!(parse_region_codes("J"), RegionSupport::JAPAN_NTSC);
+    }
+
+    #[test]
+    fn test_parse_new_hex_codes() {
+        assert_eq!(parse_region_codes("4"), RegionSupport::USA_NTSC);
+        assert_eq!(parse_region_codes("8"), RegionSupport::EUROPE_PAL);
+        assert_eq!(parse_region_codes("F"), RegionSupport::all());
+        assert_eq!(parse_region_codes("1"), RegionSupport::JAPAN_NTSC);
+    }
+
+    #[test]
+    fn test_detect_from_header_and_override() {
+        let europe = ConsoleModel::detect(&header_with_region(b"E  "), HardwareRevision::Model2, None);
+        assert_eq!(europe.region, Region::EuropePal);
+        assert!(europe.is_pal());
+        assert_eq!(europe.lines_per_frame(), 313);
+
+        let japan = ConsoleModel::detect(&header_with_region(b"J  "), HardwareRevision::Model2, None);
+        assert_eq!(japan.region, Region::JapanNtsc);
+
+        let forced = ConsoleModel::detect(&header_with_region(b"JUE"), HardwareRevision::Model2, Some(Region::JapanNtsc));
+        assert_eq!(forced.region, Region::JapanNtsc);
+    }
+
+    #[test]
+    fn test_version_register() {
+        let us = ConsoleModel::new(Region::UsaNtsc, HardwareRevision::Model1Va0);
+        assert_eq!(us.version_register(), 0xA0);
+
+        let eu = ConsoleModel::new(Region::EuropePal, HardwareRevision::Model2);
+        assert_eq!(eu.version_register(), 0xE1);
+
+        let mut jp = ConsoleModel::new(Region::JapanNtsc, HardwareRevision::Model1Va6);
+        jp.expansion_unit = true;
+        assert_eq!(jp.version_register(), 0x01);
+    }
+}
@@ -1,5 +1,6 @@
 // src/cpu/mod.rs
 
+pub mod ssp1601;
 pub mod z80;
 use std::sync::{Arc, Mutex};
 use crate::sound::Sound;
@@ -0,0 +1,748 @@
+//! Núcleo do DSP Samsung SSP1601 (SVP do Virtua Racing)
+//!
+//! O SSP1601 é um DSP de 16 bits com palavras de programa de 16 bits e
+//! espaço de endereçamento em palavras. Ele tem:
+//! - registradores gerais: `-` (lê 0xFFFF), X, Y, A (acumulador de 32 bits,
+//!   AH visível), ST, STACK, PC e P (produto X*Y*2);
+//! - registradores externos: PM0–PM4 (ponteiros de memória programáveis),
+//!   XST (compartilhado com o 68000), PMC (configuração dos ponteiros) e AL
+//!   (metade baixa do acumulador);
+//! - duas RAMs internas de 256 palavras (RAM0/RAM1), endereçadas pelos
+//!   ponteiros r0–r2 e r4–r6 (r3/r7 endereçam as quatro primeiras palavras
+//!   diretamente);
+//! - uma pilha de 6 níveis para chamadas.
+//!
+//! O acesso à ROM, DRAM e IRAM do cartucho passa pelo trait `SspBus`
+//! (implementado em `memory::svp`). Só o subconjunto de instruções usado
+//! pelo Virtua Racing é implementado; opcodes desconhecidos viram NOP.
+
+/// Endereço de início do programa após o reset (ROM, palavra 0x400)
+pub const SSP_RESET_PC: u16 = 0x400;
+/// Profundidade da pilha de chamadas
+pub const SSP_STACK_SIZE: usize = 6;
+
+// Bits do registrador ST
+const ST_Z: u16 = 0x2000;
+const ST_N: u16 = 0x8000;
+const ST_V: u16 = 0x4000;
+const ST_L: u16 = 0x1000;
+const ST_RPL: u16 = 0x0007;
+const ST_PM_ENABLE: u16 = 0x0060;
+
+// Índices dos registradores nos campos de 4 bits das instruções
+const REG_ZERO: u16 = 0;
+const REG_X: u16 = 1;
+const REG_Y: u16 = 2;
+const REG_A: u16 = 3;
+const REG_ST: u16 = 4;
+const REG_STACK: u16 = 5;
+const REG_PC: u16 = 6;
+const REG_P: u16 = 7;
+const REG_PM0: u16 = 8;
+const REG_XST: u16 = 11;
+const REG_PM4: u16 = 12;
+const REG_PMC: u16 = 14;
+const REG_AL: u16 = 15;
+
+/// Memória externa vista pelo SSP1601
+pub trait SspBus {
+    /// Palavra do espaço de programa (IRAM abaixo de 0x400, ROM acima)
+    fn fetch(&self, addr: u16) -> u16;
+    /// Palavra da ROM do cartucho (endereço em palavras)
+    fn rom_word(&self, addr: u32) -> u16;
+    /// Palavra da DRAM de 128 KB
+    fn read_dram(&self, addr: u16) -> u16;
+    fn write_dram(&mut self, addr: u16, value: u16);
+    /// Escrita na IRAM de 2 KB (código carregado pelo próprio DSP)
+    fn write_iram(&mut self, addr: u16, value: u16);
+}
+
+/// Estado da programação do PMC (endereço e modo em duas escritas/leituras)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PmcState {
+    Idle,
+    /// Metade baixa (endereço) já acessada
+    HaveAddr,
+    /// Endereço e modo prontos: o próximo acesso "cego" a um PMx os copia
+    Set,
+}
+
+/// Estado do SSP1601
+#[derive(Debug, Clone)]
+pub struct Ssp1601 {
+    pub x: u16,
+    pub y: u16,
+    pub a: u32,
+    pub st: u16,
+    pub pc: u16,
+    /// Ponteiro da pilha (registrador STACK)
+    pub sp: u16,
+    pub stack: [u16; SSP_STACK_SIZE],
+    /// PM0, PM1, PM2, XST e PM4 como registradores comuns
+    pub pm: [u16; 5],
+    /// Endereço (16 bits baixos) e modo (16 bits altos) em programação
+    pub pmc: u32,
+    /// Ponteiros programados de leitura [0] e escrita [1] de cada PMx
+    pub pmac: [[u32; 5]; 2],
+    /// Ponteiros r0–r7
+    pub r: [u8; 8],
+    /// RAM0 (0–255) e RAM1 (256–511)
+    pub ram: [u16; 512],
+    pmc_state: PmcState,
+    /// Opcode em execução (decide se um acesso a PMx é "cego")
+    op: u16,
+}
+
+impl Default for Ssp1601 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Ssp1601 {
+    pub fn new() -> Self {
+        Self {
+            x: 0,
+            y: 0,
+            a: 0,
+            st: 0,
+            pc: SSP_RESET_PC,
+            sp: 0,
+            stack: [0; SSP_STACK_SIZE],
+            pm: [0; 5],
+            pmc: 0,
+            pmac: [[0; 5]; 2],
+            r: [0; 8],
+            ram: [0; 512],
+            pmc_state: PmcState::Idle,
+            op: 0,
+        }
+    }
+
+    /// Reset do DSP: RAM interna e ponteiros são preservados, como no hardware
+    pub fn reset(&mut self) {
+        self.pc = SSP_RESET_PC;
+        self.sp = 0;
+        self.st = 0;
+        self.pmc_state = PmcState::Idle;
+    }
+
+    /// Produto P = X * Y * 2 (com sinal)
+    pub fn p(&self) -> u32 {
+        ((self.x as i16 as i32) * (self.y as i16 as i32) * 2) as u32
+    }
+
+    /// Executa `cycles` instruções
+    pub fn run(&mut self, bus: &mut impl SspBus, cycles: u32) {
+        for _ in 0..cycles {
+            self.step(bus);
+        }
+    }
+
+    // =====================================================
+    // DECODIFICAÇÃO
+    // =====================================================
+
+    /// Executa uma instrução
+    pub fn step(&mut self, bus: &mut impl SspBus) {
+        let op = self.fetch(bus);
+        self.op = op;
+        let d = (op >> 4) & 0xF;
+        let s = op & 0xF;
+
+        match op >> 9 {
+            // ld d, s
+            0x00 => {
+                if op == 0 {
+                    return;
+                }
+                if op == ((REG_A << 4) | REG_P) {
+                    self.a = self.p();
+                } else {
+                    let value = self.read_reg(bus, s);
+                    self.write_reg(bus, d, value);
+                }
+            }
+            // ld d, (ri)
+            0x01 => {
+                let index = self.ptr_index(op & 3, (op >> 8) & 1, (op >> 2) & 3);
+                self.write_reg(bus, d, self.ram[index]);
+            }
+            // ld (ri), s
+            0x02 => {
+                let value = self.read_reg(bus, d);
+                let index = self.ptr_index(op & 3, (op >> 8) & 1, (op >> 2) & 3);
+                self.ram[index] = value;
+            }
+            // ldi d, imm
+            0x04 => {
+                let imm = self.fetch(bus);
+                self.write_reg(bus, d, imm);
+            }
+            // ld d, ((ri))
+            0x05 => {
+                let value = self.ptr2_read(bus, op);
+                self.write_reg(bus, d, value);
+            }
+            // ldi (ri), imm
+            0x06 => {
+                let imm = self.fetch(bus);
+                let index = self.ptr_index(op & 3, (op >> 8) & 1, (op >> 2) & 3);
+                self.ram[index] = imm;
+            }
+            // ld adr, a
+            0x07 => self.ram[(op & 0x1FF) as usize] = (self.a >> 16) as u16,
+            // ld d, ri
+            0x09 => {
+                let value = self.r[((op & 3) | ((op >> 6) & 4)) as usize] as u16;
+                self.write_reg(bus, d, value);
+            }
+            // ld ri, s
+            0x0A => {
+                let value = self.read_reg(bus, d);
+                self.r[((op & 3) | ((op >> 6) & 4)) as usize] = value as u8;
+            }
+            // ldi ri, simm
+            0x0C..=0x0F => self.r[((op >> 8) & 7) as usize] = op as u8,
+            // call cond, addr
+            0x24 => {
+                let target = self.fetch(bus);
+                if self.condition(op) {
+                    self.push(self.pc);
+                    self.pc = target;
+                }
+            }
+            // ld d, (a)
+            0x25 => {
+                let value = bus.fetch((self.a >> 16) as u16);
+                self.write_reg(bus, d, value);
+            }
+            // bra cond, addr
+            0x26 => {
+                let target = self.fetch(bus);
+                if self.condition(op) {
+                    self.pc = target;
+                }
+            }
+            // mod cond, op
+            0x48 => {
+                if self.condition(op) {
+                    self.modify_a(op & 7);
+                }
+            }
+            // mpys / mpya / mld
+            0x1B | 0x4B | 0x5B => self.multiply(op),
+            // Operações da ALU
+            _ => self.alu(bus, op),
+        }
+    }
+
+    /// Lê a próxima palavra do programa
+    fn fetch(&mut self, bus: &impl SspBus) -> u16 {
+        let word = bus.fetch(self.pc);
+        self.pc = self.pc.wrapping_add(1);
+        word
+    }
+
+    /// Testa a condição no campo `op & 0xF0` (flag no bit 8)
+    fn condition(&self, op: u16) -> bool {
+        let expected = op & 0x100 != 0;
+        match op & 0xF0 {
+            0x00 => true,
+            0x50 => (self.st & ST_Z != 0) == expected,
+            0x70 => (self.st & ST_N != 0) == expected,
+            cond => {
+                log::debug!("SSP1601: condição {:02X} não implementada", cond);
+                false
+            }
+        }
+    }
+
+    // =====================================================
+    // ALU
+    // =====================================================
+
+    /// Operações da ALU sobre A: ld, sub, cmp, add, and, or, eor
+    fn alu(&mut self, bus: &mut impl SspBus, op: u16) {
+        let operation = op >> 13;
+        let operand = match (op >> 9) & 0xF {
+            // OP a, s
+            0x0 => {
+                if op & 0xF == REG_P {
+                    self.alu_apply(operation, self.p());
+                    return;
+                }
+                self.read_reg(bus, op & 0xF)
+            }
+            // OP a, (ri)
+            0x1 => {
+                let index = self.ptr_index(op & 3, (op >> 8) & 1, (op >> 2) & 3);
+                self.ram[index]
+            }
+            // OP a, adr
+            0x3 => self.ram[(op & 0x1FF) as usize],
+            // OPi a, imm
+            0x4 => self.fetch(bus),
+            // OP a, ((ri))
+            0x5 => self.ptr2_read(bus, op),
+            // OP a, ri
+            0x9 => self.r[((op & 3) | ((op >> 6) & 4)) as usize] as u16,
+            // OPi a, simm
+            0xC => op & 0xFF,
+            _ => {
+                log::debug!("SSP1601: opcode {:04X} desconhecido em {:04X}", op, self.pc.wrapping_sub(1));
+                return;
+            }
+        };
+        self.alu_apply(operation, (operand as u32) << 16);
+    }
+
+    fn alu_apply(&mut self, operation: u16, value: u32) {
+        match operation {
+            // ld carrega só AH e não altera as flags
+            0 => {
+                self.a = (value & 0xFFFF0000) | (self.a & 0xFFFF);
+                return;
+            }
+            1 => self.a = self.a.wrapping_sub(value),
+            3 => {
+                let result = self.a.wrapping_sub(value);
+                self.update_flags(result);
+                return;
+            }
+            4 => self.a = self.a.wrapping_add(value),
+            5 => self.a = (self.a & value) & 0xFFFF0000,
+            6 => self.a |= value,
+            7 => self.a ^= value,
+            _ => {
+                log::debug!("SSP1601: operação da ALU {} desconhecida", operation);
+                return;
+            }
+        }
+        self.update_flags(self.a);
+    }
+
+    /// Atualiza Z e N a partir de um resultado de 32 bits (V e L são zerados)
+    fn update_flags(&mut self, value: u32) {
+        self.st &= !(ST_Z | ST_N | ST_V | ST_L);
+        if value == 0 {
+            self.st |= ST_Z;
+        }
+        if value & 0x8000_0000 != 0 {
+            self.st |= ST_N;
+        }
+    }
+
+    /// `mod`: sar, shl, neg ou abs no acumulador
+    fn modify_a(&mut self, operation: u16) {
+        match operation {
+            2 => self.a = ((self.a as i32) >> 1) as u32,
+            3 => self.a <<= 1,
+            6 => self.a = (self.a as i32).wrapping_neg() as u32,
+            7 => self.a = (self.a as i32).wrapping_abs() as u32,
+            _ => {
+                log::debug!("SSP1601: mod {} não implementado", operation);
+                return;
+            }
+        }
+        self.update_flags(self.a);
+    }
+
+    /// mpys (A -= P), mpya (A += P) e mld (A = 0, flags inalteradas),
+    /// carregando X de RAM0 e Y de RAM1 para o próximo produto
+    fn multiply(&mut self, op: u16) {
+        match op >> 9 {
+            0x1B => {
+                self.a = self.a.wrapping_sub(self.p());
+                self.update_flags(self.a);
+            }
+            0x4B => {
+                self.a = self.a.wrapping_add(self.p());
+                self.update_flags(self.a);
+            }
+            _ => self.a = 0,
+        }
+        let x = self.ptr_index(op & 3, 0, (op >> 2) & 3);
+        let y = self.ptr_index((op >> 4) & 3, 1, (op >> 6) & 3);
+        self.x = self.ram[x];
+        self.y = self.ram[y];
+    }
+
+    // =====================================================
+    // PONTEIROS E PILHA
+    // =====================================================
+
+    /// Índice em RAM0/RAM1 de `(ri)`, aplicando o pós-incremento/decremento.
+    /// `modifier`: 0 = nenhum, 1 = `+!` (sem módulo), 2 = `-`, 3 = `+`
+    /// (com módulo 2^RPL). r3/r7 endereçam as palavras 0–3 diretamente.
+    fn ptr_index(&mut self, ri: u16, bank: u16, modifier: u16) -> usize {
+        let base = bank as usize * 256;
+        if ri == 3 {
+            return base + modifier as usize;
+        }
+        let reg = (bank * 4 + ri) as usize;
+        let pointer = self.r[reg];
+        self.r[reg] = match modifier {
+            1 => pointer.wrapping_add(1),
+            2 => self.modulo_step(pointer, 0xFF),
+            3 => self.modulo_step(pointer, 1),
+            _ => pointer,
+        };
+        base + pointer as usize
+    }
+
+    /// Soma `delta` ao ponteiro respeitando o módulo definido em ST (RPL)
+    fn modulo_step(&self, pointer: u8, delta: u8) -> u8 {
+        let rpl = self.st & ST_RPL;
+        if rpl == 0 {
+            return pointer.wrapping_add(delta);
+        }
+        let mask = ((1u16 << rpl) - 1) as u8;
+        (pointer & !mask) | (pointer.wrapping_add(delta) & mask)
+    }
+
+    /// `((ri))`: lê o programa no endereço guardado em `(ri)` e incrementa
+    /// a palavra da RAM
+    fn ptr2_read(&mut self, bus: &impl SspBus, op: u16) -> u16 {
+        let (ri, modifier) = (op & 3, (op >> 2) & 3);
+        if ri != 3 && modifier != 0 {
+            log::debug!("SSP1601: modo ((ri)) {:04X} não suportado", op);
+            return 0;
+        }
+        let index = self.ptr_index(ri, (op >> 8) & 1, modifier);
+        let addr = self.ram[index];
+        self.ram[index] = addr.wrapping_add(1);
+        bus.fetch(addr)
+    }
+
+    fn push(&mut self, value: u16) {
+        if self.sp as usize >= SSP_STACK_SIZE {
+            self.sp = 0;
+        }
+        self.stack[self.sp as usize] = value;
+        self.sp += 1;
+    }
+
+    fn pop(&mut self) -> u16 {
+        self.sp = match self.sp {
+            0 => SSP_STACK_SIZE as u16 - 1,
+            sp => sp - 1,
+        };
+        self.stack[self.sp as usize]
+    }
+
+    // =====================================================
+    // REGISTRADORES
+    // =====================================================
+
+    fn read_reg(&mut self, bus: &mut impl SspBus, reg: u16) -> u16 {
+        match reg {
+            REG_ZERO => 0xFFFF,
+            REG_X => self.x,
+            REG_Y => self.y,
+            REG_A => (self.a >> 16) as u16,
+            REG_ST => self.st,
+            REG_STACK => self.pop(),
+            REG_PC => self.pc,
+            REG_P => (self.p() >> 16) as u16,
+            REG_PM0..=REG_PM4 => {
+                let pm = (reg - REG_PM0) as usize;
+                if let Some(value) = self.pm_io(bus, pm, false, 0) {
+                    return value;
+                }
+                let value = self.pm[pm];
+                if reg == REG_PM0 {
+                    // Leitura pelo DSP limpa o aviso de "XST escrito pelo 68000"
+                    self.pm[0] &= !2;
+                }
+                value
+            }
+            REG_PMC => self.read_pmc(),
+            REG_AL => {
+                if self.op == 0x000F {
+                    // "ld -, al" reinicia a programação do PMC
+                    self.pmc_state = PmcState::Idle;
+                }
+                self.a as u16
+            }
+            _ => 0,
+        }
+    }
+
+    fn write_reg(&mut self, bus: &mut impl SspBus, reg: u16, value: u16) {
+        match reg {
+            REG_X => self.x = value,
+            REG_Y => self.y = value,
+            REG_A => self.a = ((value as u32) << 16) | (self.a & 0xFFFF),
+            REG_ST => self.st = value,
+            REG_STACK => self.push(value),
+            REG_PC => self.pc = value,
+            REG_PM0..=REG_PM4 => {
+                let pm = (reg - REG_PM0) as usize;
+                if self.pm_io(bus, pm, true, value).is_some() {
+                    return;
+                }
+                if reg == REG_XST {
+                    // Escrita pelo DSP avisa o 68000 (PM0 bit 0)
+                    self.pm[0] |= 1;
+                }
+                self.pm[pm] = value;
+            }
+            REG_PMC => self.write_pmc(value),
+            REG_AL => self.a = (self.a & 0xFFFF0000) | value as u32,
+            _ => {}
+        }
+    }
+
+    /// Leitura do PMC: primeiro o endereço, depois o modo com os nibbles
+    /// rotacionados
+    fn read_pmc(&mut self) -> u16 {
+        let low = self.pmc as u16;
+        if self.pmc_state == PmcState::HaveAddr {
+            self.pmc_state = PmcState::Set;
+            ((low << 4) & 0xFFF0) | ((low >> 4) & 0xF)
+        } else {
+            self.pmc_state = PmcState::HaveAddr;
+            low
+        }
+    }
+
+    /// Escrita do PMC: primeiro o endereço, depois o modo
+    fn write_pmc(&mut self, value: u16) {
+        if self.pmc_state == PmcState::HaveAddr {
+            self.pmc_state = PmcState::Set;
+            self.pmc = (self.pmc & 0xFFFF) | ((value as u32) << 16);
+        } else {
+            self.pmc_state = PmcState::HaveAddr;
+            self.pmc = (self.pmc & 0xFFFF0000) | value as u32;
+        }
+    }
+
+    // =====================================================
+    // PONTEIROS DE MEMÓRIA PROGRAMÁVEIS (PMx)
+    // =====================================================
+
+    /// Acesso a um PMx. Retorna `None` quando o registrador se comporta
+    /// como registrador comum.
+    ///
+    /// Depois de programar o PMC, um acesso "cego" (registrador `-` como
+    /// origem/destino) copia endereço e modo para o ponteiro. Com PM4, ou
+    /// com ST bits 5–6 ligados, os acessos seguintes leem/escrevem a
+    /// memória externa e avançam o ponteiro.
+    fn pm_io(&mut self, bus: &mut impl SspBus, pm: usize, write: bool, value: u16) -> Option<u16> {
+        match self.pmc_state {
+            PmcState::Set => {
+                self.pmc_state = PmcState::Idle;
+                let blind = if write { self.op & 0xFF0F == 0 } else { self.op & 0xFFF0 == 0 };
+                if blind {
+                    self.pmac[write as usize][pm] = self.pmc;
+                }
+                return Some(0);
+            }
+            PmcState::HaveAddr => self.pmc_state = PmcState::Idle,
+            PmcState::Idle => {}
+        }
+
+        if pm != 4 && self.st & ST_PM_ENABLE == 0 {
+            return None;
+        }
+
+        let pointer = self.pmac[write as usize][pm];
+        let mode = (pointer >> 16) as u16;
+        let addr = pointer as u16;
+        let result = if write {
+            if mode & 0x43FF == 0x0018 {
+                if mode & 0x0400 != 0 {
+                    bus.write_dram(addr, overwrite(bus.read_dram(addr), value));
+                } else {
+                    bus.write_dram(addr, value);
+                }
+                self.advance_pointer(1, pm, pm_increment(mode));
+            } else if mode & 0xFBFF == 0x4018 {
+                // Incremento de "célula": alterna entre +1 e +31
+                if mode & 0x0400 != 0 {
+                    bus.write_dram(addr, overwrite(bus.read_dram(addr), value));
+                } else {
+                    bus.write_dram(addr, value);
+                }
+                self.advance_pointer(1, pm, if addr & 1 != 0 { 31 } else { 1 });
+            } else if mode & 0x47FF == 0x001C {
+                bus.write_iram(addr & 0x3FF, value);
+                self.advance_pointer(1, pm, pm_increment(mode));
+            } else {
+                log::debug!("SSP1601: escrita PM{} em modo {:04X} não suportada", pm, mode);
+            }
+            0
+        } else if mode & 0xFFF0 == 0x0800 {
+            let value = bus.rom_word(addr as u32 | ((mode as u32 & 0xF) << 16));
+            self.advance_pointer(0, pm, 1);
+            value
+        } else if mode & 0x47FF == 0x0018 {
+            let value = bus.read_dram(addr);
+            self.advance_pointer(0, pm, pm_increment(mode));
+            value
+        } else {
+            log::debug!("SSP1601: leitura PM{} em modo {:04X} não suportada", pm, mode);
+            0
+        };
+
+        // O PMC passa a refletir o ponteiro usado
+        self.pmc = self.pmac[write as usize][pm];
+        Some(result)
+    }
+
+    fn advance_pointer(&mut self, direction: usize, pm: usize, increment: i32) {
+        let pointer = &mut self.pmac[direction][pm];
+        *pointer = pointer.wrapping_add_signed(increment);
+    }
+}
+
+/// Incremento do ponteiro codificado no modo (bits 11–13, sinal no bit 15)
+fn pm_increment(mode: u16) -> i32 {
+    let mut inc = ((mode >> 11) & 7) as i32;
+    if inc != 0 {
+        if inc != 7 {
+            inc -= 1;
+        }
+        inc = 1 << inc;
+        if mode & 0x8000 != 0 {
+            inc = -inc;
+        }
+    }
+    inc
+}
+
+/// Modo "overwrite": só os nibbles não nulos do valor substituem a DRAM
+fn overwrite(old: u16, value: u16) -> u16 {
+    (0..4).fold(old, |acc, nibble| {
+        let mask = 0xF << (nibble * 4);
+        if value & mask != 0 {
+            (acc & !mask) | (value & mask)
+        } else {
+            acc
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Programa em ROM a partir da palavra 0x400 e DRAM plana
+    struct TestBus {
+        program: Vec<u16>,
+        dram: Vec<u16>,
+    }
+
+    impl TestBus {
+        fn new(program: &[u16]) -> Self {
+            Self { program: program.to_vec(), dram: vec![0; 0x10000] }
+        }
+    }
+
+    impl SspBus for TestBus {
+        fn fetch(&self, addr: u16) -> u16 {
+            self.program.get(addr.wrapping_sub(SSP_RESET_PC) as usize).copied().unwrap_or(0)
+        }
+        fn rom_word(&self, _addr: u32) -> u16 {
+            0
+        }
+        fn read_dram(&self, addr: u16) -> u16 {
+            self.dram[addr as usize]
+        }
+        fn write_dram(&mut self, addr: u16, value: u16) {
+            self.dram[addr as usize] = value;
+        }
+        fn write_iram(&mut self, _addr: u16, _value: u16) {}
+    }
+
+    #[test]
+    fn test_multiply_and_add() {
+        // ldi x, 3 / ldi y, 5 / ld a, p / addi a, 2
+        let mut bus = TestBus::new(&[0x0810, 3, 0x0820, 5, 0x0037, 0x8800, 2]);
+        let mut ssp = Ssp1601::new();
+        ssp.run(&mut bus, 4);
+        assert_eq!(ssp.a, 30 + 0x20000);
+        assert_eq!(ssp.st & (ST_Z | ST_N), 0);
+    }
+
+    #[test]
+    fn test_pointer_post_increment_and_branch() {
+        // ldi r0, 0x10 / ldi (r0+), 0x1234 / cmpi a, 0 / bra z=1, 0x0400
+        let mut bus = TestBus::new(&[0x1810, 0x0C0C, 0x1234, 0x6800, 0, 0x4D50, 0x0400]);
+        let mut ssp = Ssp1601::new();
+        ssp.run(&mut bus, 4);
+        assert_eq!(ssp.ram[0x10], 0x1234);
+        assert_eq!(ssp.r[0], 0x11);
+        assert_eq!(ssp.pc, SSP_RESET_PC);
+    }
+
+    #[test]
+    fn test_pm4_writes_dram_through_pmc() {
+        // ldi pmc, 0x0010 / ldi pmc, 0x0818 / ld pm4, - / ldi x, 0xBEEF /
+        // ld pm4, x / ld pm4, x
+        let mut bus = TestBus::new(&[0x08E0, 0x0010, 0x08E0, 0x0818, 0x00C0, 0x0810, 0xBEEF, 0x00C1, 0x00C1]);
+        let mut ssp = Ssp1601::new();
+        ssp.run(&mut bus, 6);
+        assert_eq!(&bus.dram[0x10..0x12], &[0xBEEF, 0xBEEF]);
+        assert_eq!(ssp.pmc, 0x0818_0012);
+    }
+
+    #[test]
+    fn test_store_to_pointer_uses_source_field() {
+        // ldi x, 0x1234 / ld (r0), x
+        let mut bus = TestBus::new(&[0x0810, 0x1234, 0x0410]);
+        let mut ssp = Ssp1601::new();
+        ssp.run(&mut bus, 2);
+        assert_eq!(ssp.ram[0], 0x1234);
+    }
+
+    #[test]
+    fn test_or_eor_keep_al() {
+        // ldi al, 0x5678 / ldi a, 0x1200 / ori a, 0x0F / eori a, 0x0F
+        let mut bus = TestBus::new(&[0x08F0, 0x5678, 0x0830, 0x1200, 0xD80F, 0xF80F]);
+        let mut ssp = Ssp1601::new();
+        ssp.run(&mut bus, 3);
+        assert_eq!(ssp.a, 0x120F_5678);
+        assert_eq!(ssp.st & (ST_Z | ST_N), 0);
+        ssp.step(&mut bus);
+        assert_eq!(ssp.a, 0x1200_5678);
+        assert_eq!(ssp.st & (ST_Z | ST_N), 0);
+    }
+
+    #[test]
+    fn test_load_a_from_ram_keeps_al_and_flags() {
+        // ldi al, 0x5678 / ldi (r3+1), 0x8321 / ldi st, Z / ld a, 1
+        let mut bus = TestBus::new(&[0x08F0, 0x5678, 0x0C07, 0x8321, 0x0840, ST_Z, 0x0601]);
+        let mut ssp = Ssp1601::new();
+        ssp.run(&mut bus, 4);
+        assert_eq!(ssp.a, 0x8321_5678);
+        assert_eq!(ssp.st, ST_Z);
+    }
+
+    #[test]
+    fn test_mld_keeps_flags() {
+        // ldi a, 0x1234 / ldi st, N / mld (r0), (r4)
+        let mut bus = TestBus::new(&[0x0830, 0x1234, 0x0840, ST_N, 0xB600]);
+        let mut ssp = Ssp1601::new();
+        ssp.ram[0] = 3;
+        ssp.ram[256] = 5;
+        ssp.run(&mut bus, 3);
+        assert_eq!(ssp.a, 0);
+        assert_eq!(ssp.st, ST_N);
+        assert_eq!((ssp.x, ssp.y), (3, 5));
+    }
+
+    #[test]
+    fn test_cell_increment_write_overwrite() {
+        // ldi pmc, 0x0010 / ldi pmc, 0x4418 / ld pm4, - / ldi x, 0xA0B0 /
+        // ld pm4, x
+        let mut bus = TestBus::new(&[0x08E0, 0x0010, 0x08E0, 0x4418, 0x00C0, 0x0810, 0xA0B0, 0x00C1]);
+        bus.dram[0x10] = 0x1111;
+        let mut ssp = Ssp1601::new();
+        ssp.run(&mut bus, 5);
+        // Nibbles nulos do valor preservam a DRAM
+        assert_eq!(bus.dram[0x10], 0xA1B1);
+        assert_eq!(ssp.pmc, 0x4418_0011);
+    }
+}
@@ -1,4 +1,16 @@
 // src/cpu/z80.rs
+//! Zilog Z80
+//!
+//! No Mega Drive o Z80 é o processador de som (8 KB de RAM própria, PSG e
+//! YM2612). No modo Master System ele é a CPU principal: o núcleo de
+//! instruções abaixo (`step`) acessa memória e portas pelo trait `Z80Bus`,
+//! implementado pelo sistema que o hospeda (ver `sms`).
+//!
+//! O núcleo cobre o conjunto documentado completo (prefixos CB, ED, DD/FD e
+//! DDCB/FDCB), os bits não documentados X/Y (3 e 5) de F na maior parte das
+//! instruções e os três modos de interrupção. A contagem de ciclos é a de
+//! T-states por instrução, sem estados de espera.
+
 use std::sync::{Arc, Mutex};
 use crate::sound::Sound;
 
@@ -7,6 +19,36 @@ use crate::sound::Sound;
 // Usamos 0x7F11 como o endereço de escrita do PSG.
 const PSG_WRITE_ADDR: u16 = 0x7F11;
 
+// Bits do registrador F
+pub const FLAG_C: u8 = 0x01;
+pub const FLAG_N: u8 = 0x02;
+pub const FLAG_PV: u8 = 0x04;
+pub const FLAG_X: u8 = 0x08;
+pub const FLAG_H: u8 = 0x10;
+pub const FLAG_Y: u8 = 0x20;
+pub const FLAG_Z: u8 = 0x40;
+pub const FLAG_S: u8 = 0x80;
+
+/// Modo de interrupção selecionado por `IM n` (0, 0, 1, 2, 0, 0, 1, 2)
+const IM_TABLE: [u8; 8] = [0, 0, 1, 2, 0, 0, 1, 2];
+
+/// Memória e portas de I/O vistas pelo núcleo do Z80
+pub trait Z80Bus {
+    fn read(&mut self, addr: u16) -> u8;
+    fn write(&mut self, addr: u16, value: u8);
+    /// Leitura de porta (`IN`); o byte alto do endereço vem de A ou B
+    fn port_in(&mut self, port: u16) -> u8;
+    fn port_out(&mut self, port: u16, value: u8);
+}
+
+/// Registrador usado no lugar de HL (prefixos DD/FD)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Index {
+    Hl,
+    Ix,
+    Iy,
+}
+
 pub struct Z80 {
     pub ram: Vec<u8>,
     pub pc: u16,
@@ -15,6 +57,28 @@ pub struct Z80 {
     pub bus_taken: bool,
     // O Sound agora contém os chips PSG e YM2612
     pub sound: Arc<Mutex<Sound>>,
+
+    // Registradores do núcleo de instruções
+    pub a: u8,
+    pub f: u8,
+    pub b: u8,
+    pub c: u8,
+    pub d: u8,
+    pub e: u8,
+    pub h: u8,
+    pub l: u8,
+    pub ix: u16,
+    pub iy: u16,
+    pub i: u8,
+    pub r: u8,
+    /// Banco alternativo: AF', BC', DE', HL'
+    pub alt: [u16; 4],
+    pub iff1: bool,
+    pub iff2: bool,
+    /// Modo de interrupção (0, 1 ou 2)
+    pub im: u8,
+    /// Logo após `EI` a interrupção só é aceita depois da próxima instrução
+    ei_delay: bool,
 }
 
 impl Z80 {
@@ -26,15 +90,48 @@ impl Z80 {
             halted: false,
             bus_taken: false,
             sound,
+            a: 0xFF,
+            f: 0xFF,
+            b: 0,
+            c: 0,
+            d: 0,
+            e: 0,
+            h: 0,
+            l: 0,
+            ix: 0xFFFF,
+            iy: 0xFFFF,
+            i: 0,
+            r: 0,
+            alt: [0xFFFF; 4],
+            iff1: false,
+            iff2: false,
+            im: 0,
+            ei_delay: false,
         }
     }
 
+    /// Estado após o sinal de reset: PC = 0, interrupções desligadas, IM 0
+    /// (a RAM não é alterada)
+    pub fn reset(&mut self) {
+        self.pc = 0;
+        self.sp = 0xFFFF;
+        self.a = 0xFF;
+        self.f = 0xFF;
+        self.i = 0;
+        self.r = 0;
+        self.iff1 = false;
+        self.iff2 = false;
+        self.im = 0;
+        self.ei_delay = false;
+        self.halted = false;
+    }
+
     /// Avança o emulador Z80 por um ciclo de clock.
     /// O Z80 no Mega Drive roda a 3.58 MHz.
     pub fn tick(&mut self) {
         // 1 ciclo Z80 (3.58MHz) = 15 ciclos do clock principal (53.69MHz)
         const MASTER_CYCLES_PER_Z80_TICK: u32 = 15;
-        
+
         // O tick do Sound agora recebe o número de ciclos do clock principal
         // que o Z80 consumiu.
         let mut snd = self.sound.lock().unwrap();
@@ -48,7 +145,7 @@ impl Z80 {
 
     pub fn write_byte(&mut self, addr: u16, val: u8) {
         // --- Lógica de escrita nos chips de som ---
-        
+
         // Escrita no PSG (SN76489)
         if addr == PSG_WRITE_ADDR {
             // O PSG é acessado através de um endereço de I/O.
@@ -57,9 +154,1061 @@ impl Z80 {
             self.sound.lock().unwrap().psg.write().write_data(val);
             return;
         }
-        
+
         // Escrita normal na RAM do Z80
         let index = addr as usize % self.ram.len();
         self.ram[index] = val;
     }
+
+    // =====================================================
+    // PARES DE REGISTRADORES
+    // =====================================================
+
+    pub fn af(&self) -> u16 {
+        u16::from_be_bytes([self.a, self.f])
+    }
+
+    pub fn bc(&self) -> u16 {
+        u16::from_be_bytes([self.b, self.c])
+    }
+
+    pub fn de(&self) -> u16 {
+        u16::from_be_bytes([self.d, self.e])
+    }
+
+    pub fn hl(&self) -> u16 {
+        u16::from_be_bytes([self.h, self.l])
+    }
+
+    pub fn set_af(&mut self, value: u16) {
+        [self.a, self.f] = value.to_be_bytes();
+    }
+
+    pub fn set_bc(&mut self, value: u16) {
+        [self.b, self.c] = value.to_be_bytes();
+    }
+
+    pub fn set_de(&mut self, value: u16) {
+        [self.d, self.e] = value.to_be_bytes();
+    }
+
+    pub fn set_hl(&mut self, value: u16) {
+        [self.h, self.l] = value.to_be_bytes();
+    }
+
+    fn index_reg(&self, index: Index) -> u16 {
+        match index {
+            Index::Hl => self.hl(),
+            Index::Ix => self.ix,
+            Index::Iy => self.iy,
+        }
+    }
+
+    fn set_index_reg(&mut self, index: Index, value: u16) {
+        match index {
+            Index::Hl => self.set_hl(value),
+            Index::Ix => self.ix = value,
+            Index::Iy => self.iy = value,
+        }
+    }
+
+    /// Registrador de 8 bits pelo campo de 3 bits do opcode (6 = memória,
+    /// tratado por quem chama). Com DD/FD, H e L viram IXH/IXL ou IYH/IYL.
+    fn reg8(&self, r: u8, index: Index) -> u8 {
+        match r {
+            0 => self.b,
+            1 => self.c,
+            2 => self.d,
+            3 => self.e,
+            4 => (self.index_reg(index) >> 8) as u8,
+            5 => self.index_reg(index) as u8,
+            _ => self.a,
+        }
+    }
+
+    fn set_reg8(&mut self, r: u8, index: Index, value: u8) {
+        match r {
+            0 => self.b = value,
+            1 => self.c = value,
+            2 => self.d = value,
+            3 => self.e = value,
+            4 => {
+                let low = self.index_reg(index) & 0x00FF;
+                self.set_index_reg(index, ((value as u16) << 8) | low);
+            }
+            5 => {
+                let high = self.index_reg(index) & 0xFF00;
+                self.set_index_reg(index, high | value as u16);
+            }
+            _ => self.a = value,
+        }
+    }
+
+    /// Par BC, DE, HL (ou IX/IY) ou SP
+    fn rp(&self, p: u8, index: Index) -> u16 {
+        match p {
+            0 => self.bc(),
+            1 => self.de(),
+            2 => self.index_reg(index),
+            _ => self.sp,
+        }
+    }
+
+    fn set_rp(&mut self, p: u8, index: Index, value: u16) {
+        match p {
+            0 => self.set_bc(value),
+            1 => self.set_de(value),
+            2 => self.set_index_reg(index, value),
+            _ => self.sp = value,
+        }
+    }
+
+    /// Par para PUSH/POP: como `rp`, mas com AF no lugar de SP
+    fn rp2(&self, p: u8, index: Index) -> u16 {
+        if p == 3 {
+            self.af()
+        } else {
+            self.rp(p, index)
+        }
+    }
+
+    fn set_rp2(&mut self, p: u8, index: Index, value: u16) {
+        if p == 3 {
+            self.set_af(value);
+        } else {
+            self.set_rp(p, index, value);
+        }
+    }
+
+    // =====================================================
+    // ACESSO AO BARRAMENTO
+    // =====================================================
+
+    fn fetch<B: Z80Bus>(&mut self, bus: &mut B) -> u8 {
+        let value = bus.read(self.pc);
+        self.pc = self.pc.wrapping_add(1);
+        value
+    }
+
+    /// Busca de opcode (ciclo M1): incrementa os 7 bits baixos de R
+    fn fetch_opcode<B: Z80Bus>(&mut self, bus: &mut B) -> u8 {
+        self.r = (self.r & 0x80) | (self.r.wrapping_add(1) & 0x7F);
+        self.fetch(bus)
+    }
+
+    fn fetch16<B: Z80Bus>(&mut self, bus: &mut B) -> u16 {
+        let low = self.fetch(bus);
+        let high = self.fetch(bus);
+        u16::from_le_bytes([low, high])
+    }
+
+    fn read16<B: Z80Bus>(bus: &mut B, addr: u16) -> u16 {
+        u16::from_le_bytes([bus.read(addr), bus.read(addr.wrapping_add(1))])
+    }
+
+    fn write16<B: Z80Bus>(bus: &mut B, addr: u16, value: u16) {
+        let [low, high] = value.to_le_bytes();
+        bus.write(addr, low);
+        bus.write(addr.wrapping_add(1), high);
+    }
+
+    fn push<B: Z80Bus>(&mut self, bus: &mut B, value: u16) {
+        self.sp = self.sp.wrapping_sub(2);
+        Self::write16(bus, self.sp, value);
+    }
+
+    fn pop<B: Z80Bus>(&mut self, bus: &mut B) -> u16 {
+        let value = Self::read16(bus, self.sp);
+        self.sp = self.sp.wrapping_add(2);
+        value
+    }
+
+    /// Endereço do operando de memória: HL ou IX/IY + deslocamento
+    fn operand_addr<B: Z80Bus>(&mut self, bus: &mut B, index: Index) -> u16 {
+        match index {
+            Index::Hl => self.hl(),
+            _ => {
+                let offset = self.fetch(bus) as i8;
+                self.index_reg(index).wrapping_add(offset as u16)
+            }
+        }
+    }
+
+    // =====================================================
+    // EXECUÇÃO
+    // =====================================================
+
+    /// Executa uma instrução e retorna os T-states gastos. Parado em HALT,
+    /// gasta 4 ciclos por chamada até uma interrupção.
+    pub fn step<B: Z80Bus>(&mut self, bus: &mut B) -> u32 {
+        self.ei_delay = false;
+        if self.halted {
+            self.r = (self.r & 0x80) | (self.r.wrapping_add(1) & 0x7F);
+            return 4;
+        }
+        let op = self.fetch_opcode(bus);
+        self.execute(bus, op, Index::Hl)
+    }
+
+    /// Executa instruções até completar pelo menos `cycles` T-states.
+    /// Retorna os ciclos efetivamente gastos.
+    pub fn run<B: Z80Bus>(&mut self, bus: &mut B, cycles: u32) -> u32 {
+        let mut elapsed = 0;
+        while elapsed < cycles {
+            elapsed += self.step(bus);
+        }
+        elapsed
+    }
+
+    /// Interrupção mascarável (/INT). Retorna os ciclos gastos ou 0 se a
+    /// interrupção não foi aceita (IFF1 desligado ou logo após `EI`).
+    /// No modo 0 o barramento é tratado como 0xFF (RST 38h), como no
+    /// Master System.
+    pub fn interrupt<B: Z80Bus>(&mut self, bus: &mut B) -> u32 {
+        if !self.iff1 || self.ei_delay {
+            return 0;
+        }
+        self.halted = false;
+        self.iff1 = false;
+        self.iff2 = false;
+        self.r = (self.r & 0x80) | (self.r.wrapping_add(1) & 0x7F);
+        self.push(bus, self.pc);
+        if self.im == 2 {
+            let vector = ((self.i as u16) << 8) | 0xFF;
+            self.pc = Self::read16(bus, vector);
+            19
+        } else {
+            self.pc = 0x0038;
+            13
+        }
+    }
+
+    /// Interrupção não mascarável (/NMI, botão de pausa do Master System)
+    pub fn nmi<B: Z80Bus>(&mut self, bus: &mut B) -> u32 {
+        self.halted = false;
+        self.iff2 = self.iff1;
+        self.iff1 = false;
+        self.r = (self.r & 0x80) | (self.r.wrapping_add(1) & 0x7F);
+        self.push(bus, self.pc);
+        self.pc = 0x0066;
+        11
+    }
+
+    /// Condição de salto: NZ, Z, NC, C, PO, PE, P, M
+    fn condition(&self, cc: u8) -> bool {
+        let flag = [FLAG_Z, FLAG_C, FLAG_PV, FLAG_S][(cc >> 1) as usize];
+        (self.f & flag != 0) == (cc & 1 == 1)
+    }
+
+    fn jump_relative(&mut self, offset: i8) {
+        self.pc = self.pc.wrapping_add(offset as u16);
+    }
+
+    /// Instruções sem prefixo (ou com DD/FD, conforme `index`)
+    fn execute<B: Z80Bus>(&mut self, bus: &mut B, op: u8, index: Index) -> u32 {
+        let (x, y, z) = (op >> 6, (op >> 3) & 7, op & 7);
+        let (p, q) = (y >> 1, y & 1);
+        // Operando (HL) custa mais com deslocamento: (IX+d)
+        let indexed = index != Index::Hl;
+
+        match (x, z) {
+            (0, 0) => match y {
+                0 => 4,
+                1 => {
+                    let af = self.af();
+                    self.set_af(self.alt[0]);
+                    self.alt[0] = af;
+                    4
+                }
+                2 => {
+                    let offset = self.fetch(bus) as i8;
+                    self.b = self.b.wrapping_sub(1);
+                    if self.b != 0 {
+                        self.jump_relative(offset);
+                        13
+                    } else {
+                        8
+                    }
+                }
+                3 => {
+                    let offset = self.fetch(bus) as i8;
+                    self.jump_relative(offset);
+                    12
+                }
+                _ => {
+                    let offset = self.fetch(bus) as i8;
+                    if self.condition(y - 4) {
+                        self.jump_relative(offset);
+                        12
+                    } else {
+                        7
+                    }
+                }
+            },
+            (0, 1) => {
+                if q == 0 {
+                    let value = self.fetch16(bus);
+                    self.set_rp(p, index, value);
+                    10
+                } else {
+                    let result = self.add16(self.index_reg(index), self.rp(p, index));
+                    self.set_index_reg(index, result);
+                    11
+                }
+            }
+            (0, 2) => match (q, p) {
+                (0, 0) => {
+                    bus.write(self.bc(), self.a);
+                    7
+                }
+                (0, 1) => {
+                    bus.write(self.de(), self.a);
+                    7
+                }
+                (0, 2) => {
+                    let addr = self.fetch16(bus);
+                    Self::write16(bus, addr, self.index_reg(index));
+                    16
+                }
+                (0, _) => {
+                    let addr = self.fetch16(bus);
+                    bus.write(addr, self.a);
+                    13
+                }
+                (_, 0) => {
+                    self.a = bus.read(self.bc());
+                    7
+                }
+                (_, 1) => {
+                    self.a = bus.read(self.de());
+                    7
+                }
+                (_, 2) => {
+                    let addr = self.fetch16(bus);
+                    let value = Self::read16(bus, addr);
+                    self.set_index_reg(index, value);
+                    16
+                }
+                _ => {
+                    let addr = self.fetch16(bus);
+                    self.a = bus.read(addr);
+                    13
+                }
+            },
+            (0, 3) => {
+                let value = self.rp(p, index);
+                let value = if q == 0 { value.wrapping_add(1) } else { value.wrapping_sub(1) };
+                self.set_rp(p, index, value);
+                6
+            }
+            (0, 4) | (0, 5) => {
+                let increment = z == 4;
+                if y == 6 {
+                    let addr = self.operand_addr(bus, index);
+                    let value = bus.read(addr);
+                    let result = if increment { self.inc8(value) } else { self.dec8(value) };
+                    bus.write(addr, result);
+                    if indexed { 19 } else { 11 }
+                } else {
+                    let value = self.reg8(y, index);
+                    let result = if increment { self.inc8(value) } else { self.dec8(value) };
+                    self.set_reg8(y, index, result);
+                    4
+                }
+            }
+            (0, 6) => {
+                if y == 6 {
+                    let addr = self.operand_addr(bus, index);
+                    let value = self.fetch(bus);
+                    bus.write(addr, value);
+                    if indexed { 15 } else { 10 }
+                } else {
+                    let value = self.fetch(bus);
+                    self.set_reg8(y, index, value);
+                    7
+                }
+            }
+            (0, _) => {
+                self.accumulator_op(y);
+                4
+            }
+            (1, _) => {
+                if y == 6 && z == 6 {
+                    self.halted = true;
+                    4
+                } else if y == 6 {
+                    // LD (HL),r: com (IX+d), r continua sendo H/L
+                    let addr = self.operand_addr(bus, index);
+                    bus.write(addr, self.reg8(z, Index::Hl));
+                    if indexed { 15 } else { 7 }
+                } else if z == 6 {
+                    let addr = self.operand_addr(bus, index);
+                    let value = bus.read(addr);
+                    self.set_reg8(y, Index::Hl, value);
+                    if indexed { 15 } else { 7 }
+                } else {
+                    let value = self.reg8(z, index);
+                    self.set_reg8(y, index, value);
+                    4
+                }
+            }
+            (2, _) => {
+                if z == 6 {
+                    let addr = self.operand_addr(bus, index);
+                    let value = bus.read(addr);
+                    self.alu(y, value);
+                    if indexed { 15 } else { 7 }
+                } else {
+                    self.alu(y, self.reg8(z, index));
+                    4
+                }
+            }
+            (_, 0) => {
+                if self.condition(y) {
+                    self.pc = self.pop(bus);
+                    11
+                } else {
+                    5
+                }
+            }
+            (_, 1) => match (q, p) {
+                (0, _) => {
+                    let value = self.pop(bus);
+                    self.set_rp2(p, index, value);
+                    10
+                }
+                (_, 0) => {
+                    self.pc = self.pop(bus);
+                    10
+                }
+                (_, 1) => {
+                    let (bc, de, hl) = (self.bc(), self.de(), self.hl());
+                    self.set_bc(self.alt[1]);
+                    self.set_de(self.alt[2]);
+                    self.set_hl(self.alt[3]);
+                    self.alt[1..].copy_from_slice(&[bc, de, hl]);
+                    4
+                }
+                (_, 2) => {
+                    self.pc = self.index_reg(index);
+                    4
+                }
+                _ => {
+                    self.sp = self.index_reg(index);
+                    6
+                }
+            },
+            (_, 2) => {
+                let addr = self.fetch16(bus);
+                if self.condition(y) {
+                    self.pc = addr;
+                }
+                10
+            }
+            (_, 3) => match y {
+                0 => {
+                    self.pc = self.fetch16(bus);
+                    10
+                }
+                1 => self.execute_cb(bus, index),
+                2 => {
+                    let port = u16::from_be_bytes([self.a, self.fetch(bus)]);
+                    bus.port_out(port, self.a);
+                    11
+                }
+                3 => {
+                    let port = u16::from_be_bytes([self.a, self.fetch(bus)]);
+                    self.a = bus.port_in(port);
+                    11
+                }
+                4 => {
+                    let value = Self::read16(bus, self.sp);
+                    Self::write16(bus, self.sp, self.index_reg(index));
+                    self.set_index_reg(index, value);
+                    19
+                }
+                5 => {
+                    // EX DE,HL não é afetado por DD/FD
+                    let de = self.de();
+                    self.set_de(self.hl());
+                    self.set_hl(de);
+                    4
+                }
+                6 => {
+                    self.iff1 = false;
+                    self.iff2 = false;
+                    4
+                }
+                _ => {
+                    self.iff1 = true;
+                    self.iff2 = true;
+                    self.ei_delay = true;
+                    4
+                }
+            },
+            (_, 4) => {
+                let addr = self.fetch16(bus);
+                if self.condition(y) {
+                    self.push(bus, self.pc);
+                    self.pc = addr;
+                    17
+                } else {
+                    10
+                }
+            }
+            (_, 5) => match (q, p) {
+                (0, _) => {
+                    self.push(bus, self.rp2(p, index));
+                    11
+                }
+                (_, 0) => {
+                    let addr = self.fetch16(bus);
+                    self.push(bus, self.pc);
+                    self.pc = addr;
+                    17
+                }
+                (_, 1) => {
+                    let op = self.fetch_opcode(bus);
+                    self.execute(bus, op, Index::Ix) + 4
+                }
+                (_, 2) => {
+                    let op = self.fetch_opcode(bus);
+                    self.execute_ed(bus, op)
+                }
+                _ => {
+                    let op = self.fetch_opcode(bus);
+                    self.execute(bus, op, Index::Iy) + 4
+                }
+            },
+            (_, 6) => {
+                let value = self.fetch(bus);
+                self.alu(y, value);
+                7
+            }
+            _ => {
+                self.push(bus, self.pc);
+                self.pc = y as u16 * 8;
+                11
+            }
+        }
+    }
+
+    /// Prefixo CB: rotações, BIT, RES e SET. Com DD/FD o deslocamento vem
+    /// antes do opcode e o resultado também é copiado para o registrador
+    /// indicado (comportamento não documentado).
+    fn execute_cb<B: Z80Bus>(&mut self, bus: &mut B, index: Index) -> u32 {
+        let (addr, op) = if index == Index::Hl {
+            let op = self.fetch_opcode(bus);
+            (self.hl(), op)
+        } else {
+            let addr = self.operand_addr(bus, index);
+            (addr, self.fetch(bus))
+        };
+        let (x, y, z) = (op >> 6, (op >> 3) & 7, op & 7);
+        let memory = index != Index::Hl || z == 6;
+        let value = if memory { bus.read(addr) } else { self.reg8(z, Index::Hl) };
+
+        if x == 1 {
+            self.bit(y, value);
+            return match (index, z) {
+                (Index::Hl, 6) => 12,
+                (Index::Hl, _) => 8,
+                _ => 16,
+            };
+        }
+        let result = match x {
+            0 => self.rotate(y, value),
+            2 => value & !(1 << y),
+            _ => value | (1 << y),
+        };
+        if memory {
+            bus.write(addr, result);
+        }
+        if z != 6 {
+            self.set_reg8(z, Index::Hl, result);
+        }
+        match (index, z) {
+            (Index::Hl, 6) => 15,
+            (Index::Hl, _) => 8,
+            _ => 19,
+        }
+    }
+
+    /// Prefixo ED: I/O por C, aritmética de 16 bits com carry, LD de pares
+    /// na memória, NEG, RETI/RETN, IM, RRD/RLD e instruções de bloco.
+    /// Opcodes não definidos viram NOP de 8 ciclos.
+    fn execute_ed<B: Z80Bus>(&mut self, bus: &mut B, op: u8) -> u32 {
+        let (x, y, z) = (op >> 6, (op >> 3) & 7, op & 7);
+        let (p, q) = (y >> 1, y & 1);
+
+        match (x, z) {
+            (1, 0) => {
+                let value = bus.port_in(self.bc());
+                self.f = (self.f & FLAG_C) | sz53p(value);
+                if y != 6 {
+                    self.set_reg8(y, Index::Hl, value);
+                }
+                12
+            }
+            (1, 1) => {
+                let value = if y == 6 { 0 } else { self.reg8(y, Index::Hl) };
+                bus.port_out(self.bc(), value);
+                12
+            }
+            (1, 2) => {
+                let (hl, value) = (self.hl(), self.rp(p, Index::Hl));
+                let result = if q == 0 { self.sbc16(hl, value) } else { self.adc16(hl, value) };
+                self.set_hl(result);
+                15
+            }
+            (1, 3) => {
+                let addr = self.fetch16(bus);
+                if q == 0 {
+                    Self::write16(bus, addr, self.rp(p, Index::Hl));
+                } else {
+                    let value = Self::read16(bus, addr);
+                    self.set_rp(p, Index::Hl, value);
+                }
+                20
+            }
+            (1, 4) => {
+                self.a = self.sub8(0, self.a, false);
+                8
+            }
+            (1, 5) => {
+                self.pc = self.pop(bus);
+                self.iff1 = self.iff2;
+                14
+            }
+            (1, 6) => {
+                self.im = IM_TABLE[y as usize];
+                8
+            }
+            (1, 7) => match y {
+                0 => {
+                    self.i = self.a;
+                    9
+                }
+                1 => {
+                    self.r = self.a;
+                    9
+                }
+                2 | 3 => {
+                    self.a = if y == 2 { self.i } else { self.r };
+                    let pv = if self.iff2 { FLAG_PV } else { 0 };
+                    self.f = (self.f & FLAG_C) | sz53(self.a) | pv;
+                    9
+                }
+                4 | 5 => {
+                    let hl = self.hl();
+                    let value = bus.read(hl);
+                    let (memory, a) = if y == 4 {
+                        // RRD
+                        ((self.a << 4) | (value >> 4), (self.a & 0xF0) | (value & 0x0F))
+                    } else {
+                        // RLD
+                        ((value << 4) | (self.a & 0x0F), (self.a & 0xF0) | (value >> 4))
+                    };
+                    bus.write(hl, memory);
+                    self.a = a;
+                    self.f = (self.f & FLAG_C) | sz53p(a);
+                    18
+                }
+                _ => 8,
+            },
+            (2, 0..=3) if y >= 4 => self.block(bus, y, z),
+            _ => 8,
+        }
+    }
+
+    /// LDI/CPI/INI/OUTI e variantes (decremento com y ímpar, repetição com
+    /// y >= 6). A repetição volta o PC para a própria instrução.
+    fn block<B: Z80Bus>(&mut self, bus: &mut B, y: u8, z: u8) -> u32 {
+        let step: u16 = if y & 1 == 1 { 0xFFFF } else { 1 };
+        let repeat = y >= 6;
+        let hl = self.hl();
+        self.set_hl(hl.wrapping_add(step));
+
+        let again = match z {
+            0 => {
+                let value = bus.read(hl);
+                let de = self.de();
+                bus.write(de, value);
+                self.set_de(de.wrapping_add(step));
+                let bc = self.bc().wrapping_sub(1);
+                self.set_bc(bc);
+                let n = value.wrapping_add(self.a);
+                let pv = if bc != 0 { FLAG_PV } else { 0 };
+                self.f = (self.f & (FLAG_S | FLAG_Z | FLAG_C)) | pv | (n & FLAG_X) | ((n << 4) & FLAG_Y);
+                bc != 0
+            }
+            1 => {
+                let value = bus.read(hl);
+                let result = self.a.wrapping_sub(value);
+                let bc = self.bc().wrapping_sub(1);
+                self.set_bc(bc);
+                let half = (self.a ^ value ^ result) & FLAG_H;
+                let n = result.wrapping_sub((half != 0) as u8);
+                let pv = if bc != 0 { FLAG_PV } else { 0 };
+                let zero = if result == 0 { FLAG_Z } else { 0 };
+                self.f = (self.f & FLAG_C)
+                    | FLAG_N
+                    | (result & FLAG_S)
+                    | zero
+                    | half
+                    | pv
+                    | (n & FLAG_X)
+                    | ((n << 4) & FLAG_Y);
+                bc != 0 && result != 0
+            }
+            2 => {
+                let value = bus.port_in(self.bc());
+                bus.write(hl, value);
+                self.b = self.b.wrapping_sub(1);
+                self.f = sz53(self.b) | FLAG_N;
+                self.b != 0
+            }
+            _ => {
+                let value = bus.read(hl);
+                self.b = self.b.wrapping_sub(1);
+                bus.port_out(self.bc(), value);
+                self.f = sz53(self.b) | FLAG_N;
+                self.b != 0
+            }
+        };
+
+        if repeat && again {
+            self.pc = self.pc.wrapping_sub(2);
+            21
+        } else {
+            16
+        }
+    }
+
+    // =====================================================
+    // ULA
+    // =====================================================
+
+    /// ADD, ADC, SUB, SBC, AND, XOR, OR, CP com o acumulador
+    fn alu(&mut self, op: u8, value: u8) {
+        let carry = self.f & FLAG_C != 0;
+        match op {
+            0 => self.a = self.add8(self.a, value, false),
+            1 => self.a = self.add8(self.a, value, carry),
+            2 => self.a = self.sub8(self.a, value, false),
+            3 => self.a = self.sub8(self.a, value, carry),
+            4 => {
+                self.a &= value;
+                self.f = sz53p(self.a) | FLAG_H;
+            }
+            5 => {
+                self.a ^= value;
+                self.f = sz53p(self.a);
+            }
+            6 => {
+                self.a |= value;
+                self.f = sz53p(self.a);
+            }
+            _ => {
+                // CP: X/Y vêm do operando, não do resultado
+                self.sub8(self.a, value, false);
+                self.f = (self.f & !(FLAG_X | FLAG_Y)) | (value & (FLAG_X | FLAG_Y));
+            }
+        }
+    }
+
+    fn add8(&mut self, a: u8, value: u8, carry: bool) -> u8 {
+        let sum = a as u16 + value as u16 + carry as u16;
+        let result = sum as u8;
+        let overflow = ((a ^ !value) & (a ^ result) & 0x80) >> 5;
+        self.f = sz53(result) | ((a ^ value ^ result) & FLAG_H) | overflow | (sum > 0xFF) as u8;
+        result
+    }
+
+    fn sub8(&mut self, a: u8, value: u8, carry: bool) -> u8 {
+        let diff = a as i16 - value as i16 - carry as i16;
+        let result = diff as u8;
+        let overflow = ((a ^ value) & (a ^ result) & 0x80) >> 5;
+        self.f = sz53(result) | FLAG_N | ((a ^ value ^ result) & FLAG_H) | overflow | (diff < 0) as u8;
+        result
+    }
+
+    fn inc8(&mut self, value: u8) -> u8 {
+        let result = value.wrapping_add(1);
+        let half = if value & 0x0F == 0x0F { FLAG_H } else { 0 };
+        let overflow = if value == 0x7F { FLAG_PV } else { 0 };
+        self.f = (self.f & FLAG_C) | sz53(result) | half | overflow;
+        result
+    }
+
+    fn dec8(&mut self, value: u8) -> u8 {
+        let result = value.wrapping_sub(1);
+        let half = if value & 0x0F == 0 { FLAG_H } else { 0 };
+        let overflow = if value == 0x80 { FLAG_PV } else { 0 };
+        self.f = (self.f & FLAG_C) | FLAG_N | sz53(result) | half | overflow;
+        result
+    }
+
+    fn add16(&mut self, a: u16, value: u16) -> u16 {
+        let sum = a as u32 + value as u32;
+        let result = sum as u16;
+        let half = ((a ^ value ^ result) >> 8) as u8 & FLAG_H;
+        self.f = (self.f & (FLAG_S | FLAG_Z | FLAG_PV))
+            | ((result >> 8) as u8 & (FLAG_X | FLAG_Y))
+            | half
+            | (sum > 0xFFFF) as u8;
+        result
+    }
+
+    fn adc16(&mut self, a: u16, value: u16) -> u16 {
+        let sum = a as u32 + value as u32 + (self.f & FLAG_C) as u32;
+        let result = sum as u16;
+        let overflow = if (a ^ !value) & (a ^ result) & 0x8000 != 0 { FLAG_PV } else { 0 };
+        self.f = flags16(a, value, result) | overflow | (sum > 0xFFFF) as u8;
+        result
+    }
+
+    fn sbc16(&mut self, a: u16, value: u16) -> u16 {
+        let diff = a as i32 - value as i32 - (self.f & FLAG_C) as i32;
+        let result = diff as u16;
+        let overflow = if (a ^ value) & (a ^ result) & 0x8000 != 0 { FLAG_PV } else { 0 };
+        self.f = flags16(a, value, result) | overflow | FLAG_N | (diff < 0) as u8;
+        result
+    }
+
+    /// RLCA, RRCA, RLA, RRA, DAA, CPL, SCF, CCF
+    fn accumulator_op(&mut self, y: u8) {
+        let kept = self.f & (FLAG_S | FLAG_Z | FLAG_PV);
+        let carry = self.f & FLAG_C;
+        match y {
+            0..=3 => {
+                let (result, out) = match y {
+                    0 => (self.a.rotate_left(1), self.a >> 7),
+                    1 => (self.a.rotate_right(1), self.a & 1),
+                    2 => ((self.a << 1) | carry, self.a >> 7),
+                    _ => ((self.a >> 1) | (carry << 7), self.a & 1),
+                };
+                self.a = result;
+                self.f = kept | (result & (FLAG_X | FLAG_Y)) | out;
+            }
+            4 => self.daa(),
+            5 => {
+                self.a = !self.a;
+                self.f = (self.f & (FLAG_S | FLAG_Z | FLAG_PV | FLAG_C))
+                    | FLAG_H
+                    | FLAG_N
+                    | (self.a & (FLAG_X | FLAG_Y));
+            }
+            6 => self.f = kept | (self.a & (FLAG_X | FLAG_Y)) | FLAG_C,
+            _ => {
+                let half = if carry != 0 { FLAG_H } else { 0 };
+                self.f = kept | (self.a & (FLAG_X | FLAG_Y)) | half | (carry ^ FLAG_C);
+            }
+        }
+    }
+
+    /// Ajuste decimal do acumulador depois de uma soma ou subtração BCD
+    fn daa(&mut self) {
+        let a = self.a;
+        let subtract = self.f & FLAG_N != 0;
+        let mut correction = 0;
+        let mut carry = self.f & FLAG_C;
+        if self.f & FLAG_H != 0 || a & 0x0F > 9 {
+            correction |= 0x06;
+        }
+        if carry != 0 || a > 0x99 {
+            correction |= 0x60;
+            carry = FLAG_C;
+        }
+        let (result, half) = if subtract {
+            (a.wrapping_sub(correction), self.f & FLAG_H != 0 && a & 0x0F < 6)
+        } else {
+            (a.wrapping_add(correction), a & 0x0F > 9)
+        };
+        self.a = result;
+        let half = if half { FLAG_H } else { 0 };
+        self.f = sz53p(result) | (self.f & FLAG_N) | half | carry;
+    }
+
+    /// RLC, RRC, RL, RR, SLA, SRA, SLL (não documentada), SRL
+    fn rotate(&mut self, y: u8, value: u8) -> u8 {
+        let carry = self.f & FLAG_C;
+        let (result, out) = match y {
+            0 => (value.rotate_left(1), value >> 7),
+            1 => (value.rotate_right(1), value & 1),
+            2 => ((value << 1) | carry, value >> 7),
+            3 => ((value >> 1) | (carry << 7), value & 1),
+            4 => (value << 1, value >> 7),
+            5 => ((value >> 1) | (value & 0x80), value & 1),
+            6 => ((value << 1) | 1, value >> 7),
+            _ => (value >> 1, value & 1),
+        };
+        self.f = sz53p(result) | out;
+        result
+    }
+
+    fn bit(&mut self, bit: u8, value: u8) {
+        let set = value & (1 << bit);
+        let zero = if set == 0 { FLAG_Z | FLAG_PV } else { 0 };
+        self.f = (self.f & FLAG_C) | FLAG_H | (value & (FLAG_X | FLAG_Y)) | zero | (set & FLAG_S);
+    }
+}
+
+/// Flags S, Z, X e Y de um resultado de 8 bits
+fn sz53(value: u8) -> u8 {
+    let zero = if value == 0 { FLAG_Z } else { 0 };
+    (value & (FLAG_S | FLAG_Y | FLAG_X)) | zero
+}
+
+/// `sz53` com paridade par em P/V
+fn sz53p(value: u8) -> u8 {
+    let parity = if value.count_ones().is_multiple_of(2) { FLAG_PV } else { 0 };
+    sz53(value) | parity
+}
+
+/// S, Z, X, Y e H de ADC/SBC de 16 bits (overflow e carry ficam com quem chama)
+fn flags16(a: u16, value: u16, result: u16) -> u8 {
+    let high = (result >> 8) as u8;
+    let zero = if result == 0 { FLAG_Z } else { 0 };
+    (high & (FLAG_S | FLAG_X | FLAG_Y)) | zero | (((a ^ value ^ result) >> 8) as u8 & FLAG_H)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 64 KB de RAM e portas que registram as escritas
+    struct TestBus {
+        memory: Vec<u8>,
+        outputs: Vec<(u16, u8)>,
+    }
+
+    impl TestBus {
+        fn with_program(program: &[u8]) -> Self {
+            let mut memory = vec![0; 0x10000];
+            memory[..program.len()].copy_from_slice(program);
+            Self { memory, outputs: Vec::new() }
+        }
+    }
+
+    impl Z80Bus for TestBus {
+        fn read(&mut self, addr: u16) -> u8 {
+            self.memory[addr as usize]
+        }
+
+        fn write(&mut self, addr: u16, value: u8) {
+            self.memory[addr as usize] = value;
+        }
+
+        fn port_in(&mut self, port: u16) -> u8 {
+            port as u8 ^ 0xFF
+        }
+
+        fn port_out(&mut self, port: u16, value: u8) {
+            self.outputs.push((port, value));
+        }
+    }
+
+    fn cpu() -> Z80 {
+        let mut cpu = Z80::new(Arc::new(Mutex::new(Sound::new(44100))));
+        cpu.reset();
+        cpu
+    }
+
+    /// Executa até o HALT
+    fn run_until_halt(cpu: &mut Z80, bus: &mut TestBus) -> u32 {
+        let mut cycles = 0;
+        while !cpu.halted {
+            cycles += cpu.step(bus);
+        }
+        cycles
+    }
+
+    #[test]
+    fn test_arithmetic_flags_and_daa() {
+        // LD A,7Fh; ADD A,1; LD B,A; LD A,15h; ADD A,27h; DAA; HALT
+        let mut bus = TestBus::with_program(&[0x3E, 0x7F, 0xC6, 0x01, 0x47, 0x3E, 0x15, 0xC6, 0x27, 0x27, 0x76]);
+        let mut cpu = cpu();
+        cpu.step(&mut bus);
+        cpu.step(&mut bus);
+        assert_eq!(cpu.a, 0x80);
+        assert_eq!(cpu.f & (FLAG_S | FLAG_PV | FLAG_H | FLAG_Z | FLAG_C), FLAG_S | FLAG_PV | FLAG_H);
+
+        run_until_halt(&mut cpu, &mut bus);
+        assert_eq!(cpu.b, 0x80);
+        assert_eq!(cpu.a, 0x42);
+        assert_eq!(cpu.f & FLAG_C, 0);
+    }
+
+    #[test]
+    fn test_loops_block_copy_and_stack() {
+        let program = [
+            0x21, 0x00, 0x80, // LD HL,8000h
+            0x11, 0x00, 0x90, // LD DE,9000h
+            0x01, 0x04, 0x00, // LD BC,4
+            0xED, 0xB0, // LDIR
+            0x06, 0x05, // LD B,5
+            0xAF, // XOR A
+            0x3C, // INC A
+            0x10, 0xFD, // DJNZ -3
+            0xCD, 0x20, 0x00, // CALL 0020h
+            0x76, // HALT
+        ];
+        let mut bus = TestBus::with_program(&program);
+        // 0020h: PUSH AF; LD A,0; POP AF; OUT (10h),A; RET
+        bus.memory[0x20..0x27].copy_from_slice(&[0xF5, 0x3E, 0x00, 0xF1, 0xD3, 0x10, 0xC9]);
+        bus.memory[0x8000..0x8004].copy_from_slice(&[1, 2, 3, 4]);
+        let mut cpu = cpu();
+        cpu.sp = 0xF000;
+        run_until_halt(&mut cpu, &mut bus);
+
+        assert_eq!(bus.memory[0x9000..0x9004], [1, 2, 3, 4]);
+        assert_eq!(cpu.bc(), 0);
+        assert_eq!(cpu.a, 5);
+        assert_eq!(bus.outputs, vec![(0x0510, 5)]);
+        assert_eq!(cpu.sp, 0xF000);
+    }
+
+    #[test]
+    fn test_index_registers_and_bit_ops() {
+        let program = [
+            0xDD, 0x21, 0x00, 0x80, // LD IX,8000h
+            0xDD, 0x36, 0x05, 0x81, // LD (IX+5),81h
+            0xDD, 0xCB, 0x05, 0x06, // RLC (IX+5)
+            0xDD, 0x7E, 0x05, // LD A,(IX+5)
+            0xFD, 0x21, 0x10, 0x80, // LD IY,8010h
+            0xFD, 0xCB, 0xFF, 0xFE, // SET 7,(IY-1)
+            0xCB, 0x47, // BIT 0,A
+            0x76, // HALT
+        ];
+        let mut bus = TestBus::with_program(&program);
+        let mut cpu = cpu();
+        run_until_halt(&mut cpu, &mut bus);
+
+        assert_eq!(bus.memory[0x8005], 0x03);
+        assert_eq!(cpu.a, 0x03);
+        assert_eq!(bus.memory[0x800F], 0x80);
+        assert_eq!(cpu.f & FLAG_Z, 0);
+    }
+
+    #[test]
+    fn test_interrupt_mode_1_after_ei_delay() {
+        // IM 1; EI; NOP; HALT
+        let mut bus = TestBus::with_program(&[0xED, 0x56, 0xFB, 0x00, 0x76]);
+        let mut cpu = cpu();
+        cpu.sp = 0xF000;
+        cpu.step(&mut bus);
+        cpu.step(&mut bus);
+        // Logo após EI a interrupção ainda é ignorada
+        assert_eq!(cpu.interrupt(&mut bus), 0);
+        cpu.step(&mut bus);
+        cpu.step(&mut bus);
+        assert!(cpu.halted);
+
+        assert_eq!(cpu.interrupt(&mut bus), 13);
+        assert!(!cpu.halted);
+        assert_eq!(cpu.pc, 0x0038);
+        assert!(!cpu.iff1);
+        assert_eq!(Z80::read16(&mut bus, cpu.sp), 0x0005);
+    }
 }
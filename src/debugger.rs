@@ -0,0 +1,342 @@
+//! REPL do depurador
+//!
+//! Comandos em texto executados entre quadros, lidos do terminal
+//! (`--debug`) ou de um socket Unix de controle remoto (`--debug-socket`).
+//! No socket, cada linha recebida é um comando e cada resposta termina com
+//! uma linha vazia.
+//!
+//! Comandos:
+//! - `search ...`: busca na RAM (ver `ramsearch`)
+//! - `cheats`: lista as trapaças carregadas
+//! - `cheat <n>`: liga/desliga a trapaça `n`
+//! - `cheat add <código> [nome]`: adiciona e liga um código
+//! - `peek <endereço> [8|16|32]`: lê a memória
+//! - `deinterlace <weave|bob|blend|native>`: modo de desentrelaçamento
+//! - `curve <linear|md>`: curva de saída de cor
+//! - `cramdots <on|off>`: pontos de CRAM
+//! - `quit`: encerra o emulador
+
+use crate::cheats::Cheat;
+#[cfg(unix)]
+use crate::memory::flashcart::bind_socket;
+use crate::memory::Memory;
+use crate::ramsearch::{RamSearch, SearchCommand, ValueSize};
+use crate::vdp::{ColorCurve, DeinterlaceMode};
+use std::io::BufRead;
+use std::sync::mpsc::{self, Receiver};
+#[cfg(unix)]
+use std::io::{self, ErrorKind, Read, Write};
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+#[cfg(unix)]
+use std::path::Path;
+
+const HELP: &str = "\
+search start [main|z80] [8|16|32] [s|u]   inicia uma busca
+search <eq|ne|lt|gt|le|ge> [prev|valor]   filtra os candidatos
+search list [n] | search code <end> [valor] | search reset
+cheats | cheat <n> | cheat add <código> [nome]
+peek <endereço> [8|16|32]
+deinterlace <weave|bob|blend|native> | curve <linear|md> | cramdots <on|off>
+quit";
+
+/// Resposta a um comando
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Reply {
+    Text(String),
+    Quit,
+}
+
+/// Estado do depurador entre comandos
+#[derive(Debug, Default)]
+pub struct Debugger {
+    pub search: Option<RamSearch>,
+}
+
+impl Debugger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Executa uma linha de comando
+    pub fn execute(&mut self, memory: &mut Memory, line: &str) -> Reply {
+        let args: Vec<&str> = line.split_whitespace().collect();
+        let text = match args.as_slice() {
+            [] => String::new(),
+            ["quit" | "exit" | "q"] => return Reply::Quit,
+            ["help" | "?"] => HELP.to_string(),
+            ["search", rest @ ..] => match SearchCommand::parse(rest) {
+                Ok(command) => command.execute(&mut self.search, memory).unwrap_or_else(|e| e.to_string()),
+                Err(e) => e.to_string(),
+            },
+            ["cheats"] => list_cheats(memory),
+            ["cheat", "add", code, name @ ..] => add_cheat(memory, code, &name.join(" ")),
+            ["cheat", index] => match index.parse().ok().and_then(|i| memory.toggle_cheat(i)) {
+                Some(true) => format!("Trapaça {} ligada", index),
+                Some(false) => format!("Trapaça {} desligada", index),
+                None => format!("Trapaça {} não existe", index),
+            },
+            ["peek", addr, size @ ..] => peek(memory, addr, size.first().copied().unwrap_or("8")),
+            ["deinterlace", mode] => match mode.parse::<DeinterlaceMode>() {
+                Ok(mode) => {
+                    memory.vdp().set_deinterlace_mode(mode);
+                    format!("Desentrelaçamento: {:?}", mode)
+                }
+                Err(e) => e,
+            },
+            ["curve", curve] => match curve.parse::<ColorCurve>() {
+                Ok(curve) => {
+                    memory.vdp().set_color_curve(curve);
+                    format!("Curva de cor: {:?}", curve)
+                }
+                Err(e) => e,
+            },
+            ["cramdots", state @ ("on" | "off")] => {
+                memory.vdp().set_cram_dots(*state == "on");
+                format!("Pontos de CRAM: {}", state)
+            }
+            _ => format!("Comando desconhecido: {} (use \"help\")", line.trim()),
+        };
+        Reply::Text(text)
+    }
+}
+
+fn list_cheats(memory: &Memory) -> String {
+    if memory.cheats.is_empty() {
+        return "Nenhuma trapaça carregada".to_string();
+    }
+    memory
+        .cheats
+        .cheats()
+        .iter()
+        .enumerate()
+        .map(|(i, c)| format!("{:>3} [{}] {} {}", i, if c.enabled { "x" } else { " " }, c.name, c.codes.join(" ")))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn add_cheat(memory: &mut Memory, code: &str, name: &str) -> String {
+    let cheat = Cheat {
+        name: if name.is_empty() { code.to_string() } else { name.to_string() },
+        codes: vec![code.to_string()],
+        enabled: true,
+    };
+    match memory.cheats.push(cheat) {
+        Ok(index) => {
+            memory.refresh_cheats();
+            format!("Trapaça {} adicionada", index)
+        }
+        Err(e) => e.to_string(),
+    }
+}
+
+fn peek(memory: &Memory, addr: &str, size: &str) -> String {
+    let hex = addr.strip_prefix("0x").or_else(|| addr.strip_prefix('$')).unwrap_or(addr);
+    let (Ok(addr), Ok(size)) = (u32::from_str_radix(hex, 16), size.parse::<ValueSize>()) else {
+        return "uso: peek <endereço hex> [8|16|32]".to_string();
+    };
+    match size {
+        ValueSize::Byte => format!("{:06X}: {:02X}", addr, memory.read8(addr)),
+        ValueSize::Word => format!("{:06X}: {:04X}", addr, memory.read16(addr)),
+        ValueSize::Long => format!("{:06X}: {:08X}", addr, memory.read32(addr)),
+    }
+}
+
+/// Lê linhas do terminal em uma thread e as entrega pelo canal, para o
+/// laço principal executar entre quadros
+pub fn spawn_stdin_reader() -> Receiver<String> {
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        for line in std::io::stdin().lock().lines().map_while(Result::ok) {
+            if tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    rx
+}
+
+/// Controle remoto por socket Unix; um cliente por vez
+#[cfg(unix)]
+#[derive(Debug)]
+pub struct RemoteControl {
+    listener: UnixListener,
+    client: Option<UnixStream>,
+    /// Bytes recebidos ainda sem fim de linha
+    pending: Vec<u8>,
+}
+
+#[cfg(unix)]
+impl RemoteControl {
+    /// Cria o socket em `path` (substituindo um socket antigo; outros
+    /// arquivos não são apagados)
+    pub fn bind(path: &Path) -> io::Result<Self> {
+        Ok(Self { listener: bind_socket(path)?, client: None, pending: Vec::new() })
+    }
+
+    /// Aceita uma conexão pendente e retorna as linhas completas recebidas,
+    /// sem bloquear. Erros de E/S derrubam a conexão.
+    pub fn poll(&mut self) -> Vec<String> {
+        if self.client.is_none() {
+            match self.listener.accept() {
+                Ok((client, _)) => match client.set_nonblocking(true) {
+                    Ok(()) => {
+                        log::info!("Depurador: cliente remoto conectado");
+                        self.pending.clear();
+                        self.client = Some(client);
+                    }
+                    Err(e) => log::warn!("Depurador: conexão recusada: {}", e),
+                },
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+                Err(e) => log::warn!("Depurador: falha ao aceitar conexão: {}", e),
+            }
+        }
+        let Some(client) = &mut self.client else {
+            return Vec::new();
+        };
+
+        let mut buf = [0u8; 1024];
+        loop {
+            match client.read(&mut buf) {
+                Ok(0) => {
+                    log::info!("Depurador: cliente remoto desconectado");
+                    self.client = None;
+                    break;
+                }
+                Ok(n) => self.pending.extend_from_slice(&buf[..n]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => {
+                    log::warn!("Depurador: conexão perdida: {}", e);
+                    self.client = None;
+                    break;
+                }
+            }
+        }
+
+        let mut lines = Vec::new();
+        while let Some(end) = self.pending.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=end).collect();
+            lines.push(String::from_utf8_lossy(&line).trim_end().to_string());
+        }
+        lines
+    }
+
+    /// Envia a resposta de um comando ao cliente, terminada por uma linha
+    /// vazia. Sem bloquear: um cliente que não lê as respostas é desconectado.
+    pub fn reply(&mut self, text: &str) {
+        let Some(client) = &mut self.client else {
+            return;
+        };
+        let mut message = text.to_string();
+        if !message.is_empty() {
+            message.push('\n');
+        }
+        message.push('\n');
+        let mut pending = message.as_bytes();
+        while !pending.is_empty() {
+            match client.write(pending) {
+                Ok(0) => break,
+                Ok(n) => pending = &pending[n..],
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                    log::warn!("Depurador: cliente remoto não lê as respostas; desconectado");
+                    self.client = None;
+                    return;
+                }
+                Err(e) => {
+                    log::warn!("Depurador: conexão perdida: {}", e);
+                    self.client = None;
+                    return;
+                }
+            }
+        }
+        if !pending.is_empty() {
+            log::info!("Depurador: cliente remoto desconectado");
+            self.client = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(reply: Reply) -> String {
+        match reply {
+            Reply::Text(text) => text,
+            Reply::Quit => panic!("resposta inesperada"),
+        }
+    }
+
+    #[test]
+    fn test_search_session_finds_address() {
+        let mut memory = Memory::new(vec![0; 0x1000], 64 * 1024, 44100);
+        let mut debugger = Debugger::new();
+        memory.write8(0xFF1234, 3);
+
+        text(debugger.execute(&mut memory, "search start main 8"));
+        memory.write8(0xFF1234, 2);
+        text(debugger.execute(&mut memory, "search lt prev"));
+        assert_eq!(text(debugger.execute(&mut memory, "search eq 2")), "1 candidatos");
+        assert_eq!(text(debugger.execute(&mut memory, "search code FF1234 9")), "FF1234:09");
+
+        text(debugger.execute(&mut memory, "cheat add FF1234:09 Vidas"));
+        memory.apply_ram_cheats();
+        assert_eq!(memory.read8(0xFF1234), 9);
+        assert_eq!(debugger.execute(&mut memory, "quit"), Reply::Quit);
+    }
+
+    #[test]
+    fn test_video_options() {
+        let mut memory = Memory::new(vec![0; 0x1000], 64 * 1024, 44100);
+        let mut debugger = Debugger::new();
+
+        assert_eq!(text(debugger.execute(&mut memory, "deinterlace bob")), "Desentrelaçamento: Bob");
+        assert_eq!(text(debugger.execute(&mut memory, "curve linear")), "Curva de cor: Linear");
+        assert_eq!(text(debugger.execute(&mut memory, "cramdots on")), "Pontos de CRAM: on");
+        assert!(text(debugger.execute(&mut memory, "deinterlace scanlines")).contains("desconhecido"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_remote_control_socket() {
+        let path = std::env::temp_dir().join(format!("megastrife-debug-{}.sock", std::process::id()));
+        let mut remote = RemoteControl::bind(&path).unwrap();
+        let mut memory = Memory::new(vec![0; 0x1000], 64 * 1024, 44100);
+        let mut debugger = Debugger::new();
+        memory.write8(0xFF0010, 0x5A);
+
+        let mut host = UnixStream::connect(&path).unwrap();
+        host.write_all(b"peek FF0010\npeek").unwrap();
+        let lines = remote.poll();
+        assert_eq!(lines, vec!["peek FF0010".to_string()]);
+        remote.reply(&text(debugger.execute(&mut memory, &lines[0])));
+        // Linha incompleta fica guardada até o fim de linha chegar
+        host.write_all(b" FF0011\n").unwrap();
+        assert_eq!(remote.poll(), vec!["peek FF0011".to_string()]);
+
+        let mut reply = [0u8; 12];
+        host.read_exact(&mut reply).unwrap();
+        assert_eq!(&reply, b"FF0010: 5A\n\n");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_remote_control_drops_stalled_client() {
+        let path = std::env::temp_dir().join(format!("megastrife-debug-stall-{}.sock", std::process::id()));
+        let mut remote = RemoteControl::bind(&path).unwrap();
+        let _host = UnixStream::connect(&path).unwrap();
+        assert!(remote.poll().is_empty());
+
+        // O cliente nunca lê: o buffer do socket enche e o cliente cai,
+        // sem bloquear o quadro
+        let reply = "x".repeat(64 * 1024);
+        for _ in 0..64 {
+            remote.reply(&reply);
+        }
+        assert!(remote.client.is_none());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
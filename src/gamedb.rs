@@ -0,0 +1,396 @@
+//! Banco de dados de compatibilidade por jogo
+//!
+//! Registra o que o cabeçalho da ROM não informa (ou informa errado):
+//! mapper, tipo de save e pinagem da EEPROM, periféricos exigidos, região
+//! e correções específicas de cada jogo.
+//!
+//! As entradas são identificadas pelo número de série do cabeçalho
+//! (opcionalmente com o checksum) e, como alternativa, pelo CRC32 ou SHA-1
+//! da ROM (os mesmos hashes usados na identificação por DAT) — útil para
+//! hacks e protótipos com cabeçalho genérico. Cartuchos piratas, que
+//! costumam trazer cabeçalho zerado, também podem ser identificados pelo
+//! checksum calculado sobre a ROM.
+//!
+//! O banco embutido fica em `data/gamedb.ron`. O usuário pode ter o próprio
+//! arquivo (RON ou JSON) cujas entradas sobrescrevem campo a campo as do
+//! banco embutido.
+
+use crate::console::Region;
+use crate::memory::eeprom::{EepromChip, EepromConfig, EepromPins};
+use crate::memory::mapper::MapperType;
+use crate::memory::rom::RomHeader;
+use crate::romid::{config_dir, RomHashes};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Banco embutido no executável
+const BUILTIN_DATABASE: &str = include_str!("../data/gamedb.ron");
+
+/// Nome do arquivo de overrides do usuário
+pub const USER_DATABASE_FILE: &str = "gamedb.ron";
+
+/// Erros ao carregar um banco de dados
+#[derive(Debug, Error)]
+pub enum GameDbError {
+    #[error("Falha ao ler {0}: {1}")]
+    Io(PathBuf, #[source] std::io::Error),
+
+    #[error("Erro de sintaxe RON: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+
+    #[error("Erro de sintaxe JSON: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// Tipo de memória de save do cartucho
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SaveType {
+    /// Sem save (ignora um descritor "RA" incorreto no cabeçalho)
+    None,
+    /// SRAM com bateria (faixa lida do cabeçalho, ou 64 KB em 0x200000)
+    Sram,
+    /// EEPROM serial I2C
+    Eeprom,
+}
+
+/// Pinagens de EEPROM conhecidas, ou uma pinagem explícita
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EepromPinout {
+    Sega,
+    Acclaim16M,
+    Acclaim32M,
+    Ea,
+    Codemasters,
+    Custom(EepromPins),
+}
+
+impl EepromPinout {
+    /// Endereços e bits correspondentes
+    pub fn pins(&self) -> EepromPins {
+        match self {
+            EepromPinout::Sega => EepromPins::SEGA,
+            EepromPinout::Acclaim16M => EepromPins::ACCLAIM_16M,
+            EepromPinout::Acclaim32M => EepromPins::ACCLAIM_32M,
+            EepromPinout::Ea => EepromPins::EA,
+            EepromPinout::Codemasters => EepromPins::CODEMASTERS,
+            EepromPinout::Custom(pins) => *pins,
+        }
+    }
+}
+
+/// EEPROM de um jogo no banco de dados
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EepromEntry {
+    pub chip: EepromChip,
+    pub pinout: EepromPinout,
+}
+
+impl EepromEntry {
+    /// Configuração usada pela emulação da EEPROM
+    pub fn config(&self) -> EepromConfig {
+        EepromConfig {
+            chip: self.chip,
+            pins: self.pinout.pins(),
+        }
+    }
+}
+
+/// Periféricos que o jogo exige ou suporta
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Peripheral {
+    /// Controle de 6 botões
+    SixButtonPad,
+    /// Sega Mouse
+    Mouse,
+    /// Multitap Sega Team Player
+    TeamPlayer,
+    /// Multitap EA 4-Way Play
+    EaFourWayPlay,
+}
+
+/// Correções de compatibilidade específicas de um jogo
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Workaround {
+    /// O jogo lê o controle de 6 botões de forma errada: usar 3 botões
+    ThreeButtonOnly,
+    /// SRAM sempre visível, mesmo com ROM maior que 2 MB e sem escrita em 0xA130F1
+    SramAlwaysMapped,
+}
+
+/// Uma entrada do banco de dados
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameEntry {
+    /// Nome do jogo (informativo)
+    pub name: String,
+    /// Código do produto ("T-081326", "G-4060", ...)
+    pub serial: Option<String>,
+    /// Checksum do cabeçalho, para distinguir revisões com o mesmo código
+    pub checksum: Option<u16>,
+    /// Checksum calculado sobre a ROM (o `realchecksum` do Genesis Plus GX),
+    /// para cartuchos piratas cujo cabeçalho não identifica o jogo
+    pub rom_checksum: Option<u16>,
+    /// CRC32 da ROM inteira (alternativa ao cabeçalho)
+    pub crc32: Option<u32>,
+    /// SHA-1 da ROM inteira, em hexadecimal
+    pub sha1: Option<String>,
+    pub mapper: Option<MapperType>,
+    pub save: Option<SaveType>,
+    pub eeprom: Option<EepromEntry>,
+    pub peripherals: Vec<Peripheral>,
+    pub region: Option<Region>,
+    pub workarounds: Vec<Workaround>,
+}
+
+impl GameEntry {
+    /// Retorna true se a entrada corresponde ao cabeçalho (série e, se
+    /// informado, checksum)
+    fn matches_header(&self, header: &RomHeader) -> bool {
+        let Some(serial) = &self.serial else {
+            return false;
+        };
+        normalize_serial(serial) == normalize_serial(&header.serial)
+            && self.checksum.is_none_or(|c| c == header.checksum)
+    }
+
+    /// Aplica os campos definidos em `over` sobre esta entrada
+    pub fn merged(&self, over: &GameEntry) -> GameEntry {
+        let mut workarounds = self.workarounds.clone();
+        for w in &over.workarounds {
+            if !workarounds.contains(w) {
+                workarounds.push(*w);
+            }
+        }
+        GameEntry {
+            name: if over.name.is_empty() { self.name.clone() } else { over.name.clone() },
+            serial: over.serial.clone().or_else(|| self.serial.clone()),
+            checksum: over.checksum.or(self.checksum),
+            rom_checksum: over.rom_checksum.or(self.rom_checksum),
+            crc32: over.crc32.or(self.crc32),
+            sha1: over.sha1.clone().or_else(|| self.sha1.clone()),
+            mapper: over.mapper.or(self.mapper),
+            save: over.save.or(self.save),
+            eeprom: over.eeprom.or(self.eeprom),
+            peripherals: if over.peripherals.is_empty() { self.peripherals.clone() } else { over.peripherals.clone() },
+            region: over.region.or(self.region),
+            workarounds,
+        }
+    }
+
+    /// Retorna true se a correção está ativa para este jogo
+    pub fn has_workaround(&self, workaround: Workaround) -> bool {
+        self.workarounds.contains(&workaround)
+    }
+}
+
+/// Formato do arquivo: `(games: [ ... ])`
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct DatabaseFile {
+    games: Vec<GameEntry>,
+}
+
+/// Banco de dados de jogos com overrides do usuário
+#[derive(Debug, Default)]
+pub struct GameDatabase {
+    entries: Vec<GameEntry>,
+    overrides: Vec<GameEntry>,
+}
+
+impl GameDatabase {
+    /// Carrega o banco embutido no executável
+    pub fn builtin() -> Self {
+        let file: DatabaseFile = ron::from_str(BUILTIN_DATABASE).expect("data/gamedb.ron inválido");
+        Self {
+            entries: file.games,
+            overrides: Vec::new(),
+        }
+    }
+
+    /// Interpreta um banco em RON ou JSON (detectado pela extensão)
+    fn parse_file(path: &Path) -> Result<Vec<GameEntry>, GameDbError> {
+        let text = std::fs::read_to_string(path).map_err(|e| GameDbError::Io(path.to_path_buf(), e))?;
+        let file: DatabaseFile = match path.extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => serde_json::from_str(&text)?,
+            _ => ron::from_str(&text)?,
+        };
+        Ok(file.games)
+    }
+
+    /// Adiciona um arquivo de overrides do usuário. Entradas do usuário
+    /// têm prioridade e sobrescrevem campo a campo as do banco embutido.
+    pub fn load_overrides(&mut self, path: &Path) -> Result<usize, GameDbError> {
+        let games = Self::parse_file(path)?;
+        let count = games.len();
+        self.overrides.extend(games);
+        Ok(count)
+    }
+
+    /// Caminho padrão do arquivo de overrides
+    /// (`$XDG_CONFIG_HOME/megastrife/gamedb.ron` ou `~/.config/megastrife/gamedb.ron`)
+    pub fn default_user_path() -> Option<PathBuf> {
+        Some(config_dir()?.join(USER_DATABASE_FILE))
+    }
+
+    /// Número de entradas (embutidas + usuário)
+    pub fn len(&self) -> usize {
+        self.entries.len() + self.overrides.len()
+    }
+
+    /// Retorna true se o banco não tem nenhuma entrada
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Procura por cabeçalho e depois pelos hashes (ou checksum calculado) da ROM
+    fn find<'a>(list: &'a [GameEntry], header: &RomHeader, hashes: &RomHashes) -> Option<&'a GameEntry> {
+        list.iter().find(|e| e.matches_header(header)).or_else(|| {
+            list.iter().find(|e| {
+                e.crc32 == Some(hashes.crc32)
+                    || e.sha1.as_ref().is_some_and(|s| s.eq_ignore_ascii_case(&hashes.sha1))
+                    || e.rom_checksum == Some(hashes.checksum)
+            })
+        })
+    }
+
+    /// Procura o jogo correspondente a uma ROM. Retorna a entrada do banco
+    /// embutido combinada com a do usuário, se houver.
+    pub fn lookup(&self, header: &RomHeader, hashes: &RomHashes) -> Option<GameEntry> {
+        let base = Self::find(&self.entries, header, hashes);
+        let over = Self::find(&self.overrides, header, hashes);
+        match (base, over) {
+            (Some(base), Some(over)) => Some(base.merged(over)),
+            (Some(entry), None) | (None, Some(entry)) => Some(entry.clone()),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Reduz o campo de série ao código do produto:
+/// "GM T-081326 -00" → "T-081326", "GM 00001009-00" → "00001009".
+pub fn normalize_serial(serial: &str) -> String {
+    let mut tokens: Vec<&str> = serial.split_whitespace().collect();
+    // Tipo do software ("GM" jogo, "AI" educativo, ...)
+    if tokens.len() > 1 && tokens[0].len() == 2 {
+        tokens.remove(0);
+    }
+    let code = tokens.concat().to_ascii_uppercase();
+    // Número de revisão ("-00")
+    match code.rsplit_once('-') {
+        Some((product, rev)) if rev.len() == 2 && rev.bytes().all(|b| b.is_ascii_digit()) && product.len() > 2 => {
+            product.to_string()
+        }
+        _ => code,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::rom::compute_checksum;
+
+    fn lookup(db: &GameDatabase, data: &[u8]) -> Option<GameEntry> {
+        db.lookup(&RomHeader::parse(data), &RomHashes::compute(data))
+    }
+
+    fn rom_with_serial(serial: &[u8], checksum: u16) -> Vec<u8> {
+        let mut data = vec![0x20; 0x400];
+        data[0x180..0x180 + serial.len()].copy_from_slice(serial);
+        data[0x18E..0x190].copy_from_slice(&checksum.to_be_bytes());
+        data
+    }
+
+    #[test]
+    fn test_normalize_serial() {
+        assert_eq!(normalize_serial("GM T-081326 -00"), "T-081326");
+        assert_eq!(normalize_serial("GM 00001009-00"), "00001009");
+        assert_eq!(normalize_serial("T-12046"), "T-12046");
+        assert_eq!(normalize_serial("GM G-4060  -00"), "G-4060");
+    }
+
+    #[test]
+    fn test_builtin_database_parses_and_finds_eeprom_game() {
+        let db = GameDatabase::builtin();
+        assert!(!db.is_empty());
+
+        let game = lookup(&db, &rom_with_serial(b"GM T-081326-00", 0)).unwrap();
+        assert_eq!(game.save, Some(SaveType::Eeprom));
+        let eeprom = game.eeprom.unwrap().config();
+        assert_eq!(eeprom.chip, EepromChip::C24C02);
+        assert_eq!(eeprom.pins, EepromPins::ACCLAIM_16M);
+    }
+
+    #[test]
+    fn test_builtin_database_finds_unlicensed_boards() {
+        let db = GameDatabase::builtin();
+        let boards = [
+            (0xFC84u16, MapperType::Radica),
+            (0x32E9, MapperType::Radica),
+            (0x507C, MapperType::LionKing3),
+            (0x7D6E, MapperType::LionKing3),
+            (0xF894, MapperType::Smw64),
+            (0x8EC8, MapperType::SquirrelKing),
+        ];
+        for (checksum, mapper) in boards {
+            // Cabeçalho zerado, como nos cartuchos piratas: a última palavra
+            // ajusta a soma para o checksum procurado
+            let mut data = vec![0; 0x400];
+            let fix = checksum.wrapping_sub(compute_checksum(&data));
+            data[0x3FE..].copy_from_slice(&fix.to_be_bytes());
+            assert_eq!(lookup(&db, &data).unwrap().mapper, Some(mapper), "checksum {checksum:04X}");
+        }
+
+        let game = lookup(&db, &rom_with_serial(b"GM SF-001-00", 0)).unwrap();
+        assert_eq!(game.mapper, Some(MapperType::Sf001));
+    }
+
+    #[test]
+    fn test_checksum_and_crc_matching() {
+        let mut db = GameDatabase::default();
+        let data = rom_with_serial(b"GM T-99999-00", 0x1234);
+        db.entries.push(GameEntry {
+            name: "Revisão A".into(),
+            serial: Some("T-99999".into()),
+            checksum: Some(0xBEEF),
+            ..Default::default()
+        });
+        assert!(lookup(&db, &data).is_none());
+
+        db.entries.push(GameEntry {
+            name: "Pelo CRC".into(),
+            crc32: Some(crc32fast::hash(&data)),
+            ..Default::default()
+        });
+        assert_eq!(lookup(&db, &data).unwrap().name, "Pelo CRC");
+
+        db.entries.pop();
+        db.entries.push(GameEntry {
+            name: "Pelo SHA-1".into(),
+            sha1: Some(RomHashes::compute(&data).sha1.to_uppercase()),
+            ..Default::default()
+        });
+        assert_eq!(lookup(&db, &data).unwrap().name, "Pelo SHA-1");
+    }
+
+    #[test]
+    fn test_user_overrides_merge_field_by_field() {
+        let dir = std::env::temp_dir().join(format!("megastrife-gamedb-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("overrides.json");
+        std::fs::write(
+            &path,
+            r#"{ "games": [ { "serial": "T-081326", "region": "EuropePal", "workarounds": ["ThreeButtonOnly"] } ] }"#,
+        )
+        .unwrap();
+
+        let mut db = GameDatabase::builtin();
+        assert_eq!(db.load_overrides(&path).unwrap(), 1);
+        let game = lookup(&db, &rom_with_serial(b"GM T-081326-00", 0)).unwrap();
+        assert_eq!(game.region, Some(Region::EuropePal));
+        assert_eq!(game.save, Some(SaveType::Eeprom));
+        assert!(game.has_workaround(Workaround::ThreeButtonOnly));
+        assert!(!game.name.is_empty());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
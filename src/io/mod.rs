@@ -1,4 +1,6 @@
 // src/io/mod.rs
+pub mod sms;
+
 use std::sync::{Arc, Mutex};
 use crate::cpu::z80::Z80;
 use crate::sound::Sound;
@@ -0,0 +1,116 @@
+//! Portas de controle do Master System
+//!
+//! - 0x3F (escrita): controle de I/O — direção (bits 0–3) e nível de saída
+//!   (bits 4–7) dos pinos TR e TH das duas portas;
+//! - 0xDC (leitura): controle 1 completo + cima/baixo do controle 2;
+//! - 0xDD (leitura): resto do controle 2, botão de reset e os pinos TH.
+//!
+//! Todos os botões são ativos em nível baixo. Consoles de exportação
+//! devolvem em TH o nível programado como saída; os japoneses devolvem o
+//! inverso, e é assim que os jogos detectam a região.
+
+use bitflags::bitflags;
+
+bitflags! {
+    /// Botões de um controle do Master System
+    #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+    pub struct SmsButtons: u8 {
+        const UP       = 1 << 0;
+        const DOWN     = 1 << 1;
+        const LEFT     = 1 << 2;
+        const RIGHT    = 1 << 3;
+        const BUTTON_1 = 1 << 4;
+        const BUTTON_2 = 1 << 5;
+    }
+}
+
+/// Estado das portas de controle
+#[derive(Debug, Clone)]
+pub struct SmsPorts {
+    /// Botões pressionados em cada controle
+    pub pads: [SmsButtons; 2],
+    /// Botão de reset do console pressionado
+    pub reset_pressed: bool,
+    /// Último valor escrito em 0x3F
+    io_control: u8,
+    overseas: bool,
+}
+
+impl SmsPorts {
+    pub fn new(overseas: bool) -> Self {
+        Self {
+            pads: [SmsButtons::empty(); 2],
+            reset_pressed: false,
+            io_control: 0xFF,
+            overseas,
+        }
+    }
+
+    /// Escrita na porta de controle de I/O (0x3F)
+    pub fn write_control(&mut self, value: u8) {
+        self.io_control = value;
+    }
+
+    /// Nível lido em TH da porta A (`port` = 0) ou B (`port` = 1)
+    fn th_level(&self, port: u8) -> bool {
+        let is_output = self.io_control & (0x02 << (port * 2)) == 0;
+        let level = self.io_control & (0x20 << (port * 2)) != 0;
+        match (is_output, self.overseas) {
+            (false, _) => true,
+            (true, true) => level,
+            (true, false) => !level,
+        }
+    }
+
+    /// Leitura de 0xDC: controle 1 (bits 0–5) e cima/baixo do controle 2
+    pub fn read_port_a(&self) -> u8 {
+        let pressed = self.pads[0].bits() | (self.pads[1].bits() & 0x03) << 6;
+        !pressed
+    }
+
+    /// Leitura de 0xDD: esquerda/direita/botões do controle 2, reset e TH
+    pub fn read_port_b(&self) -> u8 {
+        let mut value = !(self.pads[1].bits() >> 2) & 0x0F;
+        if !self.reset_pressed {
+            value |= 0x10;
+        }
+        value |= 0x20;
+        if self.th_level(0) {
+            value |= 0x40;
+        }
+        if self.th_level(1) {
+            value |= 0x80;
+        }
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_buttons_are_active_low() {
+        let mut ports = SmsPorts::new(true);
+        assert_eq!(ports.read_port_a(), 0xFF);
+        assert_eq!(ports.read_port_b(), 0xFF);
+
+        ports.pads[0] = SmsButtons::UP | SmsButtons::BUTTON_1;
+        ports.pads[1] = SmsButtons::DOWN | SmsButtons::BUTTON_2;
+        ports.reset_pressed = true;
+        assert_eq!(ports.read_port_a(), !0x91);
+        assert_eq!(ports.read_port_b(), 0xE7);
+    }
+
+    #[test]
+    fn test_th_output_reveals_region() {
+        // TH das duas portas como saída, nível alto
+        let mut export = SmsPorts::new(true);
+        export.write_control(0xF5);
+        assert_eq!(export.read_port_b() & 0xC0, 0xC0);
+
+        let mut japan = SmsPorts::new(false);
+        japan.write_control(0xF5);
+        assert_eq!(japan.read_port_b() & 0xC0, 0x00);
+    }
+}
@@ -1,25 +1,495 @@
 // src/main.rs
+mod cheats;
+mod console;
 mod cpu;
+mod debugger;
+mod gamedb;
+mod megacd;
 mod sound;
 mod vdp;
 mod io;
 mod memory;
+mod ramsearch;
+mod romid;
 
-use std::sync::{Arc, Mutex};
-use cpu::z80::Z80;
-use sound::Sound;
-use vdp::Vdp;
-use io::Io;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use anyhow::{anyhow, bail, Context};
+use cheats::CheatEngine;
+use debugger::{Debugger, Reply};
+#[cfg(unix)]
+use debugger::RemoteControl;
+use console::{ConsoleModel, Region};
+use gamedb::{GameDatabase, GameEntry};
+use megacd::disc::{self, Disc};
+use megacd::MegaCd;
+use memory::flashcart::UsbLink;
+use memory::lockon::{self, LockOn};
+use memory::mapper::Mapper;
+use memory::{loader, patch};
+use memory::Memory;
+use memory::rom::{self, HeaderIssue, Rom, RomHeader};
+use memory::sms::MasterSystem;
+use memory::tmss::{HardwareRevision, Tmss};
+use romid::{DatCollection, RomHashes, RomIdentity};
+use vdp::{ColorCurve, DeinterlaceMode};
 
-fn main() {
-    let sound = Arc::new(Mutex::new(Sound::new()));
-    let z80 = Arc::new(Mutex::new(Z80::new(sound.clone())));
-    let vdp = Arc::new(Mutex::new(Vdp::new(false)));
+const RAM_SIZE: usize = 64 * 1024;
+const SAMPLE_RATE: u32 = 44100;
+/// Intervalo (em quadros) entre gravações automáticas da SRAM (~5 s a 60 Hz)
+const SRAM_FLUSH_INTERVAL: u32 = 300;
+/// Intervalo (em quadros) entre verificações do arquivo de trapaças (~1 s)
+const CHEAT_RELOAD_INTERVAL: u32 = 60;
+const APP_NAME: &str = "megastrife";
 
-    let mut io = Io::new(z80.clone(), vdp.clone(), sound.clone());
+/// Título da janela: nome canônico do DAT ou, na falta dele, o do cabeçalho
+fn window_title(header: &RomHeader, identity: Option<&RomIdentity>) -> String {
+    let game = match identity {
+        Some(id) => id.summary(),
+        None if !header.overseas_name.is_empty() => header.overseas_name.clone(),
+        None => header.domestic_name.clone(),
+    };
+    format!("{} - {}", APP_NAME, game)
+}
+
+/// Informações da ROM exibidas por `--info`
+fn rom_info(
+    header: &RomHeader,
+    issues: &[HeaderIssue],
+    hashes: &RomHashes,
+    identity: Option<&RomIdentity>,
+    game: &GameEntry,
+) -> Vec<String> {
+    let mut info = vec![
+        format!("Nome (doméstico): {}", header.domestic_name),
+        format!("Nome (exportação): {}", header.overseas_name),
+        format!("Série: {}  Checksum: 0x{:04X}  Região: {}", header.serial, header.checksum, header.region),
+        format!("Tamanho: {} bytes", hashes.size),
+        format!("Hashes: {}", hashes),
+    ];
+    if issues.is_empty() {
+        info.push("Cabeçalho: OK".to_string());
+    }
+    info.extend(issues.iter().map(|issue| format!("Cabeçalho: {}", issue)));
+    match identity {
+        Some(id) => {
+            info.push(format!("DAT: {} ({})", id.name, id.dat));
+            info.push(format!(
+                "Título: {}  Revisão: {}  Dump: {}{}",
+                id.title,
+                id.revision.as_deref().unwrap_or("-"),
+                id.status,
+                if id.verified { " (verificado)" } else { "" }
+            ));
+        }
+        None => info.push("DAT: ROM não encontrada nos DATs carregados".to_string()),
+    }
+    if !game.name.is_empty() {
+        info.push(format!("Banco de jogos: {}", game.name));
+    }
+    info
+}
+
+/// `megastrife fix-header <rom> [-o <saída>]`: grava uma cópia da ROM com
+/// checksum e faixa de endereços do cabeçalho corrigidos
+fn fix_header_command(mut args: impl Iterator<Item = String>) -> anyhow::Result<()> {
+    let mut rom_path: Option<PathBuf> = None;
+    let mut output: Option<PathBuf> = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" | "--output" => output = args.next().map(PathBuf::from),
+            _ => rom_path = Some(PathBuf::from(arg)),
+        }
+    }
+    let rom_path = rom_path.context("uso: megastrife fix-header <rom> [-o <saída>]")?;
+    let output = output.unwrap_or_else(|| rom_path.with_extension("fixed.bin"));
+
+    let loaded = loader::load_file(&rom_path, &[])
+        .with_context(|| format!("não foi possível carregar a ROM {}", rom_path.display()))?;
+    let mut data = loaded.rom.data().to_vec();
+    if data.len() < rom::CHECKSUM_START {
+        bail!(
+            "{} tem só {} bytes: pequena demais para ter cabeçalho (mínimo 0x{:X})",
+            rom_path.display(),
+            data.len(),
+            rom::CHECKSUM_START
+        );
+    }
+    let fixed = rom::fix_header(&mut data);
+    if fixed.is_empty() {
+        println!("Cabeçalho já está correto; nada a fazer");
+        return Ok(());
+    }
+    for issue in &fixed {
+        println!("Corrigido: {}", issue);
+    }
+    std::fs::write(&output, &data).with_context(|| format!("não foi possível gravar {}", output.display()))?;
+    println!("ROM corrigida gravada em {}", output.display());
+    Ok(())
+}
+
+/// Data de modificação do arquivo de trapaças (para recarregar quando o
+/// usuário liga/desliga um código editando o arquivo)
+fn modified_time(path: &std::path::Path) -> Option<std::time::SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+/// Modo Master System (Power Base Converter): o Z80 é a CPU principal e o
+/// PSG, a única fonte de som
+fn run_master_system(rom: Rom, rom_path: &std::path::Path, console: ConsoleModel) -> anyhow::Result<()> {
+    log::info!("Master System ({:?}, {})", console.region, if console.is_pal() { "PAL" } else { "NTSC" });
+    let mut sms = MasterSystem::new(rom, SAMPLE_RATE, console);
+    let save_path = sms
+        .attach_save_ram(rom_path)
+        .context("não foi possível carregar o arquivo de RAM do cartucho")?;
+    log::info!("RAM do cartucho associada a {}", save_path.display());
+
+    let running = Arc::new(AtomicBool::new(true));
+    {
+        let running = running.clone();
+        ctrlc::set_handler(move || running.store(false, Ordering::SeqCst))
+            .context("não foi possível instalar o tratador de Ctrl+C")?;
+    }
+
+    let mut frames = 0u32;
+    while running.load(Ordering::SeqCst) {
+        sms.run_frame();
+        frames = frames.wrapping_add(1);
+        if frames % SRAM_FLUSH_INTERVAL == 0 {
+            if let Err(e) = sms.flush_save_ram() {
+                log::error!("Falha ao gravar a RAM do cartucho: {}", e);
+            }
+        }
+        std::thread::sleep(std::time::Duration::from_millis(16));
+    }
+
+    sms.flush_save_ram().context("não foi possível gravar a RAM do cartucho ao sair")?;
+    Ok(())
+}
+
+fn main() -> anyhow::Result<()> {
+    env_logger::init();
+
+    if std::env::args().nth(1).as_deref() == Some("fix-header") {
+        return fix_header_command(std::env::args().skip(2));
+    }
+
+    let mut rom_path: Option<PathBuf> = None;
+    let mut region: Option<Region> = None;
+    let mut revision = HardwareRevision::default();
+    let mut tmss_bios: Option<PathBuf> = None;
+    let mut gamedb_path: Option<PathBuf> = None;
+    let mut dat_paths: Vec<PathBuf> = Vec::new();
+    let mut patch_paths: Vec<PathBuf> = Vec::new();
+    let mut cheats_path: Option<PathBuf> = None;
+    let mut lock_on_path: Option<PathBuf> = None;
+    let mut sk2_patch_path: Option<PathBuf> = None;
+    let mut usb_socket: Option<PathBuf> = None;
+    let mut usb_in: Option<PathBuf> = None;
+    let mut usb_out: Option<PathBuf> = None;
+    let mut cd_bios: Option<PathBuf> = None;
+    let mut cd_path: Option<PathBuf> = None;
+    let mut show_info = false;
+    let mut debug = false;
+    #[cfg(unix)]
+    let mut debug_socket: Option<PathBuf> = None;
+    let mut deinterlace = DeinterlaceMode::default();
+    let mut color_curve = ColorCurve::default();
+    let mut cram_dots = false;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--region" => {
+                let value = args.next().context("--region requer um valor (jp, us, eu, asia)")?;
+                region = Some(value.parse().map_err(|e: String| anyhow!(e))?);
+            }
+            "--model" => {
+                let value = args.next().context("--model requer um valor (model1, model1-va6, model2, model3)")?;
+                revision = value.parse().map_err(|e: String| anyhow!(e))?;
+            }
+            "--tmss-bios" => tmss_bios = args.next().map(PathBuf::from),
+            "--gamedb" => gamedb_path = args.next().map(PathBuf::from),
+            "--dat" => dat_paths.extend(args.next().map(PathBuf::from)),
+            "--patch" => patch_paths.extend(args.next().map(PathBuf::from)),
+            "--cheats" => cheats_path = args.next().map(PathBuf::from),
+            "--lock-on" => lock_on_path = args.next().map(PathBuf::from),
+            "--sk2-patch" => sk2_patch_path = args.next().map(PathBuf::from),
+            "--usb-socket" => usb_socket = args.next().map(PathBuf::from),
+            "--usb-in" => usb_in = args.next().map(PathBuf::from),
+            "--usb-out" => usb_out = args.next().map(PathBuf::from),
+            "--cd-bios" => cd_bios = args.next().map(PathBuf::from),
+            "--cd" => cd_path = args.next().map(PathBuf::from),
+            "--info" => show_info = true,
+            "--debug" => debug = true,
+            #[cfg(unix)]
+            "--debug-socket" => debug_socket = args.next().map(PathBuf::from),
+            "--deinterlace" => {
+                let value = args.next().context("--deinterlace requer um valor (weave, bob, blend, native)")?;
+                deinterlace = value.parse().map_err(|e: String| anyhow!(e))?;
+            }
+            "--color-curve" => {
+                let value = args.next().context("--color-curve requer um valor (linear, md)")?;
+                color_curve = value.parse().map_err(|e: String| anyhow!(e))?;
+            }
+            "--cram-dots" => cram_dots = true,
+            _ => rom_path = Some(PathBuf::from(arg)),
+        }
+    }
+
+    let rom_path = rom_path.context(
+        "uso: megastrife <rom (.bin, .gen, .md, .smd, .sms, .zip) | disco (.cue, .iso)> [--region jp|us|eu|asia] [--model <modelo>] [--tmss-bios <arquivo>] [--gamedb <arquivo>] [--dat <arquivo>] [--patch <ips|bps|ups>] [--cheats <arquivo>] [--lock-on <rom>] [--sk2-patch <arquivo>] [--usb-socket <caminho>] [--usb-in <arquivo> --usb-out <arquivo>] [--cd-bios <arquivo>] [--cd <imagem>] [--deinterlace weave|bob|blend|native] [--color-curve linear|md] [--cram-dots] [--info] [--debug] [--debug-socket <caminho>]\n       megastrife fix-header <rom> [-o <saída>]",
+    )?;
 
-    loop {
-        io.tick();
+    // Mega-CD: o disco é a própria "ROM" (boot pelo CD) ou vem com --cd
+    let cd_boot = disc::is_disc_image(&rom_path);
+    let disc_path = if cd_boot { Some(&rom_path) } else { cd_path.as_ref() };
+    let mut disc = match disc_path {
+        Some(path) => {
+            Some(Disc::open(path).with_context(|| format!("não foi possível abrir o disco {}", path.display()))?)
+        }
+        None => None,
+    };
+    if disc.is_some() && cd_bios.is_none() {
+        bail!("o Mega-CD requer a BIOS do usuário (--cd-bios <arquivo>)");
+    }
+
+    let rom = match &mut disc {
+        // Sem cartucho: o setor 0 do disco tem o mesmo cabeçalho da ROM
+        Some(disc) if cd_boot => Rom::new(disc.header_sector().context("não foi possível ler o cabeçalho do disco")?),
+        _ => {
+            // Patches: os da linha de comando ou, na falta deles, os ao lado da ROM
+            if patch_paths.is_empty() {
+                patch_paths = patch::sidecar_patches(&rom_path);
+            }
+            let loaded = loader::load_file(&rom_path, &patch_paths)
+                .with_context(|| format!("não foi possível carregar a ROM {}", rom_path.display()))?;
+            if let Some(entry) = &loaded.archive_entry {
+                log::info!("ROM lida de {} dentro de {}", entry, rom_path.display());
+            }
+            if !loaded.patches.is_empty() {
+                log::info!("Patches aplicados: {}", loaded.patches.join(", "));
+            }
+            if loaded.master_system {
+                let console = ConsoleModel::new(region.unwrap_or_default(), revision);
+                return run_master_system(loaded.rom, &rom_path, console);
+            }
+            loaded.rom
+        }
+    };
+
+    // Banco de jogos: embutido + overrides do usuário (--gamedb ou o arquivo padrão)
+    let mut gamedb = GameDatabase::builtin();
+    let user_db = gamedb_path.clone().or_else(GameDatabase::default_user_path);
+    if let Some(path) = user_db.filter(|p| gamedb_path.is_some() || p.exists()) {
+        let count = gamedb
+            .load_overrides(&path)
+            .with_context(|| format!("não foi possível carregar o banco de jogos {}", path.display()))?;
+        log::info!("{} entradas do usuário carregadas de {}", count, path.display());
+    }
+
+    // Identificação por DAT (diretório padrão + --dat)
+    let mut dats = DatCollection::new();
+    if let Some(dir) = DatCollection::default_dir() {
+        dats.load_dir(&dir)?;
+    }
+    for path in &dat_paths {
+        dats.load_file(path)?;
+    }
+
+    let header = rom.header().clone();
+    // O cabeçalho de um disco não tem checksum nem tamanho de ROM
+    let header_issues = if cd_boot { Vec::new() } else { rom.verify_header() };
+    for issue in &header_issues {
+        log::warn!("Cabeçalho: {}", issue);
+    }
+    let hashes = RomHashes::compute(rom.data());
+    let identity = dats.identify(&hashes);
+    let game = gamedb.lookup(&header, &hashes).unwrap_or_default();
+    if !game.name.is_empty() {
+        log::info!("Jogo reconhecido: {} ({:?})", game.name, game);
+    }
+
+    if show_info {
+        for line in rom_info(&header, &header_issues, &hashes, identity.as_ref(), &game) {
+            println!("{}", line);
+        }
+        return Ok(());
+    }
+    log::info!("{}", window_title(&header, identity.as_ref()));
+
+    let console = ConsoleModel::detect(&header, revision, region.or(game.region));
+    let mut memory = match &lock_on_path {
+        // Sonic & Knuckles com outro cartucho encaixado
+        Some(path) => {
+            let top = loader::load_file(path, &[])
+                .with_context(|| format!("não foi possível carregar a ROM encaixada {}", path.display()))?
+                .rom;
+            let patch_path = sk2_patch_path
+                .clone()
+                .or_else(|| Some(lockon::default_patch_path(&rom_path)).filter(|p| p.exists()));
+            let patch = match &patch_path {
+                Some(p) => {
+                    let loaded = loader::load_file(p, &[]).with_context(|| format!("não foi possível ler {}", p.display()))?;
+                    Some(loaded.rom)
+                }
+                None => None,
+            };
+            let lock_on = LockOn::new(&rom, top, patch)?;
+            log::info!("Lock-on: {:?} com {}", lock_on.kind, path.display());
+            Memory::with_mapper(Mapper::with_lock_on(rom, lock_on), RAM_SIZE, SAMPLE_RATE, console)
+        }
+        None => Memory::with_console(rom, RAM_SIZE, SAMPLE_RATE, console, &game),
+    };
+
+    if let Some(path) = &tmss_bios {
+        let boot_rom = Tmss::load_boot_rom(path)
+            .with_context(|| format!("não foi possível ler a BIOS TMSS {}", path.display()))?;
+        // A BIOS TMSS só existe em consoles com TMSS
+        let revision = if revision.has_tmss() { revision } else { HardwareRevision::Model1Va6 };
+        memory.configure_tmss(revision, Some(boot_rom));
+    }
+
+    if let Some(path) = &cd_bios {
+        let bios = MegaCd::load_bios(path)
+            .with_context(|| format!("não foi possível ler a BIOS do Mega-CD {}", path.display()))?;
+        let mut mega_cd = MegaCd::new(bios, disc.take(), memory.console().master_clock());
+        mega_cd.cd_boot = cd_boot;
+        memory.attach_mega_cd(mega_cd);
+        log::info!("Mega-CD conectado ({})", if cd_boot { "boot pelo disco" } else { "com cartucho" });
+        log::warn!("Mega-CD: o sub-68000 ainda não é emulado; o disco não passa da BIOS");
+    }
+
+    if let Some(path) = memory
+        // No lock-on a SRAM é do cartucho de cima
+        .attach_save_ram(lock_on_path.as_ref().unwrap_or(&rom_path))
+        .context("não foi possível carregar o arquivo de SRAM")?
+    {
+        log::info!("SRAM do cartucho associada a {}", path.display());
+    }
+
+    // Saída de vídeo; ajustável depois pelo depurador
+    {
+        let mut vdp = memory.vdp();
+        vdp.set_deinterlace_mode(deinterlace);
+        vdp.set_color_curve(color_curve);
+        vdp.set_cram_dots(cram_dots);
+    }
+
+    // USB do flash-cart: socket Unix ou par de arquivos
+    let usb_link = match (&usb_socket, &usb_in, &usb_out) {
+        (Some(path), _, _) => Some(
+            UsbLink::socket(path).with_context(|| format!("não foi possível criar o socket USB {}", path.display()))?,
+        ),
+        (None, Some(input), Some(output)) => Some(
+            UsbLink::files(input, output)
+                .with_context(|| format!("não foi possível abrir {} e {}", input.display(), output.display()))?,
+        ),
+        (None, None, None) => None,
+        _ => bail!("--usb-in e --usb-out devem ser usados juntos"),
+    };
+    if let Some(link) = usb_link {
+        if memory.attach_usb(link) {
+            log::info!("USB do flash-cart conectada ao host");
+        } else {
+            log::warn!("O cartucho não é um flash-cart; USB ignorada");
+        }
+    }
+
+    // Trapaças: --cheats ou <rom>.cheats.ron, se existir
+    let cheats_path = cheats_path.or_else(|| Some(CheatEngine::path_for(&rom_path)).filter(|p| p.exists()));
+    let mut cheats_modified = None;
+    if let Some(path) = &cheats_path {
+        let cheats = CheatEngine::load(path).with_context(|| format!("não foi possível carregar as trapaças {}", path.display()))?;
+        log::info!("{} trapaças carregadas de {}", cheats.len(), path.display());
+        memory.set_cheats(cheats);
+        cheats_modified = modified_time(path);
+    }
+
+    let running = Arc::new(AtomicBool::new(true));
+    {
+        let running = running.clone();
+        ctrlc::set_handler(move || running.store(false, Ordering::SeqCst))
+            .context("não foi possível instalar o tratador de Ctrl+C")?;
+    }
+
+    // REPL do depurador: comandos lidos do terminal ou do socket de controle
+    // remoto e executados entre quadros
+    let mut debugger = Debugger::new();
+    let commands = debug.then(debugger::spawn_stdin_reader);
+    #[cfg(unix)]
+    let mut remote = match &debug_socket {
+        Some(path) => Some(
+            RemoteControl::bind(path)
+                .with_context(|| format!("não foi possível criar o socket do depurador {}", path.display()))?,
+        ),
+        None => None,
+    };
+
+    let mut frames = 0u32;
+    let mut locked_up_reported = false;
+    while running.load(Ordering::SeqCst) {
+        for line in commands.iter().flat_map(|rx| rx.try_iter()) {
+            match debugger.execute(&mut memory, &line) {
+                Reply::Text(text) if !text.is_empty() => println!("{}", text),
+                Reply::Text(_) => {}
+                Reply::Quit => running.store(false, Ordering::SeqCst),
+            }
+        }
+        #[cfg(unix)]
+        if let Some(remote) = remote.as_mut() {
+            for line in remote.poll() {
+                match debugger.execute(&mut memory, &line) {
+                    Reply::Text(text) => remote.reply(&text),
+                    Reply::Quit => running.store(false, Ordering::SeqCst),
+                }
+            }
+        }
+        // Ainda não há núcleo do 68000: o quadro avança em fatias de uma
+        // linha, com o SVP e o Mega-CD em passo com o barramento e o VDP
+        let lines = memory.console().lines_per_frame() as u32;
+        let m68k_cycles = memory.console().m68k_cycles_per_frame();
+        for line in 0..lines {
+            memory.tick();
+            // 68000 travado pelo TMSS: como no console, nada do lado da CPU
+            // avança até o reset; o restante do hardware continua rodando
+            if memory.cpu_locked_up() {
+                continue;
+            }
+            let slice = m68k_cycles * (line + 1) / lines - m68k_cycles * line / lines;
+            memory.run_cartridge_chips(slice);
+        }
+        if memory.cpu_locked_up() {
+            if !locked_up_reported {
+                log::error!("68000 travado: acesso ao VDP antes do handshake TMSS \"SEGA\"");
+                locked_up_reported = true;
+            }
+        } else {
+            memory.apply_ram_cheats();
+        }
+        frames = frames.wrapping_add(1);
+        if let Some(path) = cheats_path.as_ref().filter(|_| frames % CHEAT_RELOAD_INTERVAL == 0) {
+            let modified = modified_time(path);
+            if modified != cheats_modified {
+                cheats_modified = modified;
+                match CheatEngine::load(path) {
+                    Ok(cheats) => {
+                        log::info!("Trapaças recarregadas de {}", path.display());
+                        memory.set_cheats(cheats);
+                    }
+                    Err(e) => log::error!("Trapaças não recarregadas: {}", e),
+                }
+            }
+        }
+        if frames % SRAM_FLUSH_INTERVAL == 0 {
+            if let Err(e) = memory.flush_save_ram() {
+                log::error!("Falha ao gravar SRAM: {}", e);
+            }
+        }
         std::thread::sleep(std::time::Duration::from_millis(16));
     }
+
+    memory.flush_save_ram().context("não foi possível gravar a SRAM ao sair")?;
+    Ok(())
 }
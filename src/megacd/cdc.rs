@@ -0,0 +1,322 @@
+//! Controlador de CD (CDC, Sanyo LC8951)
+//!
+//! Recebe os setores brutos do drive (ver `cdd`), guarda cabeçalho e dados
+//! num buffer circular de 16 KB e os transfere para o destino escolhido no
+//! gate array: leitura pelo 68000 principal ou pelo sub (palavra a palavra
+//! em 0xA12008 / 0xFF8008) ou DMA para a RAM de onda do PCM, a PRG-RAM ou a
+//! word RAM.
+//!
+//! Os registradores internos são acessados por um registrador de endereço
+//! (0xFF8005) com auto-incremento e um de dados (0xFF8007). Os bits de
+//! IFSTAT são ativos em nível baixo.
+
+/// Tamanho do buffer do CDC
+pub const CDC_BUFFER_SIZE: usize = 0x4000;
+
+// IFSTAT (ativos em nível baixo)
+pub const IFSTAT_DECI: u8 = 0x20;
+pub const IFSTAT_DTEI: u8 = 0x40;
+pub const IFSTAT_DTBSY: u8 = 0x08;
+pub const IFSTAT_DTEN: u8 = 0x02;
+
+// IFCTRL
+const IFCTRL_DTEIEN: u8 = 0x40;
+const IFCTRL_DECIEN: u8 = 0x20;
+const IFCTRL_DOUTEN: u8 = 0x02;
+
+// CTRL0
+const CTRL0_DECEN: u8 = 0x80;
+const CTRL0_WRRQ: u8 = 0x04;
+
+/// Destino das transferências (bits DD de 0xFF8004)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CdcDestination {
+    MainCpu,
+    SubCpu,
+    Pcm,
+    PrgRam,
+    WordRam,
+    /// Códigos não usados: a transferência não vai a lugar nenhum
+    None,
+}
+
+impl CdcDestination {
+    pub fn from_bits(bits: u8) -> Self {
+        match bits & 0x07 {
+            2 => Self::MainCpu,
+            3 => Self::SubCpu,
+            4 => Self::Pcm,
+            5 => Self::PrgRam,
+            7 => Self::WordRam,
+            _ => Self::None,
+        }
+    }
+
+    /// Destinos lidos pela CPU em vez de DMA
+    pub fn is_host(self) -> bool {
+        matches!(self, Self::MainCpu | Self::SubCpu)
+    }
+}
+
+/// Estado do LC8951
+#[derive(Debug, Clone)]
+pub struct Cdc {
+    pub ram: Vec<u8>,
+    /// Registrador de endereço (0–15)
+    pub address: u8,
+    pub destination: CdcDestination,
+    /// Dados prontos para a CPU (DSR)
+    pub data_ready: bool,
+    /// Fim da transferência (EDT)
+    pub end_of_transfer: bool,
+    /// Interrupção de nível 5 pendente
+    pub irq: bool,
+    ifstat: u8,
+    ifctrl: u8,
+    /// Contador de bytes (número de bytes - 1)
+    dbc: u16,
+    /// Endereço de leitura no buffer
+    dac: u16,
+    /// Ponteiro do cabeçalho do último setor
+    pt: u16,
+    /// Endereço de escrita no buffer
+    wa: u16,
+    ctrl: [u8; 2],
+    head: [u8; 4],
+    stat: [u8; 4],
+}
+
+impl Default for Cdc {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Cdc {
+    pub fn new() -> Self {
+        let mut cdc = Self {
+            ram: vec![0; CDC_BUFFER_SIZE],
+            address: 0,
+            destination: CdcDestination::None,
+            data_ready: false,
+            end_of_transfer: false,
+            irq: false,
+            ifstat: 0xFF,
+            ifctrl: 0,
+            dbc: 0,
+            dac: 0,
+            pt: 0,
+            wa: 0,
+            ctrl: [0; 2],
+            head: [0; 4],
+            stat: [0; 4],
+        };
+        cdc.reset();
+        cdc
+    }
+
+    /// Registrador RESET (15) ou reset do gate array
+    pub fn reset(&mut self) {
+        self.ifstat = 0xFF;
+        self.ifctrl = 0;
+        self.ctrl = [0; 2];
+        self.stat = [0, 0, 0, 0x80];
+        self.data_ready = false;
+        self.end_of_transfer = false;
+        self.irq = false;
+    }
+
+    /// Transferência em andamento
+    pub fn transfer_active(&self) -> bool {
+        self.ifstat & IFSTAT_DTBSY == 0
+    }
+
+    fn update_irq(&mut self) {
+        let decoded = self.ifctrl & IFCTRL_DECIEN != 0 && self.ifstat & IFSTAT_DECI == 0;
+        let transferred = self.ifctrl & IFCTRL_DTEIEN != 0 && self.ifstat & IFSTAT_DTEI == 0;
+        self.irq = decoded || transferred;
+    }
+
+    // =====================================================
+    // REGISTRADORES
+    // =====================================================
+
+    /// Leitura do registrador apontado pelo endereço (0xFF8007)
+    pub fn read_register(&mut self) -> u8 {
+        let reg = self.address & 0x0F;
+        self.address = (reg + 1) & 0x0F;
+        match reg {
+            0x0 => 0xFF,
+            0x1 => self.ifstat,
+            0x2 => self.dbc as u8,
+            0x3 => (self.dbc >> 8) as u8 & 0x0F,
+            0x4..=0x7 => self.head[reg as usize - 4],
+            0x8 => self.pt as u8,
+            0x9 => (self.pt >> 8) as u8,
+            0xA => self.wa as u8,
+            0xB => (self.wa >> 8) as u8,
+            0xC..=0xE => self.stat[reg as usize - 0xC],
+            _ => {
+                // Ler STAT3 reconhece a interrupção de decodificação
+                self.ifstat |= IFSTAT_DECI;
+                self.update_irq();
+                self.stat[3]
+            }
+        }
+    }
+
+    /// Escrita no registrador apontado pelo endereço (0xFF8007)
+    pub fn write_register(&mut self, value: u8) {
+        let reg = self.address & 0x0F;
+        self.address = (reg + 1) & 0x0F;
+        match reg {
+            0x1 => {
+                self.ifctrl = value;
+                if value & IFCTRL_DOUTEN == 0 {
+                    // Saída desligada: aborta a transferência
+                    self.ifstat |= IFSTAT_DTBSY | IFSTAT_DTEN;
+                }
+                self.update_irq();
+            }
+            0x2 => self.dbc = (self.dbc & 0xFF00) | value as u16,
+            0x3 => self.dbc = (self.dbc & 0x00FF) | ((value as u16 & 0x0F) << 8),
+            0x4 => self.dac = (self.dac & 0xFF00) | value as u16,
+            0x5 => self.dac = (self.dac & 0x00FF) | ((value as u16) << 8),
+            // DTTRG: inicia a transferência
+            0x6 if self.ifctrl & IFCTRL_DOUTEN != 0 => {
+                self.ifstat &= !(IFSTAT_DTBSY | IFSTAT_DTEN);
+                self.end_of_transfer = false;
+                self.data_ready = self.destination.is_host();
+            }
+            // DTACK: reconhece o fim da transferência
+            0x7 => {
+                self.ifstat |= IFSTAT_DTEI;
+                self.update_irq();
+            }
+            0x8 => self.wa = (self.wa & 0xFF00) | value as u16,
+            0x9 => self.wa = (self.wa & 0x00FF) | ((value as u16) << 8),
+            0xA | 0xB => self.ctrl[reg as usize - 0xA] = value,
+            0xC => self.pt = (self.pt & 0xFF00) | value as u16,
+            0xD => self.pt = (self.pt & 0x00FF) | ((value as u16) << 8),
+            0xF => self.reset(),
+            _ => {}
+        }
+    }
+
+    // =====================================================
+    // DECODIFICAÇÃO E TRANSFERÊNCIA
+    // =====================================================
+
+    /// Recebe um setor bruto do drive. Com o decodificador ligado, o
+    /// cabeçalho vai para HEAD0–3 e, com WRRQ, cabeçalho e dados são
+    /// gravados no buffer a partir do novo PT.
+    pub fn decode_sector(&mut self, sector: &[u8]) {
+        if self.ctrl[0] & CTRL0_DECEN == 0 {
+            return;
+        }
+        self.head.copy_from_slice(&sector[12..16]);
+        if self.ctrl[0] & CTRL0_WRRQ != 0 {
+            self.pt = self.wa.wrapping_add(12);
+            self.wa = self.wa.wrapping_add(sector.len() as u16);
+            for (i, &byte) in sector[12..].iter().enumerate() {
+                self.ram[(self.pt as usize + i) % CDC_BUFFER_SIZE] = byte;
+            }
+        }
+        // CRC ok, setor MODE1 válido (VALST ativo em nível baixo)
+        self.stat = [0x80, 0, 0x01, 0x00];
+        self.ifstat &= !IFSTAT_DECI;
+        self.update_irq();
+    }
+
+    /// Próxima palavra da transferência em andamento, ou `None` se não há
+    /// transferência. A última palavra encerra a transferência (EDT) e
+    /// gera a interrupção de fim de transferência.
+    pub fn next_word(&mut self) -> Option<u16> {
+        if !self.transfer_active() {
+            return None;
+        }
+        let index = self.dac as usize % CDC_BUFFER_SIZE;
+        let word = u16::from_be_bytes([self.ram[index], self.ram[(index + 1) % CDC_BUFFER_SIZE]]);
+        self.dac = self.dac.wrapping_add(2);
+        if self.dbc <= 1 {
+            self.dbc = 0x0FFF;
+            self.finish_transfer();
+        } else {
+            self.dbc -= 2;
+        }
+        Some(word)
+    }
+
+    fn finish_transfer(&mut self) {
+        self.ifstat |= IFSTAT_DTBSY | IFSTAT_DTEN;
+        self.ifstat &= !IFSTAT_DTEI;
+        self.end_of_transfer = true;
+        self.data_ready = false;
+        self.update_irq();
+    }
+
+    /// Leitura da porta de dados do host (0xA12008 / 0xFF8008) pela CPU
+    /// escolhida como destino
+    pub fn host_read(&mut self, destination: CdcDestination) -> u16 {
+        if self.destination != destination || !self.data_ready {
+            return 0;
+        }
+        self.next_word().unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_regs(cdc: &mut Cdc, first: u8, values: &[u8]) {
+        cdc.address = first;
+        for &value in values {
+            cdc.write_register(value);
+        }
+    }
+
+    #[test]
+    fn test_decode_stores_header_and_raises_irq() {
+        let mut cdc = Cdc::new();
+        write_regs(&mut cdc, 0x1, &[IFCTRL_DECIEN]);
+        write_regs(&mut cdc, 0xA, &[CTRL0_DECEN | CTRL0_WRRQ]);
+
+        let mut sector = vec![0; 2352];
+        sector[12..16].copy_from_slice(&[0x00, 0x02, 0x10, 0x01]);
+        sector[16] = 0xCA;
+        cdc.decode_sector(&sector);
+        assert!(cdc.irq);
+
+        cdc.address = 0x4;
+        let head: Vec<u8> = (0..4).map(|_| cdc.read_register()).collect();
+        assert_eq!(head, [0x00, 0x02, 0x10, 0x01]);
+        let pt = cdc.read_register() as usize | (cdc.read_register() as usize) << 8;
+        assert_eq!(cdc.ram[pt + 4], 0xCA);
+
+        // Ler STAT3 reconhece a interrupção
+        cdc.address = 0xF;
+        cdc.read_register();
+        assert!(!cdc.irq);
+    }
+
+    #[test]
+    fn test_host_transfer_ends_with_edt() {
+        let mut cdc = Cdc::new();
+        cdc.ram[0x100..0x104].copy_from_slice(&[0x12, 0x34, 0x56, 0x78]);
+        cdc.destination = CdcDestination::SubCpu;
+        write_regs(&mut cdc, 0x1, &[IFCTRL_DOUTEN | IFCTRL_DTEIEN, 0x03, 0x00, 0x00, 0x01, 0x00]);
+        assert!(cdc.data_ready);
+
+        assert_eq!(cdc.host_read(CdcDestination::MainCpu), 0);
+        assert_eq!(cdc.host_read(CdcDestination::SubCpu), 0x1234);
+        assert!(!cdc.end_of_transfer);
+        assert_eq!(cdc.host_read(CdcDestination::SubCpu), 0x5678);
+        assert!(cdc.end_of_transfer && cdc.irq && !cdc.data_ready);
+        assert_eq!(cdc.next_word(), None);
+
+        // DTACK limpa a interrupção
+        write_regs(&mut cdc, 0x7, &[0]);
+        assert!(!cdc.irq);
+    }
+}
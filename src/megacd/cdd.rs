@@ -0,0 +1,343 @@
+//! Controle do drive de CD (CDD)
+//!
+//! O sub-68000 conversa com o drive por dois blocos de 10 nibbles no gate
+//! array: status (0xFF8038–0xFF8041) e comando (0xFF8042–0xFF804B). A
+//! escrita do último nibble de comando marca o comando como pendente; o
+//! drive o executa no próximo quadro de CD (75 Hz), quando também atualiza
+//! o status e gera a interrupção de nível 4. O último nibble de cada bloco
+//! é o checksum: o complemento da soma dos 9 primeiros, em 4 bits.
+//!
+//! Layout do status: `[estado, tipo de relatório, 6 nibbles de dados,
+//! flags, checksum]`. Posições são enviadas em MSF com dígitos BCD.
+//!
+//! Durante a leitura o drive entrega um setor bruto por quadro: setores de
+//! dados seguem para o CDC e setores de trilhas de áudio para a saída
+//! CD-DA (`sound::cdda`).
+
+use crate::megacd::disc::{self, Disc, TrackKind, RAW_SECTOR_SIZE};
+use log::warn;
+
+/// Estados do drive (nibble 0 do status)
+pub const CDD_STOPPED: u8 = 0x0;
+pub const CDD_PLAYING: u8 = 0x1;
+pub const CDD_SEEKING: u8 = 0x2;
+pub const CDD_SCANNING: u8 = 0x3;
+pub const CDD_PAUSED: u8 = 0x4;
+pub const CDD_TRAY_OPEN: u8 = 0x5;
+pub const CDD_NO_DISC: u8 = 0xB;
+pub const CDD_END: u8 = 0xC;
+
+/// Relatórios pedidos pelo comando 0x2 (nibble 1 do status)
+const REPORT_ABSOLUTE: u8 = 0x0;
+const REPORT_RELATIVE: u8 = 0x1;
+const REPORT_TRACK: u8 = 0x2;
+const REPORT_LENGTH: u8 = 0x3;
+const REPORT_TRACK_RANGE: u8 = 0x4;
+const REPORT_TRACK_START: u8 = 0x5;
+
+/// Setores avançados por quadro em avanço/retrocesso rápido
+const SCAN_SPEED: u32 = 30;
+
+/// Checksum de um bloco de 10 nibbles
+pub fn checksum(nibbles: &[u8; 10]) -> u8 {
+    let sum: u32 = nibbles[..9].iter().map(|&n| n as u32).sum();
+    !(sum as u8) & 0x0F
+}
+
+/// Drive de CD
+#[derive(Debug)]
+pub struct Cdd {
+    pub disc: Option<Disc>,
+    pub status: [u8; 10],
+    pub command: [u8; 10],
+    /// Um comando completo foi escrito e aguarda o próximo quadro
+    pub command_pending: bool,
+    state: u8,
+    /// Posição da cabeça (LBA)
+    lba: u32,
+    /// Relatório atual e trilha pedida (relatório 5)
+    report: u8,
+    report_track: u8,
+    /// Direção da varredura rápida
+    scan_forward: bool,
+    /// O seek em andamento veio de um "play" (toca ao chegar)
+    play_after_seek: bool,
+}
+
+impl Cdd {
+    pub fn new(disc: Option<Disc>) -> Self {
+        let mut cdd = Self {
+            disc,
+            status: [0; 10],
+            command: [0; 10],
+            command_pending: false,
+            state: CDD_STOPPED,
+            lba: 0,
+            report: REPORT_ABSOLUTE,
+            report_track: 0,
+            scan_forward: true,
+            play_after_seek: false,
+        };
+        cdd.reset();
+        cdd
+    }
+
+    pub fn reset(&mut self) {
+        self.state = if self.disc.is_some() { CDD_STOPPED } else { CDD_NO_DISC };
+        self.lba = 0;
+        self.report = REPORT_ABSOLUTE;
+        self.command = [0; 10];
+        self.command_pending = false;
+        self.update_status();
+    }
+
+    /// Estado atual do drive
+    pub fn state(&self) -> u8 {
+        self.state
+    }
+
+    /// Posição atual da cabeça (LBA)
+    pub fn position(&self) -> u32 {
+        self.lba
+    }
+
+    /// Escrita de um nibble de comando (0–9); o nibble 9 completa o comando
+    pub fn write_command(&mut self, index: usize, value: u8) {
+        if let Some(nibble) = self.command.get_mut(index) {
+            *nibble = value & 0x0F;
+        }
+        if index == 9 {
+            self.command_pending = true;
+        }
+    }
+
+    /// Um quadro de CD (1/75 s): executa o comando pendente, avança a
+    /// cabeça e atualiza o status. Retorna o tipo da trilha se um setor foi
+    /// lido para `sector`.
+    pub fn update(&mut self, sector: &mut [u8; RAW_SECTOR_SIZE]) -> Option<TrackKind> {
+        if std::mem::take(&mut self.command_pending) {
+            if checksum(&self.command) == self.command[9] {
+                self.execute();
+            } else {
+                warn!("CDD: checksum inválido no comando {:X?}", self.command);
+            }
+        }
+
+        let mut read = None;
+        match self.state {
+            CDD_SEEKING => self.state = if self.play_after_seek { CDD_PLAYING } else { CDD_PAUSED },
+            CDD_PLAYING => {
+                let lead_out = self.disc.as_ref().map_or(0, Disc::lead_out);
+                if self.lba >= lead_out {
+                    self.state = CDD_END;
+                } else {
+                    read = self.read_current(sector);
+                    self.lba += 1;
+                }
+            }
+            CDD_SCANNING => {
+                let lead_out = self.disc.as_ref().map_or(0, Disc::lead_out);
+                self.lba = if self.scan_forward {
+                    (self.lba + SCAN_SPEED).min(lead_out)
+                } else {
+                    self.lba.saturating_sub(SCAN_SPEED)
+                };
+            }
+            _ => {}
+        }
+        self.update_status();
+        read
+    }
+
+    /// Lê o setor sob a cabeça (nada em pregaps)
+    fn read_current(&mut self, sector: &mut [u8; RAW_SECTOR_SIZE]) -> Option<TrackKind> {
+        let disc = self.disc.as_mut()?;
+        let kind = disc.track_at(self.lba)?.kind;
+        match disc.read_sector(self.lba, sector) {
+            Ok(found) => found.then_some(kind),
+            Err(e) => {
+                warn!("CDD: falha ao ler o setor {}: {}", self.lba, e);
+                None
+            }
+        }
+    }
+
+    fn execute(&mut self) {
+        let cmd = self.command;
+        let has_disc = self.disc.is_some();
+        match cmd[0] {
+            // Pedido de status: nada muda
+            0x0 => {}
+            0x1 => {
+                if has_disc {
+                    self.state = CDD_STOPPED;
+                }
+            }
+            0x2 => {
+                self.report = cmd[3];
+                self.report_track = cmd[4] * 10 + cmd[5];
+            }
+            // Play / seek: posição alvo em MSF (dígitos BCD)
+            0x3 | 0x4 if has_disc => {
+                let lba = disc::msf_to_lba(cmd[2] * 10 + cmd[3], cmd[4] * 10 + cmd[5], cmd[6] * 10 + cmd[7]);
+                self.lba = lba;
+                self.play_after_seek = cmd[0] == 0x3;
+                self.report = REPORT_ABSOLUTE;
+                self.state = CDD_SEEKING;
+            }
+            0x6 if has_disc => self.state = CDD_PAUSED,
+            0x7 if has_disc => self.state = CDD_PLAYING,
+            0x8 | 0x9 if has_disc => {
+                self.scan_forward = cmd[0] == 0x8;
+                self.state = CDD_SCANNING;
+            }
+            // Fechar a bandeja: o TOC é lido de uma vez e o drive já responde parado
+            0xC => self.state = if has_disc { CDD_STOPPED } else { CDD_NO_DISC },
+            0xD => self.state = CDD_TRAY_OPEN,
+            0x3 | 0x4 | 0x6..=0x9 => {}
+            other => warn!("CDD: comando {:X} não suportado", other),
+        }
+    }
+
+    /// Escreve o MSF de `lba` (absoluto) nos nibbles 2–7 do status
+    fn put_msf(&mut self, lba: u32) {
+        self.put_time(lba + disc::MSF_OFFSET);
+    }
+
+    /// Escreve um tempo em setores como MSF nos nibbles 2–7 do status
+    fn put_time(&mut self, sectors: u32) {
+        let (m, s, f) = disc::sectors_to_msf(sectors);
+        self.status[2..8].copy_from_slice(&[m / 10, m % 10, s / 10, s % 10, f / 10, f % 10]);
+    }
+
+    fn update_status(&mut self) {
+        self.status = [0; 10];
+        self.status[0] = self.state;
+        self.status[1] = self.report;
+
+        if let Some(disc) = &self.disc {
+            let current = disc.track_at(self.lba).map(|t| (t.number, t.start, t.kind));
+            let is_data = current.is_some_and(|(_, _, kind)| kind == TrackKind::Data);
+            match self.report {
+                REPORT_ABSOLUTE => self.put_msf(self.lba),
+                REPORT_RELATIVE => {
+                    // Tempo dentro da trilha, sem o deslocamento de 2 s
+                    let start = current.map_or(0, |(_, start, _)| start);
+                    self.put_time(self.lba.saturating_sub(start));
+                }
+                REPORT_TRACK => {
+                    let number = current.map_or(0, |(n, _, _)| n);
+                    self.status[2..4].copy_from_slice(&[number / 10, number % 10]);
+                }
+                REPORT_LENGTH => self.put_msf(disc.lead_out()),
+                REPORT_TRACK_RANGE => {
+                    let (first, last) = disc.track_range();
+                    self.status[2..6].copy_from_slice(&[first / 10, first % 10, last / 10, last % 10]);
+                }
+                REPORT_TRACK_START => {
+                    if let Some(track) = disc.track(self.report_track).cloned() {
+                        self.put_msf(track.start);
+                        // Bit 3 do nibble 6: trilha de dados
+                        if track.kind == TrackKind::Data {
+                            self.status[6] |= 0x08;
+                        }
+                        self.status[8] = track.number % 10;
+                    }
+                }
+                _ => {}
+            }
+            if is_data {
+                self.status[8] |= 0x04;
+            }
+        }
+        self.status[9] = checksum(&self.status);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::megacd::disc::DATA_SECTOR_SIZE;
+    use std::io::Cursor;
+
+    fn disc_with_sectors(count: usize) -> Disc {
+        let data: Vec<u8> = (0..count * DATA_SECTOR_SIZE).map(|i| (i / DATA_SECTOR_SIZE) as u8).collect();
+        let size = data.len() as u64;
+        Disc::from_iso(Box::new(Cursor::new(data)), size)
+    }
+
+    fn send(cdd: &mut Cdd, mut command: [u8; 10]) {
+        command[9] = checksum(&command);
+        for (i, &nibble) in command.iter().enumerate() {
+            cdd.write_command(i, nibble);
+        }
+    }
+
+    #[test]
+    fn test_status_checksum_and_no_disc() {
+        let cdd = Cdd::new(None);
+        assert_eq!(cdd.state(), CDD_NO_DISC);
+        assert_eq!(cdd.status[9], checksum(&cdd.status));
+        assert_eq!(checksum(&[0xB, 0, 0, 0, 0, 0, 0, 0, 0, 0]), 0x4);
+    }
+
+    #[test]
+    fn test_play_reads_one_sector_per_frame() {
+        let mut cdd = Cdd::new(Some(disc_with_sectors(8)));
+        let mut sector = [0; RAW_SECTOR_SIZE];
+
+        // Play em 00:02:05 (LBA 5)
+        send(&mut cdd, [0x3, 0, 0, 0, 0, 2, 0, 5, 0, 0]);
+        assert_eq!(cdd.update(&mut sector), None);
+        assert_eq!(cdd.state(), CDD_PLAYING);
+        assert_eq!(cdd.update(&mut sector), Some(TrackKind::Data));
+        assert_eq!(sector[16], 5);
+        assert_eq!(cdd.update(&mut sector), Some(TrackKind::Data));
+        assert_eq!(sector[16], 6);
+        assert_eq!(cdd.update(&mut sector), Some(TrackKind::Data));
+        assert_eq!(cdd.update(&mut sector), None);
+        assert_eq!(cdd.state(), CDD_END);
+    }
+
+    #[test]
+    fn test_play_audio_track() {
+        let cue = "FILE \"jogo.bin\" BINARY\n  TRACK 01 MODE1/2352\n    INDEX 01 00:00:00\n  TRACK 02 AUDIO\n    INDEX 01 00:00:02\n";
+        let disc = Disc::parse_cue(cue, |_| {
+            let data: Vec<u8> = (0..RAW_SECTOR_SIZE * 4).map(|i| (i / RAW_SECTOR_SIZE) as u8).collect();
+            let size = data.len() as u64;
+            Ok((Box::new(Cursor::new(data)) as Box<dyn disc::ImageSource>, size))
+        })
+        .unwrap();
+        let mut cdd = Cdd::new(Some(disc));
+        let mut sector = [0; RAW_SECTOR_SIZE];
+
+        // Play em 00:02:03 (LBA 3): trilha 2, de áudio
+        send(&mut cdd, [0x3, 0, 0, 0, 0, 2, 0, 3, 0, 0]);
+        cdd.update(&mut sector);
+        assert_eq!(cdd.update(&mut sector), Some(TrackKind::Audio));
+        assert_eq!(sector[0], 3);
+        assert_eq!(cdd.status[8] & 0x04, 0);
+    }
+
+    #[test]
+    fn test_toc_reports() {
+        let mut cdd = Cdd::new(Some(disc_with_sectors(75 * 3)));
+        let mut sector = [0; RAW_SECTOR_SIZE];
+
+        // Relatório 3: tamanho do disco (lead-out) = 00:05:00
+        send(&mut cdd, [0x2, 0, 0, 3, 0, 0, 0, 0, 0, 0]);
+        cdd.update(&mut sector);
+        assert_eq!(&cdd.status[..8], &[CDD_STOPPED, 3, 0, 0, 0, 5, 0, 0]);
+
+        // Relatório 5: início da trilha 1, marcada como dados
+        send(&mut cdd, [0x2, 0, 0, 5, 0, 1, 0, 0, 0, 0]);
+        cdd.update(&mut sector);
+        assert_eq!(&cdd.status[1..8], &[5, 0, 0, 0, 2, 0x8, 0]);
+
+        // Checksum errado: o comando é ignorado
+        cdd.write_command(0, 0x3);
+        cdd.write_command(9, 0x0);
+        cdd.update(&mut sector);
+        assert_eq!(cdd.state(), CDD_STOPPED);
+    }
+}
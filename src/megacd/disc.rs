@@ -0,0 +1,400 @@
+//! Imagens de disco do Mega-CD (CUE/BIN e ISO)
+//!
+//! - `.iso`: uma única trilha de dados MODE1, com setores de 2048 bytes
+//!   (ou 2352, se o tamanho do arquivo só fechar assim);
+//! - `.cue`: folha de trilhas apontando para um ou mais `.bin`, com trilhas
+//!   MODE1/2048, MODE1/2352 ou AUDIO (sempre 2352 bytes por setor).
+//!
+//! Os endereços são LBA absolutos do disco: LBA 0 = 00:02:00 em MSF, logo
+//! depois dos 2 s de pregap da primeira trilha. Trilhas de dados gravadas
+//! com 2048 bytes por setor ganham sincronismo e cabeçalho sintéticos, para
+//! que o CDC sempre receba setores brutos de 2352 bytes.
+
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+use thiserror::Error;
+
+/// Setor bruto (sincronismo + cabeçalho + dados + EDC/ECC)
+pub const RAW_SECTOR_SIZE: usize = 2352;
+/// Dados de usuário de um setor MODE1
+pub const DATA_SECTOR_SIZE: usize = 2048;
+/// Setores por segundo (quadros de CD)
+pub const SECTORS_PER_SECOND: u32 = 75;
+/// Deslocamento entre LBA e MSF (2 s de pregap da primeira trilha)
+pub const MSF_OFFSET: u32 = 2 * SECTORS_PER_SECOND;
+
+/// Padrão de sincronismo do início de um setor de dados
+const SYNC_PATTERN: [u8; 12] = [0x00, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x00];
+
+/// Erros ao abrir uma imagem de disco
+#[derive(Debug, Error)]
+pub enum DiscError {
+    #[error("Falha ao ler a imagem: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Linha {line} do .cue inválida: {message}")]
+    Cue { line: usize, message: String },
+
+    #[error("O .cue não declara nenhuma trilha")]
+    NoTracks,
+
+    #[error("Formato de imagem de disco não suportado: {0}")]
+    UnsupportedFormat(String),
+}
+
+/// Tipo de trilha
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrackKind {
+    /// Dados MODE1 (programa do jogo)
+    Data,
+    /// Áudio CD-DA
+    Audio,
+}
+
+/// Trilha do disco
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Track {
+    pub number: u8,
+    pub kind: TrackKind,
+    /// Primeiro setor (LBA do INDEX 01)
+    pub start: u32,
+    /// Número de setores
+    pub length: u32,
+    /// Arquivo de origem (índice em `Disc::files`)
+    file: usize,
+    /// Posição do INDEX 01 dentro do arquivo, em bytes
+    offset: u64,
+    /// Bytes por setor no arquivo (2048 ou 2352)
+    sector_size: usize,
+}
+
+impl Track {
+    /// Primeiro setor depois do fim da trilha
+    pub fn end(&self) -> u32 {
+        self.start + self.length
+    }
+}
+
+/// Arquivo de onde os setores são lidos
+pub trait ImageSource: Read + Seek + Send {}
+
+impl<T: Read + Seek + Send> ImageSource for T {}
+
+/// Disco aberto: tabela de trilhas e arquivos de origem
+pub struct Disc {
+    pub tracks: Vec<Track>,
+    files: Vec<Box<dyn ImageSource>>,
+}
+
+impl std::fmt::Debug for Disc {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Disc").field("tracks", &self.tracks).finish()
+    }
+}
+
+/// Retorna true para extensões de imagem de disco (`.cue` / `.iso`)
+pub fn is_disc_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("cue") || e.eq_ignore_ascii_case("iso"))
+}
+
+/// Converte LBA para MSF (minutos, segundos, quadros)
+pub fn lba_to_msf(lba: u32) -> (u8, u8, u8) {
+    sectors_to_msf(lba + MSF_OFFSET)
+}
+
+/// Converte um tempo em setores para (minutos, segundos, quadros)
+pub fn sectors_to_msf(sectors: u32) -> (u8, u8, u8) {
+    let frames = sectors % SECTORS_PER_SECOND;
+    let seconds = (sectors / SECTORS_PER_SECOND) % 60;
+    let minutes = sectors / SECTORS_PER_SECOND / 60;
+    (minutes as u8, seconds as u8, frames as u8)
+}
+
+/// Converte MSF para LBA (MSF antes de 00:02:00 cai no LBA 0)
+pub fn msf_to_lba(minutes: u8, seconds: u8, frames: u8) -> u32 {
+    let total = (minutes as u32 * 60 + seconds as u32) * SECTORS_PER_SECOND + frames as u32;
+    total.saturating_sub(MSF_OFFSET)
+}
+
+/// Interpreta "mm:ss:ff" de um .cue como número de setores
+fn parse_cue_time(text: &str) -> Option<u32> {
+    let mut parts = text.split(':').map(|p| p.parse::<u32>().ok());
+    let (m, s, f) = (parts.next()??, parts.next()??, parts.next()??);
+    (parts.next().is_none() && s < 60 && f < SECTORS_PER_SECOND).then_some((m * 60 + s) * SECTORS_PER_SECOND + f)
+}
+
+/// Trilha como declarada no .cue, antes de calcular as posições absolutas
+struct CueTrack {
+    number: u8,
+    kind: TrackKind,
+    sector_size: usize,
+    file: usize,
+    /// INDEX 01 em setores dentro do arquivo
+    index1: Option<u32>,
+    /// PREGAP (setores que não estão no arquivo)
+    pregap: u32,
+}
+
+impl Disc {
+    /// Abre uma imagem `.cue` (com seus `.bin`) ou `.iso`
+    pub fn open(path: &Path) -> Result<Self, DiscError> {
+        let ext = path.extension().and_then(|e| e.to_str()).unwrap_or_default().to_ascii_lowercase();
+        match ext.as_str() {
+            "iso" => {
+                let file = File::open(path)?;
+                let size = file.metadata()?.len();
+                Ok(Self::from_iso(Box::new(BufReader::new(file)), size))
+            }
+            "cue" => {
+                let text = std::fs::read_to_string(path)?;
+                let dir = path.parent().unwrap_or(Path::new("."));
+                Self::parse_cue(&text, |name| {
+                    let file = File::open(dir.join(name))?;
+                    let size = file.metadata()?.len();
+                    Ok((Box::new(BufReader::new(file)) as Box<dyn ImageSource>, size))
+                })
+            }
+            _ => Err(DiscError::UnsupportedFormat(path.display().to_string())),
+        }
+    }
+
+    /// Disco de uma trilha de dados a partir de um `.iso`
+    pub fn from_iso(source: Box<dyn ImageSource>, size: u64) -> Self {
+        let raw = !size.is_multiple_of(DATA_SECTOR_SIZE as u64) && size.is_multiple_of(RAW_SECTOR_SIZE as u64);
+        let sector_size = if raw { RAW_SECTOR_SIZE } else { DATA_SECTOR_SIZE };
+        Self {
+            tracks: vec![Track {
+                number: 1,
+                kind: TrackKind::Data,
+                start: 0,
+                length: (size / sector_size as u64) as u32,
+                file: 0,
+                offset: 0,
+                sector_size,
+            }],
+            files: vec![source],
+        }
+    }
+
+    /// Interpreta o texto de um `.cue`. `open` abre cada arquivo citado em
+    /// FILE e retorna também o seu tamanho.
+    pub fn parse_cue(
+        text: &str,
+        mut open: impl FnMut(&str) -> io::Result<(Box<dyn ImageSource>, u64)>,
+    ) -> Result<Self, DiscError> {
+        let mut files = Vec::new();
+        let mut sizes = Vec::new();
+        let mut cue_tracks: Vec<CueTrack> = Vec::new();
+
+        for (i, line) in text.lines().enumerate() {
+            let error = |message: &str| DiscError::Cue { line: i + 1, message: message.to_string() };
+            let line = line.trim();
+            let (keyword, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            match keyword.to_ascii_uppercase().as_str() {
+                "FILE" => {
+                    // FILE "nome com espaços.bin" BINARY
+                    let rest = rest.trim();
+                    let name = match rest.strip_prefix('"') {
+                        Some(quoted) => quoted.split('"').next().unwrap_or_default(),
+                        None => rest.rsplit_once(char::is_whitespace).map_or(rest, |(name, _)| name),
+                    };
+                    let (source, size) = open(name)?;
+                    files.push(source);
+                    sizes.push(size);
+                }
+                "TRACK" => {
+                    let mut fields = rest.split_whitespace();
+                    let number = fields.next().and_then(|n| n.parse().ok()).ok_or_else(|| error("número da trilha"))?;
+                    let (kind, sector_size) = match fields.next().map(str::to_ascii_uppercase).as_deref() {
+                        Some("MODE1/2048") => (TrackKind::Data, DATA_SECTOR_SIZE),
+                        Some("MODE1/2352") => (TrackKind::Data, RAW_SECTOR_SIZE),
+                        Some("AUDIO") => (TrackKind::Audio, RAW_SECTOR_SIZE),
+                        _ => return Err(error("tipo de trilha não suportado")),
+                    };
+                    if files.is_empty() {
+                        return Err(error("TRACK antes de FILE"));
+                    }
+                    cue_tracks.push(CueTrack {
+                        number,
+                        kind,
+                        sector_size,
+                        file: files.len() - 1,
+                        index1: None,
+                        pregap: 0,
+                    });
+                }
+                "INDEX" | "PREGAP" => {
+                    let track = cue_tracks.last_mut().ok_or_else(|| error("INDEX/PREGAP fora de uma trilha"))?;
+                    let mut fields = rest.split_whitespace();
+                    if keyword.eq_ignore_ascii_case("PREGAP") {
+                        track.pregap = fields.next().and_then(parse_cue_time).ok_or_else(|| error("tempo inválido"))?;
+                    } else if fields.next() == Some("01") {
+                        track.index1 = Some(fields.next().and_then(parse_cue_time).ok_or_else(|| error("tempo inválido"))?);
+                    }
+                }
+                _ => {}
+            }
+        }
+        if cue_tracks.is_empty() {
+            return Err(DiscError::NoTracks);
+        }
+
+        // Posições absolutas: cada arquivo começa onde o anterior terminou e
+        // os PREGAPs (que não estão no arquivo) empurram as trilhas seguintes
+        let mut tracks: Vec<Track> = Vec::with_capacity(cue_tracks.len());
+        let mut file_base = 0;
+        let mut gap = 0;
+        for (i, cue) in cue_tracks.iter().enumerate() {
+            if i > 0 && cue.file != cue_tracks[i - 1].file {
+                file_base = tracks.last().map_or(0, Track::end) - gap;
+            }
+            gap += cue.pregap;
+            let index1 = cue.index1.unwrap_or(0);
+            let offset = index1 as u64 * cue.sector_size as u64;
+            let length = match cue_tracks.get(i + 1).filter(|next| next.file == cue.file) {
+                Some(next) => next.index1.unwrap_or(index1).saturating_sub(index1),
+                None => (sizes[cue.file].saturating_sub(offset) / cue.sector_size as u64) as u32,
+            };
+            tracks.push(Track {
+                number: cue.number,
+                kind: cue.kind,
+                start: file_base + index1 + gap,
+                length,
+                file: cue.file,
+                offset,
+                sector_size: cue.sector_size,
+            });
+        }
+        Ok(Self { tracks, files })
+    }
+
+    /// Primeira e última trilha
+    pub fn track_range(&self) -> (u8, u8) {
+        let first = self.tracks.first().map_or(1, |t| t.number);
+        let last = self.tracks.last().map_or(1, |t| t.number);
+        (first, last)
+    }
+
+    /// LBA do lead-out (fim do disco)
+    pub fn lead_out(&self) -> u32 {
+        self.tracks.last().map_or(0, Track::end)
+    }
+
+    /// Trilha que contém o setor `lba`
+    pub fn track_at(&self, lba: u32) -> Option<&Track> {
+        self.tracks.iter().find(|t| (t.start..t.end()).contains(&lba))
+    }
+
+    /// Trilha pelo número
+    pub fn track(&self, number: u8) -> Option<&Track> {
+        self.tracks.iter().find(|t| t.number == number)
+    }
+
+    /// Lê o setor `lba` como setor bruto de 2352 bytes. Retorna false (e
+    /// zera `out`) para setores fora das trilhas, como pregaps.
+    pub fn read_sector(&mut self, lba: u32, out: &mut [u8; RAW_SECTOR_SIZE]) -> io::Result<bool> {
+        let Some(track) = self.track_at(lba).cloned() else {
+            out.fill(0);
+            return Ok(false);
+        };
+        let file = &mut self.files[track.file];
+        file.seek(SeekFrom::Start(track.offset + (lba - track.start) as u64 * track.sector_size as u64))?;
+        if track.sector_size == RAW_SECTOR_SIZE {
+            file.read_exact(out)?;
+        } else {
+            out.fill(0);
+            file.read_exact(&mut out[16..16 + DATA_SECTOR_SIZE])?;
+            out[..12].copy_from_slice(&SYNC_PATTERN);
+            let (m, s, f) = lba_to_msf(lba);
+            out[12..16].copy_from_slice(&[to_bcd(m), to_bcd(s), to_bcd(f), 0x01]);
+        }
+        Ok(true)
+    }
+
+    /// Dados (2048 bytes) do primeiro setor, onde fica o cabeçalho do jogo
+    /// ("SEGADISCSYSTEM" e, em 0x100, o mesmo cabeçalho dos cartuchos)
+    pub fn header_sector(&mut self) -> io::Result<Vec<u8>> {
+        let mut raw = [0; RAW_SECTOR_SIZE];
+        self.read_sector(0, &mut raw)?;
+        Ok(raw[16..16 + DATA_SECTOR_SIZE].to_vec())
+    }
+}
+
+/// Converte um valor (0–99) para BCD
+pub fn to_bcd(value: u8) -> u8 {
+    ((value / 10) << 4) | (value % 10)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn source(data: Vec<u8>) -> (Box<dyn ImageSource>, u64) {
+        let size = data.len() as u64;
+        (Box::new(Cursor::new(data)), size)
+    }
+
+    #[test]
+    fn test_msf_conversion() {
+        assert_eq!(lba_to_msf(0), (0, 2, 0));
+        assert_eq!(lba_to_msf(4500 - 150), (1, 0, 0));
+        assert_eq!(msf_to_lba(1, 0, 0), 4350);
+        assert_eq!(msf_to_lba(0, 0, 10), 0);
+    }
+
+    #[test]
+    fn test_iso_sectors_get_synthetic_header() {
+        let mut data = vec![0; DATA_SECTOR_SIZE * 4];
+        data[DATA_SECTOR_SIZE * 2] = 0x5A;
+        let (file, size) = source(data);
+        let mut disc = Disc::from_iso(file, size);
+        assert_eq!(disc.lead_out(), 4);
+
+        let mut sector = [0; RAW_SECTOR_SIZE];
+        assert!(disc.read_sector(2, &mut sector).unwrap());
+        assert_eq!(&sector[..12], &SYNC_PATTERN);
+        assert_eq!(&sector[12..16], &[0x00, 0x02, 0x02, 0x01]);
+        assert_eq!(sector[16], 0x5A);
+        assert!(!disc.read_sector(9, &mut sector).unwrap());
+    }
+
+    #[test]
+    fn test_cue_with_audio_tracks_and_separate_files() {
+        let cue = "FILE \"jogo (track 1).bin\" BINARY\n  TRACK 01 MODE1/2352\n    INDEX 01 00:00:00\n  TRACK 02 AUDIO\n    PREGAP 00:02:00\n    INDEX 01 00:00:10\nFILE \"faixa 3.bin\" BINARY\n  TRACK 03 AUDIO\n    INDEX 00 00:00:00\n    INDEX 01 00:00:05\n";
+        let mut names = Vec::new();
+        let mut disc = Disc::parse_cue(cue, |name| {
+            names.push(name.to_string());
+            Ok(source(vec![0xAB; RAW_SECTOR_SIZE * 20]))
+        })
+        .unwrap();
+
+        assert_eq!(names, ["jogo (track 1).bin", "faixa 3.bin"]);
+        let spans: Vec<_> = disc.tracks.iter().map(|t| (t.number, t.kind, t.start, t.length)).collect();
+        assert_eq!(
+            spans,
+            [
+                (1, TrackKind::Data, 0, 10),
+                (2, TrackKind::Audio, 160, 10),
+                (3, TrackKind::Audio, 175, 15)
+            ]
+        );
+        assert_eq!(disc.track_range(), (1, 3));
+        assert_eq!(disc.track_at(165).map(|t| t.number), Some(2));
+        assert_eq!(disc.track_at(172), None);
+
+        let mut sector = [0; RAW_SECTOR_SIZE];
+        assert!(disc.read_sector(176, &mut sector).unwrap());
+        assert_eq!(sector[0], 0xAB);
+    }
+
+    #[test]
+    fn test_cue_errors() {
+        let no_file = Disc::parse_cue("TRACK 01 MODE1/2352\n", |_| Ok(source(Vec::new())));
+        assert!(matches!(no_file, Err(DiscError::Cue { line: 1, .. })));
+        assert!(matches!(Disc::parse_cue("REM vazio\n", |_| Ok(source(Vec::new()))), Err(DiscError::NoTracks)));
+    }
+}
@@ -0,0 +1,184 @@
+//! Registradores simples do gate array do Mega-CD
+//!
+//! Estado compartilhado entre os dois 68000 que não pertence a nenhum chip:
+//! reset e pedido de barramento do sub, banco e proteção da PRG-RAM, vetor
+//! de HINT, flags e palavras de comunicação, LED, temporizador, cronômetro,
+//! máscara de interrupções do sub e o gerador de fontes.
+//!
+//! Interrupções do sub-68000 (nível = bit da máscara em 0xFF8033):
+//! 1 gráficos, 2 pedido do principal (IFL2), 3 temporizador, 4 CDD,
+//! 5 CDC, 6 subcódigo.
+
+/// Ciclos do sub-68000 por tique do temporizador e do cronômetro (30,72 µs)
+pub const TIMER_DIVIDER: u32 = 384;
+
+pub const IRQ_GRAPHICS: u8 = 1;
+pub const IRQ_MAIN: u8 = 2;
+pub const IRQ_TIMER: u8 = 3;
+pub const IRQ_CDD: u8 = 4;
+pub const IRQ_CDC: u8 = 5;
+
+#[derive(Debug, Clone, Default)]
+pub struct GateArray {
+    /// SRES: o sub-68000 está fora do reset
+    pub sub_released: bool,
+    /// SBRQ: o principal pediu o barramento do sub (sub parado)
+    pub bus_request: bool,
+    /// Banco da PRG-RAM visto pelo principal (0–3)
+    pub prg_bank: u8,
+    /// Proteção de escrita da PRG-RAM (unidades de 512 bytes)
+    pub write_protect: u8,
+    /// Vetor de HINT apresentado ao principal no boot pelo disco
+    pub hint_vector: u16,
+    /// Flags de comunicação (byte alto: principal, baixo: sub)
+    pub main_flags: u8,
+    pub sub_flags: u8,
+    /// Palavras de comando (escritas pelo principal) e de status (pelo sub)
+    pub comm_command: [u16; 8],
+    pub comm_status: [u16; 8],
+    pub led: u8,
+    /// Intervalo do temporizador de nível 3 (0 = desligado)
+    pub timer: u8,
+    /// Máscara de interrupções do sub (bits 1–6)
+    pub int_mask: u8,
+    /// Cronômetro de 12 bits
+    pub stopwatch: u16,
+    /// Controle do CDD (bit 2: HOCK, o sub aceita o status do drive)
+    pub cdd_control: u8,
+    pub fader: u16,
+    /// Cores do gerador de fontes (nibble baixo: fundo, alto: frente)
+    pub font_color: u8,
+    pub font_bits: u16,
+    pending: u8,
+    timer_counter: u8,
+    /// Ciclos acumulados até o próximo tique de 30,72 µs
+    phase: u32,
+}
+
+impl GateArray {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Retorna true se o sub-68000 está parado (em reset ou sem barramento)
+    pub fn sub_halted(&self) -> bool {
+        !self.sub_released || self.bus_request
+    }
+
+    // =====================================================
+    // INTERRUPÇÕES DO SUB
+    // =====================================================
+
+    /// Pede uma interrupção, se o nível está habilitado na máscara
+    pub fn raise(&mut self, level: u8) {
+        if self.int_mask & (1 << level) != 0 {
+            self.pending |= 1 << level;
+        }
+    }
+
+    /// Nível da interrupção pendente mais alta (0 = nenhuma)
+    pub fn interrupt_level(&self) -> u8 {
+        let pending = self.pending & self.int_mask;
+        if pending == 0 { 0 } else { 7 - pending.leading_zeros() as u8 }
+    }
+
+    /// Reconhecimento da interrupção pelo sub
+    pub fn acknowledge(&mut self, level: u8) {
+        self.pending &= !(1 << level);
+    }
+
+    /// IFL2 lido pelo principal: pedido de nível 2 ainda não atendido
+    pub fn ifl2(&self) -> bool {
+        self.pending & (1 << IRQ_MAIN) != 0
+    }
+
+    /// Escrita da máscara (0xFF8033); níveis desligados perdem o pedido
+    pub fn write_int_mask(&mut self, value: u8) {
+        self.int_mask = value & 0x7E;
+        self.pending &= self.int_mask;
+    }
+
+    // =====================================================
+    // TEMPORIZADOR / CRONÔMETRO
+    // =====================================================
+
+    /// Escrita do intervalo do temporizador (0xFF8031)
+    pub fn write_timer(&mut self, value: u8) {
+        self.timer = value;
+        self.timer_counter = value;
+    }
+
+    /// Avança temporizador e cronômetro por ciclos do sub-68000
+    pub fn clock(&mut self, sub_cycles: u32) {
+        self.phase += sub_cycles;
+        while self.phase >= TIMER_DIVIDER {
+            self.phase -= TIMER_DIVIDER;
+            self.stopwatch = (self.stopwatch + 1) & 0x0FFF;
+            if self.timer != 0 {
+                if self.timer_counter == 0 {
+                    self.timer_counter = self.timer;
+                    self.raise(IRQ_TIMER);
+                } else {
+                    self.timer_counter -= 1;
+                }
+            }
+        }
+    }
+
+    // =====================================================
+    // GERADOR DE FONTES
+    // =====================================================
+
+    /// Palavra `index` (0–3) da fonte expandida (0xFF8050–0xFF8057):
+    /// 4 pixels de 4 bits, um para cada bit de `font_bits`, do mais alto
+    /// para o mais baixo
+    pub fn font_data(&self, index: usize) -> u16 {
+        let (background, foreground) = ((self.font_color & 0x0F) as u16, (self.font_color >> 4) as u16);
+        (0..4).fold(0, |word, pixel| {
+            let bit = 15 - (index * 4 + pixel);
+            let color = if self.font_bits & (1 << bit) != 0 { foreground } else { background };
+            (word << 4) | color
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interrupt_priority_and_mask() {
+        let mut gate = GateArray::new();
+        gate.raise(IRQ_CDC);
+        assert_eq!(gate.interrupt_level(), 0);
+
+        gate.write_int_mask(0x7E);
+        gate.raise(IRQ_MAIN);
+        gate.raise(IRQ_CDD);
+        assert!(gate.ifl2());
+        assert_eq!(gate.interrupt_level(), IRQ_CDD);
+        gate.acknowledge(IRQ_CDD);
+        assert_eq!(gate.interrupt_level(), IRQ_MAIN);
+    }
+
+    #[test]
+    fn test_timer_and_font() {
+        let mut gate = GateArray::new();
+        gate.write_int_mask(1 << IRQ_TIMER);
+        gate.write_timer(1);
+        gate.clock(TIMER_DIVIDER);
+        assert_eq!(gate.interrupt_level(), 0);
+        gate.clock(TIMER_DIVIDER);
+        assert_eq!(gate.interrupt_level(), IRQ_TIMER);
+        assert_eq!(gate.stopwatch, 2);
+
+        gate.font_color = 0x51;
+        gate.font_bits = 0xA000;
+        assert_eq!(gate.font_data(0), 0x5151);
+        assert_eq!(gate.font_data(3), 0x1111);
+    }
+}
@@ -0,0 +1,565 @@
+//! Mega-CD (Sega CD): unidade de expansão com um segundo 68000
+//!
+//! Componentes:
+//! - `gate_array`: registradores compartilhados, comunicação e interrupções
+//! - `word_ram`: 256 KB compartilhados nos modos 2M e 1M
+//! - `cdc` / `cdd`: controlador e drive de CD, lendo `disc` (CUE/BIN, ISO)
+//! - `sound::rf5c164`: PCM de 8 canais, mixado em `sound::Sound`
+//! - `sound::cdda`: áudio das trilhas de CD, também mixado em `sound::Sound`
+//!
+//! Mapa do 68000 principal (área de expansão; em 0x000000 no boot pelo
+//! disco, em 0x400000 com cartucho):
+//! - +0x000000–0x01FFFF: BIOS (o vetor de HINT em 0x72 vem do gate array)
+//! - +0x020000–0x03FFFF: janela de 128 KB da PRG-RAM (banco BK), acessível
+//!   só com o sub parado
+//! - +0x200000–0x23FFFF: word RAM
+//! - 0xA12000–0xA1202F: registradores do gate array
+//!
+//! Mapa do sub-68000 (12,5 MHz):
+//! - 0x000000–0x07FFFF: PRG-RAM (512 KB, protegida abaixo de WP × 512)
+//! - 0x080000–0x0DFFFF: word RAM (ver `word_ram`)
+//! - 0xFE0000–0xFEFFFF: RAM de backup (8 KB, bytes ímpares)
+//! - 0xFF0000–0xFF7FFF: PCM (bytes ímpares: registradores e janela de onda)
+//! - 0xFF8000–0xFF81FF: registradores do gate array
+//!
+//! Escopo atual: o emulador ainda não tem núcleo do 68000 (nem para o
+//! principal), então o sub-68000 não executa código e nenhum jogo passa da
+//! BIOS. O que existe é o hardware em volta dele, pronto para o núcleo:
+//! `run` avança temporizador, drive, CDC, DMA, PCM e CD-DA no clock do sub
+//! e devolve os ciclos que o sub-68000 deve executar (zero enquanto ele
+//! está em reset ou sem o barramento), e `sub_read*`/`sub_write*` e
+//! `sub_interrupt_level` formam o barramento dele. O ASIC de gráficos
+//! (rotação/escala) também não é emulado.
+
+pub mod cdc;
+pub mod cdd;
+pub mod disc;
+pub mod gate_array;
+pub mod word_ram;
+
+use crate::sound::cdda::Cdda;
+use crate::sound::rf5c164::{Rf5c164, PCM_BANK_SIZE};
+use cdc::{Cdc, CdcDestination};
+use cdd::Cdd;
+use disc::{Disc, TrackKind, RAW_SECTOR_SIZE};
+use gate_array::{GateArray, IRQ_CDC, IRQ_CDD, IRQ_MAIN};
+use log::warn;
+use parking_lot::RwLock;
+use std::path::Path;
+use std::sync::Arc;
+use word_ram::WordRam;
+
+/// Clock do sub-68000
+pub const SUB_CPU_CLOCK: u32 = 12_500_000;
+/// Tamanho da BIOS
+pub const BIOS_SIZE: usize = 0x20000;
+/// Tamanho da PRG-RAM
+pub const PRG_RAM_SIZE: usize = 0x80000;
+/// Janela da PRG-RAM vista pelo principal
+pub const PRG_RAM_WINDOW: usize = 0x20000;
+/// RAM de backup interna
+pub const BACKUP_RAM_SIZE: usize = 0x2000;
+/// Ciclos do sub por quadro de CD (75 Hz)
+pub const CD_FRAME_CYCLES: u32 = SUB_CPU_CLOCK / disc::SECTORS_PER_SECOND;
+
+/// Divisor do clock principal para o 68000 principal
+const M68K_DIVIDER: f64 = 7.0;
+/// Bit HOCK do controle do CDD: o sub recebe o status do drive
+const CDD_HOCK: u8 = 0x04;
+
+pub struct MegaCd {
+    pub bios: Vec<u8>,
+    pub prg_ram: Vec<u8>,
+    pub word_ram: WordRam,
+    pub backup_ram: Vec<u8>,
+    pub gate: GateArray,
+    pub cdc: Cdc,
+    pub cdd: Cdd,
+    pub pcm: Arc<RwLock<Rf5c164>>,
+    pub cdda: Arc<RwLock<Cdda>>,
+    /// Boot pelo disco (sem cartucho): a área de expansão fica em 0x000000
+    pub cd_boot: bool,
+    /// Bits DD (destino do CDC) como escritos em 0xFF8004
+    cdc_destination_bits: u8,
+    /// Endereço de DMA do CDC (0xFF800A)
+    dma_address: u16,
+    /// Palavra lida por byte na porta de dados do host (principal, sub)
+    host_latch: [u16; 2],
+    master_clock: u32,
+    /// Fração de ciclo do sub acumulada
+    cycle_budget: f64,
+    /// Ciclos do sub até o próximo quadro de CD
+    cd_frame_cycles: u32,
+}
+
+impl MegaCd {
+    /// Monta a unidade com a BIOS do usuário e, opcionalmente, um disco na
+    /// bandeja. `master_clock` é o do console (NTSC ou PAL).
+    pub fn new(bios: Vec<u8>, disc: Option<Disc>, master_clock: u32) -> Self {
+        if bios.len() != BIOS_SIZE {
+            warn!("BIOS do Mega-CD com {} bytes (esperado {})", bios.len(), BIOS_SIZE);
+        }
+        Self {
+            bios,
+            prg_ram: vec![0; PRG_RAM_SIZE],
+            word_ram: WordRam::new(),
+            backup_ram: vec![0; BACKUP_RAM_SIZE],
+            gate: GateArray::new(),
+            cdc: Cdc::new(),
+            cdd: Cdd::new(disc),
+            pcm: Arc::new(RwLock::new(Rf5c164::new())),
+            cdda: Arc::new(RwLock::new(Cdda::new())),
+            cd_boot: false,
+            cdc_destination_bits: 0,
+            dma_address: 0,
+            host_latch: [0; 2],
+            master_clock,
+            cycle_budget: 0.0,
+            cd_frame_cycles: 0,
+        }
+    }
+
+    /// Carrega a BIOS do Mega-CD de um arquivo
+    pub fn load_bios(path: &Path) -> std::io::Result<Vec<u8>> {
+        std::fs::read(path)
+    }
+
+    /// Reset do console: sub em reset, word RAM com o principal
+    pub fn reset(&mut self) {
+        self.gate.reset();
+        self.word_ram.reset();
+        self.cdc.reset();
+        self.cdd.reset();
+        self.pcm.write().reset();
+        self.cdda.write().reset();
+        self.cdc_destination_bits = 0;
+        self.cycle_budget = 0.0;
+        self.cd_frame_cycles = 0;
+    }
+
+    // =====================================================
+    // 68000 PRINCIPAL
+    // =====================================================
+
+    /// Leitura na área de expansão; `addr` pode ser absoluto (só os 22 bits
+    /// baixos importam)
+    pub fn main_read8(&self, addr: u32) -> u8 {
+        let offset = addr as usize & 0x3FFFFF;
+        if offset >= 0x200000 {
+            return self.word_ram.main_read8(offset as u32);
+        }
+        let offset = offset & 0x3FFFF;
+        match offset {
+            // O gate array substitui o vetor de HINT quando a BIOS está em 0
+            0x72 if self.cd_boot => (self.gate.hint_vector >> 8) as u8,
+            0x73 if self.cd_boot => self.gate.hint_vector as u8,
+            0x00000..=0x1FFFF if self.bios.is_empty() => 0,
+            0x00000..=0x1FFFF => self.bios[offset % self.bios.len()],
+            _ if self.gate.sub_halted() => self.prg_ram[self.main_prg_index(offset)],
+            _ => 0,
+        }
+    }
+
+    pub fn main_write8(&mut self, addr: u32, value: u8) {
+        let offset = addr as usize & 0x3FFFFF;
+        if offset >= 0x200000 {
+            self.word_ram.main_write8(offset as u32, value);
+        } else if offset & 0x3FFFF >= PRG_RAM_WINDOW && self.gate.sub_halted() {
+            let index = self.main_prg_index(offset & 0x3FFFF);
+            self.prg_ram[index] = value;
+        }
+    }
+
+    fn main_prg_index(&self, offset: usize) -> usize {
+        self.gate.prg_bank as usize * PRG_RAM_WINDOW + (offset - PRG_RAM_WINDOW)
+    }
+
+    /// Leitura de palavra dos registradores 0xA12000–0xA1203F
+    fn main_register16(&mut self, reg: u32) -> u16 {
+        let gate = &self.gate;
+        match reg & 0x3E {
+            0x00 => ((gate.ifl2() as u16) << 8) | ((gate.bus_request as u16) << 1) | gate.sub_released as u16,
+            0x02 => {
+                ((gate.write_protect as u16) << 8) | ((gate.prg_bank as u16) << 6) | self.word_ram.mode_bits() as u16
+            }
+            0x04 => self.cdc_mode(),
+            0x06 => gate.hint_vector,
+            0x08 => self.host_read(CdcDestination::MainCpu),
+            0x0C => gate.stopwatch,
+            0x0E => ((gate.main_flags as u16) << 8) | gate.sub_flags as u16,
+            r @ 0x10..=0x1E => gate.comm_command[(r as usize - 0x10) / 2],
+            r @ 0x20..=0x2E => gate.comm_status[(r as usize - 0x20) / 2],
+            _ => 0,
+        }
+    }
+
+    /// Leitura de byte dos registradores do principal (0xA12000–0xA1203F)
+    pub fn main_read_register(&mut self, addr: u32) -> u8 {
+        let reg = addr & 0x3F;
+        match reg {
+            // Porta de dados: o byte alto puxa a palavra, o baixo a repete
+            0x08 => {
+                self.host_latch[0] = self.main_register16(reg);
+                (self.host_latch[0] >> 8) as u8
+            }
+            0x09 => self.host_latch[0] as u8,
+            _ => {
+                let word = self.main_register16(reg);
+                if reg & 1 == 0 { (word >> 8) as u8 } else { word as u8 }
+            }
+        }
+    }
+
+    /// Escrita de byte nos registradores do principal (0xA12000–0xA1203F)
+    pub fn main_write_register(&mut self, addr: u32, value: u8) {
+        let gate = &mut self.gate;
+        match addr & 0x3F {
+            // IFL2: interrupção de nível 2 no sub
+            0x00 if value & 0x01 != 0 => gate.raise(IRQ_MAIN),
+            0x01 => {
+                gate.sub_released = value & 0x01 != 0;
+                gate.bus_request = value & 0x02 != 0;
+            }
+            0x02 => gate.write_protect = value,
+            0x03 => {
+                gate.prg_bank = (value >> 6) & 0x03;
+                self.word_ram.main_write(value);
+            }
+            0x06 => gate.hint_vector = (gate.hint_vector & 0x00FF) | ((value as u16) << 8),
+            0x07 => gate.hint_vector = (gate.hint_vector & 0xFF00) | value as u16,
+            0x0E => gate.main_flags = value,
+            r @ 0x10..=0x1F => write_byte(&mut gate.comm_command[(r as usize - 0x10) / 2], r, value),
+            _ => {}
+        }
+    }
+
+    // =====================================================
+    // SUB-68000
+    // =====================================================
+
+    pub fn sub_read8(&mut self, addr: u32) -> u8 {
+        let addr = addr & 0xFFFFFF;
+        match addr {
+            0x000000..=0x07FFFF => self.prg_ram[addr as usize],
+            0x080000..=0x0DFFFF => self.word_ram.sub_read8(addr - 0x080000),
+            0xFE0000..=0xFEFFFF if addr & 1 != 0 => self.backup_ram[(addr as usize >> 1) & (BACKUP_RAM_SIZE - 1)],
+            0xFF0000..=0xFF7FFF if addr & 1 != 0 => {
+                let offset = (addr as usize & 0x3FFF) >> 1;
+                let pcm = self.pcm.read();
+                if offset < PCM_BANK_SIZE {
+                    pcm.read_register(offset as u8)
+                } else {
+                    pcm.read_ram(offset - PCM_BANK_SIZE)
+                }
+            }
+            0xFF8000..=0xFF81FF => self.sub_read_register(addr),
+            _ => 0,
+        }
+    }
+
+    pub fn sub_read16(&mut self, addr: u32) -> u16 {
+        let addr = addr & 0xFFFFFE;
+        if (0xFF8000..=0xFF81FF).contains(&addr) {
+            return self.sub_register16(addr);
+        }
+        ((self.sub_read8(addr) as u16) << 8) | self.sub_read8(addr + 1) as u16
+    }
+
+    pub fn sub_write8(&mut self, addr: u32, value: u8) {
+        let addr = addr & 0xFFFFFF;
+        match addr {
+            0x000000..=0x07FFFF if addr >= self.gate.write_protect as u32 * 0x200 => {
+                self.prg_ram[addr as usize] = value;
+            }
+            0x080000..=0x0DFFFF => self.word_ram.sub_write8(addr - 0x080000, value),
+            0xFE0000..=0xFEFFFF if addr & 1 != 0 => {
+                self.backup_ram[(addr as usize >> 1) & (BACKUP_RAM_SIZE - 1)] = value;
+            }
+            0xFF0000..=0xFF7FFF if addr & 1 != 0 => {
+                let offset = (addr as usize & 0x3FFF) >> 1;
+                let mut pcm = self.pcm.write();
+                if offset < PCM_BANK_SIZE {
+                    pcm.write_register(offset as u8, value);
+                } else {
+                    pcm.write_ram(offset - PCM_BANK_SIZE, value);
+                }
+            }
+            0xFF8000..=0xFF81FF => self.sub_write_register(addr, value),
+            _ => {}
+        }
+    }
+
+    pub fn sub_write16(&mut self, addr: u32, value: u16) {
+        let addr = addr & 0xFFFFFE;
+        self.sub_write8(addr, (value >> 8) as u8);
+        self.sub_write8(addr + 1, value as u8);
+    }
+
+    /// Nível de interrupção pedido ao sub-68000 (0 = nenhum)
+    pub fn sub_interrupt_level(&self) -> u8 {
+        self.gate.interrupt_level()
+    }
+
+    /// Reconhecimento de interrupção pelo sub-68000
+    pub fn acknowledge_sub_interrupt(&mut self, level: u8) {
+        self.gate.acknowledge(level);
+    }
+
+    /// Modo do CDC (0xA12004 / 0xFF8004): EDT, DSR e destino
+    fn cdc_mode(&self) -> u16 {
+        ((self.cdc.end_of_transfer as u16) << 15)
+            | ((self.cdc.data_ready as u16) << 14)
+            | ((self.cdc_destination_bits as u16) << 8)
+    }
+
+    fn host_read(&mut self, destination: CdcDestination) -> u16 {
+        let word = self.cdc.host_read(destination);
+        if self.cdc.irq {
+            self.gate.raise(IRQ_CDC);
+        }
+        word
+    }
+
+    fn sub_register16(&mut self, reg: u32) -> u16 {
+        let reg = reg & 0x1FE;
+        let gate = &self.gate;
+        let pair = |nibbles: &[u8; 10], i: usize| ((nibbles[i] as u16) << 8) | nibbles[i + 1] as u16;
+        match reg {
+            // LED e RES (sub pronto)
+            0x00 => ((gate.led as u16) << 8) | 0x01,
+            0x02 => ((gate.write_protect as u16) << 8) | self.word_ram.mode_bits() as u16,
+            0x04 => self.cdc_mode() | self.cdc.address as u16,
+            0x06 => self.cdc.read_register() as u16,
+            0x08 => self.host_read(CdcDestination::SubCpu),
+            0x0A => self.dma_address,
+            0x0C => gate.stopwatch,
+            0x0E => ((gate.main_flags as u16) << 8) | gate.sub_flags as u16,
+            r @ 0x10..=0x1E => gate.comm_command[(r as usize - 0x10) / 2],
+            r @ 0x20..=0x2E => gate.comm_status[(r as usize - 0x20) / 2],
+            0x30 => gate.timer as u16,
+            0x32 => gate.int_mask as u16,
+            0x34 => gate.fader,
+            0x36 => gate.cdd_control as u16,
+            r @ 0x38..=0x41 => pair(&self.cdd.status, r as usize - 0x38),
+            r @ 0x42..=0x4B => pair(&self.cdd.command, r as usize - 0x42),
+            0x4C => gate.font_color as u16,
+            0x4E => gate.font_bits,
+            r @ 0x50..=0x57 => gate.font_data((r as usize - 0x50) / 2),
+            _ => 0,
+        }
+    }
+
+    fn sub_read_register(&mut self, addr: u32) -> u8 {
+        let reg = addr & 0x1FF;
+        match reg {
+            0x06 => 0,
+            0x07 => self.cdc.read_register(),
+            0x08 => {
+                self.host_latch[1] = self.sub_register16(reg);
+                (self.host_latch[1] >> 8) as u8
+            }
+            0x09 => self.host_latch[1] as u8,
+            _ => {
+                let word = self.sub_register16(reg);
+                if reg & 1 == 0 { (word >> 8) as u8 } else { word as u8 }
+            }
+        }
+    }
+
+    fn sub_write_register(&mut self, addr: u32, value: u8) {
+        let reg = addr & 0x1FF;
+        let gate = &mut self.gate;
+        match reg {
+            0x00 => gate.led = value,
+            0x03 => self.word_ram.sub_write(value),
+            0x04 => {
+                self.cdc_destination_bits = value & 0x07;
+                self.cdc.destination = CdcDestination::from_bits(value);
+            }
+            0x05 => self.cdc.address = value & 0x0F,
+            0x07 => self.cdc.write_register(value),
+            0x0A => self.dma_address = (self.dma_address & 0x00FF) | ((value as u16) << 8),
+            0x0B => self.dma_address = (self.dma_address & 0xFF00) | value as u16,
+            // Qualquer escrita zera o cronômetro
+            0x0C | 0x0D => gate.stopwatch = 0,
+            0x0F => gate.sub_flags = value,
+            r @ 0x20..=0x2F => write_byte(&mut gate.comm_status[(r as usize - 0x20) / 2], r, value),
+            0x31 => gate.write_timer(value),
+            0x33 => gate.write_int_mask(value),
+            r @ (0x34 | 0x35) => write_byte(&mut gate.fader, r, value),
+            0x37 => gate.cdd_control = value,
+            r @ 0x42..=0x4B => self.cdd.write_command(r as usize - 0x42, value),
+            0x4D => gate.font_color = value,
+            r @ (0x4E | 0x4F) => write_byte(&mut gate.font_bits, r, value),
+            _ => {}
+        }
+    }
+
+    // =====================================================
+    // CICLOS
+    // =====================================================
+
+    /// Avança a unidade pelos ciclos do 68000 principal decorridos.
+    /// Retorna quantos ciclos o sub-68000 deve executar no mesmo intervalo
+    /// (zero se ele está parado).
+    pub fn run(&mut self, m68k_cycles: u32) -> u32 {
+        self.cycle_budget += m68k_cycles as f64 * M68K_DIVIDER * SUB_CPU_CLOCK as f64 / self.master_clock as f64;
+        let sub_cycles = self.cycle_budget as u32;
+        self.cycle_budget -= sub_cycles as f64;
+
+        self.gate.clock(sub_cycles);
+        self.pcm.write().tick(sub_cycles);
+        self.cdda.write().tick(sub_cycles);
+        self.cd_frame_cycles += sub_cycles;
+        while self.cd_frame_cycles >= CD_FRAME_CYCLES {
+            self.cd_frame_cycles -= CD_FRAME_CYCLES;
+            self.cd_frame();
+        }
+        self.run_dma();
+
+        if self.gate.sub_halted() { 0 } else { sub_cycles }
+    }
+
+    /// Um quadro de CD: o drive entrega um setor ao CDC (dados) ou ao CD-DA
+    /// (áudio) e responde ao sub
+    fn cd_frame(&mut self) {
+        let mut sector = [0; RAW_SECTOR_SIZE];
+        match self.cdd.update(&mut sector) {
+            Some(TrackKind::Data) => self.cdc.decode_sector(&sector),
+            Some(TrackKind::Audio) => self.cdda.write().push_sector(&sector),
+            None => {}
+        }
+        if self.gate.cdd_control & CDD_HOCK != 0 {
+            self.gate.raise(IRQ_CDD);
+        }
+        if self.cdc.irq {
+            self.gate.raise(IRQ_CDC);
+        }
+    }
+
+    /// Executa de uma vez a transferência de DMA pendente do CDC
+    fn run_dma(&mut self) {
+        let destination = self.cdc.destination;
+        if destination.is_host() || destination == CdcDestination::None {
+            return;
+        }
+        let (mut addr, shift) = match destination {
+            CdcDestination::Pcm => ((self.dma_address as usize & 0x3FF) << 2, 2),
+            _ => ((self.dma_address as usize) << 3, 3),
+        };
+        while let Some(word) = self.cdc.next_word() {
+            match destination {
+                CdcDestination::Pcm => {
+                    let mut pcm = self.pcm.write();
+                    pcm.write_ram(addr & (PCM_BANK_SIZE - 1), (word >> 8) as u8);
+                    pcm.write_ram((addr + 1) & (PCM_BANK_SIZE - 1), word as u8);
+                }
+                CdcDestination::PrgRam => {
+                    let index = addr & (PRG_RAM_SIZE - 2);
+                    self.prg_ram[index..index + 2].copy_from_slice(&word.to_be_bytes());
+                }
+                _ => self.word_ram.dma_write16(addr as u32, word),
+            }
+            addr += 2;
+        }
+        self.dma_address = (addr >> shift) as u16;
+        if self.cdc.irq {
+            self.gate.raise(IRQ_CDC);
+        }
+    }
+}
+
+/// Escreve o byte alto (endereço par) ou baixo (ímpar) de um registrador
+fn write_byte(word: &mut u16, addr: u32, value: u8) {
+    *word = if addr & 1 == 0 {
+        (*word & 0x00FF) | ((value as u16) << 8)
+    } else {
+        (*word & 0xFF00) | value as u16
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sound::NTSC_MASTER_CLOCK;
+    use std::io::Cursor;
+
+    fn mega_cd(disc: Option<Disc>) -> MegaCd {
+        let mut bios = vec![0; BIOS_SIZE];
+        bios[0x100..0x104].copy_from_slice(b"SEGA");
+        MegaCd::new(bios, disc, NTSC_MASTER_CLOCK)
+    }
+
+    #[test]
+    fn test_main_and_sub_communication() {
+        let mut cd = mega_cd(None);
+        cd.cd_boot = true;
+        assert_eq!(cd.main_read8(0x100), b'S');
+
+        // PRG-RAM só é acessível ao principal com o sub parado
+        cd.main_write_register(0xA12003, 0x40);
+        cd.main_write8(0x020010, 0x5A);
+        assert_eq!(cd.prg_ram[PRG_RAM_WINDOW + 0x10], 0x5A);
+        cd.main_write_register(0xA12001, 0x01);
+        assert_eq!(cd.main_read8(0x020010), 0);
+
+        // Palavras de comunicação e flags
+        cd.main_write_register(0xA12010, 0x12);
+        cd.main_write_register(0xA12011, 0x34);
+        cd.main_write_register(0xA1200E, 0x80);
+        assert_eq!(cd.sub_read16(0xFF8010), 0x1234);
+        assert_eq!(cd.sub_read8(0xFF800E), 0x80);
+        cd.sub_write16(0xFF8020, 0xBEEF);
+        assert_eq!(cd.main_read_register(0xA12020), 0xBE);
+
+        // IFL2 e vetor de HINT
+        cd.sub_write8(0xFF8033, 1 << IRQ_MAIN);
+        cd.main_write_register(0xA12000, 0x01);
+        assert_eq!(cd.sub_interrupt_level(), IRQ_MAIN);
+        assert_eq!(cd.main_read_register(0xA12000), 0x01);
+        cd.main_write_register(0xA12006, 0xFD);
+        cd.main_write_register(0xA12007, 0x0C);
+        assert_eq!((cd.main_read8(0x72), cd.main_read8(0x73)), (0xFD, 0x0C));
+    }
+
+    #[test]
+    fn test_cd_read_and_dma_to_prg_ram() {
+        let mut data = vec![0; 2048 * 4];
+        data[2048..2052].copy_from_slice(&[1, 2, 3, 4]);
+        let size = data.len() as u64;
+        let mut cd = mega_cd(Some(Disc::from_iso(Box::new(Cursor::new(data)), size)));
+        cd.sub_write8(0xFF8033, (1 << IRQ_CDD) | (1 << IRQ_CDC));
+        cd.sub_write8(0xFF8037, CDD_HOCK);
+
+        // CDC: decodificação com escrita no buffer e interrupção
+        cd.sub_write8(0xFF8005, 0x1);
+        cd.sub_write8(0xFF8007, 0x20 | 0x02);
+        cd.sub_write8(0xFF8005, 0xA);
+        cd.sub_write8(0xFF8007, 0x80 | 0x04);
+
+        // CDD: play em 00:02:01 (LBA 1)
+        let mut command = [0x3, 0, 0, 0, 0, 2, 0, 1, 0, 0];
+        command[9] = cdd::checksum(&command);
+        for (i, &nibble) in command.iter().enumerate() {
+            cd.sub_write8(0xFF8042 + i as u32, nibble);
+        }
+        // Seek, depois a leitura do setor
+        cd.cd_frame();
+        cd.cd_frame();
+        assert_eq!(cd.sub_interrupt_level(), IRQ_CDC);
+        assert_eq!(cd.sub_read8(0xFF8038), cdd::CDD_PLAYING);
+
+        // PT aponta o cabeçalho; os dados começam 4 bytes depois
+        cd.sub_write8(0xFF8005, 0x8);
+        let pt = cd.sub_read8(0xFF8007) as u16 | (cd.sub_read8(0xFF8007) as u16) << 8;
+        cd.sub_write8(0xFF8004, 5);
+        cd.sub_write16(0xFF800A, 0x0100 >> 3);
+        cd.sub_write8(0xFF8005, 0x2);
+        for value in [0x03, 0x00, (pt + 4) as u8, ((pt + 4) >> 8) as u8, 0x00] {
+            cd.sub_write8(0xFF8007, value);
+        }
+        cd.run(0);
+        assert_eq!(&cd.prg_ram[0x100..0x104], &[1, 2, 3, 4]);
+        assert_eq!(cd.sub_read16(0xFF8004) & 0x8000, 0x8000);
+    }
+}
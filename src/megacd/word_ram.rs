@@ -0,0 +1,278 @@
+//! Word RAM do Mega-CD (256 KB compartilhados entre os dois 68000)
+//!
+//! Modo 2M: a RAM inteira pertence a uma CPU por vez. O principal a entrega
+//! escrevendo DMNA = 1; o sub a devolve escrevendo RET = 1. Com RET = 1 o
+//! principal é o dono (0x200000 no seu mapa); com RET = 0, o sub (0x080000).
+//!
+//! Modo 1M: a RAM é dividida em dois bancos de 128 KB, entrelaçados por
+//! palavra (banco 0 nas palavras pares, banco 1 nas ímpares), um para cada
+//! CPU. Com RET = 0 o principal fica com o banco 0 e o sub com o banco 1;
+//! com RET = 1, o contrário. O sub troca os bancos escrevendo RET; DMNA = 1
+//! do principal pede a troca e é limpo quando ela acontece. O sub vê seu
+//! banco em 0x0C0000 e, em 0x080000, como imagem de pontos (um pixel de
+//! 4 bits por byte). O principal vê seu banco em 0x200000 e, em 0x220000,
+//! como imagem em células, pronta para o DMA do VDP.
+
+/// Tamanho total da word RAM
+pub const WORD_RAM_SIZE: usize = 0x40000;
+/// Tamanho de um banco no modo 1M
+pub const WORD_RAM_BANK_SIZE: usize = 0x20000;
+
+// Bits de modo (0xA12003 / 0xFF8003)
+pub const MODE_RET: u8 = 0x01;
+pub const MODE_DMNA: u8 = 0x02;
+pub const MODE_1M: u8 = 0x04;
+
+/// Modo de divisão da word RAM
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WordRamMode {
+    TwoMega,
+    OneMega,
+}
+
+#[derive(Debug, Clone)]
+pub struct WordRam {
+    pub data: Vec<u8>,
+    pub mode: WordRamMode,
+    ret: bool,
+    dmna: bool,
+}
+
+impl Default for WordRam {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Início da imagem em células na janela do principal (0x220000)
+const CELL_IMAGE_START: usize = 0x20000;
+
+/// Converte um offset da imagem em células no offset linear do banco.
+///
+/// A imagem é dividida em faixas de 64 colunas de 8 pixels, com 32, 16, 8,
+/// 4 e 4 células de altura. Dentro de uma faixa, palavras longas seguidas
+/// (uma linha de célula cada) descem pela coluna; no banco linear, as 64
+/// colunas de uma linha ficam lado a lado.
+fn cell_image_offset(offset: usize) -> usize {
+    let long = (offset & (WORD_RAM_BANK_SIZE - 1)) >> 2;
+    let (base, height) = match long {
+        0x0000..=0x3FFF => (0x0000, 256),
+        0x4000..=0x5FFF => (0x4000, 128),
+        0x6000..=0x6FFF => (0x6000, 64),
+        0x7000..=0x77FF => (0x7000, 32),
+        _ => (0x7800, 32),
+    };
+    let local = long - base;
+    let linear = base + (local % height) * 64 + local / height;
+    (linear << 2) | (offset & 3)
+}
+
+/// Posição de um byte do banco `bank` (modo 1M) na RAM entrelaçada
+fn bank_index(bank: usize, offset: usize) -> usize {
+    let offset = offset & (WORD_RAM_BANK_SIZE - 1);
+    ((offset & !1) << 1) | (bank << 1) | (offset & 1)
+}
+
+impl WordRam {
+    pub fn new() -> Self {
+        Self {
+            data: vec![0; WORD_RAM_SIZE],
+            mode: WordRamMode::TwoMega,
+            ret: true,
+            dmna: false,
+        }
+    }
+
+    /// Volta ao modo 2M com a RAM do principal
+    pub fn reset(&mut self) {
+        self.mode = WordRamMode::TwoMega;
+        self.ret = true;
+        self.dmna = false;
+    }
+
+    /// Bits MODE, DMNA e RET como lidos nos registradores de modo
+    pub fn mode_bits(&self) -> u8 {
+        let mut bits = 0;
+        if self.mode == WordRamMode::OneMega {
+            bits |= MODE_1M;
+        }
+        if self.dmna {
+            bits |= MODE_DMNA;
+        }
+        if self.ret {
+            bits |= MODE_RET;
+        }
+        bits
+    }
+
+    /// Escrita do principal em 0xA12003 (só DMNA tem efeito)
+    pub fn main_write(&mut self, value: u8) {
+        if value & MODE_DMNA == 0 {
+            return;
+        }
+        self.dmna = true;
+        if self.mode == WordRamMode::TwoMega {
+            self.ret = false;
+        }
+    }
+
+    /// Escrita do sub em 0xFF8003 (MODE e RET)
+    pub fn sub_write(&mut self, value: u8) {
+        self.mode = if value & MODE_1M != 0 { WordRamMode::OneMega } else { WordRamMode::TwoMega };
+        let ret = value & MODE_RET != 0;
+        match self.mode {
+            WordRamMode::TwoMega if ret => {
+                self.ret = true;
+                self.dmna = false;
+            }
+            WordRamMode::TwoMega => {}
+            WordRamMode::OneMega => {
+                self.ret = ret;
+                self.dmna = false;
+            }
+        }
+    }
+
+    /// Banco do principal e do sub no modo 1M
+    fn banks(&self) -> (usize, usize) {
+        if self.ret { (1, 0) } else { (0, 1) }
+    }
+
+    /// Índice na RAM de um acesso do principal (offset na janela de 256 KB)
+    fn main_index(&self, offset: u32) -> Option<usize> {
+        let offset = offset as usize & (WORD_RAM_SIZE - 1);
+        match self.mode {
+            WordRamMode::TwoMega => self.ret.then_some(offset),
+            WordRamMode::OneMega if offset >= CELL_IMAGE_START => {
+                Some(bank_index(self.banks().0, cell_image_offset(offset)))
+            }
+            WordRamMode::OneMega => Some(bank_index(self.banks().0, offset)),
+        }
+    }
+
+    pub fn main_read8(&self, offset: u32) -> u8 {
+        self.main_index(offset).map_or(0, |i| self.data[i])
+    }
+
+    pub fn main_write8(&mut self, offset: u32, value: u8) {
+        if let Some(i) = self.main_index(offset) {
+            self.data[i] = value;
+        }
+    }
+
+    /// Leitura do sub; `offset` é relativo a 0x080000 (0–0x5FFFF)
+    pub fn sub_read8(&self, offset: u32) -> u8 {
+        let offset = offset as usize;
+        match self.mode {
+            WordRamMode::TwoMega if !self.ret && offset < WORD_RAM_SIZE => self.data[offset],
+            WordRamMode::TwoMega => 0,
+            WordRamMode::OneMega if offset >= WORD_RAM_SIZE => self.data[bank_index(self.banks().1, offset)],
+            WordRamMode::OneMega => {
+                // Imagem de pontos: um pixel (nibble) por byte
+                let byte = self.data[bank_index(self.banks().1, offset >> 1)];
+                if offset & 1 == 0 { byte >> 4 } else { byte & 0x0F }
+            }
+        }
+    }
+
+    /// Escrita do sub; `offset` é relativo a 0x080000 (0–0x5FFFF)
+    pub fn sub_write8(&mut self, offset: u32, value: u8) {
+        let offset = offset as usize;
+        match self.mode {
+            WordRamMode::TwoMega if !self.ret && offset < WORD_RAM_SIZE => self.data[offset] = value,
+            WordRamMode::TwoMega => {}
+            WordRamMode::OneMega if offset >= WORD_RAM_SIZE => {
+                let i = bank_index(self.banks().1, offset);
+                self.data[i] = value;
+            }
+            WordRamMode::OneMega => {
+                let i = bank_index(self.banks().1, offset >> 1);
+                let byte = &mut self.data[i];
+                *byte = if offset & 1 == 0 {
+                    (*byte & 0x0F) | (value << 4)
+                } else {
+                    (*byte & 0xF0) | (value & 0x0F)
+                };
+            }
+        }
+    }
+
+    /// Escrita de DMA do CDC (sempre na parte que pertence ao sub)
+    pub fn dma_write16(&mut self, offset: u32, value: u16) {
+        let [high, low] = value.to_be_bytes();
+        let offset = offset as usize & !1;
+        let (a, b) = match self.mode {
+            WordRamMode::TwoMega => (offset & (WORD_RAM_SIZE - 1), (offset + 1) & (WORD_RAM_SIZE - 1)),
+            WordRamMode::OneMega => {
+                let bank = self.banks().1;
+                (bank_index(bank, offset), bank_index(bank, offset + 1))
+            }
+        };
+        self.data[a] = high;
+        self.data[b] = low;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_2m_ownership_handshake() {
+        let mut ram = WordRam::new();
+        ram.main_write8(0x10, 0xAA);
+        assert_eq!(ram.sub_read8(0x10), 0);
+
+        ram.main_write(MODE_DMNA);
+        assert_eq!(ram.mode_bits(), MODE_DMNA);
+        assert_eq!(ram.sub_read8(0x10), 0xAA);
+        assert_eq!(ram.main_read8(0x10), 0);
+
+        ram.sub_write(MODE_RET);
+        assert_eq!(ram.mode_bits(), MODE_RET);
+        assert_eq!(ram.main_read8(0x10), 0xAA);
+    }
+
+    #[test]
+    fn test_1m_banks_and_dot_image() {
+        let mut ram = WordRam::new();
+        ram.sub_write(MODE_1M);
+        // RET = 0: principal no banco 0, sub no banco 1
+        ram.main_write8(0x0, 0x11);
+        ram.sub_write8(0x40000, 0x22);
+        assert_eq!(&ram.data[..4], &[0x11, 0x00, 0x22, 0x00]);
+
+        // Imagem de pontos: pixels 0 e 1 são os nibbles do byte 0 do banco
+        assert_eq!((ram.sub_read8(0x0), ram.sub_read8(0x1)), (0x2, 0x2));
+        ram.sub_write8(0x1, 0x7);
+        assert_eq!(ram.sub_read8(0x40000), 0x27);
+
+        // Troca de bancos
+        ram.sub_write(MODE_1M | MODE_RET);
+        assert_eq!(ram.main_read8(0x0), 0x27);
+        assert_eq!(ram.sub_read8(0x40000), 0x11);
+    }
+
+    #[test]
+    fn test_1m_cell_image_view() {
+        let mut ram = WordRam::new();
+        ram.sub_write(MODE_1M);
+
+        // Faixa de 32 células: a segunda palavra longa é a linha 1 da coluna 0
+        ram.main_write8(0x20004, 0x12);
+        assert_eq!(ram.main_read8(0x100), 0x12);
+        // A linha 256 começa a próxima coluna
+        ram.main_write8(0x20401, 0x34);
+        assert_eq!(ram.main_read8(0x5), 0x34);
+
+        // Faixa de 16 células (0x230000): colunas de 128 linhas
+        ram.main_write8(0x30202, 0x56);
+        assert_eq!(ram.main_read8(0x10006), 0x56);
+
+        // Últimas faixas de 4 células
+        ram.main_write8(0x3E000, 0x78);
+        assert_eq!(ram.main_read8(0x1E000), 0x78);
+        ram.main_write8(0x3E080, 0x9A);
+        assert_eq!(ram.main_read8(0x1E004), 0x9A);
+    }
+}
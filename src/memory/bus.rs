@@ -1,17 +1,92 @@
-// src/memory/bus.rs
+//! Barramento principal do 68000 (Mega Drive / Genesis)
+//!
+//! O espaço de endereçamento de 24 bits é dividido em 256 páginas de 64 KB.
+//! Cada página aponta diretamente para uma fatia de memória (ROM ou RAM),
+//! permitindo leituras sem lock e sem `match` de endereço, ou para um
+//! dispositivo (Z80, I/O, mapper, VDP) que trata o acesso.
+//!
+//! Trocas de banco do mapper apenas reapontam as páginas do cartucho
+//! (0x000000–0x3FFFFF), sem copiar dados.
+//!
+//! Quando o TMSS está ativo, acessos ao VDP antes do handshake "SEGA"
+//! travam o 68000, e uma ROM de boot opcional ocupa a área do cartucho.
+//!
+//! Com o Mega-CD, BIOS, PRG-RAM e word RAM ocupam a área de expansão
+//! (ou a do cartucho, no boot pelo disco) e os registradores do gate
+//! array ficam em 0xA12000.
+
+use crate::console::ConsoleModel;
 use crate::cpu::z80::Z80;
+use crate::megacd::MegaCd;
+use crate::memory::tmss::{Tmss, TMSS_CART_SELECT_ADDR};
 use crate::memory::{Mapper, Ram, Rom};
 use crate::sound::Sound;
 use crate::vdp::Vdp;
 use std::sync::{Arc, Mutex};
 
+/// Deslocamento (em bits) do número da página dentro do endereço
+pub const PAGE_SHIFT: u32 = 16;
+/// Tamanho de uma página do barramento (64 KB)
+pub const PAGE_SIZE: usize = 1 << PAGE_SHIFT;
+/// Número de páginas no espaço de 24 bits do 68000
+pub const PAGE_COUNT: usize = 256;
+/// Número de páginas da área do cartucho (4 MB)
+pub const CART_PAGES: usize = 0x40;
+
+/// Páginas da área de expansão (0x400000–0x7FFFFF)
+pub const EXPANSION_PAGES: std::ops::Range<usize> = 0x40..0x80;
+
+/// Ciclos do clock principal por tick do VDP (um pixel: 3420 por linha)
+const MASTER_CYCLES_PER_TICK: u32 = 10;
+
+/// Retorna true para os registradores de chips do cartucho em 0xA15000
+/// (SVP), acessados como palavra
+fn is_cartridge_register(addr: u32) -> bool {
+    (0xA15000..=0xA150FF).contains(&addr)
+}
+
+/// Dispositivos que tratam acessos de uma página inteira
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BusDevice {
+    /// Área do cartucho que precisa do mapper (SRAM, EEPROM, ROM espelhada,
+    /// registradores de placas piratas)
+    Cartridge,
+    /// ROM de boot do TMSS mapeada no lugar do cartucho
+    BootRom,
+    /// RAM e chips de som do Z80 (0xA00000–0xA0FFFF)
+    Z80,
+    /// Portas de I/O e registradores do mapper (0xA10000–0xA1FFFF)
+    Io,
+    /// Portas de dados/controle do VDP (0xC00000–0xDFFFFF)
+    Vdp,
+    /// BIOS, janela da PRG-RAM e word RAM do Mega-CD
+    MegaCd,
+}
+
+/// Entrada da tabela de páginas
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Page {
+    /// Leitura direta da ROM — offset da página dentro dos dados da ROM
+    Rom(usize),
+    /// Leitura/escrita direta na RAM principal
+    Ram,
+    /// Acesso tratado por um dispositivo
+    Device(BusDevice),
+    /// Nada mapeado (open bus)
+    Unmapped,
+}
+
 pub struct Bus {
     pub z80: Arc<Mutex<Z80>>,
     pub vdp: Arc<Mutex<Vdp>>,
     pub sound: Arc<Mutex<Sound>>,
-    pub ram: Arc<Mutex<Ram>>,
-    pub rom: Arc<Mutex<Rom>>,
+    pub ram: Ram,
+    pub rom: Arc<Rom>,
     pub mapper: Arc<Mutex<Mapper>>,
+    pub tmss: Tmss,
+    pub console: ConsoleModel,
+    pub mega_cd: Option<Arc<Mutex<MegaCd>>>,
+    pages: [Page; PAGE_COUNT],
 }
 
 impl Bus {
@@ -19,57 +94,468 @@ impl Bus {
         z80: Arc<Mutex<Z80>>,
         vdp: Arc<Mutex<Vdp>>,
         sound: Arc<Mutex<Sound>>,
-        ram: Arc<Mutex<Ram>>,
-        rom: Arc<Mutex<Rom>>,
+        ram: Ram,
         mapper: Arc<Mutex<Mapper>>,
     ) -> Self {
-        Self {
+        let (rom, expansion) = {
+            let mapper = mapper.lock().unwrap();
+            (mapper.rom.clone(), mapper.decodes_expansion_area())
+        };
+
+        let mut pages = [Page::Unmapped; PAGE_COUNT];
+        pages[0xA0] = Page::Device(BusDevice::Z80);
+        pages[0xA1] = Page::Device(BusDevice::Io);
+        // Placas piratas que usam a área de expansão (sem Mega-CD)
+        if expansion {
+            pages[EXPANSION_PAGES].fill(Page::Device(BusDevice::Cartridge));
+        }
+        for page in &mut pages[0xC0..=0xDF] {
+            *page = Page::Device(BusDevice::Vdp);
+        }
+        // A RAM de 64 KB é espelhada em toda a faixa 0xE00000–0xFFFFFF
+        for page in &mut pages[0xE0..=0xFF] {
+            *page = Page::Ram;
+        }
+
+        let mut bus = Self {
             z80,
             vdp,
             sound,
             ram,
             rom,
             mapper,
+            tmss: Tmss::disabled(),
+            console: ConsoleModel::default(),
+            mega_cd: None,
+            pages,
+        };
+        bus.remap_cartridge();
+        bus
+    }
+
+    // =====================================================
+    // TABELA DE PÁGINAS
+    // =====================================================
+
+    /// Retorna a entrada da tabela de páginas para um endereço
+    #[inline]
+    pub fn page(&self, addr: u32) -> Page {
+        self.pages[((addr >> PAGE_SHIFT) as usize) & (PAGE_COUNT - 1)]
+    }
+
+    /// Instala o TMSS (modo e ROM de boot) e refaz o mapeamento do cartucho
+    pub fn install_tmss(&mut self, tmss: Tmss) {
+        self.tmss = tmss;
+        self.remap_cartridge();
+    }
+
+    /// Conecta o Mega-CD: a área de expansão passa a ser dele, ou a do
+    /// cartucho no boot pelo disco
+    pub fn install_mega_cd(&mut self, mega_cd: Arc<Mutex<MegaCd>>) {
+        let cd_boot = mega_cd.lock().unwrap().cd_boot;
+        self.mega_cd = Some(mega_cd);
+        if cd_boot {
+            self.remap_cartridge();
+        } else {
+            self.pages[EXPANSION_PAGES].fill(Page::Device(BusDevice::MegaCd));
+        }
+    }
+
+    /// Retorna true se o 68000 travou por acessar o VDP sem o handshake TMSS
+    pub fn cpu_locked_up(&self) -> bool {
+        self.tmss.lockup.get()
+    }
+
+    /// Reaponta as páginas do cartucho conforme o estado atual do mapper.
+    /// Páginas cujo conteúdo não cabe inteiro na ROM (ou que pertencem a
+    /// SRAM/EEPROM) ficam a cargo do mapper.
+    pub fn remap_cartridge(&mut self) {
+        if self.tmss.boot_rom_mapped() {
+            self.pages[..CART_PAGES].fill(Page::Device(BusDevice::BootRom));
+            return;
+        }
+        if self.mega_cd.as_ref().is_some_and(|cd| cd.lock().unwrap().cd_boot) {
+            self.pages[..CART_PAGES].fill(Page::Device(BusDevice::MegaCd));
+            return;
+        }
+
+        let mapper = self.mapper.lock().unwrap();
+        for page in 0..CART_PAGES {
+            self.pages[page] = match mapper.rom_page_offset(page) {
+                Some(offset) if offset + PAGE_SIZE <= self.rom.size() => Page::Rom(offset),
+                _ => Page::Device(BusDevice::Cartridge),
+            };
         }
     }
 
+    /// Reaponta as páginas se o mapper trocou bancos (ou patches) desde a
+    /// última consulta
+    pub fn sync_mapper_banks(&mut self) {
+        let changed = {
+            let mut mapper = self.mapper.lock().unwrap();
+            std::mem::take(&mut mapper.banks_dirty)
+        };
+        if changed {
+            self.remap_cartridge();
+        }
+    }
+
+    // =====================================================
+    // LEITURA
+    // =====================================================
+
     pub fn read8(&self, addr: u32) -> u8 {
+        let addr = addr & 0xFFFFFF;
+        match self.page(addr) {
+            Page::Rom(offset) => self.rom.data()[offset + (addr as usize & (PAGE_SIZE - 1))],
+            Page::Ram => self.ram.read8(addr),
+            Page::Device(device) => self.device_read8(device, addr),
+            Page::Unmapped => 0,
+        }
+    }
+
+    /// Leitura de palavra (16 bits, big-endian) sem decompor em bytes
+    /// para ROM e RAM.
+    pub fn read16(&self, addr: u32) -> u16 {
+        let addr = addr & 0xFFFFFF;
+        let low = addr as usize & (PAGE_SIZE - 1);
+        if low > PAGE_SIZE - 2 {
+            // Acesso desalinhado cruzando a página
+            return ((self.read8(addr) as u16) << 8) | self.read8(addr.wrapping_add(1)) as u16;
+        }
+        match self.page(addr) {
+            Page::Rom(offset) => {
+                let data = self.rom.data();
+                u16::from_be_bytes([data[offset + low], data[offset + low + 1]])
+            }
+            Page::Ram => self.ram.read16(addr),
+            Page::Device(BusDevice::Vdp) => {
+                if !self.tmss.check_vdp_access(addr) {
+                    return 0;
+                }
+                self.vdp.lock().unwrap().bus_read16(addr)
+            }
+            Page::Device(BusDevice::Cartridge) => self.mapper.lock().unwrap().read16(addr),
+            Page::Device(BusDevice::Io) if is_cartridge_register(addr) => self.mapper.lock().unwrap().read16(addr),
+            Page::Device(device) => {
+                ((self.device_read8(device, addr) as u16) << 8)
+                    | self.device_read8(device, addr + 1) as u16
+            }
+            Page::Unmapped => 0,
+        }
+    }
+
+    /// Leitura de longword (32 bits, big-endian)
+    pub fn read32(&self, addr: u32) -> u32 {
+        let addr = addr & 0xFFFFFF;
+        let low = addr as usize & (PAGE_SIZE - 1);
+        match self.page(addr) {
+            Page::Rom(offset) if low <= PAGE_SIZE - 4 => {
+                let data = self.rom.data();
+                let i = offset + low;
+                u32::from_be_bytes([data[i], data[i + 1], data[i + 2], data[i + 3]])
+            }
+            Page::Ram if low <= PAGE_SIZE - 4 => self.ram.read32(addr),
+            _ => ((self.read16(addr) as u32) << 16) | self.read16(addr.wrapping_add(2)) as u32,
+        }
+    }
+
+    fn device_read8(&self, device: BusDevice, addr: u32) -> u8 {
+        match device {
+            BusDevice::Cartridge => self.mapper.lock().unwrap().read8(addr),
+            BusDevice::BootRom => self.tmss.read_boot_rom(addr),
+            BusDevice::Z80 => self.z80.lock().unwrap().read_byte(addr as u16),
+            BusDevice::Io => self.io_read8(addr),
+            BusDevice::Vdp if !self.tmss.check_vdp_access(addr) => 0,
+            BusDevice::Vdp => self.vdp.lock().unwrap().bus_read(addr),
+            BusDevice::MegaCd => self.mega_cd.as_ref().map_or(0, |cd| cd.lock().unwrap().main_read8(addr)),
+        }
+    }
+
+    /// Leitura na página de I/O (0xA1xxxx)
+    fn io_read8(&self, addr: u32) -> u8 {
         match addr {
-            0xA00000..=0xA0FFFF => self.z80.lock().unwrap().read_byte(addr as u16),
-            0xC00000..=0xC0001F => self.vdp.lock().unwrap().bus_read(addr),
-            0xFF0000..=0xFFFFFF => self.ram.lock().unwrap().read8(addr),
+            // Registrador de versão (região, PAL/NTSC, expansão, TMSS)
+            0xA10000..=0xA10001 => self.console.version_register(),
+            0xA13000..=0xA130FF | 0xA15000..=0xA150FF => self.mapper.lock().unwrap().read_register(addr),
+            0xA12000..=0xA1203F => {
+                self.mega_cd.as_ref().map_or(0, |cd| cd.lock().unwrap().main_read_register(addr))
+            }
+            0xA14000..=0xA14003 | TMSS_CART_SELECT_ADDR => self.tmss.read_register(addr),
             _ => 0,
         }
     }
 
-    pub fn write8(&self, addr: u32, value: u8) {
+    // =====================================================
+    // ESCRITA
+    // =====================================================
+
+    pub fn write8(&mut self, addr: u32, value: u8) {
+        let addr = addr & 0xFFFFFF;
+        match self.page(addr) {
+            // A ROM não é gravável, mas alguns mappers decodificam escritas
+            // na área do cartucho (ex: Codemasters).
+            Page::Rom(_) => self.mapper_write8(addr, value),
+            Page::Ram => self.ram.write8(addr, value),
+            Page::Device(device) => self.device_write8(device, addr, value),
+            Page::Unmapped => {}
+        }
+    }
+
+    /// Escrita de palavra (16 bits, big-endian)
+    pub fn write16(&mut self, addr: u32, value: u16) {
+        let addr = addr & 0xFFFFFF;
+        let low = addr as usize & (PAGE_SIZE - 1);
+        match self.page(addr) {
+            Page::Ram if low <= PAGE_SIZE - 2 => self.ram.write16(addr, value),
+            Page::Device(BusDevice::Vdp) => {
+                if self.tmss.check_vdp_access(addr) {
+                    self.vdp.lock().unwrap().bus_write16(addr, value);
+                }
+            }
+            Page::Device(BusDevice::Cartridge) if low <= PAGE_SIZE - 2 => self.mapper_write16(addr, value),
+            Page::Device(BusDevice::Io) if is_cartridge_register(addr) => self.mapper_write16(addr, value),
+            _ => {
+                self.write8(addr, (value >> 8) as u8);
+                self.write8(addr.wrapping_add(1), value as u8);
+            }
+        }
+    }
+
+    /// Escrita de longword (32 bits, big-endian)
+    pub fn write32(&mut self, addr: u32, value: u32) {
+        let addr = addr & 0xFFFFFF;
+        let low = addr as usize & (PAGE_SIZE - 1);
+        match self.page(addr) {
+            Page::Ram if low <= PAGE_SIZE - 4 => self.ram.write32(addr, value),
+            _ => {
+                self.write16(addr, (value >> 16) as u16);
+                self.write16(addr.wrapping_add(2), value as u16);
+            }
+        }
+    }
+
+    fn device_write8(&mut self, device: BusDevice, addr: u32, value: u8) {
+        match device {
+            BusDevice::Cartridge => self.mapper_write8(addr, value),
+            BusDevice::BootRom => {}
+            BusDevice::Z80 => self.z80.lock().unwrap().write_byte(addr as u16, value),
+            BusDevice::Io => self.io_write8(addr, value),
+            BusDevice::Vdp => {
+                if self.tmss.check_vdp_access(addr) {
+                    self.vdp.lock().unwrap().bus_write(addr, value);
+                }
+            }
+            BusDevice::MegaCd => {
+                if let Some(cd) = &self.mega_cd {
+                    cd.lock().unwrap().main_write8(addr, value);
+                }
+            }
+        }
+    }
+
+    /// Escrita na página de I/O (0xA1xxxx)
+    fn io_write8(&mut self, addr: u32, value: u8) {
         match addr {
-            0xA00000..=0xA0FFFF => self.z80.lock().unwrap().write_byte(addr as u16, value),
-            0xC00000..=0xC0003F => self.vdp.lock().unwrap().bus_write(addr, value),
-            0xFF0000..=0xFFFFFF => self.ram.lock().unwrap().write8(addr, value),
+            0xA13000..=0xA130FF | 0xA15000..=0xA150FF => self.mapper_write8(addr, value),
+            0xA12000..=0xA1203F => {
+                if let Some(cd) = &self.mega_cd {
+                    cd.lock().unwrap().main_write_register(addr, value);
+                }
+            }
+            0xA14000..=0xA14003 | TMSS_CART_SELECT_ADDR => {
+                if self.tmss.write_register(addr, value) {
+                    self.remap_cartridge();
+                }
+            }
             _ => {}
         }
     }
 
+    fn mapper_write8(&mut self, addr: u32, value: u8) {
+        self.mapper.lock().unwrap().write8(addr, value);
+        self.sync_mapper_banks();
+    }
+
+    fn mapper_write16(&mut self, addr: u32, value: u16) {
+        self.mapper.lock().unwrap().write16(addr, value);
+        self.sync_mapper_banks();
+    }
+
+    // =====================================================
+    // CICLOS / VÍDEO
+    // =====================================================
+
     pub fn tick(&self) {
         self.vdp.lock().unwrap().tick();
-        self.sound.lock().unwrap().tick();
+        self.sound.lock().unwrap().tick(MASTER_CYCLES_PER_TICK);
     }
 
     pub fn render_frame(&self) -> Vec<u32> {
         let mut vdp = self.vdp.lock().unwrap();
-        vdp.render_frame();
-        vdp.framebuffer.pixels.clone()
+        vdp.render_frame().pixels.clone()
     }
 
-    pub fn read16(&self, addr: u32) -> u16 {
-        let lo = self.read8(addr) as u16;
-        let hi = self.read8(addr + 1) as u16;
-        (hi << 8) | lo
+    // =====================================================
+    // DIAGNÓSTICO
+    // =====================================================
+
+    pub fn vram_dump(&self) -> Vec<u8> {
+        self.vdp.lock().unwrap().vram.dump()
     }
 
-    pub fn write16(&self, addr: u32, value: u16) {
-        self.write8(addr, (value & 0xFF) as u8);
-        self.write8(addr + 1, (value >> 8) as u8);
+    pub fn cram_dump(&self) -> Vec<u16> {
+        self.vdp.lock().unwrap().cram.dump()
+    }
+
+    pub fn ram_dump(&self) -> Vec<u8> {
+        self.ram.dump()
+    }
+
+    pub fn z80_ram_dump(&self) -> Vec<u8> {
+        self.z80.lock().unwrap().ram.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::mapper::MapperType;
+
+    fn create_bus(rom_data: Vec<u8>, mapper_type: MapperType) -> Bus {
+        let sound = Arc::new(Mutex::new(Sound::new(44100)));
+        let z80 = Arc::new(Mutex::new(Z80::new(sound.clone())));
+        let vdp = Arc::new(Mutex::new(Vdp::new(false)));
+        let mapper = Arc::new(Mutex::new(Mapper::new(Rom::new(rom_data), mapper_type)));
+        Bus::new(z80, vdp, sound, Ram::new(0x10000), mapper)
+    }
+
+    #[test]
+    fn test_page_table_layout() {
+        let bus = create_bus(vec![0; 0x80000], MapperType::Standard);
+        assert_eq!(bus.page(0x000000), Page::Rom(0));
+        assert_eq!(bus.page(0x050000), Page::Rom(0x50000));
+        assert_eq!(bus.page(0xA00000), Page::Device(BusDevice::Z80));
+        assert_eq!(bus.page(0xA10000), Page::Device(BusDevice::Io));
+        assert_eq!(bus.page(0xC00004), Page::Device(BusDevice::Vdp));
+        assert_eq!(bus.page(0xFF0000), Page::Ram);
+        assert_eq!(bus.page(0xB00000), Page::Unmapped);
+    }
+
+    #[test]
+    fn test_rom_word_and_long_reads() {
+        let data = (0..=255u8).cycle().take(0x20000).collect::<Vec<u8>>();
+        let bus = create_bus(data, MapperType::Standard);
+        assert_eq!(bus.read8(0x10), 0x10);
+        assert_eq!(bus.read16(0x10), 0x1011);
+        assert_eq!(bus.read32(0x10), 0x10111213);
+        // Longword cruzando a fronteira de página
+        assert_eq!(bus.read32(0xFFFE), 0xFEFF0001);
+    }
+
+    #[test]
+    fn test_ram_mirroring_and_big_endian() {
+        let mut bus = create_bus(vec![0; 0x10000], MapperType::Standard);
+        bus.write32(0xFF0100, 0x12345678);
+        assert_eq!(bus.read16(0xFF0100), 0x1234);
+        assert_eq!(bus.read8(0xFF0103), 0x78);
+        assert_eq!(bus.read32(0xE00100), 0x12345678);
+    }
+
+    #[test]
+    fn test_small_rom_falls_back_to_mapper() {
+        let bus = create_bus(vec![0xAA, 0xBB, 0xCC, 0xDD], MapperType::Standard);
+        assert_eq!(bus.page(0x000000), Page::Device(BusDevice::Cartridge));
+        assert_eq!(bus.read16(0x000002), 0xCCDD);
+        assert_eq!(bus.read8(0x000004), 0xAA);
+    }
+
+    #[test]
+    fn test_tmss_locks_vdp_until_handshake() {
+        use crate::memory::tmss::HardwareRevision;
+
+        let mut bus = create_bus(vec![0; 0x10000], MapperType::Standard);
+        bus.install_tmss(Tmss::new(HardwareRevision::Model1Va6, None));
+
+        bus.write16(0xC00004, 0x8144);
+        assert!(bus.cpu_locked_up());
+
+        // Handshake tardio: a CPU continua travada e o VDP inacessível
+        bus.write32(0xA14000, u32::from_be_bytes(*b"SEGA"));
+        assert!(bus.cpu_locked_up());
+        assert!(!bus.tmss.check_vdp_access(0xC00004));
+        assert_eq!(bus.read16(0xC00004), 0);
+    }
+
+    #[test]
+    fn test_tmss_boot_rom_hands_over_to_cartridge() {
+        use crate::memory::tmss::HardwareRevision;
+
+        let mut bus = create_bus(vec![0x4E; 0x10000], MapperType::Standard);
+        bus.install_tmss(Tmss::new(HardwareRevision::Model2, Some(vec![0xB0; 0x800])));
+        assert_eq!(bus.page(0x000000), Page::Device(BusDevice::BootRom));
+        assert_eq!(bus.read8(0x000100), 0xB0);
+
+        bus.write8(0xA14101, 0x01);
+        assert_eq!(bus.page(0x000000), Page::Rom(0));
+        assert_eq!(bus.read8(0x000100), 0x4E);
+    }
+
+    #[test]
+    fn test_version_register_read() {
+        use crate::console::Region;
+        use crate::memory::tmss::HardwareRevision;
+
+        let mut bus = create_bus(vec![0; 0x10000], MapperType::Standard);
+        bus.console = ConsoleModel::new(Region::EuropePal, HardwareRevision::Model1Va0);
+        assert_eq!(bus.read8(0xA10001), 0xE0);
+        assert_eq!(bus.read16(0xA10000), 0xE0E0);
+    }
+
+    #[test]
+    fn test_expansion_area_routed_to_pirate_board() {
+        let mut bus = create_bus(vec![0; 0x10000], MapperType::SquirrelKing);
+        assert_eq!(bus.page(0x400000), Page::Device(BusDevice::Cartridge));
+        bus.write8(0x400000, 0x6C);
+        assert_eq!(bus.read8(0x400004), 0x6C);
+
+        let bus = create_bus(vec![0; 0x10000], MapperType::Standard);
+        assert_eq!(bus.page(0x400000), Page::Unmapped);
+    }
+
+    #[test]
+    fn test_mega_cd_expansion_and_disc_boot() {
+        use crate::megacd::BIOS_SIZE;
+
+        let mut bus = create_bus(vec![0x4E; 0x10000], MapperType::Standard);
+        let cd = Arc::new(Mutex::new(MegaCd::new(vec![0xB1; BIOS_SIZE], None, 53_693_175)));
+        bus.install_mega_cd(cd.clone());
+        assert_eq!(bus.page(0x400000), Page::Device(BusDevice::MegaCd));
+        assert_eq!(bus.read8(0x400100), 0xB1);
+        assert_eq!(bus.read8(0x000100), 0x4E);
+
+        bus.write16(0xA12010, 0xCAFE);
+        assert_eq!(cd.lock().unwrap().gate.comm_command[0], 0xCAFE);
+        bus.write8(0x600000, 0x77);
+        assert_eq!(bus.read8(0x600000), 0x77);
+
+        // Boot pelo disco: a BIOS aparece em 0x000000
+        let mut bus = create_bus(vec![0x4E; 0x10000], MapperType::Standard);
+        cd.lock().unwrap().cd_boot = true;
+        bus.install_mega_cd(cd);
+        assert_eq!(bus.page(0x000000), Page::Device(BusDevice::MegaCd));
+        assert_eq!(bus.read8(0x000100), 0xB1);
+    }
+
+    #[test]
+    fn test_bank_switch_repoints_pages() {
+        let mut data = vec![0u8; 0x100000];
+        data[0x80000] = 0x42;
+        let mut bus = create_bus(data, MapperType::Sega);
+        assert_eq!(bus.read8(0x080000), 0x42);
+
+        bus.write8(0xA130F3, 0);
+        assert_eq!(bus.page(0x080000), Page::Rom(0));
+        assert_eq!(bus.read8(0x080000), 0x00);
     }
 }
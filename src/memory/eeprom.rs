@@ -0,0 +1,570 @@
+//! EEPROM serial I2C (família 24Cxx) usada para saves em cartuchos
+//!
+//! O 68000 controla as linhas SDA e SCL por bit-banging, escrevendo e lendo
+//! bits em endereços do cartucho. Cada fabricante ligou os pinos de forma
+//! diferente (endereço e bit de SDA de entrada, SDA de saída e SCL), por
+//! isso chip e pinagem vêm do banco de dados de jogos (`gamedb`).
+//!
+//! Protocolo suportado:
+//! - condições de START (SDA desce com SCL alto) e STOP (SDA sobe com SCL alto)
+//! - X24C01: endereço de 7 bits no próprio byte de controle
+//! - 24C01–24C16: byte de dispositivo 1010xxxR + 1 byte de endereço
+//!   (bits altos do endereço nos bits A2–A0 do dispositivo)
+//! - 24C32–24C65: byte de dispositivo + 2 bytes de endereço
+//! - escrita em página (o endereço dá a volta dentro da página)
+//! - leitura sequencial (o endereço dá a volta no fim da memória)
+
+use crate::memory::sram::{read_save_file, write_save_file};
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Modelos de EEPROM suportados
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EepromChip {
+    /// Xicor X24C01 (128 bytes, sem byte de dispositivo)
+    X24C01,
+    /// 24C01 (128 bytes)
+    C24C01,
+    /// 24C02 (256 bytes)
+    C24C02,
+    /// 24C04 (512 bytes)
+    C24C04,
+    /// 24C08 (1 KB)
+    C24C08,
+    /// 24C16 (2 KB)
+    C24C16,
+    /// 24C32 (4 KB)
+    C24C32,
+    /// 24C64 (8 KB)
+    C24C64,
+    /// 24C65 (8 KB, páginas de 64 bytes)
+    C24C65,
+}
+
+/// Forma de endereçamento do chip
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Addressing {
+    /// Endereço de 7 bits no byte de controle (X24C01)
+    Mode1,
+    /// Byte de dispositivo + 1 byte de endereço
+    Mode2,
+    /// Byte de dispositivo + 2 bytes de endereço
+    Mode3,
+}
+
+impl EepromChip {
+    /// Capacidade em bytes
+    pub fn size(&self) -> usize {
+        match self {
+            EepromChip::X24C01 | EepromChip::C24C01 => 128,
+            EepromChip::C24C02 => 256,
+            EepromChip::C24C04 => 512,
+            EepromChip::C24C08 => 1024,
+            EepromChip::C24C16 => 2048,
+            EepromChip::C24C32 => 4096,
+            EepromChip::C24C64 | EepromChip::C24C65 => 8192,
+        }
+    }
+
+    /// Tamanho da página de escrita em bytes
+    pub fn page_size(&self) -> usize {
+        match self {
+            EepromChip::X24C01 => 4,
+            EepromChip::C24C01 | EepromChip::C24C02 => 8,
+            EepromChip::C24C04 | EepromChip::C24C08 | EepromChip::C24C16 => 16,
+            EepromChip::C24C32 | EepromChip::C24C64 => 32,
+            EepromChip::C24C65 => 64,
+        }
+    }
+
+    fn addressing(&self) -> Addressing {
+        match self {
+            EepromChip::X24C01 => Addressing::Mode1,
+            EepromChip::C24C32 | EepromChip::C24C64 | EepromChip::C24C65 => Addressing::Mode3,
+            _ => Addressing::Mode2,
+        }
+    }
+}
+
+/// Ligação das linhas da EEPROM no barramento do cartucho
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EepromPins {
+    /// Endereço/bit onde o 68000 escreve SDA
+    pub sda_in_addr: u32,
+    pub sda_in_bit: u8,
+    /// Endereço/bit onde o 68000 lê SDA
+    pub sda_out_addr: u32,
+    pub sda_out_bit: u8,
+    /// Endereço/bit onde o 68000 escreve SCL
+    pub scl_addr: u32,
+    pub scl_bit: u8,
+}
+
+impl EepromPins {
+    /// Cartuchos SEGA (Wonder Boy in Monster World, Mega Man: The Wily Wars)
+    pub const SEGA: Self = Self::new((0x200001, 0), (0x200001, 0), (0x200001, 1));
+    /// Acclaim 16 Mbit (NBA Jam)
+    pub const ACCLAIM_16M: Self = Self::new((0x200001, 0), (0x200001, 1), (0x200000, 0));
+    /// Acclaim 32 Mbit (NBA Jam TE, NFL Quarterback Club)
+    pub const ACCLAIM_32M: Self = Self::new((0x200000, 0), (0x200001, 0), (0x200000, 1));
+    /// Electronic Arts (NHLPA Hockey '93, Rings of Power)
+    pub const EA: Self = Self::new((0x200001, 7), (0x200001, 7), (0x200001, 6));
+    /// Codemasters (Micro Machines, Brian Lara Cricket)
+    pub const CODEMASTERS: Self = Self::new((0x300000, 0), (0x380001, 7), (0x300000, 1));
+
+    /// Cria uma pinagem a partir de pares (endereço, bit) de SDA in, SDA out e SCL
+    pub const fn new(sda_in: (u32, u8), sda_out: (u32, u8), scl: (u32, u8)) -> Self {
+        Self {
+            sda_in_addr: sda_in.0,
+            sda_in_bit: sda_in.1,
+            sda_out_addr: sda_out.0,
+            sda_out_bit: sda_out.1,
+            scl_addr: scl.0,
+            scl_bit: scl.1,
+        }
+    }
+
+    /// Retorna true se `addr` é um dos endereços ligados à EEPROM
+    pub fn contains(&self, addr: u32) -> bool {
+        addr == self.sda_in_addr || addr == self.sda_out_addr || addr == self.scl_addr
+    }
+}
+
+/// Configuração da EEPROM de um jogo
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EepromConfig {
+    pub chip: EepromChip,
+    pub pins: EepromPins,
+}
+
+impl EepromConfig {
+    /// Configuração usada quando o mapper é forçado para EEPROM sem
+    /// o jogo ser conhecido
+    pub fn fallback() -> Self {
+        Self {
+            chip: EepromChip::X24C01,
+            pins: EepromPins::SEGA,
+        }
+    }
+}
+
+/// Fase do protocolo I2C
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum I2cState {
+    /// Aguardando START
+    Standby,
+    /// Recebendo o byte de controle/dispositivo
+    Device,
+    /// Recebendo o byte alto do endereço (modo 3)
+    AddressHigh,
+    /// Recebendo o byte baixo do endereço
+    AddressLow,
+    /// Recebendo dados para escrita
+    Write,
+    /// Confirmando um pedido de leitura (os dados começam após o ACK)
+    ReadAck,
+    /// Enviando dados para o 68000
+    Read,
+}
+
+/// EEPROM serial com estado do protocolo I2C
+#[derive(Debug)]
+pub struct Eeprom {
+    pub config: EepromConfig,
+    data: Vec<u8>,
+    state: I2cState,
+    /// Bits transferidos no byte atual (8 = ciclo de ACK)
+    bit_count: u8,
+    shift: u8,
+    /// Endereço de palavra atual
+    address: usize,
+    /// Último nível das linhas escritas pelo 68000
+    scl: bool,
+    sda: bool,
+    /// Nível que a EEPROM coloca em SDA (true = linha liberada)
+    sda_out: bool,
+    /// O 68000 confirmou (ACK) o último byte lido
+    master_ack: bool,
+    dirty: bool,
+    path: Option<PathBuf>,
+}
+
+impl Eeprom {
+    /// Cria uma EEPROM apagada (0xFF)
+    pub fn new(config: EepromConfig) -> Self {
+        Self {
+            config,
+            data: vec![0xFF; config.chip.size()],
+            state: I2cState::Standby,
+            bit_count: 0,
+            shift: 0,
+            address: 0,
+            scl: true,
+            sda: true,
+            sda_out: true,
+            master_ack: false,
+            dirty: false,
+            path: None,
+        }
+    }
+
+    /// Retorna true se `addr` é um dos pinos da EEPROM
+    pub fn contains(&self, addr: u32) -> bool {
+        self.config.pins.contains(addr)
+    }
+
+    /// Leitura do 68000: só o endereço de SDA de saída é decodificado
+    pub fn read8(&self, addr: u32) -> Option<u8> {
+        let pins = &self.config.pins;
+        (addr == pins.sda_out_addr).then(|| ((self.sda && self.sda_out) as u8) << pins.sda_out_bit)
+    }
+
+    /// Escrita do 68000 nos pinos SDA/SCL
+    pub fn write8(&mut self, addr: u32, value: u8) {
+        let pins = self.config.pins;
+        let mut scl = self.scl;
+        let mut sda = self.sda;
+        if addr == pins.scl_addr {
+            scl = value & (1 << pins.scl_bit) != 0;
+        }
+        if addr == pins.sda_in_addr {
+            sda = value & (1 << pins.sda_in_bit) != 0;
+        }
+        if addr == pins.scl_addr || addr == pins.sda_in_addr {
+            self.set_lines(scl, sda);
+        }
+    }
+
+    /// Atualiza as linhas e avança a máquina de estados I2C
+    pub fn set_lines(&mut self, scl: bool, sda: bool) {
+        let (old_scl, old_sda) = (self.scl, self.sda);
+        self.scl = scl;
+        self.sda = sda;
+
+        if old_scl && scl {
+            if old_sda && !sda {
+                self.start();
+            } else if !old_sda && sda {
+                self.stop();
+            }
+        } else if !old_scl && scl {
+            self.clock_rise();
+        } else if old_scl && !scl {
+            self.clock_fall();
+        }
+    }
+
+    fn start(&mut self) {
+        self.state = I2cState::Device;
+        self.bit_count = 0;
+        self.shift = 0;
+        self.sda_out = true;
+    }
+
+    fn stop(&mut self) {
+        self.state = I2cState::Standby;
+        self.bit_count = 0;
+        self.sda_out = true;
+    }
+
+    /// Borda de subida de SCL: o receptor amostra SDA
+    fn clock_rise(&mut self) {
+        match self.state {
+            I2cState::Standby => {}
+            I2cState::Read => {
+                if self.bit_count < 8 {
+                    self.bit_count += 1;
+                } else if self.bit_count == 8 {
+                    self.master_ack = !self.sda;
+                    self.bit_count = 9;
+                }
+            }
+            _ => {
+                if self.bit_count < 8 {
+                    self.shift = (self.shift << 1) | self.sda as u8;
+                    self.bit_count += 1;
+                }
+            }
+        }
+    }
+
+    /// Borda de descida de SCL: o transmissor troca o nível de SDA
+    fn clock_fall(&mut self) {
+        match self.state {
+            I2cState::Standby => {}
+            I2cState::Read => match self.bit_count {
+                1..=7 => self.sda_out = self.current_byte() & (0x80 >> self.bit_count) != 0,
+                8 => self.sda_out = true, // libera SDA para o ACK do 68000
+                9 => {
+                    if self.master_ack {
+                        self.address = (self.address + 1) % self.data.len();
+                        self.begin_read_byte();
+                    } else {
+                        self.state = I2cState::Standby;
+                        self.bit_count = 0;
+                    }
+                }
+                _ => {}
+            },
+            _ => match self.bit_count {
+                8 => {
+                    let ack = self.receive_byte(self.shift);
+                    self.sda_out = !ack;
+                    self.bit_count = 9;
+                }
+                9 => {
+                    self.sda_out = true;
+                    self.bit_count = 0;
+                    self.shift = 0;
+                    if self.state == I2cState::ReadAck {
+                        self.state = I2cState::Read;
+                        self.begin_read_byte();
+                    }
+                }
+                _ => {}
+            },
+        }
+    }
+
+    fn current_byte(&self) -> u8 {
+        self.data[self.address % self.data.len()]
+    }
+
+    /// Começa a enviar o byte do endereço atual (primeiro bit já em SDA)
+    fn begin_read_byte(&mut self) {
+        self.bit_count = 0;
+        self.sda_out = self.current_byte() & 0x80 != 0;
+    }
+
+    /// Processa um byte recebido e define o próximo estado.
+    /// Retorna true se a EEPROM confirma (ACK) o byte.
+    fn receive_byte(&mut self, byte: u8) -> bool {
+        let size = self.data.len();
+        let addressing = self.config.chip.addressing();
+        match self.state {
+            I2cState::Device => match addressing {
+                Addressing::Mode1 => {
+                    self.address = (byte >> 1) as usize % size;
+                    self.state = if byte & 1 != 0 { I2cState::ReadAck } else { I2cState::Write };
+                    true
+                }
+                Addressing::Mode2 | Addressing::Mode3 => {
+                    if byte & 0xF0 != 0xA0 {
+                        self.state = I2cState::Standby;
+                        return false;
+                    }
+                    if byte & 1 != 0 {
+                        self.state = I2cState::ReadAck;
+                    } else if addressing == Addressing::Mode2 {
+                        // Bits A2–A0 selecionam o bloco de 256 bytes nos chips maiores
+                        self.address = (((byte >> 1) & 0x07) as usize) << 8;
+                        self.state = I2cState::AddressLow;
+                    } else {
+                        self.state = I2cState::AddressHigh;
+                    }
+                    true
+                }
+            },
+            I2cState::AddressHigh => {
+                self.address = (byte as usize) << 8;
+                self.state = I2cState::AddressLow;
+                true
+            }
+            I2cState::AddressLow => {
+                self.address = ((self.address & !0xFF) | byte as usize) % size;
+                self.state = I2cState::Write;
+                true
+            }
+            I2cState::Write => {
+                self.write_byte(byte);
+                true
+            }
+            I2cState::ReadAck | I2cState::Read | I2cState::Standby => false,
+        }
+    }
+
+    /// Escreve um byte e avança o endereço dentro da página
+    fn write_byte(&mut self, byte: u8) {
+        let page = self.config.chip.page_size();
+        let address = self.address % self.data.len();
+        if self.data[address] != byte {
+            self.data[address] = byte;
+            self.dirty = true;
+        }
+        self.address = (address & !(page - 1)) | ((address + 1) & (page - 1));
+    }
+
+    /// Conteúdo bruto da EEPROM
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Retorna true se há alterações ainda não gravadas em disco
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Associa um arquivo de save e carrega o conteúdo dele, se existir
+    pub fn attach_file(&mut self, path: PathBuf) -> io::Result<()> {
+        if let Some(bytes) = read_save_file(&path)? {
+            info!("EEPROM carregada de {} ({} bytes)", path.display(), bytes.len());
+            let len = bytes.len().min(self.data.len());
+            self.data[..len].copy_from_slice(&bytes[..len]);
+        }
+        self.path = Some(path);
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// Arquivo de save associado, se houver
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Grava a EEPROM no arquivo associado se houve alterações.
+    /// Retorna true se o arquivo foi escrito.
+    pub fn flush(&mut self) -> io::Result<bool> {
+        let Some(path) = &self.path else {
+            return Ok(false);
+        };
+        if !self.dirty {
+            return Ok(false);
+        }
+        write_save_file(path, &self.data)?;
+        self.dirty = false;
+        Ok(true)
+    }
+}
+
+impl Drop for Eeprom {
+    fn drop(&mut self) {
+        if let Err(e) = self.flush() {
+            error!("Falha ao gravar EEPROM: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Helpers que imitam o bit-banging feito pelo 68000
+
+    fn start(e: &mut Eeprom) {
+        e.set_lines(true, true);
+        e.set_lines(true, false);
+        e.set_lines(false, false);
+    }
+
+    fn stop(e: &mut Eeprom) {
+        e.set_lines(false, false);
+        e.set_lines(true, false);
+        e.set_lines(true, true);
+    }
+
+    /// Envia um byte e retorna true se a EEPROM respondeu com ACK
+    fn send_byte(e: &mut Eeprom, byte: u8) -> bool {
+        for i in (0..8).rev() {
+            let bit = byte & (1 << i) != 0;
+            e.set_lines(false, bit);
+            e.set_lines(true, bit);
+            e.set_lines(false, bit);
+        }
+        e.set_lines(false, true);
+        e.set_lines(true, true);
+        let ack = !e.sda_out;
+        e.set_lines(false, true);
+        ack
+    }
+
+    fn recv_byte(e: &mut Eeprom, ack: bool) -> u8 {
+        let mut byte = 0;
+        for _ in 0..8 {
+            e.set_lines(false, true);
+            e.set_lines(true, true);
+            byte = (byte << 1) | e.sda_out as u8;
+            e.set_lines(false, true);
+        }
+        e.set_lines(false, !ack);
+        e.set_lines(true, !ack);
+        e.set_lines(false, !ack);
+        byte
+    }
+
+    fn chip(chip: EepromChip) -> Eeprom {
+        Eeprom::new(EepromConfig { chip, pins: EepromPins::SEGA })
+    }
+
+    #[test]
+    fn test_24c02_random_write_and_sequential_read() {
+        let mut e = chip(EepromChip::C24C02);
+        start(&mut e);
+        assert!(send_byte(&mut e, 0xA0));
+        assert!(send_byte(&mut e, 0x10));
+        assert!(send_byte(&mut e, 0x12));
+        assert!(send_byte(&mut e, 0x34));
+        stop(&mut e);
+        assert_eq!(&e.data()[0x10..0x12], &[0x12, 0x34]);
+        assert!(e.is_dirty());
+
+        // Leitura aleatória: escrita "falsa" do endereço + START repetido
+        start(&mut e);
+        send_byte(&mut e, 0xA0);
+        send_byte(&mut e, 0x10);
+        start(&mut e);
+        assert!(send_byte(&mut e, 0xA1));
+        assert_eq!(recv_byte(&mut e, true), 0x12);
+        assert_eq!(recv_byte(&mut e, false), 0x34);
+        stop(&mut e);
+    }
+
+    #[test]
+    fn test_x24c01_mode1_and_page_wrap() {
+        let mut e = chip(EepromChip::X24C01);
+        start(&mut e);
+        send_byte(&mut e, 0x06 << 1); // endereço 6, escrita
+        for b in [1, 2, 3] {
+            send_byte(&mut e, b);
+        }
+        stop(&mut e);
+        // página de 4 bytes: 6, 7, depois volta para 4
+        assert_eq!(&e.data()[4..8], &[3, 0xFF, 1, 2]);
+
+        start(&mut e);
+        send_byte(&mut e, (0x06 << 1) | 1);
+        assert_eq!(recv_byte(&mut e, false), 1);
+        stop(&mut e);
+    }
+
+    #[test]
+    fn test_mode3_two_address_bytes_and_device_check() {
+        let mut e = chip(EepromChip::C24C65);
+        start(&mut e);
+        assert!(!send_byte(&mut e, 0x50)); // não é 1010xxxx: sem ACK
+        stop(&mut e);
+
+        start(&mut e);
+        send_byte(&mut e, 0xA0);
+        send_byte(&mut e, 0x12);
+        send_byte(&mut e, 0x34);
+        send_byte(&mut e, 0x99);
+        stop(&mut e);
+        assert_eq!(e.data()[0x1234], 0x99);
+    }
+
+    #[test]
+    fn test_pin_mapping_through_bus_writes() {
+        let mut e = Eeprom::new(EepromConfig { chip: EepromChip::C24C02, pins: EepromPins::ACCLAIM_16M });
+        assert!(e.contains(0x200000));
+        // SCL alto (0x200000 bit 0) e SDA alto (0x200001 bit 0): linha ociosa
+        e.write8(0x200000, 0x01);
+        e.write8(0x200001, 0x01);
+        assert_eq!(e.read8(0x200001), Some(0x02));
+        // START
+        e.write8(0x200001, 0x00);
+        assert_eq!(e.state, I2cState::Device);
+        assert_eq!(e.read8(0x200002), None);
+    }
+}
@@ -0,0 +1,385 @@
+//! Flash-cart para homebrew (Mega EverDrive, mapper SSF estendido)
+//!
+//! O jogo roda de uma PSRAM que o cartucho expõe em oito janelas de 512 KB,
+//! como o mapper SEGA, mas com registradores próprios em 0xA130xx:
+//! - 0xA130E2: dado da porta USB (byte em 0xA130E3). A leitura consome um
+//!   byte recebido do host; a escrita envia um byte ao host;
+//! - 0xA130E4: estado (byte em 0xA130E5) — bit 0: pode escrever na USB,
+//!   bit 1: há dados para ler;
+//! - 0xA130F0: controle — bit 15 (P) libera a escrita no registrador,
+//!   bit 13 (W) libera a escrita na área da ROM, bit 12 (L) liga o LED. O
+//!   byte baixo (0xA130F1) escolhe o banco da janela 0;
+//! - 0xA130F3–0xA130FF: banco das janelas 1–7.
+//!
+//! A porta USB do emulador é um socket Unix local ou um par de arquivos
+//! (entrada lida incrementalmente, saída em modo append). Os dados são
+//! trocados com o host uma vez por quadro (`UsbPort::poll`).
+//!
+//! Como a ROM pode ser alterada pelo próprio jogo, a área do cartucho
+//! nunca é lida direto pela tabela de páginas do barramento.
+
+use crate::memory::mapper::{SEGA_BANK_SIZE, SEGA_WINDOWS};
+use crate::memory::rom::Rom;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fs::{File, OpenOptions};
+use std::io::{self, ErrorKind, Read, Write};
+use std::path::Path;
+#[cfg(unix)]
+use std::os::unix::fs::FileTypeExt;
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+
+/// Dado da porta USB (byte baixo de 0xA130E2)
+pub const USB_DATA_ADDR: u32 = 0xA130E3;
+/// Estado da porta USB (byte baixo de 0xA130E4)
+pub const USB_STATUS_ADDR: u32 = 0xA130E5;
+/// Registrador de controle do mapper (byte alto)
+pub const FLASH_CTRL_ADDR: u32 = 0xA130F0;
+
+/// Bits do byte alto do controle
+pub const CTRL_PROTECT: u8 = 0x80;
+pub const CTRL_WRITE: u8 = 0x20;
+
+/// Bits do estado da USB
+pub const USB_TX_READY: u8 = 0x01;
+pub const USB_RX_READY: u8 = 0x02;
+
+/// Tamanho mínimo da PSRAM exposta (área inteira do cartucho)
+const MIN_MEMORY_SIZE: usize = 0x400000;
+
+/// Estado do flash-cart
+#[derive(Debug)]
+pub struct FlashCart {
+    /// PSRAM com a ROM carregada
+    memory: Vec<u8>,
+    /// Byte alto do registrador de controle
+    pub ctrl: u8,
+    /// Banco de 512 KB de cada janela
+    pub banks: [usize; SEGA_WINDOWS],
+    pub usb: UsbPort,
+}
+
+impl FlashCart {
+    /// Carrega a ROM na PSRAM (arredondada para bancos de 512 KB)
+    pub fn new(rom: &Rom) -> Self {
+        let size = rom.size().next_multiple_of(SEGA_BANK_SIZE).max(MIN_MEMORY_SIZE);
+        let mut memory = vec![0xFF; size];
+        memory[..rom.size()].copy_from_slice(rom.data());
+        Self {
+            memory,
+            ctrl: 0,
+            banks: std::array::from_fn(|window| window),
+            usb: UsbPort::default(),
+        }
+    }
+
+    /// Volta os bancos e o controle ao power-on (a PSRAM e a USB ficam)
+    pub fn reset(&mut self) {
+        self.ctrl = 0;
+        self.banks = std::array::from_fn(|window| window);
+    }
+
+    /// Retorna true se o jogo liberou a escrita na área da ROM
+    pub fn rom_writable(&self) -> bool {
+        self.ctrl & (CTRL_PROTECT | CTRL_WRITE) == CTRL_PROTECT | CTRL_WRITE
+    }
+
+    fn bank_count(&self) -> usize {
+        self.memory.len() / SEGA_BANK_SIZE
+    }
+
+    fn offset(&self, addr: u32) -> usize {
+        let window = (addr as usize / SEGA_BANK_SIZE) % SEGA_WINDOWS;
+        self.banks[window] * SEGA_BANK_SIZE + addr as usize % SEGA_BANK_SIZE
+    }
+
+    /// Leitura na área do cartucho (0x000000–0x3FFFFF)
+    pub fn read8(&self, addr: u32) -> u8 {
+        self.memory[self.offset(addr)]
+    }
+
+    /// Leitura dos registradores (0xA130xx). `None` se não é do flash-cart.
+    pub fn read_register(&self, addr: u32) -> Option<u8> {
+        match addr {
+            USB_DATA_ADDR => Some(self.usb.read()),
+            USB_STATUS_ADDR => Some(self.usb.status()),
+            FLASH_CTRL_ADDR => Some(self.ctrl),
+            0xA130F1 => Some(self.banks[0] as u8),
+            0xA130E2 | 0xA130E4 => Some(0),
+            _ => None,
+        }
+    }
+
+    /// Escrita na área do cartucho ou nos registradores. Retorna false se
+    /// o acesso não é tratado pelo flash-cart.
+    pub fn write8(&mut self, addr: u32, value: u8) -> bool {
+        match addr {
+            0x000000..=0x3FFFFF => {
+                if self.rom_writable() {
+                    let offset = self.offset(addr);
+                    self.memory[offset] = value;
+                }
+            }
+            USB_DATA_ADDR => self.usb.write(value),
+            0xA130E2 => {}
+            // Sem o bit P a escrita no controle é ignorada
+            FLASH_CTRL_ADDR => {
+                if value & CTRL_PROTECT != 0 {
+                    self.ctrl = value;
+                }
+            }
+            0xA130F1 => {
+                if self.ctrl & CTRL_PROTECT != 0 {
+                    self.banks[0] = value as usize % self.bank_count();
+                }
+            }
+            0xA130F3..=0xA130FF if addr & 1 == 1 => {
+                let window = ((addr - 0xA130F1) >> 1) as usize;
+                self.banks[window] = value as usize % self.bank_count();
+            }
+            _ => return false,
+        }
+        true
+    }
+}
+
+// =====================================================
+// PORTA USB
+// =====================================================
+
+/// Ligação da porta USB com o host
+#[derive(Debug, Default)]
+pub enum UsbLink {
+    /// Nada conectado: escritas são descartadas
+    #[default]
+    None,
+    /// Socket Unix local; um cliente por vez
+    #[cfg(unix)]
+    Socket {
+        listener: UnixListener,
+        stream: Option<UnixStream>,
+    },
+    /// Par de arquivos: bytes acrescentados à entrada chegam ao jogo, e o
+    /// que o jogo envia é acrescentado à saída
+    Files { input: File, output: File },
+}
+
+impl UsbLink {
+    /// Cria o socket Unix em `path` (substituindo um socket antigo)
+    #[cfg(unix)]
+    pub fn socket(path: &Path) -> io::Result<Self> {
+        Ok(Self::Socket { listener: bind_socket(path)?, stream: None })
+    }
+
+    /// Abre o par de arquivos (a entrada é criada vazia se não existir)
+    pub fn files(input: &Path, output: &Path) -> io::Result<Self> {
+        let input = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(input)?;
+        let output = OpenOptions::new().create(true).append(true).open(output)?;
+        Ok(Self::Files { input, output })
+    }
+}
+
+/// Cria um socket Unix não bloqueante em `path`. Um socket antigo no
+/// caminho é substituído; qualquer outro arquivo é preservado e vira erro.
+#[cfg(unix)]
+pub fn bind_socket(path: &Path) -> io::Result<UnixListener> {
+    match std::fs::symlink_metadata(path) {
+        Ok(meta) if meta.file_type().is_socket() => std::fs::remove_file(path)?,
+        Ok(_) => {
+            return Err(io::Error::new(
+                ErrorKind::AlreadyExists,
+                format!("{} já existe e não é um socket", path.display()),
+            ))
+        }
+        Err(e) if e.kind() == ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+    let listener = UnixListener::bind(path)?;
+    listener.set_nonblocking(true)?;
+    Ok(listener)
+}
+
+/// FIFOs da porta USB
+#[derive(Debug, Default)]
+pub struct UsbPort {
+    /// Bytes recebidos do host e ainda não lidos pelo jogo
+    rx: RefCell<VecDeque<u8>>,
+    /// Bytes enviados pelo jogo e ainda não entregues ao host
+    tx: Vec<u8>,
+    pub link: UsbLink,
+}
+
+impl UsbPort {
+    /// Estado visto pelo jogo em 0xA130E5
+    pub fn status(&self) -> u8 {
+        let rx = if self.rx.borrow().is_empty() { 0 } else { USB_RX_READY };
+        USB_TX_READY | rx
+    }
+
+    /// Consome um byte recebido (0 se a FIFO está vazia)
+    pub fn read(&self) -> u8 {
+        self.rx.borrow_mut().pop_front().unwrap_or(0)
+    }
+
+    pub fn write(&mut self, value: u8) {
+        self.tx.push(value);
+    }
+
+    /// Troca dados com o host: aceita uma conexão pendente, lê o que chegou
+    /// e envia o que o jogo escreveu. Erros de E/S derrubam a conexão.
+    pub fn poll(&mut self) {
+        let mut buf = [0u8; 4096];
+        let rx = self.rx.get_mut();
+        match &mut self.link {
+            UsbLink::None => self.tx.clear(),
+            #[cfg(unix)]
+            UsbLink::Socket { listener, stream } => {
+                if stream.is_none() {
+                    match listener.accept() {
+                        Ok((client, _)) => match client.set_nonblocking(true) {
+                            Ok(()) => {
+                                log::info!("USB: host conectado");
+                                *stream = Some(client);
+                            }
+                            Err(e) => log::warn!("USB: conexão recusada: {}", e),
+                        },
+                        Err(e) if e.kind() == ErrorKind::WouldBlock => {}
+                        Err(e) => log::warn!("USB: falha ao aceitar conexão: {}", e),
+                    }
+                }
+                if let Some(client) = stream {
+                    let result = read_available(client, rx, &mut buf)
+                        .and_then(|open| write_pending(client, &mut self.tx).map(|_| open));
+                    match result {
+                        Ok(true) => {}
+                        Ok(false) => {
+                            log::info!("USB: host desconectado");
+                            *stream = None;
+                        }
+                        Err(e) => {
+                            log::warn!("USB: conexão perdida: {}", e);
+                            *stream = None;
+                        }
+                    }
+                }
+            }
+            UsbLink::Files { input, output } => {
+                let result = read_available(input, rx, &mut buf).and_then(|_| write_pending(output, &mut self.tx));
+                if let Err(e) = result {
+                    log::warn!("USB: falha de E/S nos arquivos: {}", e);
+                }
+            }
+        }
+    }
+}
+
+/// Lê tudo o que está disponível sem bloquear. Retorna false se a outra
+/// ponta fechou a conexão (fim de arquivo num socket).
+fn read_available(source: &mut impl Read, rx: &mut VecDeque<u8>, buf: &mut [u8]) -> io::Result<bool> {
+    loop {
+        match source.read(buf) {
+            Ok(0) => return Ok(false),
+            Ok(n) => rx.extend(&buf[..n]),
+            Err(e) if e.kind() == ErrorKind::WouldBlock => return Ok(true),
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Envia os bytes pendentes; o que não couber fica para o próximo quadro
+fn write_pending(sink: &mut impl Write, tx: &mut Vec<u8>) -> io::Result<()> {
+    while !tx.is_empty() {
+        match sink.write(tx) {
+            Ok(0) => break,
+            Ok(n) => {
+                tx.drain(..n);
+            }
+            Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    sink.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_banks_and_rom_write_enable() {
+        let data = (0..0x100000).map(|i| (i / SEGA_BANK_SIZE) as u8 + 1).collect::<Vec<u8>>();
+        let mut cart = FlashCart::new(&Rom::new(data));
+        assert_eq!(cart.read8(0x080000), 2);
+
+        // Escrita na ROM ignorada até P e W serem ligados
+        cart.write8(0x000010, 0x55);
+        assert_eq!(cart.read8(0x000010), 1);
+        cart.write8(FLASH_CTRL_ADDR, CTRL_PROTECT | CTRL_WRITE);
+        cart.write8(0x000010, 0x55);
+        assert_eq!(cart.read8(0x000010), 0x55);
+
+        // Janela 7 aponta para o banco 1
+        cart.write8(0xA130FF, 1);
+        assert_eq!(cart.read8(0x380000), 2);
+    }
+
+    #[test]
+    fn test_usb_file_pair() {
+        let dir = std::env::temp_dir().join(format!("megastrife-usb-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let (input, output) = (dir.join("in.bin"), dir.join("out.bin"));
+        std::fs::write(&input, b"").unwrap();
+
+        let mut cart = FlashCart::new(&Rom::new(vec![0; 0x1000]));
+        cart.usb.link = UsbLink::files(&input, &output).unwrap();
+        assert_eq!(cart.read_register(USB_STATUS_ADDR), Some(USB_TX_READY));
+
+        std::fs::OpenOptions::new().append(true).open(&input).unwrap().write_all(b"hi").unwrap();
+        cart.write8(USB_DATA_ADDR, b'o');
+        cart.write8(USB_DATA_ADDR, b'k');
+        cart.usb.poll();
+
+        assert_eq!(cart.read_register(USB_STATUS_ADDR), Some(USB_TX_READY | USB_RX_READY));
+        assert_eq!((cart.read_register(USB_DATA_ADDR), cart.read_register(USB_DATA_ADDR)), (Some(b'h'), Some(b'i')));
+        assert_eq!(cart.read_register(USB_STATUS_ADDR), Some(USB_TX_READY));
+        assert_eq!(std::fs::read(&output).unwrap(), b"ok");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_usb_unix_socket() {
+        let path = std::env::temp_dir().join(format!("megastrife-usb-{}.sock", std::process::id()));
+        let mut cart = FlashCart::new(&Rom::new(vec![0; 0x1000]));
+        cart.usb.link = UsbLink::socket(&path).unwrap();
+
+        let mut host = UnixStream::connect(&path).unwrap();
+        host.write_all(b"\x42").unwrap();
+        cart.write8(USB_DATA_ADDR, 0x99);
+        cart.usb.poll();
+        cart.usb.poll();
+
+        assert_eq!(cart.read_register(USB_DATA_ADDR), Some(0x42));
+        let mut reply = [0u8; 1];
+        host.read_exact(&mut reply).unwrap();
+        assert_eq!(reply, [0x99]);
+
+        // Um socket antigo é substituído
+        drop(cart);
+        assert!(UsbLink::socket(&path).is_ok());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_usb_socket_keeps_regular_file() {
+        let path = std::env::temp_dir().join(format!("megastrife-usb-{}.txt", std::process::id()));
+        std::fs::write(&path, b"notas").unwrap();
+        assert!(UsbLink::socket(&path).is_err());
+        assert_eq!(std::fs::read(&path).unwrap(), b"notas");
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,340 @@
+//! Carregamento de ROMs do disco
+//!
+//! Normaliza os formatos de dump mais comuns antes de criar a `Rom`:
+//! - `.bin` / `.gen` / `.md`: imagem linear (com ou sem cabeçalho de copiadora)
+//! - `.smd`: formato Super Magic Drive — cabeçalho de 512 bytes seguido de
+//!   blocos de 16 KB entrelaçados (8 KB de bytes ímpares + 8 KB de bytes pares)
+//! - `.sms`: cartucho de Master System (roda pelo Power Base Converter)
+//! - `.zip`: a ROM é lida direto do arquivo compactado
+//!
+//! Patches IPS/BPS/UPS (ver `patch`) são aplicados depois da normalização
+//! e antes de `Rom::new` ler o cabeçalho.
+//!
+//! A detecção não depende só da extensão: o cabeçalho de copiadora é
+//! reconhecido pelo tamanho (múltiplo de 16 KB + 512) e o entrelaçamento
+//! pela presença de "SEGA" em 0x100 depois da conversão. ROMs de Master
+//! System são reconhecidas pela extensão ou pelo "TMR SEGA" do cabeçalho.
+
+use crate::memory::patch::{self, PatchError};
+use crate::memory::rom::Rom;
+use log::{info, warn};
+use std::io::{Cursor, Read};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Tamanho do cabeçalho de copiadora
+pub const COPIER_HEADER_SIZE: usize = 512;
+/// Tamanho de um bloco entrelaçado SMD
+pub const SMD_BLOCK_SIZE: usize = 16 * 1024;
+
+/// Extensões de ROM aceitas (dentro ou fora de um .zip)
+pub const ROM_EXTENSIONS: [&str; 5] = ["bin", "gen", "md", "smd", "sms"];
+
+/// Erros ao carregar uma ROM
+#[derive(Debug, Error)]
+pub enum LoadError {
+    #[error("Falha ao ler o arquivo: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Arquivo .zip inválido: {0}")]
+    Zip(#[from] zip::result::ZipError),
+
+    #[error("Nenhuma ROM (.bin, .gen, .md, .smd, .sms) encontrada no arquivo compactado")]
+    NoRomInArchive,
+
+    #[error("A ROM está vazia")]
+    Empty,
+
+    #[error("Falha ao aplicar o patch {name}: {source}")]
+    Patch {
+        name: String,
+        #[source]
+        source: PatchError,
+    },
+}
+
+/// Formato original do dump
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DumpFormat {
+    /// Imagem linear
+    Binary,
+    /// Imagem linear com cabeçalho de copiadora removido
+    CopierHeader,
+    /// Super Magic Drive (entrelaçado), convertido para linear
+    Smd,
+}
+
+/// ROM carregada, já normalizada
+pub struct LoadedRom {
+    pub rom: Rom,
+    pub format: DumpFormat,
+    /// Nome do arquivo dentro do .zip, se veio de um
+    pub archive_entry: Option<String>,
+    /// Patches aplicados, na ordem
+    pub patches: Vec<String>,
+    /// Cartucho de Master System (modo Power Base Converter)
+    pub master_system: bool,
+}
+
+/// Retorna a extensão em minúsculas
+fn extension_of(name: &str) -> Option<String> {
+    Path::new(name).extension().and_then(|e| e.to_str()).map(str::to_ascii_lowercase)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default()
+}
+
+/// Carrega uma ROM de um arquivo (.bin, .gen, .md, .smd, .sms ou .zip) e aplica
+/// os patches indicados, na ordem
+pub fn load_file(path: &Path, patches: &[PathBuf]) -> Result<LoadedRom, LoadError> {
+    let bytes = std::fs::read(path)?;
+    let patches = patches
+        .iter()
+        .map(|p| Ok((file_name(p), std::fs::read(p)?)))
+        .collect::<Result<Vec<_>, LoadError>>()?;
+    load_bytes(bytes, &file_name(path), &patches)
+}
+
+/// Carrega uma ROM a partir dos bytes do arquivo. `name` é usado só como
+/// dica de formato (extensão); `patches` são pares (nome, conteúdo).
+pub fn load_bytes(bytes: Vec<u8>, name: &str, patches: &[(String, Vec<u8>)]) -> Result<LoadedRom, LoadError> {
+    let (mut data, format, archive_entry) = read_dump(bytes, name)?;
+
+    for (patch_name, patch_data) in patches {
+        data = patch::apply(patch_data, &data).map_err(|source| LoadError::Patch {
+            name: patch_name.clone(),
+            source,
+        })?;
+        info!("Patch {} aplicado ({} bytes)", patch_name, data.len());
+    }
+    if data.is_empty() {
+        return Err(LoadError::Empty);
+    }
+
+    let rom_name = archive_entry.as_deref().unwrap_or(name);
+    let master_system = extension_of(rom_name).as_deref() == Some("sms")
+        || (!has_sega_signature(&data) && has_tmr_signature(&data));
+    if master_system {
+        info!("ROM de Master System: modo Power Base Converter");
+    }
+
+    Ok(LoadedRom {
+        rom: Rom::new(data),
+        format,
+        archive_entry,
+        patches: patches.iter().map(|(name, _)| name.clone()).collect(),
+        master_system,
+    })
+}
+
+/// Lê o dump (descompactando se preciso) e normaliza para imagem linear
+fn read_dump(bytes: Vec<u8>, name: &str) -> Result<(Vec<u8>, DumpFormat, Option<String>), LoadError> {
+    let ext = extension_of(name);
+    if ext.as_deref() == Some("zip") || bytes.starts_with(b"PK\x03\x04") {
+        let (entry, data) = extract_from_zip(bytes)?;
+        let (data, format, _) = read_dump(data, &entry)?;
+        return Ok((data, format, Some(entry)));
+    }
+
+    if let Some(ext) = ext.as_deref().filter(|e| !ROM_EXTENSIONS.contains(e)) {
+        warn!("Extensão desconhecida \".{}\", tratando como imagem binária", ext);
+    }
+
+    let (data, format) = normalize(bytes, ext.as_deref() == Some("smd"));
+    if data.is_empty() {
+        return Err(LoadError::Empty);
+    }
+    if format != DumpFormat::Binary {
+        info!("ROM convertida do formato {:?} ({} bytes)", format, data.len());
+    }
+    Ok((data, format, None))
+}
+
+/// Escolhe e extrai a ROM de um .zip: a primeira entrada com extensão de
+/// ROM conhecida ou, na falta dela, o maior arquivo.
+fn extract_from_zip(bytes: Vec<u8>) -> Result<(String, Vec<u8>), LoadError> {
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes))?;
+
+    let mut by_extension = None;
+    let mut largest: Option<(usize, u64)> = None;
+    for i in 0..archive.len() {
+        let file = archive.by_index(i)?;
+        if file.is_dir() {
+            continue;
+        }
+        if by_extension.is_none() && extension_of(file.name()).is_some_and(|e| ROM_EXTENSIONS.contains(&e.as_str())) {
+            by_extension = Some(i);
+        }
+        if largest.is_none_or(|(_, size)| file.size() > size) {
+            largest = Some((i, file.size()));
+        }
+    }
+
+    let index = by_extension.or(largest.map(|(i, _)| i)).ok_or(LoadError::NoRomInArchive)?;
+    let mut file = archive.by_index(index)?;
+    let mut data = Vec::with_capacity(file.size() as usize);
+    file.read_to_end(&mut data)?;
+    Ok((file.name().to_string(), data))
+}
+
+/// Retorna true se há "SEGA" no cabeçalho (0x100 ou 0x101, alguns jogos usam " SEGA")
+fn has_sega_signature(data: &[u8]) -> bool {
+    data.get(0x100..0x105).is_some_and(|s| s.starts_with(b"SEGA") || s[1..].starts_with(b"SEGA"))
+}
+
+/// Retorna true se há o "TMR SEGA" do Master System em 0x7FF0, 0x3FF0 ou 0x1FF0
+fn has_tmr_signature(data: &[u8]) -> bool {
+    [0x7FF0, 0x3FF0, 0x1FF0]
+        .iter()
+        .any(|&offset| data.get(offset..offset + 8) == Some(b"TMR SEGA".as_slice()))
+}
+
+/// Remove cabeçalho de copiadora e desfaz o entrelaçamento SMD.
+/// `smd_hint` indica que o arquivo tinha extensão .smd.
+pub fn normalize(mut data: Vec<u8>, smd_hint: bool) -> (Vec<u8>, DumpFormat) {
+    let mut format = DumpFormat::Binary;
+    let mut smd_header = false;
+
+    if data.len() % SMD_BLOCK_SIZE == COPIER_HEADER_SIZE {
+        // Cabeçalho SMD: byte 1 = 0x03, bytes 8–9 = 0xAA 0xBB
+        smd_header = data[1] == 0x03 || (data[8] == 0xAA && data[9] == 0xBB);
+        data.drain(..COPIER_HEADER_SIZE);
+        format = DumpFormat::CopierHeader;
+    }
+
+    if has_sega_signature(&data) || has_tmr_signature(&data) || !data.len().is_multiple_of(SMD_BLOCK_SIZE) {
+        return (data, format);
+    }
+
+    let deinterleaved = deinterleave_smd(&data);
+    if has_sega_signature(&deinterleaved) || smd_header || smd_hint {
+        return (deinterleaved, DumpFormat::Smd);
+    }
+    (data, format)
+}
+
+/// Converte blocos SMD de 16 KB para linear: a primeira metade de cada
+/// bloco tem os bytes ímpares e a segunda, os pares.
+pub fn deinterleave_smd(data: &[u8]) -> Vec<u8> {
+    let half = SMD_BLOCK_SIZE / 2;
+    let mut out = vec![0; data.len()];
+    for (block, dst) in data.chunks_exact(SMD_BLOCK_SIZE).zip(out.chunks_exact_mut(SMD_BLOCK_SIZE)) {
+        for i in 0..half {
+            dst[i * 2] = block[half + i];
+            dst[i * 2 + 1] = block[i];
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    /// ROM linear de 32 KB com "SEGA" em 0x100
+    fn linear_rom() -> Vec<u8> {
+        let mut data: Vec<u8> = (0..2 * SMD_BLOCK_SIZE).map(|i| (i * 7) as u8).collect();
+        data[0x100..0x104].copy_from_slice(b"SEGA");
+        data
+    }
+
+    /// Operação inversa de `deinterleave_smd`
+    fn interleave(data: &[u8]) -> Vec<u8> {
+        let half = SMD_BLOCK_SIZE / 2;
+        let mut out = vec![0; data.len()];
+        for (src, block) in data.chunks_exact(SMD_BLOCK_SIZE).zip(out.chunks_exact_mut(SMD_BLOCK_SIZE)) {
+            for i in 0..half {
+                block[half + i] = src[i * 2];
+                block[i] = src[i * 2 + 1];
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn test_plain_binary_is_untouched() {
+        let loaded = load_bytes(linear_rom(), "jogo.bin", &[]).unwrap();
+        assert_eq!(loaded.format, DumpFormat::Binary);
+        assert_eq!(loaded.rom.data(), linear_rom().as_slice());
+    }
+
+    #[test]
+    fn test_copier_header_is_stripped() {
+        let mut data = vec![0; COPIER_HEADER_SIZE];
+        data.extend(linear_rom());
+        let loaded = load_bytes(data, "jogo.gen", &[]).unwrap();
+        assert_eq!(loaded.format, DumpFormat::CopierHeader);
+        assert!(loaded.rom.header().console_name.starts_with("SEGA"));
+    }
+
+    #[test]
+    fn test_smd_is_deinterleaved() {
+        let mut data = vec![0; COPIER_HEADER_SIZE];
+        data[0] = 2;
+        data[1] = 0x03;
+        data[8] = 0xAA;
+        data[9] = 0xBB;
+        data.extend(interleave(&linear_rom()));
+
+        let loaded = load_bytes(data, "jogo.smd", &[]).unwrap();
+        assert_eq!(loaded.format, DumpFormat::Smd);
+        assert_eq!(loaded.rom.data(), linear_rom().as_slice());
+    }
+
+    #[test]
+    fn test_zip_picks_rom_entry() {
+        let mut buffer = Cursor::new(Vec::new());
+        {
+            let mut zip = zip::ZipWriter::new(&mut buffer);
+            let options = zip::write::SimpleFileOptions::default();
+            zip.start_file("leiame.txt", options).unwrap();
+            zip.write_all(&[b'x'; 40000]).unwrap();
+            zip.start_file("jogo.md", options).unwrap();
+            zip.write_all(&linear_rom()).unwrap();
+            zip.finish().unwrap();
+        }
+
+        let loaded = load_bytes(buffer.into_inner(), "jogo.zip", &[]).unwrap();
+        assert_eq!(loaded.archive_entry.as_deref(), Some("jogo.md"));
+        assert_eq!(loaded.rom.data(), linear_rom().as_slice());
+    }
+
+    #[test]
+    fn test_patch_is_applied_before_header_parse() {
+        let mut ips = b"PATCH".to_vec();
+        ips.extend([0x00, 0x01, 0x20, 0x00, 0x04]);
+        ips.extend(b"HACK");
+        ips.extend(b"EOF");
+
+        let loaded = load_bytes(linear_rom(), "jogo.bin", &[("jogo.ips".to_string(), ips)]).unwrap();
+        assert!(loaded.rom.header().domestic_name.starts_with("HACK"));
+        assert_eq!(loaded.patches, ["jogo.ips"]);
+
+        let bad = load_bytes(linear_rom(), "jogo.bin", &[("ruim.bps".to_string(), b"BPS1".to_vec())]);
+        assert!(matches!(bad, Err(LoadError::Patch { .. })));
+    }
+
+    #[test]
+    fn test_master_system_rom_is_detected() {
+        let mut data: Vec<u8> = (0..2 * SMD_BLOCK_SIZE).map(|i| (i * 3) as u8).collect();
+        data[0x7FF0..0x7FF8].copy_from_slice(b"TMR SEGA");
+        let mut dump = vec![0; COPIER_HEADER_SIZE];
+        dump.extend(&data);
+
+        // Sem extensão útil: só o cabeçalho é removido, nada de entrelaçamento
+        let loaded = load_bytes(dump, "jogo.bin", &[]).unwrap();
+        assert!(loaded.master_system);
+        assert_eq!(loaded.format, DumpFormat::CopierHeader);
+        assert_eq!(loaded.rom.data(), data.as_slice());
+
+        assert!(load_bytes(vec![0; 0x2000], "jogo.sms", &[]).unwrap().master_system);
+        assert!(!load_bytes(linear_rom(), "jogo.bin", &[]).unwrap().master_system);
+    }
+
+    #[test]
+    fn test_empty_rom_is_rejected() {
+        assert!(matches!(load_bytes(Vec::new(), "vazia.bin", &[]), Err(LoadError::Empty)));
+    }
+}
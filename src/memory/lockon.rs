@@ -0,0 +1,193 @@
+//! Cartucho "lock-on" do Sonic & Knuckles
+//!
+//! O cartucho do S&K tem um conector no topo onde outro cartucho é
+//! encaixado. O S&K (2 MB) fica em 0x000000–0x1FFFFF e o cartucho de cima
+//! em 0x200000–0x3FFFFF. O comportamento depende do jogo encaixado:
+//! - Sonic 3: vira "Sonic 3 & Knuckles". A SRAM do Sonic 3 divide a faixa
+//!   com a ROM e é ligada/desligada por 0xA130F1 (como em qualquer cartucho
+//!   com mais de 2 MB).
+//! - Sonic 2: "Knuckles in Sonic 2". O Sonic 2 (1 MB) fica em 0x200000 e
+//!   a ROM de patch de 256 KB do S&K (`sk2chip.bin`) em 0x300000, espelhada
+//!   até 0x3FFFFF.
+//! - Qualquer outro jogo: "Blue Sphere". O S&K só lê o cabeçalho do
+//!   cartucho de cima (em 0x200100) para gerar as fases; apenas os
+//!   primeiros 2 MB dele ficam visíveis.
+//!
+//! As duas (ou três) ROMs são montadas em uma única imagem de 4 MB, para
+//! que o barramento continue lendo a ROM direto pela tabela de páginas.
+
+use crate::gamedb::normalize_serial;
+use crate::memory::rom::Rom;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Tamanho de cada metade da área do cartucho (S&K e cartucho de cima)
+pub const LOCK_ON_HALF: usize = 0x200000;
+/// Tamanho da ROM de patch do Sonic 2
+pub const SK2_PATCH_SIZE: usize = 0x40000;
+/// Nome usual do dump da ROM de patch do Sonic 2
+pub const SK2_PATCH_FILE: &str = "sk2chip.bin";
+
+const SONIC_KNUCKLES_SERIAL: &str = "MK-1563";
+const SONIC_3_SERIAL: &str = "MK-1079";
+const SONIC_2_SERIAL: &str = "00001051";
+
+/// Erros ao montar o lock-on
+#[derive(Debug, Error)]
+pub enum LockOnError {
+    #[error("O lock-on requer a ROM do Sonic & Knuckles como cartucho principal (série encontrada: \"{0}\")")]
+    NotSonicAndKnuckles(String),
+
+    #[error("A ROM de patch do Sonic 2 deve ter 256 KB, mas tem {0} bytes")]
+    BadPatchRom(usize),
+}
+
+/// Combinação resultante do cartucho encaixado
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockOnKind {
+    /// Sonic 3 & Knuckles
+    Sonic3,
+    /// Knuckles in Sonic 2 (com a ROM de patch)
+    Sonic2,
+    /// Blue Sphere (qualquer outro cartucho)
+    BlueSphere,
+}
+
+impl LockOnKind {
+    /// Identifica a combinação pela série do cartucho de cima
+    pub fn detect(top: &Rom) -> Self {
+        match normalize_serial(&top.header().serial).as_str() {
+            SONIC_3_SERIAL => Self::Sonic3,
+            SONIC_2_SERIAL => Self::Sonic2,
+            _ => Self::BlueSphere,
+        }
+    }
+}
+
+/// Retorna true se a ROM é o Sonic & Knuckles
+pub fn is_sonic_and_knuckles(rom: &Rom) -> bool {
+    normalize_serial(&rom.header().serial) == SONIC_KNUCKLES_SERIAL
+}
+
+/// Caminho padrão da ROM de patch do Sonic 2: ao lado da ROM do S&K
+pub fn default_patch_path(sk_path: &Path) -> PathBuf {
+    sk_path.with_file_name(SK2_PATCH_FILE)
+}
+
+/// Cartucho encaixado no S&K
+#[derive(Debug, Clone)]
+pub struct LockOn {
+    pub kind: LockOnKind,
+    /// ROM do cartucho de cima (sem alterações)
+    pub top: Rom,
+    /// ROM de patch mapeada em 0x300000 (só no Sonic 2)
+    pub patch: Option<Rom>,
+}
+
+impl LockOn {
+    /// Monta o lock-on. A ROM de patch só é usada com o Sonic 2; sem ela o
+    /// Sonic 2 roda como Blue Sphere.
+    pub fn new(sk: &Rom, top: Rom, patch: Option<Rom>) -> Result<Self, LockOnError> {
+        if !is_sonic_and_knuckles(sk) {
+            return Err(LockOnError::NotSonicAndKnuckles(sk.header().serial.clone()));
+        }
+        if let Some(patch) = &patch {
+            if patch.size() != SK2_PATCH_SIZE {
+                return Err(LockOnError::BadPatchRom(patch.size()));
+            }
+        }
+
+        let kind = match LockOnKind::detect(&top) {
+            LockOnKind::Sonic2 if patch.is_none() => {
+                log::warn!("ROM de patch do Sonic 2 ({}) ausente: o S&K vai rodar o Blue Sphere", SK2_PATCH_FILE);
+                LockOnKind::BlueSphere
+            }
+            kind => kind,
+        };
+        let patch = patch.filter(|_| kind == LockOnKind::Sonic2);
+        Ok(Self { kind, top, patch })
+    }
+
+    /// Imagem de 4 MB vista pelo 68000 (S&K + cartucho de cima)
+    pub fn compose(&self, sk: &Rom) -> Vec<u8> {
+        let mut image = vec![0xFF; 2 * LOCK_ON_HALF];
+        fill_mirrored(&mut image[..LOCK_ON_HALF], sk.data());
+
+        let upper = &mut image[LOCK_ON_HALF..];
+        match &self.patch {
+            Some(patch) => {
+                let (game, patch_area) = upper.split_at_mut(LOCK_ON_HALF / 2);
+                fill_mirrored(game, self.top.data());
+                fill_mirrored(patch_area, patch.data());
+            }
+            None => fill_mirrored(upper, self.top.data()),
+        }
+        image
+    }
+}
+
+/// Preenche `dst` com `src`, repetindo se `src` for menor (linhas de
+/// endereço não decodificadas) e truncando se for maior
+fn fill_mirrored(dst: &mut [u8], src: &[u8]) {
+    if src.is_empty() {
+        return;
+    }
+    for chunk in dst.chunks_mut(src.len()) {
+        chunk.copy_from_slice(&src[..chunk.len()]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rom_with_serial(serial: &[u8], size: usize, fill: u8) -> Rom {
+        let mut data = vec![fill; size];
+        data[0x180..0x180 + serial.len()].copy_from_slice(serial);
+        Rom::new(data)
+    }
+
+    #[test]
+    fn test_sonic3_maps_top_cartridge_at_2mb() {
+        let sk = rom_with_serial(b"GM MK-1563 -00", LOCK_ON_HALF, 0x11);
+        let s3 = rom_with_serial(b"GM MK-1079 -00", LOCK_ON_HALF, 0x33);
+        let lock_on = LockOn::new(&sk, s3, None).unwrap();
+        assert_eq!(lock_on.kind, LockOnKind::Sonic3);
+
+        let image = lock_on.compose(&sk);
+        assert_eq!(image.len(), 0x400000);
+        assert_eq!((image[0x1FFFFF], image[0x200000]), (0x11, 0x33));
+    }
+
+    #[test]
+    fn test_sonic2_gets_patch_rom() {
+        let sk = rom_with_serial(b"GM MK-1563 -00", LOCK_ON_HALF, 0x11);
+        let s2 = rom_with_serial(b"GM 00001051-00", 0x100000, 0x22);
+        let patch = Rom::new(vec![0x55; SK2_PATCH_SIZE]);
+
+        let lock_on = LockOn::new(&sk, s2.clone(), Some(patch)).unwrap();
+        assert_eq!(lock_on.kind, LockOnKind::Sonic2);
+        let image = lock_on.compose(&sk);
+        assert_eq!((image[0x2FFFFF], image[0x300000], image[0x3FFFFF]), (0x22, 0x55, 0x55));
+
+        // Sem a ROM de patch, Blue Sphere
+        assert_eq!(LockOn::new(&sk, s2, None).unwrap().kind, LockOnKind::BlueSphere);
+    }
+
+    #[test]
+    fn test_blue_sphere_and_wrong_base() {
+        let sk = rom_with_serial(b"GM MK-1563 -00", LOCK_ON_HALF, 0x11);
+        let other = rom_with_serial(b"GM MK-1009 -00", 0x80000, 0x44);
+        let lock_on = LockOn::new(&sk, other.clone(), None).unwrap();
+        assert_eq!(lock_on.kind, LockOnKind::BlueSphere);
+        // Cartucho de 512 KB espelhado na área de cima: o cabeçalho aparece em 0x200180
+        let image = lock_on.compose(&sk);
+        assert_eq!(&image[0x200180..0x200182], b"GM");
+        assert_eq!(image[0x380000], 0x44);
+
+        assert!(matches!(
+            LockOn::new(&other, sk, None),
+            Err(LockOnError::NotSonicAndKnuckles(_))
+        ));
+    }
+}
@@ -2,132 +2,548 @@
 //!
 //! Controla o roteamento de endereços entre ROM, SRAM e variações de mapeamento
 //! (SEGA, Codemasters, etc). Fornece suporte básico a EEPROM serial.
+//!
+//! O mapper SEGA (estilo Super Street Fighter II) divide os 4 MB do cartucho
+//! em oito janelas de 512 KB. A janela 0 é fixa no banco 0; as janelas 1–7
+//! são selecionadas pelos registradores 0xA130F3–0xA130FF.
+//!
+//! A SRAM com bateria é independente do tipo de mapper: ela é criada a
+//! partir do descritor "RA" do cabeçalho e controlada por 0xA130F1.
+//! Jogos que salvam em EEPROM I2C são identificados pelo banco de dados de
+//! jogos, que também pode corrigir ou remover a SRAM declarada (`apply_game`).
+//!
+//! O lock-on do Sonic & Knuckles (ver `lockon`) usa o mapeamento linear
+//! sobre a imagem composta das duas ROMs, com a SRAM do cartucho de cima.
+//!
+//! O Virtua Racing traz o SVP (ver `svp`): a ROM é linear e a faixa
+//! 0x300000–0x3FFFFF, além dos registradores em 0xA15000, pertence ao chip.
+//!
+//! Cartuchos não licenciados e piratas (Realtec, Radica, proteções, RPGs
+//! chineses com SRAM chaveada) usam as placas de `unlicensed`.
+//!
+//! O flash-cart de homebrew (mapper SSF estendido, ver `flashcart`) é
+//! escolhido pelo cabeçalho ("SEGA SSF") ou pelo banco de dados.
+//!
+//! Patches de ROM (códigos Game Genie) são aplicados nas leituras pelo
+//! endereço do 68000; páginas com patch deixam o acesso direto do barramento
+//! e passam pelo mapper.
+
+use crate::memory::rom::{Rom, RomHeader, SaveRamInfo};
+use crate::gamedb::{GameEntry, SaveType, Workaround};
+use crate::memory::eeprom::{Eeprom, EepromConfig};
+use crate::memory::flashcart::{FlashCart, UsbLink};
+use crate::memory::lockon::{LockOn, LockOnKind};
+use crate::memory::sram::{Sram, SRAM_CONTROL_ADDR};
+use crate::memory::svp::Svp;
+use crate::memory::unlicensed::{Board, SF001_SRAM};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
 
-use crate::memory::rom::Rom;
-use std::sync::{Arc, Mutex};
+/// Tamanho de uma janela/banco do mapper SEGA (512 KB)
+pub const SEGA_BANK_SIZE: usize = 0x80000;
+/// Número de janelas de 512 KB na área de 4 MB do cartucho
+pub const SEGA_WINDOWS: usize = 8;
 
 /// Tipos de mapper suportados
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum MapperType {
     /// Mapeamento direto padrão (ROM linear de até 4 MB)
     Standard,
-    /// Mapper SEGA — usado por jogos maiores (ex: Sonic 3, Virtua Racing)
+    /// Mapper SEGA — oito janelas de 512 KB (ex: Super Street Fighter II)
     Sega,
     /// Mapper Codemasters — usado em jogos como "Micro Machines"
     Codemasters,
-    /// Mapper com SRAM (bateria)
+    /// Força SRAM de 64 KB em 0x200000 mesmo sem descritor no cabeçalho
     Sram,
-    /// Mapper com EEPROM (serial)
+    /// Força EEPROM serial (X24C01, pinagem SEGA) se o jogo não é conhecido
     Eeprom,
+    /// Sega Virtua Processor — DSP SSP1601 do Virtua Racing
+    Svp,
+    /// Realtec — ROM de boot e blocos escolhidos em 0x400000–0x404000
+    Realtec,
+    /// Radica / "Sega Classics" — banco escolhido por leituras em 0xA130xx
+    Radica,
+    /// Lion King 3, Super King Kong 99 — proteção em 0x600000, banco em 0x700000
+    LionKing3,
+    /// Super Mario World 64 — proteção e bancos em 0x600000–0x67FFFF
+    Smw64,
+    /// Squirrel King — registrador de proteção em 0x400000
+    SquirrelKing,
+    /// SF-001 — RPGs chineses com SRAM chaveada (ex: Beggar Prince)
+    Sf001,
+    /// Flash-cart de homebrew (Mega EverDrive, SSF estendido) com porta USB
+    FlashCart,
+}
+
+impl MapperType {
+    /// Mapper indicado pelo próprio cabeçalho, para jogos fora do banco de
+    /// dados: homebrew para flash-cart declara "SEGA SSF" como console
+    pub fn from_header(header: &RomHeader) -> Self {
+        if header.console_name.contains("SSF") {
+            Self::FlashCart
+        } else {
+            Self::Standard
+        }
+    }
 }
 
 /// Estrutura principal de mapeamento de ROM/SRAM.
 pub struct Mapper {
-    pub rom: Arc<Mutex<Rom>>,
-    pub sram: Option<Vec<u8>>,
+    pub rom: Arc<Rom>,
+    pub sram: Option<Sram>,
+    pub eeprom: Option<Eeprom>,
     pub mapper_type: MapperType,
-    pub bank: usize, // banco ativo para mappers SEGA/Codemasters
+    pub bank: usize, // banco ativo para o mapper Codemasters
+    pub sega_banks: [usize; SEGA_WINDOWS], // banco de 512 KB de cada janela (mapper SEGA)
+    pub banks_dirty: bool, // bancos trocados desde a última consulta do barramento
+    rom_patches: HashMap<u32, u16>, // palavras substituídas (endereço par -> valor)
+    pub lock_on: Option<LockOn>, // cartucho encaixado no Sonic & Knuckles
+    pub svp: Option<Svp>, // DSP do Virtua Racing
+    pub board: Option<Board>, // placa não licenciada/pirata
+    pub flash_cart: Option<FlashCart>, // PSRAM, bancos e USB do flash-cart
 }
 
 impl Mapper {
     /// Cria um novo mapper com a ROM e tipo desejado.
     pub fn new(rom: Rom, mapper_type: MapperType) -> Self {
-        let sram = match mapper_type {
-            MapperType::Sram => Some(vec![0; 0x10000]), // 64KB de SRAM
+        let eeprom = (mapper_type == MapperType::Eeprom).then(|| Eeprom::new(EepromConfig::fallback()));
+        // Cartuchos com EEPROM também costumam declarar "RA" no cabeçalho
+        let sram = match (rom.header().sram, mapper_type) {
+            _ if eeprom.is_some() => None,
+            (_, MapperType::Sf001) => Some(Sram::new(SF001_SRAM, rom.size())),
+            (Some(info), _) => Some(Sram::new(info, rom.size())),
+            (None, MapperType::Sram) => Some(Sram::new(SaveRamInfo::fallback(), rom.size())),
             _ => None,
         };
 
+        let rom = Arc::new(rom);
+        let svp = (mapper_type == MapperType::Svp).then(|| Svp::new(rom.clone()));
+        let flash_cart = (mapper_type == MapperType::FlashCart).then(|| FlashCart::new(&rom));
         Self {
-            rom: Arc::new(Mutex::new(rom)),
+            rom,
             sram,
+            eeprom,
             mapper_type,
             bank: 0,
+            sega_banks: Self::sega_power_on_banks(),
+            banks_dirty: false,
+            rom_patches: HashMap::new(),
+            lock_on: None,
+            svp,
+            board: Board::new(mapper_type),
+            flash_cart,
+        }
+    }
+
+    /// Cria o mapper do Sonic & Knuckles com outro cartucho encaixado.
+    /// A SRAM vem do cabeçalho do cartucho de cima (Sonic 3) e, como a
+    /// imagem tem 4 MB, começa desligada até o jogo escrever em 0xA130F1.
+    pub fn with_lock_on(sk: Rom, lock_on: LockOn) -> Self {
+        let mut mapper = Self::new(Rom::new(lock_on.compose(&sk)), MapperType::Standard);
+        mapper.sram = match lock_on.kind {
+            LockOnKind::Sonic3 => lock_on.top.header().sram.map(|info| Sram::new(info, mapper.rom.size())),
+            _ => None,
+        };
+        mapper.lock_on = Some(lock_on);
+        mapper
+    }
+
+    /// Aplica as configurações do banco de dados de jogos: tipo de save,
+    /// chip/pinagem da EEPROM e correções que afetam o mapper.
+    pub fn apply_game(&mut self, game: &GameEntry) {
+        match game.save {
+            Some(SaveType::None) => {
+                self.sram = None;
+                self.eeprom = None;
+            }
+            Some(SaveType::Sram) => {
+                self.eeprom = None;
+                if self.sram.is_none() {
+                    let info = self.rom.header().sram.unwrap_or_else(SaveRamInfo::fallback);
+                    self.sram = Some(Sram::new(info, self.rom.size()));
+                }
+            }
+            Some(SaveType::Eeprom) => {
+                self.sram = None;
+                let config = game.eeprom.map(|e| e.config()).unwrap_or_else(EepromConfig::fallback);
+                self.eeprom = Some(Eeprom::new(config));
+            }
+            None => {}
+        }
+        if game.has_workaround(Workaround::SramAlwaysMapped) {
+            if let Some(sram) = &mut self.sram {
+                sram.set_always_mapped();
+            }
+        }
+        self.banks_dirty = true;
+    }
+
+    /// Configuração de bancos na inicialização: janela N aponta para o banco N
+    fn sega_power_on_banks() -> [usize; SEGA_WINDOWS] {
+        std::array::from_fn(|window| window)
+    }
+
+    /// Volta os bancos ao estado de power-on
+    pub fn reset(&mut self) {
+        self.bank = 0;
+        self.sega_banks = Self::sega_power_on_banks();
+        if let Some(sram) = &mut self.sram {
+            sram.reset();
+        }
+        if let Some(svp) = &mut self.svp {
+            svp.reset();
+        }
+        if let Some(board) = &mut self.board {
+            board.reset();
+        }
+        if let Some(flash_cart) = &mut self.flash_cart {
+            flash_cart.reset();
+        }
+        self.sync_board_sram();
+        self.banks_dirty = true;
+    }
+
+    /// Substitui o conjunto de patches de ROM (endereço par -> palavra)
+    pub fn set_rom_patches(&mut self, patches: HashMap<u32, u16>) {
+        if patches != self.rom_patches {
+            self.rom_patches = patches;
+            self.banks_dirty = true;
+        }
+    }
+
+    /// Liga/desliga a SRAM quando ela é controlada pela placa (SF-001)
+    fn sync_board_sram(&mut self) {
+        if let (Some(sram), Some(mapped)) = (&mut self.sram, self.board.as_ref().and_then(Board::sram_mapped)) {
+            sram.mapped = mapped;
+        }
+    }
+
+    /// Retorna true se o cartucho responde na área de expansão
+    /// (0x400000–0x7FFFFF)
+    pub fn decodes_expansion_area(&self) -> bool {
+        self.board.as_ref().is_some_and(Board::decodes_expansion_area)
+    }
+
+    /// Aplica um eventual patch ao byte lido da ROM
+    fn patched(&self, addr: u32, value: u8) -> u8 {
+        match self.rom_patches.get(&(addr & 0xFFFFFE)) {
+            Some(&word) if addr & 1 == 0 => (word >> 8) as u8,
+            Some(&word) => word as u8,
+            None => value,
         }
     }
 
+    /// Número de bancos de 512 KB existentes na ROM
+    pub fn sega_bank_count(&self) -> usize {
+        self.rom.size().div_ceil(SEGA_BANK_SIZE).max(1)
+    }
+
+    /// Retorna a configuração atual das janelas do mapper SEGA
+    /// (banco de 512 KB mapeado em cada janela).
+    pub fn bank_windows(&self) -> [usize; SEGA_WINDOWS] {
+        self.sega_banks
+    }
+
+    /// Retorna informações de debug sobre o mapeamento atual
+    pub fn debug_info(&self) -> Vec<String> {
+        let mut info = Vec::new();
+        info.push(format!("Mapper: {:?}", self.mapper_type));
+        info.push(format!("ROM: {} bytes", self.rom.size()));
+        if let Some(lock_on) = &self.lock_on {
+            info.push(format!(
+                "Lock-on: {:?} ({}), 0x200000-0x3FFFFF{}",
+                lock_on.kind,
+                lock_on.top.header().overseas_name,
+                if lock_on.patch.is_some() { ", patch ROM em 0x300000" } else { "" }
+            ));
+        }
+        match self.mapper_type {
+            MapperType::Sega => {
+                for (window, &bank) in self.sega_banks.iter().enumerate() {
+                    let start = window * SEGA_BANK_SIZE;
+                    info.push(format!(
+                        "  Janela {} (0x{:06X}-0x{:06X}): banco {} (ROM 0x{:06X})",
+                        window,
+                        start,
+                        start + SEGA_BANK_SIZE - 1,
+                        bank,
+                        bank * SEGA_BANK_SIZE
+                    ));
+                }
+            }
+            MapperType::Codemasters => info.push(format!("  Banco: {}", self.bank)),
+            _ => {}
+        }
+        if let Some(board) = &self.board {
+            info.push(format!("  Placa: {:?}", board));
+        }
+        if let Some(flash_cart) = &self.flash_cart {
+            info.push(format!(
+                "  Bancos: {:?}, ROM {}",
+                flash_cart.banks,
+                if flash_cart.rom_writable() { "gravável" } else { "protegida" }
+            ));
+        }
+        if let Some(sram) = &self.sram {
+            info.push(format!(
+                "SRAM: 0x{:06X}-0x{:06X} {:?}, {} bytes, {}{}",
+                sram.info.start,
+                sram.info.end,
+                sram.info.lanes,
+                sram.info.size(),
+                if sram.mapped { "mapeada" } else { "desligada" },
+                if sram.write_protected { ", protegida" } else { "" }
+            ));
+        }
+        if let Some(eeprom) = &self.eeprom {
+            info.push(format!("EEPROM: {:?}, {:?}", eeprom.config.chip, eeprom.config.pins));
+        }
+        if let Some(svp) = &self.svp {
+            info.push(format!(
+                "SVP: PC 0x{:04X}, XST 0x{:04X}, PM0 0x{:04X}",
+                svp.ssp.pc, svp.ssp.pm[3], svp.ssp.pm[0]
+            ));
+        }
+        info
+    }
+
     /// Lê um byte (8 bits) de ROM ou SRAM conforme o tipo de mapper.
     pub fn read8(&self, addr: u32) -> u8 {
-        match self.mapper_type {
-            MapperType::Standard => self.read_rom(addr),
-            MapperType::Sega => self.read_sega(addr),
-            MapperType::Codemasters => self.read_codemasters(addr),
-            MapperType::Sram => self.read_sram(addr),
-            MapperType::Eeprom => self.read_eeprom(addr),
+        if let Some(svp) = self.svp.as_ref().filter(|_| Svp::maps(addr)) {
+            return svp.read8(addr);
+        }
+        if let Some(sram) = self.sram.as_ref().filter(|s| s.contains(addr)) {
+            return sram.read8(addr);
         }
+        if let Some(value) = self.eeprom.as_ref().and_then(|e| e.read8(addr)) {
+            return value;
+        }
+        if let Some(flash_cart) = self.flash_cart.as_ref().filter(|_| addr < 0x400000) {
+            return self.patched(addr, flash_cart.read8(addr));
+        }
+        let value = match (&self.board, self.mapper_type) {
+            (Some(board), _) => {
+                if let Some(value) = board.read8(addr) {
+                    return value;
+                }
+                board.rom_offset(addr).map_or(0, |offset| self.rom.read8((offset % self.rom.size()) as u32))
+            }
+            (None, MapperType::Sega) => self.read_sega(addr),
+            (None, MapperType::Codemasters) => self.read_codemasters(addr),
+            (None, _) => self.read_rom(addr),
+        };
+        self.patched(addr, value)
+    }
+
+    /// Leitura de palavra (16 bits). Necessária porque alguns registradores
+    /// do cartucho (estado do SVP) têm efeito colateral na leitura.
+    pub fn read16(&mut self, addr: u32) -> u16 {
+        if let Some(svp) = self.svp.as_mut().filter(|_| Svp::maps(addr)) {
+            return svp.read16(addr);
+        }
+        ((self.read8(addr) as u16) << 8) | self.read8(addr.wrapping_add(1)) as u16
+    }
+
+    /// Escrita de palavra (16 bits)
+    pub fn write16(&mut self, addr: u32, value: u16) {
+        if let Some(svp) = self.svp.as_mut().filter(|_| Svp::maps(addr)) {
+            svp.write16(addr, value);
+            return;
+        }
+        self.write8(addr, (value >> 8) as u8);
+        self.write8(addr.wrapping_add(1), value as u8);
     }
 
     /// Escrita de byte (8 bits) — SRAM, EEPROM ou troca de banco.
     pub fn write8(&mut self, addr: u32, value: u8) {
+        if let Some(svp) = self.svp.as_mut().filter(|_| Svp::maps(addr)) {
+            svp.write8(addr, value);
+            return;
+        }
+        // O flash-cart usa 0xA130F1 como banco da janela 0, não como
+        // controle da SRAM
+        if let Some(flash_cart) = &mut self.flash_cart {
+            if flash_cart.write8(addr, value) {
+                return;
+            }
+        }
+        if let Some(sram) = &mut self.sram {
+            if addr == SRAM_CONTROL_ADDR {
+                self.banks_dirty |= sram.write_control(value);
+                return;
+            }
+            if sram.contains(addr) {
+                sram.write8(addr, value);
+                return;
+            }
+        }
+        if let Some(eeprom) = self.eeprom.as_mut().filter(|e| e.contains(addr)) {
+            eeprom.write8(addr, value);
+            return;
+        }
+        if let Some(board) = &mut self.board {
+            if board.write8(addr, value) {
+                self.banks_dirty = true;
+                self.sync_board_sram();
+            }
+            return;
+        }
         match self.mapper_type {
-            MapperType::Sram => self.write_sram(addr, value),
-            MapperType::Eeprom => self.write_eeprom(addr, value),
             MapperType::Sega => self.handle_sega_bank_switch(addr, value),
             MapperType::Codemasters => self.handle_codemasters_bank_switch(addr, value),
             _ => {}
         }
     }
 
-    /// Leitura direta de ROM padrão (espelhada até 4MB)
-    fn read_rom(&self, addr: u32) -> u8 {
-        let rom = self.rom.lock().unwrap();
-        rom.read8(addr % rom.size() as u32)
+    /// Leitura dos registradores do cartucho (0xA130xx, 0xA150xx)
+    pub fn read_register(&self, addr: u32) -> u8 {
+        if let Some(value) = self.flash_cart.as_ref().and_then(|f| f.read_register(addr)) {
+            return value;
+        }
+        match (&self.svp, &self.board) {
+            (Some(svp), _) if Svp::maps(addr) => svp.read8(addr),
+            (_, Some(board)) => board.read8(addr).unwrap_or(0),
+            _ => 0,
+        }
     }
 
-    /// Leitura de ROM com mapeamento SEGA (banco de 512 KB)
-    fn read_sega(&self, addr: u32) -> u8 {
-        let rom = self.rom.lock().unwrap();
-        let bank_offset = (self.bank * 0x80000) as u32;
-        let offset = (addr % 0x80000) + bank_offset;
-        rom.read8(offset % rom.size() as u32)
+    /// Avança os chips do cartucho (SVP) pelos ciclos do 68000 decorridos
+    /// e troca dados com o host pela USB do flash-cart
+    pub fn run_chips(&mut self, m68k_cycles: u32) {
+        if let Some(svp) = &mut self.svp {
+            svp.run(m68k_cycles);
+        }
+        if let Some(flash_cart) = &mut self.flash_cart {
+            flash_cart.usb.poll();
+        }
     }
 
-    /// Leitura Codemasters (banco de 256 KB)
-    fn read_codemasters(&self, addr: u32) -> u8 {
-        let rom = self.rom.lock().unwrap();
-        let bank_offset = (self.bank * 0x40000) as u32;
-        let offset = (addr % 0x40000) + bank_offset;
-        rom.read8(offset % rom.size() as u32)
+    /// Conecta a porta USB do flash-cart ao host. Retorna false se o
+    /// cartucho não tem USB.
+    pub fn attach_usb(&mut self, link: UsbLink) -> bool {
+        match &mut self.flash_cart {
+            Some(flash_cart) => {
+                flash_cart.usb.link = link;
+                true
+            }
+            None => false,
+        }
     }
 
-    /// Leitura com SRAM (faixa 0x200000–0x20FFFF)
-    fn read_sram(&self, addr: u32) -> u8 {
-        if (0x200000..=0x20FFFF).contains(&addr) {
-            if let Some(ref sram) = self.sram {
-                return sram[(addr as usize - 0x200000) % sram.len()];
+    /// Offset na ROM da página de 64 KB `page` da área do cartucho.
+    /// Retorna `None` quando a página precisa ser tratada pelo mapper
+    /// a cada acesso (SRAM, EEPROM).
+    pub fn rom_page_offset(&self, page: usize) -> Option<usize> {
+        let addr = (page << 16) as u32;
+        if self.sram_covers_page(page) || self.eeprom_covers_page(page) || self.patch_covers_page(page) {
+            return None;
+        }
+        if let Some(board) = &self.board {
+            return board.page_offset(page);
+        }
+        if self.flash_cart.is_some() {
+            return None;
+        }
+        match self.mapper_type {
+            MapperType::Standard | MapperType::Sram | MapperType::Eeprom => Some(addr as usize),
+            MapperType::Svp if (0x30..=0x3F).contains(&page) => None,
+            MapperType::Svp => Some(addr as usize),
+            MapperType::Sega => {
+                let window = page / (SEGA_BANK_SIZE >> 16);
+                Some(self.sega_banks[window % SEGA_WINDOWS] * SEGA_BANK_SIZE + addr as usize % SEGA_BANK_SIZE)
             }
+            MapperType::Codemasters => Some((addr % 0x40000) as usize + self.bank * 0x40000),
+            // Placas não licenciadas, tratadas acima
+            _ => Some(addr as usize),
         }
-        self.read_rom(addr)
     }
 
-    /// Escrita na SRAM (com bateria)
-    fn write_sram(&mut self, addr: u32, value: u8) {
-        if (0x200000..=0x20FFFF).contains(&addr) {
-            if let Some(ref mut sram) = self.sram {
-                let offset = (addr as usize - 0x200000) % sram.len();
-                sram[offset] = value;
-            }
+    /// Retorna true se a SRAM está mapeada em alguma parte da página de 64 KB
+    fn sram_covers_page(&self, page: usize) -> bool {
+        self.sram.as_ref().is_some_and(|sram| {
+            let first = (sram.info.start >> 16) as usize;
+            let last = (sram.info.end >> 16) as usize;
+            sram.mapped && (first..=last).contains(&page)
+        })
+    }
+
+    /// Retorna true se algum patch de ROM cai na página de 64 KB
+    fn patch_covers_page(&self, page: usize) -> bool {
+        self.rom_patches.keys().any(|&addr| (addr >> 16) as usize == page)
+    }
+
+    /// Retorna true se algum pino da EEPROM fica na página de 64 KB
+    fn eeprom_covers_page(&self, page: usize) -> bool {
+        self.eeprom.as_ref().is_some_and(|eeprom| {
+            let pins = &eeprom.config.pins;
+            [pins.sda_in_addr, pins.sda_out_addr, pins.scl_addr]
+                .iter()
+                .any(|&addr| (addr >> 16) as usize == page)
+        })
+    }
+
+    /// Associa a memória de save do cartucho (SRAM com bateria ou EEPROM)
+    /// a um arquivo e carrega o conteúdo existente. Retorna false se o
+    /// cartucho não tem nada a persistir.
+    pub fn attach_save_file(&mut self, path: &Path) -> io::Result<bool> {
+        if let Some(eeprom) = &mut self.eeprom {
+            eeprom.attach_file(path.to_path_buf())?;
+            return Ok(true);
+        }
+        match self.sram.as_mut().filter(|s| s.info.battery) {
+            Some(sram) => sram.attach_file(path.to_path_buf()).map(|_| true),
+            None => Ok(false),
         }
     }
 
-    /// Leitura simulada de EEPROM serial (faixa 0x200000–0x200001)
-    fn read_eeprom(&self, addr: u32) -> u8 {
-        if addr & 1 == 0 {
-            // bit de status
-            0xFF
-        } else {
-            0x00
+    /// Grava a memória de save em disco se houve alterações.
+    /// Retorna true se o arquivo foi escrito.
+    pub fn flush_saves(&mut self) -> io::Result<bool> {
+        if let Some(eeprom) = &mut self.eeprom {
+            return eeprom.flush();
+        }
+        match &mut self.sram {
+            Some(sram) => sram.flush(),
+            None => Ok(false),
         }
     }
 
-    /// Escrita simulada de EEPROM serial (não persiste, apenas emula ACK)
-    fn write_eeprom(&mut self, _addr: u32, _value: u8) {
-        // EEPROM fictícia — sem armazenamento persistente.
+    /// Leitura direta de ROM padrão (espelhada até 4MB)
+    fn read_rom(&self, addr: u32) -> u8 {
+        self.rom.read8(addr % self.rom.size() as u32)
+    }
+
+    /// Leitura de ROM com mapeamento SEGA (oito janelas de 512 KB)
+    fn read_sega(&self, addr: u32) -> u8 {
+        let window = (addr as usize / SEGA_BANK_SIZE) % SEGA_WINDOWS;
+        let offset = self.sega_banks[window] * SEGA_BANK_SIZE + addr as usize % SEGA_BANK_SIZE;
+        self.rom.read8((offset % self.rom.size()) as u32)
+    }
+
+    /// Leitura Codemasters (banco de 256 KB)
+    fn read_codemasters(&self, addr: u32) -> u8 {
+        let bank_offset = (self.bank * 0x40000) as u32;
+        let offset = (addr % 0x40000) + bank_offset;
+        self.rom.read8(offset % self.rom.size() as u32)
     }
 
-    /// Troca de banco SEGA — escrita em 0xA130F1 define banco ativo
+    /// Troca de banco SEGA — 0xA130F3, F5, ..., FF selecionam o banco das
+    /// janelas 1–7. A janela 0 é fixa e 0xA130F1 é o controle de SRAM.
+    /// Bancos além do tamanho da ROM são espelhados.
     fn handle_sega_bank_switch(&mut self, addr: u32, value: u8) {
-        if addr == 0xA130F1 {
-            self.bank = (value & 0x07) as usize;
+        if let 0xA130F3..=0xA130FF = addr {
+            if addr & 1 == 0 {
+                return;
+            }
+            let window = ((addr - 0xA130F1) >> 1) as usize;
+            let bank = (value & 0x3F) as usize % self.sega_bank_count();
+            if self.sega_banks[window] != bank {
+                self.sega_banks[window] = bank;
+                self.banks_dirty = true;
+            }
         }
     }
 
@@ -135,6 +551,7 @@ impl Mapper {
     fn handle_codemasters_bank_switch(&mut self, addr: u32, value: u8) {
         if addr & 0x400000 == 0x000000 {
             self.bank = (value & 0x0F) as usize;
+            self.banks_dirty = true;
         }
     }
 }
@@ -142,6 +559,8 @@ impl Mapper {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::gamedb::{EepromEntry, EepromPinout};
+    use crate::memory::eeprom::EepromChip;
     use crate::memory::rom::Rom;
 
     #[test]
@@ -153,6 +572,23 @@ mod tests {
         assert_eq!(mapper.read8(0x200000), data[0]);
     }
 
+    #[test]
+    fn test_rom_patch_overrides_reads() {
+        let data = (0..=255).cycle().take(0x20000).collect::<Vec<u8>>();
+        let mut mapper = Mapper::new(Rom::new(data), MapperType::Standard);
+        assert_eq!(mapper.rom_page_offset(0x01), Some(0x10000));
+
+        mapper.set_rom_patches(HashMap::from([(0x010020, 0x4E71)]));
+        assert!(mapper.banks_dirty);
+        assert_eq!(mapper.rom_page_offset(0x01), None);
+        assert_eq!((mapper.read8(0x010020), mapper.read8(0x010021)), (0x4E, 0x71));
+        assert_eq!(mapper.read8(0x010022), 0x22);
+
+        mapper.set_rom_patches(HashMap::new());
+        assert_eq!(mapper.read8(0x010020), 0x20);
+        assert_eq!(mapper.rom_page_offset(0x01), Some(0x10000));
+    }
+
     #[test]
     fn test_sram_write_and_read() {
         let data = vec![0; 256];
@@ -162,13 +598,153 @@ mod tests {
         assert_eq!(mapper.read8(0x200000), 0xAA);
     }
 
+    #[test]
+    fn test_sram_from_header_and_control_register() {
+        // ROM de 4 MB com SRAM em bytes ímpares a partir de 0x200001
+        let mut data = vec![0x11; 0x400000];
+        data[0x1B0..0x1BC].copy_from_slice(&[
+            b'R', b'A', 0xF8, 0x20, 0x00, 0x20, 0x00, 0x01, 0x00, 0x20, 0x3F, 0xFF,
+        ]);
+        let mut mapper = Mapper::new(Rom::new(data), MapperType::Standard);
+        assert_eq!(mapper.sram.as_ref().unwrap().data().len(), 0x2000);
+
+        // Power-on: ROM visível na faixa
+        assert_eq!(mapper.read8(0x200001), 0x11);
+        assert_eq!(mapper.rom_page_offset(0x20), Some(0x200000));
+
+        mapper.write8(SRAM_CONTROL_ADDR, 0x01);
+        assert!(mapper.banks_dirty);
+        assert_eq!(mapper.rom_page_offset(0x20), None);
+        mapper.write8(0x200001, 0x5A);
+        assert_eq!(mapper.read8(0x200001), 0x5A);
+    }
+
+    #[test]
+    fn test_eeprom_game_gets_eeprom_instead_of_sram() {
+        let mut data = vec![0; 0x100000];
+        data[0x1B0..0x1BC].copy_from_slice(&[
+            b'R', b'A', 0xE8, 0x40, 0x00, 0x20, 0x00, 0x01, 0x00, 0x20, 0x00, 0x01,
+        ]);
+        let mut mapper = Mapper::new(Rom::new(data), MapperType::Standard);
+        assert!(mapper.sram.is_some());
+
+        mapper.apply_game(&GameEntry {
+            save: Some(SaveType::Eeprom),
+            eeprom: Some(EepromEntry { chip: EepromChip::C24C02, pinout: EepromPinout::Acclaim16M }),
+            ..Default::default()
+        });
+        assert!(mapper.sram.is_none());
+        assert!(mapper.eeprom.is_some());
+        assert_eq!(mapper.rom_page_offset(0x20), None);
+
+        // Linhas ociosas (SCL e SDA altos): SDA de saída lido em 0x200001 bit 1
+        mapper.write8(0x200000, 0x01);
+        mapper.write8(0x200001, 0x01);
+        assert_eq!(mapper.read8(0x200001), 0x02);
+    }
+
+    #[test]
+    fn test_lock_on_uses_top_cartridge_sram() {
+        let mut sk = vec![0x11; 0x200000];
+        sk[0x180..0x18E].copy_from_slice(b"GM MK-1563 -00");
+        let mut s3 = vec![0x33; 0x200000];
+        s3[0x180..0x18E].copy_from_slice(b"GM MK-1079 -00");
+        s3[0x1B0..0x1BC].copy_from_slice(&[
+            b'R', b'A', 0xF8, 0x20, 0x00, 0x20, 0x00, 0x01, 0x00, 0x20, 0x03, 0xFF,
+        ]);
+        let sk = Rom::new(sk);
+        let lock_on = LockOn::new(&sk, Rom::new(s3), None).unwrap();
+        let mut mapper = Mapper::with_lock_on(sk, lock_on);
+
+        assert_eq!((mapper.read8(0x000001), mapper.read8(0x200001)), (0x11, 0x33));
+        assert_eq!(mapper.rom_page_offset(0x20), Some(0x200000));
+
+        mapper.write8(SRAM_CONTROL_ADDR, 0x01);
+        mapper.write8(0x200001, 0x5A);
+        assert_eq!(mapper.read8(0x200001), 0x5A);
+        mapper.write8(SRAM_CONTROL_ADDR, 0x00);
+        assert_eq!(mapper.read8(0x200001), 0x33);
+    }
+
+    #[test]
+    fn test_svp_maps_dram_and_registers() {
+        let mut mapper = Mapper::new(Rom::new(vec![0x77; 0x200000]), MapperType::Svp);
+        assert_eq!(mapper.rom_page_offset(0x10), Some(0x100000));
+        assert_eq!(mapper.rom_page_offset(0x30), None);
+
+        mapper.write16(0x300010, 0xCAFE);
+        assert_eq!(mapper.read16(0x300010), 0xCAFE);
+        assert_eq!(mapper.read8(0x300011), 0xFE);
+
+        mapper.write16(0xA15000, 0x0001);
+        assert_eq!(mapper.read_register(0xA15005) & 2, 2);
+        assert_eq!(mapper.read8(0x000000), 0x77);
+    }
+
+    #[test]
+    fn test_unlicensed_board_banks_and_sram() {
+        let data = (0..0x400000).map(|i| (i >> 15) as u8).collect::<Vec<u8>>();
+        let mut mapper = Mapper::new(Rom::new(data.clone()), MapperType::LionKing3);
+        assert!(mapper.decodes_expansion_area());
+        mapper.write8(0x700000, 5);
+        assert!(mapper.banks_dirty);
+        assert_eq!(mapper.rom_page_offset(0), None);
+        assert_eq!((mapper.read8(0x000000), mapper.read8(0x008000)), (5, 1));
+
+        let mut mapper = Mapper::new(Rom::new(data), MapperType::Sf001);
+        assert_eq!(mapper.read8(0x3C0001), 0x78);
+        mapper.write8(0x000E01, 0x80);
+        mapper.write8(0x3C0001, 0x5A);
+        assert_eq!(mapper.read8(0x3C0001), 0x5A);
+        assert_eq!(mapper.read8(0x000000), 0x70);
+    }
+
+    #[test]
+    fn test_flash_cart_from_header() {
+        let mut data = vec![0x11; 0x100000];
+        data[0x100..0x110].copy_from_slice(b"SEGA SSF        ");
+        let rom = Rom::new(data);
+        assert_eq!(MapperType::from_header(rom.header()), MapperType::FlashCart);
+
+        let mut mapper = Mapper::new(rom, MapperType::FlashCart);
+        assert_eq!(mapper.rom_page_offset(0), None);
+        mapper.write8(0xA130F0, 0xA0);
+        mapper.write8(0x000200, 0x42);
+        assert_eq!(mapper.read8(0x000200), 0x42);
+        assert_eq!(mapper.read_register(0xA130E5), 0x01);
+    }
+
     #[test]
     fn test_sega_bank_switch() {
-        let data = (0..255).cycle().take(0x100000).collect::<Vec<u8>>();
+        let data = (0..0x500000).map(|i| (i / SEGA_BANK_SIZE) as u8).collect::<Vec<u8>>();
         let rom = Rom::new(data);
         let mut mapper = Mapper::new(rom, MapperType::Sega);
-        mapper.handle_sega_bank_switch(0xA130F1, 3);
-        assert_eq!(mapper.bank, 3);
+
+        // Power-on: janela N = banco N
+        assert_eq!(mapper.bank_windows(), [0, 1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(mapper.read8(0x380000), 7);
+
+        // Janela 7 aponta para o banco 9 (ROM acima de 4 MB)
+        mapper.write8(0xA130FF, 9);
+        assert!(mapper.banks_dirty);
+        assert_eq!(mapper.read8(0x380000), 9);
+        assert_eq!(mapper.rom_page_offset(0x38), Some(9 * SEGA_BANK_SIZE));
+
+        // 0xA130F1 é o registrador de SRAM, não troca bancos
+        mapper.write8(0xA130F1, 3);
+        assert_eq!(mapper.bank_windows()[0], 0);
+    }
+
+    #[test]
+    fn test_sega_bank_mirroring_and_reset() {
+        let rom = Rom::new(vec![0; 0x200000]);
+        let mut mapper = Mapper::new(rom, MapperType::Sega);
+        mapper.write8(0xA130F3, 5); // apenas 4 bancos na ROM
+        assert_eq!(mapper.bank_windows()[1], 1);
+
+        mapper.reset();
+        assert_eq!(mapper.bank_windows(), [0, 1, 2, 3, 4, 5, 6, 7]);
+        assert!(mapper.debug_info().iter().any(|l| l.contains("Janela 7")));
     }
 
     #[test]
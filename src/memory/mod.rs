@@ -7,51 +7,197 @@
 //! - RAM principal
 //! - VDP (vídeo e CRAM)
 //! - Som (FM e PSG)
+//! - Mega-CD (opcional, na área de expansão)
 //! - I/O (portas, controladores, interface com Z80)
 //!
 //! Também fornece funções convenientes para leitura, escrita e
 //! atualização por ciclo (tick/frame).
 
 pub mod bus;
+pub mod eeprom;
+pub mod flashcart;
+pub mod loader;
+pub mod lockon;
 pub mod mapper;
+pub mod patch;
 pub mod ram;
 pub mod rom;
+pub mod sms;
+pub mod sram;
+pub mod svp;
+pub mod tmss;
+pub mod unlicensed;
 
 use bus::*;
+use flashcart::UsbLink;
 use mapper::*;
 use ram::*;
 use rom::*;
+use tmss::*;
+use crate::cheats::{CheatCode, CheatEngine};
+use crate::console::ConsoleModel;
+use crate::gamedb::{GameDatabase, GameEntry};
+use crate::megacd::MegaCd;
+use crate::romid::RomHashes;
 use crate::vdp::Vdp;
 use crate::sound::Sound;
-use crate::io::Io;
 use crate::cpu::z80::Z80;
-use std::sync::{Arc, Mutex};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
 
 /// Estrutura de alto nível que representa o sistema de memória
 /// unificado do Mega Drive.
 pub struct Memory {
     pub bus: Bus,
+    /// Trapaças do jogo (fora do estado salvo em save states)
+    pub cheats: CheatEngine,
 }
 
 impl Memory {
     /// Cria uma nova instância completa do subsistema de memória,
     /// com barramento, VDP, som e I/O inicializados.
     ///
+    /// Mapper, tipo de save e região vêm do banco de dados de jogos
+    /// embutido; na falta de uma entrada, do cabeçalho da ROM.
+    ///
     /// # Parâmetros
     /// - `rom_data`: conteúdo bruto da ROM (carregado do cartucho)
     /// - `ram_size`: tamanho da RAM principal (normalmente 64 KB)
-    /// - `mapper_type`: define o tipo de mapeamento (Standard, SegaMapper, etc.)
     /// - `sound_rate`: taxa de amostragem do áudio (ex: 44100 Hz)
-    pub fn new(rom_data: Vec<u8>, ram_size: usize, mapper_type: MapperType, sound_rate: u32) -> Self {
-        let rom = Rom::new(rom_data);
-        let mapper = Mapper::new(rom, mapper_type);
-        let vdp = Arc::new(Mutex::new(Vdp::new()));
-        let sound = Arc::new(Mutex::new(Sound::new(sound_rate)));
-        let io = Arc::new(Mutex::new(Io::new()));
-        let z80 = Arc::new(Mutex::new(Z80::new()));
-        
-        let bus = Bus::new(mapper, ram_size, vdp, sound, io, z80);
-        Self { bus }
+    pub fn new(rom_data: Vec<u8>, ram_size: usize, sound_rate: u32) -> Self {
+        let header = RomHeader::parse(&rom_data);
+        let game = GameDatabase::builtin()
+            .lookup(&header, &RomHashes::compute(&rom_data))
+            .unwrap_or_default();
+        let console = ConsoleModel::detect(&header, HardwareRevision::default(), game.region);
+        Self::with_console(Rom::new(rom_data), ram_size, sound_rate, console, &game)
+    }
+
+    /// Cria o subsistema de memória para um modelo de console específico
+    /// (override manual de região e revisão de hardware) e um jogo já
+    /// procurado no banco de dados. A ROM já deve estar normalizada
+    /// (ver `loader`).
+    pub fn with_console(
+        rom: Rom,
+        ram_size: usize,
+        sound_rate: u32,
+        console: ConsoleModel,
+        game: &GameEntry,
+    ) -> Self {
+        let mapper_type = game.mapper.unwrap_or_else(|| MapperType::from_header(rom.header()));
+        let mut mapper = Mapper::new(rom, mapper_type);
+        mapper.apply_game(game);
+        Self::with_mapper(mapper, ram_size, sound_rate, console)
+    }
+
+    /// Cria o subsistema de memória com um mapper já montado (ex: lock-on
+    /// do Sonic & Knuckles)
+    pub fn with_mapper(mapper: Mapper, ram_size: usize, sound_rate: u32, console: ConsoleModel) -> Self {
+        let mapper = Arc::new(Mutex::new(mapper));
+        let vdp = Arc::new(Mutex::new(Vdp::new(console.is_pal())));
+        let sound = Arc::new(Mutex::new(Sound::with_master_clock(sound_rate, console.master_clock())));
+        let z80 = Arc::new(Mutex::new(Z80::new(sound.clone())));
+
+        let mut bus = Bus::new(z80, vdp, sound, Ram::new(ram_size), mapper);
+        bus.console = console;
+        bus.install_tmss(Tmss::new(console.revision, None));
+        Self {
+            bus,
+            cheats: CheatEngine::default(),
+        }
+    }
+
+    /// Retorna o modelo de console emulado
+    pub fn console(&self) -> &ConsoleModel {
+        &self.bus.console
+    }
+
+    /// Configura o TMSS conforme a revisão de hardware, com uma ROM de
+    /// boot opcional que roda antes do cartucho.
+    pub fn configure_tmss(&mut self, revision: HardwareRevision, boot_rom: Option<Vec<u8>>) {
+        self.bus.console.revision = revision;
+        self.bus.install_tmss(Tmss::new(revision, boot_rom));
+    }
+
+    /// Retorna true se o 68000 travou por acessar o VDP antes do handshake
+    /// TMSS. O travamento só termina com o reset do console.
+    pub fn cpu_locked_up(&self) -> bool {
+        self.bus.cpu_locked_up()
+    }
+
+    /// Conecta o Mega-CD: marca a unidade de expansão no registrador de
+    /// versão, mixa o PCM e o CD-DA no som e mapeia BIOS, PRG-RAM e word RAM.
+    pub fn attach_mega_cd(&mut self, mega_cd: MegaCd) {
+        self.bus.console.expansion_unit = true;
+        {
+            let mut sound = self.bus.sound.lock().unwrap();
+            sound.attach_pcm(mega_cd.pcm.clone());
+            sound.attach_cdda(mega_cd.cdda.clone());
+        }
+        self.bus.install_mega_cd(Arc::new(Mutex::new(mega_cd)));
+    }
+
+    // =====================================================
+    // SAVE RAM
+    // =====================================================
+
+    /// Associa a memória de save do cartucho (SRAM com bateria ou EEPROM)
+    /// ao arquivo `.srm` ao lado da ROM e carrega o save existente.
+    /// Retorna o caminho usado, ou `None` se o cartucho não salva.
+    pub fn attach_save_ram(&mut self, rom_path: &Path) -> io::Result<Option<PathBuf>> {
+        let path = sram::save_path_for(rom_path);
+        let attached = self.bus.mapper.lock().unwrap().attach_save_file(&path)?;
+        Ok(attached.then_some(path))
+    }
+
+    /// Grava a SRAM/EEPROM em disco se houve alterações. Deve ser chamado ao
+    /// sair e periodicamente durante o jogo. Retorna true se o arquivo foi escrito.
+    pub fn flush_save_ram(&self) -> io::Result<bool> {
+        self.bus.mapper.lock().unwrap().flush_saves()
+    }
+
+    /// Conecta a porta USB do flash-cart ao host (socket Unix ou par de
+    /// arquivos). Retorna false se o cartucho não é um flash-cart.
+    pub fn attach_usb(&mut self, link: UsbLink) -> bool {
+        self.bus.mapper.lock().unwrap().attach_usb(link)
+    }
+
+    // =====================================================
+    // TRAPAÇAS
+    // =====================================================
+
+    /// Instala as trapaças do jogo e aplica as que estão ligadas
+    pub fn set_cheats(&mut self, cheats: CheatEngine) {
+        self.cheats = cheats;
+        self.refresh_cheats();
+    }
+
+    /// Liga/desliga uma trapaça em tempo de execução. Retorna o novo estado.
+    pub fn toggle_cheat(&mut self, index: usize) -> Option<bool> {
+        let enabled = self.cheats.toggle(index)?;
+        self.refresh_cheats();
+        Some(enabled)
+    }
+
+    /// Repassa os patches de ROM ativos ao mapper. Deve ser chamado depois
+    /// de alterar `cheats` diretamente e depois de carregar um save state.
+    pub fn refresh_cheats(&mut self) {
+        let patches = self.cheats.rom_patches();
+        self.bus.mapper.lock().unwrap().set_rom_patches(patches);
+        self.bus.sync_mapper_banks();
+    }
+
+    /// Refaz as escritas em RAM das trapaças Action Replay (uma vez por quadro)
+    pub fn apply_ram_cheats(&mut self) {
+        for code in self.cheats.ram_writes() {
+            match code {
+                CheatCode::RamWrite8 { addr, value } => self.bus.ram.write8(addr, value),
+                CheatCode::RamWrite16 { addr, value } => self.bus.ram.write16(addr, value),
+                CheatCode::Z80Write8 { addr, value } => self.bus.z80.lock().unwrap().write_byte(addr, value),
+                CheatCode::RomPatch { .. } => {}
+            }
+        }
     }
 
     // =====================================================
@@ -68,6 +214,11 @@ impl Memory {
         self.bus.read16(addr)
     }
 
+    /// Lê um longword (32 bits) da memória mapeada.
+    pub fn read32(&self, addr: u32) -> u32 {
+        self.bus.read32(addr)
+    }
+
     /// Escreve um byte na memória mapeada.
     pub fn write8(&mut self, addr: u32, value: u8) {
         self.bus.write8(addr, value);
@@ -78,6 +229,11 @@ impl Memory {
         self.bus.write16(addr, value);
     }
 
+    /// Escreve um longword (32 bits) na memória mapeada.
+    pub fn write32(&mut self, addr: u32, value: u32) {
+        self.bus.write32(addr, value);
+    }
+
     // =====================================================
     // CICLOS / ATUALIZAÇÃO
     // =====================================================
@@ -88,15 +244,35 @@ impl Memory {
         self.bus.tick();
     }
 
+    /// Avança os chips do cartucho (SVP) e o Mega-CD em passo travado com
+    /// o 68000. Deve ser chamado com os ciclos do 68000 de cada passo da CPU.
+    pub fn run_cartridge_chips(&mut self, m68k_cycles: u32) {
+        self.bus.mapper.lock().unwrap().run_chips(m68k_cycles);
+        if let Some(mega_cd) = &self.bus.mega_cd {
+            mega_cd.lock().unwrap().run(m68k_cycles);
+        }
+    }
+
     /// Renderiza um frame completo do vídeo (VDP) e retorna o framebuffer RGBA.
     pub fn render_frame(&mut self) -> Vec<u32> {
         self.bus.render_frame()
     }
 
+    /// Acesso ao VDP, para as opções da saída de vídeo (desentrelaçamento,
+    /// curva de cor, pontos de CRAM)
+    pub fn vdp(&self) -> MutexGuard<'_, Vdp> {
+        self.bus.vdp.lock().unwrap()
+    }
+
     // =====================================================
     // DIAGNÓSTICO
     // =====================================================
 
+    /// Retorna o estado da RAM do Z80 (8 KB).
+    pub fn dump_z80_ram(&self) -> Vec<u8> {
+        self.bus.z80_ram_dump()
+    }
+
     /// Retorna o estado atual da VRAM (para debug).
     pub fn dump_vram(&self) -> Vec<u8> {
         self.bus.vram_dump()
@@ -116,11 +292,10 @@ impl Memory {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::memory::mapper::MapperType;
 
     #[test]
     fn test_memory_read_write() {
-        let mut mem = Memory::new(vec![0xAA, 0xBB, 0xCC, 0xDD], 64 * 1024, MapperType::Standard, 44100);
+        let mut mem = Memory::new(vec![0xAA, 0xBB, 0xCC, 0xDD], 64 * 1024, 44100);
 
         mem.write8(0xFF0000, 0x42);
         let val = mem.read8(0xFF0000);
@@ -129,15 +304,35 @@ mod tests {
 
     #[test]
     fn test_memory_vdp_integration() {
-        let mut mem = Memory::new(vec![0; 8], 64 * 1024, MapperType::Standard, 44100);
+        let mut mem = Memory::new(vec![0; 8], 64 * 1024, 44100);
         mem.write16(0xC00000, 0x1234);
         let val = mem.read16(0xC00000);
         assert_eq!(val & 0xFF, 0x34);
     }
 
+    #[test]
+    fn test_cheats_patch_rom_and_ram() {
+        let mut mem = Memory::new(vec![0; 0x20000], 64 * 1024, 44100);
+        let cheats = CheatEngine::from_ron(
+            r#"(cheats: [
+                (name: "NOP", codes: ["001000:4E71"], enabled: true),
+                (name: "Vidas", codes: ["FF0F24:0009"], enabled: true),
+            ])"#,
+        )
+        .unwrap();
+        mem.set_cheats(cheats);
+        assert_eq!(mem.read16(0x001000), 0x4E71);
+
+        mem.apply_ram_cheats();
+        assert_eq!(mem.read16(0xFF0F24), 0x0009);
+
+        assert_eq!(mem.toggle_cheat(0), Some(false));
+        assert_eq!(mem.read16(0x001000), 0x0000);
+    }
+
     #[test]
     fn test_memory_frame_render() {
-        let mut mem = Memory::new(vec![0; 8], 64 * 1024, MapperType::Standard, 44100);
+        let mut mem = Memory::new(vec![0; 8], 64 * 1024, 44100);
         let frame = mem.render_frame();
         assert!(!frame.is_empty());
     }
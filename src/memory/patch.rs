@@ -0,0 +1,450 @@
+//! Soft-patching de ROMs (IPS, BPS e UPS)
+//!
+//! Os patches são aplicados em memória, sobre a ROM já normalizada pelo
+//! `loader` e antes de o cabeçalho ser lido — o arquivo original nunca é
+//! alterado. Patches ao lado da ROM (`jogo.ips`, `jogo.bps`, `jogo.ups`)
+//! são encontrados automaticamente.
+//!
+//! BPS e UPS trazem CRC32 da ROM de origem, do resultado e do próprio
+//! patch; qualquer divergência é um erro, para não rodar um jogo corrompido.
+
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Bytes máximos de um número de tamanho variável (BPS/UPS) com 64 bits
+const VARINT_MAX_BYTES: usize = 10;
+
+/// Erros ao aplicar um patch
+#[derive(Debug, Error)]
+pub enum PatchError {
+    #[error("Formato de patch desconhecido (esperado IPS, BPS ou UPS)")]
+    UnknownFormat,
+
+    #[error("Patch {0:?} truncado ou corrompido")]
+    Truncated(PatchFormat),
+
+    #[error("O patch {format:?} é para outra ROM: CRC32 esperado {expected:08X}, ROM carregada {actual:08X}")]
+    SourceChecksum { format: PatchFormat, expected: u32, actual: u32 },
+
+    #[error("Resultado do patch {format:?} inválido: CRC32 esperado {expected:08X}, obtido {actual:08X}")]
+    TargetChecksum { format: PatchFormat, expected: u32, actual: u32 },
+
+    #[error("Arquivo de patch {format:?} corrompido: CRC32 esperado {expected:08X}, obtido {actual:08X}")]
+    PatchChecksum { format: PatchFormat, expected: u32, actual: u32 },
+
+    #[error("O patch {format:?} espera uma ROM de {expected} bytes, mas a carregada tem {actual}")]
+    SourceSize { format: PatchFormat, expected: usize, actual: usize },
+}
+
+/// Formatos de patch suportados
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatchFormat {
+    Ips,
+    Bps,
+    Ups,
+}
+
+impl PatchFormat {
+    /// Extensões procuradas ao lado da ROM
+    pub const EXTENSIONS: [&'static str; 3] = ["ips", "bps", "ups"];
+
+    /// Identifica o formato pela assinatura
+    pub fn detect(patch: &[u8]) -> Option<Self> {
+        if patch.starts_with(b"PATCH") {
+            Some(Self::Ips)
+        } else if patch.starts_with(b"BPS1") {
+            Some(Self::Bps)
+        } else if patch.starts_with(b"UPS1") {
+            Some(Self::Ups)
+        } else {
+            None
+        }
+    }
+}
+
+/// Patches com o mesmo nome da ROM (`jogo.md` -> `jogo.ips`, ...)
+pub fn sidecar_patches(rom_path: &Path) -> Vec<PathBuf> {
+    PatchFormat::EXTENSIONS
+        .iter()
+        .map(|ext| rom_path.with_extension(ext))
+        .filter(|p| p.is_file())
+        .collect()
+}
+
+/// Aplica um patch (formato detectado pela assinatura) e retorna a ROM resultante
+pub fn apply(patch: &[u8], rom: &[u8]) -> Result<Vec<u8>, PatchError> {
+    match PatchFormat::detect(patch).ok_or(PatchError::UnknownFormat)? {
+        PatchFormat::Ips => apply_ips(patch, rom),
+        PatchFormat::Bps => apply_bps(patch, rom),
+        PatchFormat::Ups => apply_ups(patch, rom),
+    }
+}
+
+// =====================================================
+// IPS
+// =====================================================
+
+/// Leitor sequencial do arquivo de patch
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    format: PatchFormat,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8], pos: usize, format: PatchFormat) -> Self {
+        Self { data, pos, format }
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], PatchError> {
+        let range = span(self.pos, len, self.format)?;
+        let slice = self.data.get(range).ok_or(PatchError::Truncated(self.format))?;
+        self.pos += len;
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8, PatchError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn be(&mut self, len: usize) -> Result<usize, PatchError> {
+        Ok(self.bytes(len)?.iter().fold(0, |acc, &b| (acc << 8) | b as usize))
+    }
+
+    /// Número de tamanho variável usado por BPS e UPS. Valores que não
+    /// cabem em `usize` são tratados como patch corrompido.
+    fn varint(&mut self) -> Result<usize, PatchError> {
+        let mut value = 0usize;
+        let mut shift = 1usize;
+        for _ in 0..VARINT_MAX_BYTES {
+            let byte = self.u8()?;
+            value = (byte as usize & 0x7F)
+                .checked_mul(shift)
+                .and_then(|v| v.checked_add(value))
+                .ok_or(PatchError::Truncated(self.format))?;
+            if byte & 0x80 != 0 {
+                return Ok(value);
+            }
+            shift = shift.checked_mul(0x80).ok_or(PatchError::Truncated(self.format))?;
+            value = value.checked_add(shift).ok_or(PatchError::Truncated(self.format))?;
+        }
+        Err(PatchError::Truncated(self.format))
+    }
+}
+
+/// Intervalo `at..at + len`, com erro se a soma transborda
+fn span(at: usize, len: usize, format: PatchFormat) -> Result<Range<usize>, PatchError> {
+    let end = at.checked_add(len).ok_or(PatchError::Truncated(format))?;
+    Ok(at..end)
+}
+
+/// IPS: registros (offset de 24 bits, tamanho de 16 bits, dados). Tamanho 0
+/// indica um registro RLE (contagem de 16 bits + byte). Após "EOF" pode vir
+/// um tamanho de 24 bits para truncar a ROM.
+fn apply_ips(patch: &[u8], rom: &[u8]) -> Result<Vec<u8>, PatchError> {
+    let mut out = rom.to_vec();
+    let mut reader = Reader::new(patch, 5, PatchFormat::Ips);
+
+    loop {
+        let offset = reader.bytes(3)?;
+        if offset == b"EOF" {
+            break;
+        }
+        let offset = offset.iter().fold(0, |acc, &b| (acc << 8) | b as usize);
+        let size = reader.be(2)?;
+        let (len, data) = if size == 0 {
+            let count = reader.be(2)?;
+            (count, None)
+        } else {
+            (size, Some(reader.bytes(size)?))
+        };
+
+        if out.len() < offset + len {
+            out.resize(offset + len, 0);
+        }
+        match data {
+            Some(data) => out[offset..offset + len].copy_from_slice(data),
+            None => out[offset..offset + len].fill(reader.u8()?),
+        }
+    }
+
+    // Extensão de truncamento
+    if let Ok(size) = reader.be(3) {
+        out.truncate(size);
+    }
+    Ok(out)
+}
+
+// =====================================================
+// BPS / UPS
+// =====================================================
+
+/// Lê e confere o rodapé (CRC32 de origem, destino e do próprio patch)
+fn footer(patch: &[u8], format: PatchFormat) -> Result<(u32, u32), PatchError> {
+    if patch.len() < 4 + 12 {
+        return Err(PatchError::Truncated(format));
+    }
+    let crc = |at: usize| u32::from_le_bytes(patch[at..at + 4].try_into().unwrap());
+    let end = patch.len() - 12;
+
+    let expected = crc(end + 8);
+    let actual = crc32fast::hash(&patch[..end + 8]);
+    if expected != actual {
+        return Err(PatchError::PatchChecksum { format, expected, actual });
+    }
+    Ok((crc(end), crc(end + 4)))
+}
+
+fn check_source(format: PatchFormat, rom: &[u8], size: usize, crc: u32) -> Result<(), PatchError> {
+    if rom.len() != size {
+        return Err(PatchError::SourceSize { format, expected: size, actual: rom.len() });
+    }
+    let actual = crc32fast::hash(rom);
+    if actual != crc {
+        return Err(PatchError::SourceChecksum { format, expected: crc, actual });
+    }
+    Ok(())
+}
+
+fn check_target(format: PatchFormat, out: &[u8], crc: u32) -> Result<(), PatchError> {
+    let actual = crc32fast::hash(out);
+    if actual != crc {
+        return Err(PatchError::TargetChecksum { format, expected: crc, actual });
+    }
+    Ok(())
+}
+
+/// BPS: comandos SourceRead, TargetRead, SourceCopy e TargetCopy sobre a ROM
+fn apply_bps(patch: &[u8], rom: &[u8]) -> Result<Vec<u8>, PatchError> {
+    const FORMAT: PatchFormat = PatchFormat::Bps;
+    let (source_crc, target_crc) = footer(patch, FORMAT)?;
+    let end = patch.len() - 12;
+    let mut reader = Reader::new(&patch[..end], 4, FORMAT);
+
+    let source_size = reader.varint()?;
+    let target_size = reader.varint()?;
+    let metadata_size = reader.varint()?;
+    reader.bytes(metadata_size)?;
+    check_source(FORMAT, rom, source_size, source_crc)?;
+
+    let mut out = Vec::new();
+    let mut source_rel = 0isize;
+    let mut target_rel = 0isize;
+    // Deslocamento relativo com sinal no bit 0
+    let relative = |reader: &mut Reader, base: &mut isize| -> Result<usize, PatchError> {
+        let data = reader.varint()?;
+        let delta = (data >> 1) as isize;
+        let moved = if data & 1 != 0 { base.checked_sub(delta) } else { base.checked_add(delta) };
+        *base = moved.ok_or(PatchError::Truncated(FORMAT))?;
+        usize::try_from(*base).map_err(|_| PatchError::Truncated(FORMAT))
+    };
+    // Avança um deslocamento relativo depois de uma cópia
+    let advance = |base: &mut isize, len: usize| -> Result<(), PatchError> {
+        *base = isize::try_from(len)
+            .ok()
+            .and_then(|len| base.checked_add(len))
+            .ok_or(PatchError::Truncated(FORMAT))?;
+        Ok(())
+    };
+
+    while reader.pos < end {
+        let data = reader.varint()?;
+        let len = (data >> 2) + 1;
+        // Nenhum comando pode passar do tamanho declarado do resultado
+        if len > target_size - out.len() {
+            return Err(PatchError::Truncated(FORMAT));
+        }
+        match data & 3 {
+            // SourceRead: copia da ROM na mesma posição
+            0 => {
+                let range = span(out.len(), len, FORMAT)?;
+                out.extend_from_slice(rom.get(range).ok_or(PatchError::Truncated(FORMAT))?);
+            }
+            // TargetRead: bytes literais do patch
+            1 => out.extend_from_slice(reader.bytes(len)?),
+            // SourceCopy: copia de outro ponto da ROM
+            2 => {
+                let at = relative(&mut reader, &mut source_rel)?;
+                out.extend_from_slice(rom.get(span(at, len, FORMAT)?).ok_or(PatchError::Truncated(FORMAT))?);
+                advance(&mut source_rel, len)?;
+            }
+            // TargetCopy: copia do próprio resultado (pode sobrepor, byte a byte)
+            _ => {
+                let at = relative(&mut reader, &mut target_rel)?;
+                for i in span(at, len, FORMAT)? {
+                    let byte = *out.get(i).ok_or(PatchError::Truncated(FORMAT))?;
+                    out.push(byte);
+                }
+                advance(&mut target_rel, len)?;
+            }
+        }
+    }
+
+    if out.len() != target_size {
+        return Err(PatchError::Truncated(FORMAT));
+    }
+    check_target(FORMAT, &out, target_crc)?;
+    Ok(out)
+}
+
+/// UPS: blocos de XOR separados por distâncias relativas
+fn apply_ups(patch: &[u8], rom: &[u8]) -> Result<Vec<u8>, PatchError> {
+    const FORMAT: PatchFormat = PatchFormat::Ups;
+    let (source_crc, target_crc) = footer(patch, FORMAT)?;
+    let end = patch.len() - 12;
+    let mut reader = Reader::new(&patch[..end], 4, FORMAT);
+
+    let source_size = reader.varint()?;
+    let target_size = reader.varint()?;
+    check_source(FORMAT, rom, source_size, source_crc)?;
+
+    let mut out = rom.to_vec();
+    out.resize(target_size, 0);
+    let mut pos = 0usize;
+    while reader.pos < end {
+        pos = pos.checked_add(reader.varint()?).ok_or(PatchError::Truncated(FORMAT))?;
+        loop {
+            let xor = reader.u8()?;
+            if let Some(byte) = out.get_mut(pos) {
+                *byte ^= xor;
+            }
+            pos += 1;
+            if xor == 0 {
+                break;
+            }
+        }
+    }
+
+    check_target(FORMAT, &out, target_crc)?;
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn varint(mut value: usize, out: &mut Vec<u8>) {
+        loop {
+            let byte = (value & 0x7F) as u8;
+            value >>= 7;
+            if value == 0 {
+                out.push(byte | 0x80);
+                return;
+            }
+            out.push(byte);
+            value -= 1;
+        }
+    }
+
+    fn with_footer(mut patch: Vec<u8>, source: &[u8], target: &[u8]) -> Vec<u8> {
+        patch.extend(crc32fast::hash(source).to_le_bytes());
+        patch.extend(crc32fast::hash(target).to_le_bytes());
+        let crc = crc32fast::hash(&patch);
+        patch.extend(crc.to_le_bytes());
+        patch
+    }
+
+    #[test]
+    fn test_ips_records_rle_and_truncation() {
+        let rom = vec![0u8; 16];
+        let mut patch = b"PATCH".to_vec();
+        patch.extend([0x00, 0x00, 0x02, 0x00, 0x02, 0xAA, 0xBB]); // 2 bytes em 0x02
+        patch.extend([0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x03, 0x55]); // RLE 3x 0x55 em 0x08
+        patch.extend(b"EOF");
+        patch.extend([0x00, 0x00, 0x0C]); // trunca em 12 bytes
+
+        let out = apply(&patch, &rom).unwrap();
+        assert_eq!(out, [0, 0, 0xAA, 0xBB, 0, 0, 0, 0, 0x55, 0x55, 0x55, 0]);
+    }
+
+    #[test]
+    fn test_bps_patch_and_checksums() {
+        let source = b"SEGA GENESIS".to_vec();
+        let target = b"SEGA MEGA DRIVE".to_vec();
+
+        let mut body = b"BPS1".to_vec();
+        varint(source.len(), &mut body);
+        varint(target.len(), &mut body);
+        varint(0, &mut body);
+        varint((5 - 1) << 2, &mut body); // SourceRead "SEGA "
+        varint(((10 - 1) << 2) | 1, &mut body); // TargetRead "MEGA DRIVE"
+        body.extend(b"MEGA DRIVE");
+        let patch = with_footer(body, &source, &target);
+
+        assert_eq!(apply(&patch, &source).unwrap(), target);
+        assert!(matches!(
+            apply(&patch, b"SEGA GENESIZ"),
+            Err(PatchError::SourceChecksum { .. })
+        ));
+
+        let mut corrupted = patch.clone();
+        corrupted[10] ^= 1;
+        assert!(matches!(apply(&corrupted, &source), Err(PatchError::PatchChecksum { .. })));
+    }
+
+    #[test]
+    fn test_bps_rejects_oversized_values() {
+        let source = b"SEGA".to_vec();
+        let target = b"SEGASEGA".to_vec();
+        let header = |body: &mut Vec<u8>| {
+            varint(source.len(), body);
+            varint(target.len(), body);
+        };
+
+        // Metadados com tamanho que transborda a posição de leitura
+        let mut body = b"BPS1".to_vec();
+        header(&mut body);
+        varint(usize::MAX - 2, &mut body);
+        let patch = with_footer(body, &source, &target);
+        assert!(matches!(apply(&patch, &source), Err(PatchError::Truncated(PatchFormat::Bps))));
+
+        // Número sem fim: mais bytes do que cabem em 64 bits
+        let mut body = b"BPS1".to_vec();
+        body.extend([0x7F; 12]);
+        let patch = with_footer(body, &source, &target);
+        assert!(matches!(apply(&patch, &source), Err(PatchError::Truncated(PatchFormat::Bps))));
+
+        // TargetCopy sobreposto além do tamanho declarado do resultado
+        let mut body = b"BPS1".to_vec();
+        header(&mut body);
+        varint(0, &mut body);
+        varint((4 - 1) << 2, &mut body); // SourceRead "SEGA"
+        varint(((1000 - 1) << 2) | 3, &mut body); // TargetCopy de 1000 bytes
+        varint(0, &mut body);
+        let patch = with_footer(body, &source, &target);
+        assert!(matches!(apply(&patch, &source), Err(PatchError::Truncated(PatchFormat::Bps))));
+
+        // A mesma cópia dentro do tamanho declarado funciona
+        let mut body = b"BPS1".to_vec();
+        header(&mut body);
+        varint(0, &mut body);
+        varint((4 - 1) << 2, &mut body);
+        varint(((4 - 1) << 2) | 3, &mut body);
+        varint(0, &mut body);
+        let patch = with_footer(body, &source, &target);
+        assert_eq!(apply(&patch, &source).unwrap(), target);
+    }
+
+    #[test]
+    fn test_ups_xor_blocks() {
+        let source = vec![1u8, 2, 3, 4];
+        let target = vec![1u8, 7, 3, 4, 9];
+
+        let mut body = b"UPS1".to_vec();
+        varint(source.len(), &mut body);
+        varint(target.len(), &mut body);
+        varint(1, &mut body);
+        body.extend([2 ^ 7, 0]);
+        varint(1, &mut body);
+        body.extend([9, 0]);
+        let patch = with_footer(body, &source, &target);
+
+        assert_eq!(apply(&patch, &source).unwrap(), target);
+    }
+
+    #[test]
+    fn test_unknown_format_is_rejected() {
+        assert!(matches!(apply(b"NOTAPATCH", &[0; 4]), Err(PatchError::UnknownFormat)));
+    }
+}
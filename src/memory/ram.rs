@@ -41,6 +41,10 @@ impl Ram {
 
     /// Lê uma palavra (16 bits, big-endian) da RAM.
     pub fn read16(&self, addr: u32) -> u16 {
+        let index: usize = (addr as usize) % self.data.len();
+        if index + 2 <= self.data.len() {
+            return u16::from_be_bytes([self.data[index], self.data[index + 1]]);
+        }
         let hi: u16 = self.read8(addr) as u16;
         let lo: u16 = self.read8(addr.wrapping_add(1)) as u16;
         (hi << 8) | lo
@@ -48,6 +52,12 @@ impl Ram {
 
     /// Lê um longword (32 bits, big-endian) da RAM.
     pub fn read32(&self, addr: u32) -> u32 {
+        let index: usize = (addr as usize) % self.data.len();
+        if index + 4 <= self.data.len() {
+            let mut bytes = [0u8; 4];
+            bytes.copy_from_slice(&self.data[index..index + 4]);
+            return u32::from_be_bytes(bytes);
+        }
         let b0: u32 = self.read8(addr) as u32;
         let b1: u32 = self.read8(addr.wrapping_add(1)) as u32;
         let b2: u32 = self.read8(addr.wrapping_add(2)) as u32;
@@ -63,12 +73,22 @@ impl Ram {
 
     /// Escreve uma palavra (16 bits) na RAM.
     pub fn write16(&mut self, addr: u32, value: u16) {
+        let index: usize = (addr as usize) % self.data.len();
+        if index + 2 <= self.data.len() {
+            self.data[index..index + 2].copy_from_slice(&value.to_be_bytes());
+            return;
+        }
         self.write8(addr, (value >> 8) as u8);
         self.write8(addr.wrapping_add(1), (value & 0xFF) as u8);
     }
 
     /// Escreve um longword (32 bits) na RAM.
     pub fn write32(&mut self, addr: u32, value: u32) {
+        let index: usize = (addr as usize) % self.data.len();
+        if index + 4 <= self.data.len() {
+            self.data[index..index + 4].copy_from_slice(&value.to_be_bytes());
+            return;
+        }
         self.write8(addr, ((value >> 24) & 0xFF) as u8);
         self.write8(addr.wrapping_add(1), ((value >> 16) & 0xFF) as u8);
         self.write8(addr.wrapping_add(2), ((value >> 8) & 0xFF) as u8);
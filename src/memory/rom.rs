@@ -24,9 +24,88 @@ pub struct RomHeader {
     pub rom_end: u32,
     pub ram_start: u32,
     pub ram_end: u32,
+    /// Descritor de SRAM do cartucho ("RA" em 0x1B0), se presente
+    pub sram: Option<SaveRamInfo>,
     pub region: String,
 }
 
+/// Linhas de dados do barramento ligadas à SRAM do cartucho
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SramLanes {
+    /// SRAM de 16 bits (bytes pares e ímpares)
+    Word,
+    /// Apenas bytes em endereços pares (D8–D15)
+    Even,
+    /// Apenas bytes em endereços ímpares (D0–D7), o caso mais comum
+    Odd,
+}
+
+/// Descritor de SRAM do cabeçalho (0x1B0–0x1BB):
+/// - 0x1B0: "RA"
+/// - 0x1B2: flags (bit 6 = bateria, bits 4–3 = linhas: 00/01 16 bits, 10 pares, 11 ímpares)
+/// - 0x1B3: 0x20
+/// - 0x1B4: endereço inicial, 0x1B8: endereço final
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SaveRamInfo {
+    pub start: u32,
+    pub end: u32,
+    pub lanes: SramLanes,
+    /// Conteúdo mantido por bateria (deve ser persistido em disco)
+    pub battery: bool,
+}
+
+impl SaveRamInfo {
+    /// SRAM genérica de 64 KB em 0x200000, usada quando o mapper é forçado
+    /// para SRAM mas o cabeçalho não declara nenhuma.
+    pub fn fallback() -> Self {
+        Self {
+            start: 0x200000,
+            end: 0x20FFFF,
+            lanes: SramLanes::Word,
+            battery: true,
+        }
+    }
+
+    /// Interpreta o descritor "RA" a partir dos bytes brutos do cabeçalho
+    pub fn parse(descriptor: &[u8]) -> Option<Self> {
+        if descriptor.len() < 12 || &descriptor[0..2] != b"RA" {
+            return None;
+        }
+        let flags = descriptor[2];
+        let get_u32 = |i: usize| u32::from_be_bytes([descriptor[i], descriptor[i + 1], descriptor[i + 2], descriptor[i + 3]]);
+        let start = get_u32(4) & 0xFFFFFF;
+        let end = get_u32(8) & 0xFFFFFF;
+        if end < start {
+            return None;
+        }
+        let lanes = match (flags >> 3) & 0x03 {
+            0b10 => SramLanes::Even,
+            0b11 => SramLanes::Odd,
+            _ => SramLanes::Word,
+        };
+        Some(Self {
+            start,
+            end,
+            lanes,
+            battery: flags & 0x40 != 0,
+        })
+    }
+
+    /// Tamanho da SRAM em bytes (cada linha de 8 bits guarda um byte a cada 2 endereços)
+    pub fn size(&self) -> usize {
+        let span = (self.end | 1) - (self.start & !1) + 1;
+        match self.lanes {
+            SramLanes::Word => span as usize,
+            SramLanes::Even | SramLanes::Odd => span as usize / 2,
+        }
+    }
+
+    /// Retorna true se `addr` está dentro da faixa declarada
+    pub fn contains(&self, addr: u32) -> bool {
+        ((self.start & !1)..=(self.end | 1)).contains(&addr)
+    }
+}
+
 impl fmt::Debug for Rom {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("Rom")
@@ -48,6 +127,11 @@ impl Rom {
         self.data.len()
     }
 
+    /// Retorna os dados brutos da ROM (para acesso direto pelo barramento).
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
     /// Lê um byte (8 bits) da ROM.
     pub fn read8(&self, addr: u32) -> u8 {
         let index = (addr as usize) % self.data.len();
@@ -74,6 +158,88 @@ impl Rom {
     pub fn header(&self) -> &RomHeader {
         &self.header
     }
+
+    /// Confere checksum e faixa de endereços do cabeçalho com o conteúdo
+    /// real da ROM. Lista vazia = cabeçalho consistente.
+    pub fn verify_header(&self) -> Vec<HeaderIssue> {
+        let mut issues = Vec::new();
+        let computed = compute_checksum(&self.data);
+        if computed != self.header.checksum {
+            issues.push(HeaderIssue::ChecksumMismatch { stored: self.header.checksum, computed });
+        }
+        if self.header.rom_start != 0 {
+            issues.push(HeaderIssue::RomStart(self.header.rom_start));
+        }
+        let actual_end = self.data.len().saturating_sub(1) as u32;
+        if self.header.rom_end != actual_end {
+            issues.push(HeaderIssue::RomEnd { stored: self.header.rom_end, actual: actual_end });
+        }
+        issues
+    }
+}
+
+// =====================================================
+// CHECKSUM
+// =====================================================
+
+/// Início da área coberta pelo checksum (logo após o cabeçalho)
+pub const CHECKSUM_START: usize = 0x200;
+
+/// Checksum padrão do Mega Drive: soma (com overflow) das palavras
+/// big-endian de 0x200 até o fim da ROM. Um byte final ímpar entra como
+/// parte alta de uma palavra.
+pub fn compute_checksum(data: &[u8]) -> u16 {
+    data.get(CHECKSUM_START..)
+        .unwrap_or_default()
+        .chunks(2)
+        .map(|w| u16::from_be_bytes([w[0], w.get(1).copied().unwrap_or(0)]))
+        .fold(0u16, u16::wrapping_add)
+}
+
+/// Inconsistência encontrada no cabeçalho
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeaderIssue {
+    /// Checksum em 0x18E diferente do calculado
+    ChecksumMismatch { stored: u16, computed: u16 },
+    /// Início da ROM (0x1A0) diferente de 0
+    RomStart(u32),
+    /// Fim da ROM (0x1A4) diferente do tamanho real - 1
+    RomEnd { stored: u32, actual: u32 },
+}
+
+impl fmt::Display for HeaderIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ChecksumMismatch { stored, computed } => write!(
+                f,
+                "checksum do cabeçalho 0x{:04X} difere do calculado 0x{:04X} (muitos jogos não iniciam assim)",
+                stored, computed
+            ),
+            Self::RomStart(start) => write!(f, "início da ROM no cabeçalho é 0x{:06X}, esperado 0x000000", start),
+            Self::RomEnd { stored, actual } => write!(
+                f,
+                "fim da ROM no cabeçalho é 0x{:06X}, mas o tamanho real termina em 0x{:06X}",
+                stored, actual
+            ),
+        }
+    }
+}
+
+/// Corrige o cabeçalho no lugar: faixa da ROM (0x1A0–0x1A7) e checksum
+/// (0x18E). Usado pelo comando `fix-header` em builds de homebrew.
+/// Retorna os problemas que foram corrigidos.
+pub fn fix_header(data: &mut [u8]) -> Vec<HeaderIssue> {
+    if data.len() < CHECKSUM_START {
+        return Vec::new();
+    }
+    let issues = Rom::new(data.to_vec()).verify_header();
+    let end = (data.len() - 1) as u32;
+    data[0x1A0..0x1A4].copy_from_slice(&0u32.to_be_bytes());
+    data[0x1A4..0x1A8].copy_from_slice(&end.to_be_bytes());
+    // O checksum não cobre o cabeçalho, então pode ser calculado por último
+    let checksum = compute_checksum(data);
+    data[0x18E..0x190].copy_from_slice(&checksum.to_be_bytes());
+    issues
 }
 
 /// Implementação de leitura e decodificação do cabeçalho SEGA.
@@ -120,6 +286,7 @@ impl RomHeader {
             rom_end: get_u32(0x1A4),
             ram_start: get_u32(0x1A8),
             ram_end: get_u32(0x1AC),
+            sram: data.get(0x1B0..0x1BC).and_then(SaveRamInfo::parse),
             region: safe_get_str(0x1F0, 3),
         }
     }
@@ -156,6 +323,46 @@ mod tests {
         assert_eq!(header.region, "JUE");
     }
 
+    #[test]
+    fn test_sram_descriptor_parsing() {
+        let mut data = vec![0; 512];
+        data[0x1B0..0x1BC].copy_from_slice(&[
+            b'R', b'A', 0xF8, 0x20, 0x00, 0x20, 0x00, 0x01, 0x00, 0x20, 0x3F, 0xFF,
+        ]);
+        let sram = Rom::new(data.clone()).header().sram.unwrap();
+        assert_eq!(sram.lanes, SramLanes::Odd);
+        assert!(sram.battery);
+        assert_eq!((sram.start, sram.end), (0x200001, 0x203FFF));
+        assert_eq!(sram.size(), 0x2000);
+
+        data[0x1B2] = 0xE0; // 16 bits
+        let sram = Rom::new(data.clone()).header().sram.unwrap();
+        assert_eq!(sram.lanes, SramLanes::Word);
+        assert_eq!(sram.size(), 0x4000);
+
+        data[0x1B0] = b' ';
+        assert!(Rom::new(data).header().sram.is_none());
+    }
+
+    #[test]
+    fn test_checksum_verification_and_fix() {
+        let mut data = vec![0; 0x400];
+        data[0x100..0x104].copy_from_slice(b"SEGA");
+        data[0x200..0x204].copy_from_slice(&[0x12, 0x34, 0xFF, 0xFF]);
+        data[0x3FF] = 0x01;
+        assert_eq!(compute_checksum(&data), 0x1234u16.wrapping_add(0xFFFF).wrapping_add(0x0001));
+
+        let issues = Rom::new(data.clone()).verify_header();
+        assert!(matches!(issues[0], HeaderIssue::ChecksumMismatch { stored: 0, computed: 0x1234 }));
+        assert!(issues.contains(&HeaderIssue::RomEnd { stored: 0, actual: 0x3FF }));
+
+        assert_eq!(fix_header(&mut data).len(), 2);
+        let rom = Rom::new(data);
+        assert!(rom.verify_header().is_empty());
+        assert_eq!(rom.header().checksum, 0x1234);
+        assert_eq!(rom.header().rom_end, 0x3FF);
+    }
+
     #[test]
     fn test_read_block() {
         let data = (0..64).collect::<Vec<u8>>();
@@ -0,0 +1,352 @@
+//! Modo Master System (Power Base Converter)
+//!
+//! Com um cartucho de Master System o Mega Drive desliga o 68000 e o Z80
+//! passa a ser a CPU principal, com o mapa de memória do Master System:
+//! - 0x0000–0xBFFF: cartucho em três janelas de 16 KB (o primeiro 1 KB é
+//!   fixo na página 0, por causa dos vetores de interrupção);
+//! - 0xC000–0xDFFF: 8 KB de RAM, espelhados em 0xE000–0xFFFF;
+//! - 0xFFFC–0xFFFF: registradores do mapper da Sega (escritos também na
+//!   RAM): controle da RAM do cartucho e páginas das janelas 0, 1 e 2.
+//!
+//! As portas de I/O são decodificadas pelos bits 7, 6 e 0 do endereço:
+//! controle de memória/I/O (0x3E/0x3F), contadores V/H e PSG (0x7E/0x7F),
+//! VDP em modo 4 (0xBE/0xBF) e controles (0xDC/0xDD). O YM2612 fica
+//! inacessível: o PSG é a única fonte de som.
+
+use crate::console::ConsoleModel;
+use crate::cpu::z80::{Z80, Z80Bus};
+use crate::io::sms::{SmsButtons, SmsPorts};
+use crate::memory::rom::Rom;
+use crate::memory::sram;
+use crate::sound::Sound;
+use crate::vdp::mode4::{Mode4Vdp, MODE4_HEIGHT};
+use crate::vdp::{FrameBuffer, VdpRenderer, VdpVideoMode};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Ciclos do Z80 por linha (3,58 MHz / 262 linhas / 60 Hz)
+pub const SMS_CYCLES_PER_LINE: u32 = 228;
+/// RAM principal do Master System
+pub const SMS_RAM_SIZE: usize = 0x2000;
+/// Tamanho de uma página do mapper
+pub const SMS_PAGE_SIZE: usize = 0x4000;
+/// RAM do cartucho (dois bancos de 16 KB)
+pub const SMS_CART_RAM_SIZE: usize = 0x8000;
+
+// Bits do registrador de controle (0xFFFC)
+const CONTROL_RAM_BANK: u8 = 0x04;
+const CONTROL_RAM_ENABLE: u8 = 0x08;
+
+// =====================================================
+// MAPPER DA SEGA
+// =====================================================
+
+/// Mapper da Sega (0xFFFC–0xFFFF) com a RAM opcional do cartucho
+pub struct SmsMapper {
+    rom: Rom,
+    /// Registrador 0xFFFC
+    pub control: u8,
+    /// Página de cada janela (0xFFFD–0xFFFF)
+    pub pages: [u8; 3],
+    pub cart_ram: Vec<u8>,
+    /// A RAM do cartucho foi alterada desde a última gravação
+    ram_dirty: bool,
+}
+
+impl SmsMapper {
+    pub fn new(rom: Rom) -> Self {
+        Self {
+            rom,
+            control: 0,
+            pages: [0, 1, 2],
+            cart_ram: vec![0; SMS_CART_RAM_SIZE],
+            ram_dirty: false,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.control = 0;
+        self.pages = [0, 1, 2];
+    }
+
+    fn page_count(&self) -> usize {
+        self.rom.size().div_ceil(SMS_PAGE_SIZE).max(1)
+    }
+
+    fn cart_ram_offset(&self, addr: u16) -> usize {
+        let bank = if self.control & CONTROL_RAM_BANK != 0 { SMS_PAGE_SIZE } else { 0 };
+        bank + (addr as usize & (SMS_PAGE_SIZE - 1))
+    }
+
+    /// Leitura na área do cartucho (0x0000–0xBFFF)
+    pub fn read(&self, addr: u16) -> u8 {
+        let window = addr as usize / SMS_PAGE_SIZE;
+        match addr {
+            0x0000..=0x03FF => self.rom.read8(addr as u32),
+            0x8000..=0xBFFF if self.control & CONTROL_RAM_ENABLE != 0 => self.cart_ram[self.cart_ram_offset(addr)],
+            0x0400..=0xBFFF => {
+                let page = self.pages[window] as usize % self.page_count();
+                self.rom.read8((page * SMS_PAGE_SIZE + (addr as usize & (SMS_PAGE_SIZE - 1))) as u32)
+            }
+            _ => 0xFF,
+        }
+    }
+
+    /// Escrita na RAM do cartucho ou nos registradores do mapper
+    pub fn write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x8000..=0xBFFF if self.control & CONTROL_RAM_ENABLE != 0 => {
+                let offset = self.cart_ram_offset(addr);
+                self.cart_ram[offset] = value;
+                self.ram_dirty = true;
+            }
+            0xFFFC => self.control = value,
+            0xFFFD..=0xFFFF => self.pages[(addr - 0xFFFD) as usize] = value,
+            _ => {}
+        }
+    }
+}
+
+// =====================================================
+// BARRAMENTO DO Z80
+// =====================================================
+
+/// Tudo o que o Z80 enxerga no modo Master System
+pub struct SmsBus {
+    pub mapper: SmsMapper,
+    pub ram: Vec<u8>,
+    pub vdp: Mode4Vdp,
+    pub ports: SmsPorts,
+    pub sound: Arc<Mutex<Sound>>,
+    /// Último valor escrito no controle de memória (0x3E)
+    pub memory_control: u8,
+    /// Ciclos já executados na linha atual (para o contador H)
+    line_cycles: u32,
+}
+
+impl Z80Bus for SmsBus {
+    fn read(&mut self, addr: u16) -> u8 {
+        if addr >= 0xC000 {
+            self.ram[addr as usize % SMS_RAM_SIZE]
+        } else {
+            self.mapper.read(addr)
+        }
+    }
+
+    fn write(&mut self, addr: u16, value: u8) {
+        if addr >= 0xC000 {
+            self.ram[addr as usize % SMS_RAM_SIZE] = value;
+        }
+        if !(0xC000..0xFFFC).contains(&addr) {
+            self.mapper.write(addr, value);
+        }
+    }
+
+    fn port_in(&mut self, port: u16) -> u8 {
+        match port as u8 & 0xC1 {
+            0x40 => self.vdp.v_counter(),
+            0x41 => self.vdp.h_counter(self.line_cycles),
+            0x80 => self.vdp.read_data(),
+            0x81 => self.vdp.read_control(),
+            0xC0 => self.ports.read_port_a(),
+            0xC1 => self.ports.read_port_b(),
+            _ => 0xFF,
+        }
+    }
+
+    fn port_out(&mut self, port: u16, value: u8) {
+        match port as u8 & 0xC1 {
+            0x00 => self.memory_control = value,
+            0x01 => self.ports.write_control(value),
+            0x40 | 0x41 => self.sound.lock().unwrap().psg.write().write_data(value),
+            0x80 => self.vdp.write_data(value),
+            0x81 => self.vdp.write_control(value),
+            _ => {}
+        }
+    }
+}
+
+// =====================================================
+// SISTEMA
+// =====================================================
+
+/// Master System completo: Z80, barramento e renderizador do modo 4
+pub struct MasterSystem {
+    pub z80: Z80,
+    pub bus: SmsBus,
+    pub renderer: VdpRenderer,
+    /// Botão de pausa pressionado (gera NMI na próxima linha)
+    pause_pending: bool,
+    save_path: Option<PathBuf>,
+}
+
+impl MasterSystem {
+    /// Monta o sistema para uma ROM de Master System. A região do console
+    /// define PAL/NTSC e o resultado da detecção de região pelos pinos TH.
+    pub fn new(rom: Rom, sound_rate: u32, console: ConsoleModel) -> Self {
+        let sound = Arc::new(Mutex::new(Sound::with_master_clock(sound_rate, console.master_clock())));
+        let mut z80 = Z80::new(sound.clone());
+        z80.reset();
+        let bus = SmsBus {
+            mapper: SmsMapper::new(rom),
+            ram: vec![0; SMS_RAM_SIZE],
+            vdp: Mode4Vdp::new(console.is_pal()),
+            ports: SmsPorts::new(console.region.is_overseas()),
+            sound,
+            memory_control: 0,
+            line_cycles: 0,
+        };
+        Self {
+            z80,
+            bus,
+            renderer: VdpRenderer::new(VdpVideoMode::new_default()),
+            pause_pending: false,
+            save_path: None,
+        }
+    }
+
+    /// Botão de reset do console: reinicia CPU, mapper e VDP (a RAM fica)
+    pub fn reset(&mut self) {
+        self.z80.reset();
+        self.bus.mapper.reset();
+        self.bus.vdp.reset();
+        self.bus.line_cycles = 0;
+    }
+
+    /// Botão de pausa (ligado à NMI do Z80)
+    pub fn press_pause(&mut self) {
+        self.pause_pending = true;
+    }
+
+    /// Estado dos botões de um controle (0 ou 1)
+    pub fn set_buttons(&mut self, pad: usize, buttons: SmsButtons) {
+        if let Some(state) = self.bus.ports.pads.get_mut(pad) {
+            *state = buttons;
+        }
+    }
+
+    /// Executa um quadro inteiro, linha a linha
+    pub fn run_frame(&mut self) {
+        for _ in 0..self.bus.vdp.lines_per_frame() {
+            self.run_line();
+        }
+    }
+
+    /// Renderiza a linha atual (se ativa) e executa os ciclos do Z80
+    /// correspondentes, atendendo a /INT do VDP entre instruções
+    fn run_line(&mut self) {
+        let line = self.bus.vdp.line as usize;
+        if line < MODE4_HEIGHT {
+            self.bus.vdp.status |= self.renderer.render_mode4_line(&self.bus.vdp, line);
+        }
+        if std::mem::take(&mut self.pause_pending) {
+            self.bus.line_cycles += self.z80.nmi(&mut self.bus);
+        }
+
+        while self.bus.line_cycles < SMS_CYCLES_PER_LINE {
+            let mut cycles = 0;
+            if self.bus.vdp.irq() {
+                cycles = self.z80.interrupt(&mut self.bus);
+            }
+            if cycles == 0 {
+                cycles = self.z80.step(&mut self.bus);
+            }
+            self.bus.line_cycles += cycles;
+        }
+        self.bus.line_cycles -= SMS_CYCLES_PER_LINE;
+        self.bus.vdp.end_line();
+        self.bus.sound.lock().unwrap().psg.write().tick(SMS_CYCLES_PER_LINE);
+    }
+
+    /// Framebuffer do último quadro (256x192)
+    pub fn frame_buffer(&self) -> &FrameBuffer {
+        &self.renderer.frame_buffer
+    }
+
+    /// Amostra de áudio: só o PSG
+    pub fn sample(&self) -> f32 {
+        self.bus.sound.lock().unwrap().psg.read().sample()
+    }
+
+    // =====================================================
+    // SAVE RAM
+    // =====================================================
+
+    /// Associa a RAM do cartucho ao arquivo `.srm` ao lado da ROM e carrega
+    /// o save existente
+    pub fn attach_save_ram(&mut self, rom_path: &Path) -> io::Result<PathBuf> {
+        let path = sram::save_path_for(rom_path);
+        if let Some(bytes) = sram::read_save_file(&path)? {
+            let len = bytes.len().min(SMS_CART_RAM_SIZE);
+            self.bus.mapper.cart_ram[..len].copy_from_slice(&bytes[..len]);
+        }
+        self.save_path = Some(path.clone());
+        Ok(path)
+    }
+
+    /// Grava a RAM do cartucho se o jogo escreveu nela. Retorna true se o
+    /// arquivo foi escrito.
+    pub fn flush_save_ram(&mut self) -> io::Result<bool> {
+        match &self.save_path {
+            Some(path) if self.bus.mapper.ram_dirty => {
+                sram::write_save_file(path, &self.bus.mapper.cart_ram)?;
+                self.bus.mapper.ram_dirty = false;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rom_with_pages(count: usize) -> Rom {
+        let mut data = vec![0; count * SMS_PAGE_SIZE];
+        for (page, chunk) in data.chunks_mut(SMS_PAGE_SIZE).enumerate() {
+            chunk.fill(page as u8);
+        }
+        Rom::new(data)
+    }
+
+    #[test]
+    fn test_sega_mapper_pages_and_cart_ram() {
+        let mut mapper = SmsMapper::new(rom_with_pages(8));
+        assert_eq!((mapper.read(0x0000), mapper.read(0x4000), mapper.read(0x8000)), (0, 1, 2));
+
+        mapper.write(0xFFFD, 5);
+        mapper.write(0xFFFF, 9);
+        // O primeiro 1 KB continua na página 0; 9 dá a volta em 8 páginas
+        assert_eq!((mapper.read(0x0100), mapper.read(0x0400), mapper.read(0x8000)), (0, 5, 1));
+
+        mapper.write(0xFFFC, CONTROL_RAM_ENABLE | CONTROL_RAM_BANK);
+        mapper.write(0x8010, 0x42);
+        assert_eq!(mapper.read(0x8010), 0x42);
+        assert_eq!(mapper.cart_ram[SMS_PAGE_SIZE + 0x10], 0x42);
+        assert!(mapper.ram_dirty);
+    }
+
+    #[test]
+    fn test_program_drives_vdp_and_interrupts() {
+        // Página 0:
+        //   0000: DI; IM 1; LD SP,DFF0h; LD A,20h; OUT (BFh),A;
+        //         LD A,81h; OUT (BFh),A; EI; HALT; JR -3
+        //   0038: IN A,(BFh); LD HL,C000h; INC (HL); EI; RETI
+        let mut data = vec![0; SMS_PAGE_SIZE * 2];
+        data[..20].copy_from_slice(&[
+            0xF3, 0xED, 0x56, 0x31, 0xF0, 0xDF, 0x3E, 0x20, 0xD3, 0xBF, 0x3E, 0x81, 0xD3, 0xBF, 0xFB, 0x76, 0x18,
+            0xFD, 0x00, 0x00,
+        ]);
+        data[0x38..0x42].copy_from_slice(&[0xDB, 0xBF, 0x21, 0x00, 0xC0, 0x34, 0xFB, 0xED, 0x4D, 0x00]);
+        let mut sms = MasterSystem::new(Rom::new(data), 44100, ConsoleModel::default());
+
+        sms.run_frame();
+        sms.run_frame();
+        // Registrador 1 com a interrupção de quadro ligada; uma por quadro
+        assert_eq!(sms.bus.vdp.regs[1], 0x20);
+        assert_eq!(sms.bus.ram[0], 2);
+        assert_eq!(sms.frame_buffer().width, 256);
+    }
+}
@@ -0,0 +1,260 @@
+//! SRAM com bateria (save RAM) dos cartuchos
+//!
+//! A faixa de endereços e as linhas de dados usadas vêm do descritor "RA"
+//! do cabeçalho (ver `SaveRamInfo`). Em cartuchos com mais de 2 MB a SRAM
+//! divide o espaço com a ROM, e o registrador 0xA130F1 escolhe qual dos dois
+//! aparece na faixa:
+//! - bit 0: 1 = SRAM mapeada, 0 = ROM
+//! - bit 1: 1 = SRAM protegida contra escrita
+//!
+//! O conteúdo de SRAMs com bateria é persistido em um arquivo `.srm` ao
+//! lado da ROM, gravado apenas quando algo mudou desde a última gravação.
+
+use crate::memory::rom::{SaveRamInfo, SramLanes};
+use log::{error, info};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Registrador de controle SRAM/ROM
+pub const SRAM_CONTROL_ADDR: u32 = 0xA130F1;
+
+/// Estado da SRAM do cartucho
+#[derive(Debug)]
+pub struct Sram {
+    pub info: SaveRamInfo,
+    data: Vec<u8>,
+    /// SRAM visível na faixa (bit 0 de 0xA130F1)
+    pub mapped: bool,
+    /// Escritas ignoradas (bit 1 de 0xA130F1)
+    pub write_protected: bool,
+    /// Estado de `mapped` ao ligar o console
+    power_on_mapped: bool,
+    /// Conteúdo alterado desde a última gravação em disco
+    dirty: bool,
+    /// Arquivo `.srm` associado
+    path: Option<PathBuf>,
+}
+
+impl Sram {
+    /// Cria a SRAM descrita por `info`. Se a ROM não alcança a faixa da
+    /// SRAM, ela fica sempre visível; caso contrário começa desligada e o
+    /// jogo a habilita via 0xA130F1.
+    pub fn new(info: SaveRamInfo, rom_size: usize) -> Self {
+        let power_on_mapped = rom_size <= (info.start & !1) as usize;
+        Self {
+            info,
+            // SRAM nova vem "apagada" (0xFF), como em cartuchos sem bateria carregada
+            data: vec![0xFF; info.size()],
+            mapped: power_on_mapped,
+            write_protected: false,
+            power_on_mapped,
+            dirty: false,
+            path: None,
+        }
+    }
+
+    /// Retorna true se o acesso em `addr` vai para a SRAM
+    pub fn contains(&self, addr: u32) -> bool {
+        self.mapped && self.info.contains(addr)
+    }
+
+    /// Índice no buffer para um endereço, respeitando as linhas de dados
+    fn index(&self, addr: u32) -> Option<usize> {
+        if !self.info.contains(addr) {
+            return None;
+        }
+        let offset = (addr - (self.info.start & !1)) as usize;
+        match self.info.lanes {
+            SramLanes::Word => Some(offset),
+            SramLanes::Odd if addr & 1 == 1 => Some(offset >> 1),
+            SramLanes::Even if addr & 1 == 0 => Some(offset >> 1),
+            _ => None,
+        }
+    }
+
+    /// Lê um byte. Endereços fora das linhas de dados retornam barramento aberto (0xFF).
+    pub fn read8(&self, addr: u32) -> u8 {
+        self.index(addr).map_or(0xFF, |i| self.data[i])
+    }
+
+    /// Escreve um byte (ignorado se protegida ou fora das linhas de dados)
+    pub fn write8(&mut self, addr: u32, value: u8) {
+        if self.write_protected {
+            return;
+        }
+        if let Some(i) = self.index(addr) {
+            if self.data[i] != value {
+                self.data[i] = value;
+                self.dirty = true;
+            }
+        }
+    }
+
+    /// Escrita em 0xA130F1. Retorna true se a faixa passou a mostrar
+    /// outro conteúdo (ROM <-> SRAM).
+    pub fn write_control(&mut self, value: u8) -> bool {
+        let mapped = value & 0x01 != 0;
+        let changed = mapped != self.mapped;
+        self.mapped = mapped;
+        self.write_protected = value & 0x02 != 0;
+        changed
+    }
+
+    /// Deixa a SRAM sempre visível, inclusive após reset (jogos que
+    /// dependem disso sem escrever em 0xA130F1)
+    pub fn set_always_mapped(&mut self) {
+        self.mapped = true;
+        self.power_on_mapped = true;
+    }
+
+    /// Volta o registrador de controle ao estado de power-on
+    pub fn reset(&mut self) {
+        self.mapped = self.power_on_mapped;
+        self.write_protected = false;
+    }
+
+    /// Conteúdo bruto da SRAM
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+
+    /// Substitui o conteúdo (arquivos menores completam com 0xFF, maiores são truncados)
+    pub fn load_bytes(&mut self, bytes: &[u8]) {
+        let len = bytes.len().min(self.data.len());
+        self.data[..len].copy_from_slice(&bytes[..len]);
+        self.data[len..].fill(0xFF);
+    }
+
+    /// Retorna true se há alterações ainda não gravadas em disco
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Arquivo `.srm` associado, se houver
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Associa um arquivo `.srm` e carrega o conteúdo dele, se existir.
+    /// SRAMs sem bateria não são persistidas.
+    pub fn attach_file(&mut self, path: PathBuf) -> io::Result<()> {
+        if !self.info.battery {
+            return Ok(());
+        }
+        if let Some(bytes) = read_save_file(&path)? {
+            info!("SRAM carregada de {} ({} bytes)", path.display(), bytes.len());
+            self.load_bytes(&bytes);
+        }
+        self.path = Some(path);
+        self.dirty = false;
+        Ok(())
+    }
+
+    /// Grava a SRAM no arquivo associado se houve alterações.
+    /// Retorna true se o arquivo foi escrito.
+    pub fn flush(&mut self) -> io::Result<bool> {
+        let Some(path) = &self.path else {
+            return Ok(false);
+        };
+        if !self.dirty {
+            return Ok(false);
+        }
+        write_save_file(path, &self.data)?;
+        self.dirty = false;
+        Ok(true)
+    }
+}
+
+impl Drop for Sram {
+    fn drop(&mut self) {
+        if let Err(e) = self.flush() {
+            error!("Falha ao gravar SRAM: {}", e);
+        }
+    }
+}
+
+/// Caminho do arquivo `.srm` correspondente a uma ROM
+pub fn save_path_for(rom_path: &Path) -> PathBuf {
+    rom_path.with_extension("srm")
+}
+
+/// Lê um arquivo de save. Retorna `None` se ele ainda não existe.
+pub fn read_save_file(path: &Path) -> io::Result<Option<Vec<u8>>> {
+    match std::fs::read(path) {
+        Ok(bytes) => Ok(Some(bytes)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Grava um arquivo de save. Escreve em um arquivo temporário e renomeia,
+/// para não corromper o save se o programa for interrompido no meio.
+pub fn write_save_file(path: &Path, data: &[u8]) -> io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    std::fs::write(&tmp, data)?;
+    std::fs::rename(&tmp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn odd_sram(rom_size: usize) -> Sram {
+        let info = SaveRamInfo {
+            start: 0x200001,
+            end: 0x203FFF,
+            lanes: SramLanes::Odd,
+            battery: true,
+        };
+        Sram::new(info, rom_size)
+    }
+
+    #[test]
+    fn test_odd_lane_addressing() {
+        let mut sram = odd_sram(0x100000);
+        assert!(sram.contains(0x200001));
+        sram.write8(0x200001, 0x12);
+        sram.write8(0x200003, 0x34);
+        sram.write8(0x200002, 0x56); // linha par: ignorada
+        assert_eq!(&sram.data()[..2], &[0x12, 0x34]);
+        assert_eq!(sram.read8(0x200002), 0xFF);
+        assert!(sram.is_dirty());
+    }
+
+    #[test]
+    fn test_control_register_switches_rom_and_sram() {
+        let mut sram = odd_sram(0x400000);
+        assert!(!sram.contains(0x200001));
+
+        assert!(sram.write_control(0x01));
+        assert!(sram.contains(0x200001));
+
+        sram.write_control(0x03);
+        sram.write8(0x200001, 0xAA);
+        assert_eq!(sram.read8(0x200001), 0xFF);
+
+        sram.reset();
+        assert!(!sram.mapped);
+    }
+
+    #[test]
+    fn test_persistence_round_trip() {
+        let dir = std::env::temp_dir().join(format!("megastrife-sram-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = save_path_for(&dir.join("jogo.md"));
+        assert_eq!(path.extension().unwrap(), "srm");
+
+        let mut sram = odd_sram(0x100000);
+        sram.attach_file(path.clone()).unwrap();
+        assert!(!sram.flush().unwrap());
+        sram.write8(0x200005, 0x77);
+        assert!(sram.flush().unwrap());
+        drop(sram);
+
+        let mut reloaded = odd_sram(0x100000);
+        reloaded.attach_file(path).unwrap();
+        assert_eq!(reloaded.read8(0x200005), 0x77);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,207 @@
+//! SVP (Sega Virtua Processor) do Virtua Racing
+//!
+//! O cartucho traz um DSP SSP1601 (ver `cpu::ssp1601`) com 2 KB de IRAM
+//! e 128 KB de DRAM próprios. Para o 68000 ele aparece como:
+//! - 0x300000–0x31FFFF: DRAM do SVP, leitura e escrita;
+//! - 0x390000–0x39FFFF e 0x3A0000–0x3AFFFF: a mesma DRAM reorganizada em
+//!   células de 8x8 pixels (duas variações), usada pelo 68000 para copiar o
+//!   quadro renderizado para a VRAM por DMA;
+//! - 0xA15000/0xA15002: XST, registrador compartilhado com o DSP (escrita
+//!   liga o bit 1 de PM0 para avisar o DSP);
+//! - 0xA15004: estado (PM0); o bit 0 indica que o DSP escreveu em XST e é
+//!   limpo pela leitura.
+//!
+//! O DSP roda em passo travado com o 68000: `run` deve ser chamado com os
+//! ciclos do 68000 decorridos (o SSP1601 roda a ~23 MHz, três instruções
+//! por ciclo do 68000 numa aproximação grosseira).
+
+use crate::cpu::ssp1601::{Ssp1601, SspBus};
+use crate::memory::rom::Rom;
+use std::sync::Arc;
+
+/// Instruções do SSP1601 por ciclo do 68000
+pub const SSP_CYCLES_PER_M68K_CYCLE: u32 = 3;
+/// Tamanho da IRAM em palavras (2 KB)
+pub const SVP_IRAM_WORDS: usize = 0x400;
+/// Tamanho da DRAM em palavras (128 KB)
+pub const SVP_DRAM_WORDS: usize = 0x10000;
+
+/// Registrador XST compartilhado (0xA15000, espelhado em 0xA15002)
+pub const SVP_XST_ADDR: u32 = 0xA15000;
+/// Registrador de estado (PM0) visto pelo 68000
+pub const SVP_STATUS_ADDR: u32 = 0xA15004;
+
+/// Memória externa do DSP: ROM do cartucho, IRAM e DRAM
+struct SvpMemory {
+    rom: Arc<Rom>,
+    iram: Vec<u16>,
+    dram: Vec<u16>,
+}
+
+impl SspBus for SvpMemory {
+    fn fetch(&self, addr: u16) -> u16 {
+        if (addr as usize) < SVP_IRAM_WORDS {
+            self.iram[addr as usize]
+        } else {
+            self.rom_word(addr as u32)
+        }
+    }
+
+    fn rom_word(&self, addr: u32) -> u16 {
+        let offset = ((addr as usize) << 1) % self.rom.size().max(2);
+        ((self.rom.read8(offset as u32) as u16) << 8) | self.rom.read8(offset as u32 + 1) as u16
+    }
+
+    fn read_dram(&self, addr: u16) -> u16 {
+        self.dram[addr as usize]
+    }
+
+    fn write_dram(&mut self, addr: u16, value: u16) {
+        self.dram[addr as usize] = value;
+    }
+
+    fn write_iram(&mut self, addr: u16, value: u16) {
+        self.iram[addr as usize % SVP_IRAM_WORDS] = value;
+    }
+}
+
+/// Chip SVP: DSP e sua memória
+pub struct Svp {
+    pub ssp: Ssp1601,
+    mem: SvpMemory,
+}
+
+impl Svp {
+    pub fn new(rom: Arc<Rom>) -> Self {
+        Self {
+            ssp: Ssp1601::new(),
+            mem: SvpMemory {
+                rom,
+                iram: vec![0; SVP_IRAM_WORDS],
+                dram: vec![0; SVP_DRAM_WORDS],
+            },
+        }
+    }
+
+    /// Retorna true se o endereço do 68000 pertence ao SVP (DRAM, visões
+    /// em células ou registradores)
+    pub fn maps(addr: u32) -> bool {
+        matches!(addr, 0x300000..=0x31FFFF | 0x390000..=0x3AFFFF | 0xA15000..=0xA1500F)
+    }
+
+    pub fn reset(&mut self) {
+        self.ssp.reset();
+    }
+
+    /// Avança o DSP pelo equivalente a `m68k_cycles` ciclos do 68000
+    pub fn run(&mut self, m68k_cycles: u32) {
+        self.ssp.run(&mut self.mem, m68k_cycles * SSP_CYCLES_PER_M68K_CYCLE);
+    }
+
+    /// Retorna o conteúdo da DRAM (para debug)
+    pub fn dram(&self) -> &[u16] {
+        &self.mem.dram
+    }
+
+    // =====================================================
+    // ACESSO PELO 68000
+    // =====================================================
+
+    /// Índice na DRAM de um endereço do 68000 (DRAM direta ou visões em
+    /// células)
+    fn dram_index(addr: u32) -> Option<usize> {
+        let word = ((addr & 0xFFFE) >> 1) as usize;
+        match addr {
+            0x300000..=0x31FFFF => Some(((addr & 0x1FFFF) >> 1) as usize),
+            0x390000..=0x39FFFF => Some((word & 0x7001) | ((word & 0x3E) << 6) | ((word & 0xFC0) >> 5)),
+            0x3A0000..=0x3AFFFF => Some((word & 0x7801) | ((word & 0x1E) << 6) | ((word & 0x7E0) >> 4)),
+            _ => None,
+        }
+    }
+
+    /// Leitura de palavra pelo 68000. Ler o estado em 0xA15004 limpa o
+    /// aviso de "XST escrito pelo DSP".
+    pub fn read16(&mut self, addr: u32) -> u16 {
+        let value = self.peek16(addr);
+        if addr & 0xFFFFFE == SVP_STATUS_ADDR {
+            self.ssp.pm[0] &= !1;
+        }
+        value
+    }
+
+    /// Leitura de palavra sem efeitos colaterais
+    pub fn peek16(&self, addr: u32) -> u16 {
+        if let Some(index) = Self::dram_index(addr) {
+            return self.mem.dram[index];
+        }
+        match addr & 0xFFFFFE {
+            0xA15000 | 0xA15002 => self.ssp.pm[3],
+            SVP_STATUS_ADDR => self.ssp.pm[0],
+            _ => 0,
+        }
+    }
+
+    /// Leitura de byte pelo 68000 (sem efeitos colaterais)
+    pub fn read8(&self, addr: u32) -> u8 {
+        let word = self.peek16(addr);
+        if addr & 1 == 0 {
+            (word >> 8) as u8
+        } else {
+            word as u8
+        }
+    }
+
+    /// Escrita de palavra pelo 68000. Só a DRAM direta e o XST são graváveis.
+    pub fn write16(&mut self, addr: u32, value: u16) {
+        match addr & 0xFFFFFE {
+            0x300000..=0x31FFFF => self.mem.dram[((addr & 0x1FFFF) >> 1) as usize] = value,
+            0xA15000 | 0xA15002 => {
+                self.ssp.pm[3] = value;
+                self.ssp.pm[0] |= 2;
+            }
+            _ => {}
+        }
+    }
+
+    /// Escrita de byte pelo 68000 (combinada com a outra metade da palavra)
+    pub fn write8(&mut self, addr: u32, value: u8) {
+        let word = self.peek16(addr);
+        let word = if addr & 1 == 0 {
+            (word & 0x00FF) | ((value as u16) << 8)
+        } else {
+            (word & 0xFF00) | value as u16
+        };
+        self.write16(addr, word);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dram_and_cell_views() {
+        let mut svp = Svp::new(Arc::new(Rom::new(vec![0; 0x1000])));
+        svp.write16(0x300000, 0x1234);
+        svp.write8(0x300003, 0x56);
+        assert_eq!(svp.dram()[..2], [0x1234, 0x0056]);
+
+        // Terceira palavra da visão em células: linha seguinte na DRAM
+        svp.mem.dram[0x80] = 0xABCD;
+        assert_eq!(svp.read16(0x390004), 0xABCD);
+        assert_eq!(svp.read16(0x3A0004), 0xABCD);
+        assert_eq!(svp.read16(0x390002), 0x0056);
+    }
+
+    #[test]
+    fn test_xst_handshake() {
+        let mut svp = Svp::new(Arc::new(Rom::new(vec![0; 0x1000])));
+        svp.write16(SVP_XST_ADDR, 0x4321);
+        assert_eq!(svp.ssp.pm[3], 0x4321);
+        assert_eq!(svp.ssp.pm[0] & 2, 2);
+
+        svp.ssp.pm[0] |= 1;
+        assert_eq!(svp.read16(SVP_STATUS_ADDR) & 1, 1);
+        assert_eq!(svp.read16(SVP_STATUS_ADDR) & 1, 0);
+    }
+}
@@ -0,0 +1,212 @@
+//! TMSS (Trademark Security System) do Mega Drive / Genesis
+//!
+//! A partir do Model 1 VA6, o console trava o 68000 ao acessar o VDP
+//! até que o programa escreva a string "SEGA" em 0xA14000. Opcionalmente,
+//! uma ROM de boot (BIOS TMSS) é mapeada em 0x000000 no lugar do cartucho,
+//! exibe a tela de licenciamento e depois troca para o cartucho escrevendo
+//! o bit 0 de 0xA14101.
+
+use log::warn;
+use std::cell::Cell;
+use std::path::Path;
+
+/// Valor que destrava o VDP quando escrito em 0xA14000
+pub const TMSS_KEY: [u8; 4] = *b"SEGA";
+
+/// Endereço do registrador de destravamento (4 bytes)
+pub const TMSS_REG_ADDR: u32 = 0xA14000;
+/// Endereço do registrador de seleção BIOS/cartucho
+pub const TMSS_CART_SELECT_ADDR: u32 = 0xA14101;
+
+/// Revisões de hardware do console, que definem a presença do TMSS
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HardwareRevision {
+    /// Model 1 VA0–VA5 (sem TMSS)
+    #[default]
+    Model1Va0,
+    /// Model 1 VA6 ou posterior (com TMSS)
+    Model1Va6,
+    /// Model 2 (com TMSS)
+    Model2,
+    /// Genesis 3 / Model 3 (com TMSS)
+    Model3,
+}
+
+impl HardwareRevision {
+    /// Retorna true se esta revisão possui o TMSS
+    pub fn has_tmss(&self) -> bool {
+        !matches!(self, HardwareRevision::Model1Va0)
+    }
+}
+
+/// Estado do TMSS
+#[derive(Debug, Clone)]
+pub struct Tmss {
+    /// TMSS presente (depende do modelo do console)
+    pub enabled: bool,
+    /// Conteúdo do registrador 0xA14000–0xA14003
+    pub key: [u8; 4],
+    /// ROM de boot fornecida pelo usuário (opcional)
+    pub boot_rom: Option<Vec<u8>>,
+    /// true = cartucho mapeado em 0x000000, false = ROM de boot
+    pub cart_mapped: bool,
+    /// O 68000 acessou o VDP com o TMSS travado. Como no hardware real, a
+    /// CPU fica travada até o reset, mesmo que escreva "SEGA" depois.
+    pub lockup: Cell<bool>,
+}
+
+impl Tmss {
+    /// Cria o TMSS para uma revisão de hardware, com ROM de boot opcional.
+    /// A ROM de boot só é usada em revisões que possuem TMSS.
+    pub fn new(revision: HardwareRevision, boot_rom: Option<Vec<u8>>) -> Self {
+        let enabled = revision.has_tmss();
+        let boot_rom = boot_rom.filter(|rom| enabled && !rom.is_empty());
+        Self {
+            enabled,
+            key: [0; 4],
+            cart_mapped: boot_rom.is_none(),
+            boot_rom,
+            lockup: Cell::new(false),
+        }
+    }
+
+    /// TMSS desabilitado (consoles sem TMSS)
+    pub fn disabled() -> Self {
+        Self::new(HardwareRevision::Model1Va0, None)
+    }
+
+    /// Carrega uma ROM de boot TMSS de um arquivo
+    pub fn load_boot_rom(path: &Path) -> std::io::Result<Vec<u8>> {
+        std::fs::read(path)
+    }
+
+    /// Retorna true se o VDP ainda está travado
+    pub fn vdp_locked(&self) -> bool {
+        self.enabled && self.key != TMSS_KEY
+    }
+
+    /// Verifica um acesso do 68000 ao VDP. Retorna false (e registra o
+    /// travamento) se o acesso não é permitido. Depois do travamento todo
+    /// acesso é recusado até `reset()`.
+    pub fn check_vdp_access(&self, addr: u32) -> bool {
+        if self.lockup.get() {
+            return false;
+        }
+        if !self.vdp_locked() {
+            return true;
+        }
+        warn!("TMSS: acesso ao VDP em 0x{:06X} antes do handshake \"SEGA\" — 68000 travado", addr);
+        self.lockup.set(true);
+        false
+    }
+
+    /// Retorna true se a ROM de boot está mapeada no lugar do cartucho
+    pub fn boot_rom_mapped(&self) -> bool {
+        self.boot_rom.is_some() && !self.cart_mapped
+    }
+
+    /// Lê um byte da ROM de boot (espelhada em toda a área do cartucho)
+    pub fn read_boot_rom(&self, addr: u32) -> u8 {
+        match &self.boot_rom {
+            Some(rom) => rom[addr as usize % rom.len()],
+            None => 0xFF,
+        }
+    }
+
+    /// Lê os registradores do TMSS
+    pub fn read_register(&self, addr: u32) -> u8 {
+        match addr {
+            0xA14000..=0xA14003 => self.key[(addr - TMSS_REG_ADDR) as usize],
+            TMSS_CART_SELECT_ADDR => self.cart_mapped as u8,
+            _ => 0,
+        }
+    }
+
+    /// Escreve nos registradores do TMSS. Retorna true se o mapeamento
+    /// do cartucho mudou (a tabela de páginas precisa ser refeita).
+    pub fn write_register(&mut self, addr: u32, value: u8) -> bool {
+        match addr {
+            0xA14000..=0xA14003 if self.enabled => {
+                self.key[(addr - TMSS_REG_ADDR) as usize] = value;
+                false
+            }
+            TMSS_CART_SELECT_ADDR if self.boot_rom.is_some() => {
+                let cart_mapped = value & 0x01 != 0;
+                let changed = cart_mapped != self.cart_mapped;
+                self.cart_mapped = cart_mapped;
+                changed
+            }
+            _ => false,
+        }
+    }
+
+    /// Reseta o estado (o console volta a travar o VDP e a mapear a BIOS)
+    pub fn reset(&mut self) {
+        self.key = [0; 4];
+        self.cart_mapped = self.boot_rom.is_none();
+        self.lockup.set(false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tmss_disabled_never_locks() {
+        let tmss = Tmss::disabled();
+        assert!(!tmss.vdp_locked());
+        assert!(tmss.check_vdp_access(0xC00004));
+        assert!(!tmss.lockup.get());
+    }
+
+    #[test]
+    fn test_tmss_handshake_unlocks_vdp() {
+        let mut tmss = Tmss::new(HardwareRevision::Model1Va6, None);
+        assert!(tmss.vdp_locked());
+
+        for (i, &b) in TMSS_KEY.iter().enumerate() {
+            tmss.write_register(TMSS_REG_ADDR + i as u32, b);
+        }
+        assert!(!tmss.vdp_locked());
+        assert!(tmss.check_vdp_access(0xC00004));
+        assert!(!tmss.lockup.get());
+    }
+
+    #[test]
+    fn test_tmss_lockup_is_sticky_until_reset() {
+        let mut tmss = Tmss::new(HardwareRevision::Model1Va6, None);
+        assert!(!tmss.check_vdp_access(0xC00004));
+        assert!(tmss.lockup.get());
+
+        // "SEGA" tardio não destrava a CPU
+        for (i, &b) in TMSS_KEY.iter().enumerate() {
+            tmss.write_register(TMSS_REG_ADDR + i as u32, b);
+        }
+        assert!(!tmss.check_vdp_access(0xC00004));
+        assert!(tmss.lockup.get());
+
+        tmss.reset();
+        assert!(!tmss.lockup.get());
+    }
+
+    #[test]
+    fn test_tmss_boot_rom_switch() {
+        let mut tmss = Tmss::new(HardwareRevision::Model2, Some(vec![0x12, 0x34]));
+        assert!(tmss.boot_rom_mapped());
+        assert_eq!(tmss.read_boot_rom(0x000003), 0x34);
+
+        assert!(tmss.write_register(TMSS_CART_SELECT_ADDR, 0x01));
+        assert!(!tmss.boot_rom_mapped());
+
+        tmss.reset();
+        assert!(tmss.boot_rom_mapped());
+    }
+
+    #[test]
+    fn test_boot_rom_ignored_without_tmss() {
+        let tmss = Tmss::new(HardwareRevision::Model1Va0, Some(vec![0; 2048]));
+        assert!(!tmss.boot_rom_mapped());
+        assert!(tmss.boot_rom.is_none());
+    }
+}
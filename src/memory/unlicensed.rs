@@ -0,0 +1,524 @@
+//! Placas de cartuchos não licenciados e piratas
+//!
+//! Cada placa decide onde a ROM aparece na área do cartucho e trata seus
+//! registradores (troca de banco e proteção). O `Mapper` consulta a placa
+//! antes do mapeamento linear; o tipo vem do banco de dados de jogos
+//! (`MapperType`).
+//!
+//! Várias dessas placas decodificam a área de expansão (0x400000–0x7FFFFF),
+//! que num console sem Mega-CD fica livre para o cartucho.
+//!
+//! Placas suportadas:
+//! - Realtec: ROM de boot de 8 KB espelhada até o jogo escolher uma faixa
+//!   de blocos de 64 KB (0x400000, 0x402000, 0x404000);
+//! - Radica ("Sega Classics" plug-and-play): uma leitura em 0xA130xx escolhe
+//!   o banco de 64 KB que aparece em 0x000000;
+//! - Lion King 3 / Super King Kong 99: chip de proteção em 0x600000 e banco
+//!   de 32 KB em 0x000000 escolhido por 0x700000;
+//! - Super Mario World 64: registradores de proteção em 0x600000–0x67FFFF e
+//!   bancos de 64 KB da metade de cima da ROM em 0x600000/0x610000;
+//! - Squirrel King: registrador em 0x400000 que devolve o último valor
+//!   escrito;
+//! - SF-001 (Beggar Prince e outros RPGs chineses): escritas no primeiro MB
+//!   com os bits 8–11 do endereço em 0xE (ex: 0x000E01) trocam os primeiros
+//!   256 KB pelo último banco e ligam a SRAM em 0x3C0000, com trava contra
+//!   novas trocas.
+
+use crate::memory::mapper::MapperType;
+use crate::memory::rom::{SaveRamInfo, SramLanes};
+use std::cell::Cell;
+
+/// Início da área de expansão (Mega-CD) decodificada por algumas placas
+pub const EXPANSION_START: u32 = 0x400000;
+
+/// SRAM da placa SF-001: 32 KB em bytes ímpares a partir de 0x3C0001
+pub const SF001_SRAM: SaveRamInfo = SaveRamInfo {
+    start: 0x3C0001,
+    end: 0x3CFFFF,
+    lanes: SramLanes::Odd,
+    battery: true,
+};
+
+/// Estado de uma placa não licenciada
+#[derive(Debug, Clone)]
+pub enum Board {
+    Realtec(Realtec),
+    Radica(Radica),
+    LionKing3(LionKing3),
+    Smw64(Smw64),
+    SquirrelKing(SquirrelKing),
+    Sf001(Sf001),
+}
+
+impl Board {
+    /// Cria a placa correspondente ao tipo de mapper (se houver)
+    pub fn new(mapper_type: MapperType) -> Option<Self> {
+        Some(match mapper_type {
+            MapperType::Realtec => Self::Realtec(Realtec::default()),
+            MapperType::Radica => Self::Radica(Radica::default()),
+            MapperType::LionKing3 => Self::LionKing3(LionKing3::default()),
+            MapperType::Smw64 => Self::Smw64(Smw64::default()),
+            MapperType::SquirrelKing => Self::SquirrelKing(SquirrelKing::default()),
+            MapperType::Sf001 => Self::Sf001(Sf001::default()),
+            _ => return None,
+        })
+    }
+
+    /// Volta a placa ao estado de power-on
+    pub fn reset(&mut self) {
+        *self = match self {
+            Self::Realtec(_) => Self::Realtec(Realtec::default()),
+            Self::Radica(_) => Self::Radica(Radica::default()),
+            Self::LionKing3(_) => Self::LionKing3(LionKing3::default()),
+            Self::Smw64(_) => Self::Smw64(Smw64::default()),
+            Self::SquirrelKing(_) => Self::SquirrelKing(SquirrelKing::default()),
+            Self::Sf001(_) => Self::Sf001(Sf001::default()),
+        };
+    }
+
+    /// Retorna true se a placa responde na área de expansão
+    pub fn decodes_expansion_area(&self) -> bool {
+        !matches!(self, Self::Radica(_) | Self::Sf001(_))
+    }
+
+    /// Leitura de registradores/proteção. `None` segue para a ROM.
+    pub fn read8(&self, addr: u32) -> Option<u8> {
+        match self {
+            Self::Radica(radica) => radica.read8(addr),
+            Self::LionKing3(lk3) => lk3.read8(addr),
+            Self::Smw64(smw64) => smw64.read8(addr),
+            Self::SquirrelKing(sk) => sk.read8(addr),
+            Self::Realtec(_) | Self::Sf001(_) => None,
+        }
+    }
+
+    /// Escrita em registrador. Retorna true se o mapeamento da ROM mudou.
+    pub fn write8(&mut self, addr: u32, value: u8) -> bool {
+        match self {
+            Self::Realtec(realtec) => realtec.write8(addr, value),
+            Self::Radica(_) => false,
+            Self::LionKing3(lk3) => lk3.write8(addr, value),
+            Self::Smw64(smw64) => smw64.write8(addr, value),
+            Self::SquirrelKing(sk) => sk.write8(addr, value),
+            Self::Sf001(sf001) => sf001.write8(addr, value),
+        }
+    }
+
+    /// Offset na ROM do byte em `addr`, ou `None` se nada é lido ali
+    pub fn rom_offset(&self, addr: u32) -> Option<usize> {
+        match self {
+            Self::Realtec(realtec) => realtec.rom_offset(addr),
+            Self::Radica(radica) => radica.rom_offset(addr),
+            Self::LionKing3(lk3) => lk3.rom_offset(addr),
+            Self::Smw64(smw64) => smw64.rom_offset(addr),
+            Self::SquirrelKing(_) => linear(addr),
+            Self::Sf001(sf001) => sf001.rom_offset(addr),
+        }
+    }
+
+    /// Offset na ROM da página de 64 KB inteira, quando ela pode ser lida
+    /// direto pelo barramento
+    pub fn page_offset(&self, page: usize) -> Option<usize> {
+        match self {
+            // A troca de banco acontece numa leitura, que não pode refazer
+            // a tabela de páginas: toda a área passa pela placa
+            Self::Radica(_) => None,
+            Self::Realtec(realtec) if !realtec.mapped => None,
+            Self::LionKing3(lk3) if page == 0 && lk3.bank.is_some() => None,
+            _ => self.rom_offset((page << 16) as u32),
+        }
+    }
+
+    /// Estado da SRAM controlada pela placa (`None` se a placa não controla)
+    pub fn sram_mapped(&self) -> Option<bool> {
+        match self {
+            Self::Sf001(sf001) => Some(sf001.sram_mode()),
+            _ => None,
+        }
+    }
+}
+
+/// ROM linear na área do cartucho
+fn linear(addr: u32) -> Option<usize> {
+    (addr < EXPANSION_START).then_some(addr as usize)
+}
+
+// =====================================================
+// REALTEC
+// =====================================================
+
+/// Offset da ROM de boot (últimos 8 KB de um jogo de 512 KB)
+const REALTEC_BOOT_OFFSET: usize = 0x7E000;
+
+#[derive(Debug, Clone, Default)]
+pub struct Realtec {
+    /// Bits baixos da base (0x404000)
+    base_low: u8,
+    /// Bits altos da base (0x400000, bits 1–2)
+    base_high: u8,
+    /// Número de blocos de 64 KB espelhados (0x402000, em unidades de 128 KB)
+    blocks: u8,
+    /// Faixa escolhida; antes disso só a ROM de boot aparece
+    pub mapped: bool,
+}
+
+impl Realtec {
+    fn write8(&mut self, addr: u32, value: u8) -> bool {
+        match addr {
+            0x402000 => self.blocks = value << 1,
+            0x404000 => self.base_low = value & 7,
+            0x400000 => {
+                self.base_high = value & 6;
+                if self.blocks != 0 {
+                    self.mapped = true;
+                    return true;
+                }
+            }
+            _ => {}
+        }
+        false
+    }
+
+    fn rom_offset(&self, addr: u32) -> Option<usize> {
+        linear(addr)?;
+        if !self.mapped {
+            return Some(REALTEC_BOOT_OFFSET + (addr as usize & 0x1FFF));
+        }
+        let base = ((self.base_low as usize) << 1) | ((self.base_high as usize) << 3);
+        let block = base + (addr as usize >> 16) % self.blocks as usize;
+        Some((block << 16) | (addr as usize & 0xFFFF))
+    }
+}
+
+// =====================================================
+// RADICA
+// =====================================================
+
+#[derive(Debug, Clone, Default)]
+pub struct Radica {
+    /// Banco de 64 KB que aparece em 0x000000
+    base: Cell<usize>,
+}
+
+impl Radica {
+    fn read8(&self, addr: u32) -> Option<u8> {
+        if !(0xA13000..=0xA1307F).contains(&addr) {
+            return None;
+        }
+        self.base.set((addr as usize >> 1) & 0x3F);
+        Some(0xFF)
+    }
+
+    fn rom_offset(&self, addr: u32) -> Option<usize> {
+        linear(addr)?;
+        let bank = (self.base.get() + (addr as usize >> 16)) & 0x3F;
+        Some((bank << 16) | (addr as usize & 0xFFFF))
+    }
+}
+
+// =====================================================
+// LION KING 3 / SUPER KING KONG 99
+// =====================================================
+
+#[derive(Debug, Clone, Default)]
+pub struct LionKing3 {
+    /// Operação do chip de proteção (0x600002)
+    cmd: u8,
+    /// Dado do chip de proteção (0x600000)
+    data: u8,
+    /// Offset do banco de 32 KB mapeado em 0x000000
+    pub bank: Option<usize>,
+}
+
+impl LionKing3 {
+    fn read8(&self, addr: u32) -> Option<u8> {
+        if !(0x600000..=0x7FFFFF).contains(&addr) {
+            return None;
+        }
+        let data = self.data;
+        Some(match self.cmd {
+            1 => data >> 1,
+            2 => data.rotate_left(4),
+            3 => data.reverse_bits(),
+            _ => 0,
+        })
+    }
+
+    fn write8(&mut self, addr: u32, value: u8) -> bool {
+        match addr {
+            0x600000..=0x6FFFFF if addr & 2 != 0 => self.cmd = value,
+            0x600000..=0x6FFFFF => self.data = value,
+            0x700000..=0x7FFFFF => {
+                self.bank = Some((value as usize) << 15);
+                return true;
+            }
+            _ => {}
+        }
+        false
+    }
+
+    fn rom_offset(&self, addr: u32) -> Option<usize> {
+        match self.bank {
+            Some(bank) if addr < 0x8000 => Some(bank + addr as usize),
+            _ => linear(addr),
+        }
+    }
+}
+
+// =====================================================
+// SUPER MARIO WORLD 64
+// =====================================================
+
+/// Metade de cima da ROM, de onde vêm os bancos de 0x600000/0x610000
+const SMW64_UPPER_HALF: usize = 0x80000;
+
+#[derive(Debug, Clone)]
+pub struct Smw64 {
+    /// Registradores internos (controle, dados e valores devolvidos)
+    regs: Cell<[u8; 9]>,
+    /// Banco em 0x600000 (e 0x680000)
+    bank60: usize,
+    /// Banco em 0x610000 (e 0x690000)
+    bank61: usize,
+}
+
+impl Default for Smw64 {
+    fn default() -> Self {
+        Self {
+            regs: Cell::new([0; 9]),
+            bank60: SMW64_UPPER_HALF,
+            bank61: SMW64_UPPER_HALF,
+        }
+    }
+}
+
+impl Smw64 {
+    fn read8(&self, addr: u32) -> Option<u8> {
+        if !(0x600000..=0x6FFFFF).contains(&addr) {
+            return None;
+        }
+        let mut regs = self.regs.get();
+        let value = match (addr >> 16) & 0x7 {
+            0 | 1 => return None,
+            6 => match (addr >> 1) & 7 {
+                0 => regs[6],
+                1 => regs[6].wrapping_add(1),
+                2 => regs[7],
+                3 => regs[7].wrapping_add(1),
+                n => regs[8].wrapping_add(n as u8 - 4),
+            },
+            7 => {
+                let mut value = match (regs[2] & 0x80 != 0, regs[5] & 0x40 != 0) {
+                    (false, _) => 0,
+                    (true, true) => regs[3] & regs[4],
+                    (true, false) => regs[3] ^ 0xFF,
+                };
+                if addr & 2 != 0 {
+                    value &= 0x7F;
+                } else if regs[5] & 0x80 != 0 {
+                    if regs[5] & 0x20 != 0 {
+                        regs[8] = (regs[4] << 2) & 0xFC;
+                    } else {
+                        regs[6] = (regs[1] ^ (regs[3] << 1)) & 0xFE;
+                    }
+                    self.regs.set(regs);
+                }
+                value
+            }
+            _ => 0,
+        };
+        Some(value)
+    }
+
+    fn write8(&mut self, addr: u32, value: u8) -> bool {
+        if !(0x600000..=0x6FFFFF).contains(&addr) {
+            return false;
+        }
+        let regs = self.regs.get_mut();
+        let data_reg = addr & 2 != 0;
+        let mut changed = false;
+        match (addr >> 16) & 0x7 {
+            0 if data_reg => {
+                match regs[0] & 7 {
+                    0 => regs[6] = ((regs[6] ^ regs[1]) ^ value) & 0xFE,
+                    1 => regs[7] = value & 0xFE,
+                    7 => {
+                        self.bank61 = SMW64_UPPER_HALF + ((value as usize & 0x1C) << 14);
+                        changed = true;
+                    }
+                    _ => {}
+                }
+                regs[1] = value;
+            }
+            0 => regs[0] = value,
+            1 if data_reg => regs[2] = value,
+            4 if data_reg => regs[4] = value,
+            4 => regs[3] = value,
+            7 if !data_reg => {
+                regs[5] = value;
+                if value & 0x80 != 0 {
+                    self.bank60 = SMW64_UPPER_HALF + ((value as usize & 0x1C) << 14);
+                    changed = true;
+                }
+            }
+            _ => {}
+        }
+        changed
+    }
+
+    fn rom_offset(&self, addr: u32) -> Option<usize> {
+        match addr >> 16 {
+            0x60 | 0x68 => Some(self.bank60 + (addr as usize & 0xFFFF)),
+            0x61 | 0x69 => Some(self.bank61 + (addr as usize & 0xFFFF)),
+            _ => linear(addr),
+        }
+    }
+}
+
+// =====================================================
+// SQUIRREL KING
+// =====================================================
+
+#[derive(Debug, Clone, Default)]
+pub struct SquirrelKing {
+    /// Último valor escrito no registrador de proteção
+    latch: u8,
+}
+
+impl SquirrelKing {
+    const REGISTERS: std::ops::RangeInclusive<u32> = 0x400000..=0x40000F;
+
+    fn read8(&self, addr: u32) -> Option<u8> {
+        Self::REGISTERS.contains(&addr).then_some(self.latch)
+    }
+
+    fn write8(&mut self, addr: u32, value: u8) -> bool {
+        if Self::REGISTERS.contains(&addr) {
+            self.latch = value;
+        }
+        false
+    }
+}
+
+// =====================================================
+// SF-001
+// =====================================================
+
+/// Fim da faixa em que a SF-001 decodifica o registrador de controle (o
+/// Genesis Plus GX só instala o handler nas 16 primeiras páginas de 64 KB)
+const SF001_REGISTER_END: u32 = 0x0FFFFF;
+
+#[derive(Debug, Clone, Default)]
+pub struct Sf001 {
+    /// Último valor escrito no registrador de controle
+    mode: u8,
+    /// Bit 5: trocas bloqueadas até o reset
+    locked: bool,
+}
+
+impl Sf001 {
+    /// Bit 6: cartucho desligado
+    fn disabled(&self) -> bool {
+        self.mode & 0x40 != 0
+    }
+
+    /// Bit 7: primeiros 256 KB mostram o último banco e a SRAM aparece
+    fn sram_mode(&self) -> bool {
+        !self.disabled() && self.mode & 0x80 != 0
+    }
+
+    fn write8(&mut self, addr: u32, value: u8) -> bool {
+        if self.locked || addr > SF001_REGISTER_END || (addr >> 8) & 0xF != 0xE {
+            return false;
+        }
+        self.mode = value;
+        self.locked = value & 0x20 != 0;
+        true
+    }
+
+    fn rom_offset(&self, addr: u32) -> Option<usize> {
+        if self.disabled() {
+            return None;
+        }
+        match addr {
+            0x000000..=0x03FFFF if self.sram_mode() => Some(0x380000 + addr as usize),
+            _ => linear(addr),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_realtec_boot_then_mapped_blocks() {
+        let mut board = Board::new(MapperType::Realtec).unwrap();
+        assert_eq!(board.rom_offset(0x012345), Some(REALTEC_BOOT_OFFSET + 0x0345));
+        assert_eq!(board.page_offset(0), None);
+
+        board.write8(0x402000, 1); // 2 blocos de 64 KB
+        board.write8(0x404000, 1); // base 0x20000
+        assert!(board.write8(0x400000, 0));
+        assert_eq!(board.rom_offset(0x000010), Some(0x20010));
+        assert_eq!(board.rom_offset(0x020010), Some(0x20010));
+        assert_eq!(board.page_offset(1), Some(0x30000));
+    }
+
+    #[test]
+    fn test_radica_bank_selected_by_read() {
+        let board = Board::new(MapperType::Radica).unwrap();
+        assert_eq!(board.read8(0xA13006), Some(0xFF));
+        assert_eq!(board.rom_offset(0x000000), Some(0x30000));
+        assert_eq!(board.rom_offset(0x3F0000), Some(0x20000));
+    }
+
+    #[test]
+    fn test_lion_king_3_protection_and_bank() {
+        let mut board = Board::new(MapperType::LionKing3).unwrap();
+        board.write8(0x600000, 0x12);
+        board.write8(0x600002, 2);
+        assert_eq!(board.read8(0x600000), Some(0x21));
+        board.write8(0x600002, 3);
+        assert_eq!(board.read8(0x600000), Some(0x48));
+
+        assert!(board.write8(0x700000, 4));
+        assert_eq!(board.rom_offset(0x000100), Some(0x20100));
+        assert_eq!(board.rom_offset(0x008000), Some(0x8000));
+        assert_eq!(board.page_offset(0), None);
+    }
+
+    #[test]
+    fn test_smw64_bank_and_protection() {
+        let mut board = Board::new(MapperType::Smw64).unwrap();
+        assert_eq!(board.rom_offset(0x600010), Some(0x80010));
+
+        assert!(board.write8(0x670001, 0x80 | 0x08));
+        assert_eq!(board.rom_offset(0x680010), Some(0xA0010));
+
+        board.write8(0x610003, 0x80); // leitura da proteção ligada
+        board.write8(0x640001, 0x0F);
+        assert_eq!(board.read8(0x670003), Some(0x70));
+    }
+
+    #[test]
+    fn test_sf001_sram_mode_and_lock() {
+        let mut board = Board::new(MapperType::Sf001).unwrap();
+        assert_eq!(board.sram_mapped(), Some(false));
+
+        // Fora do primeiro MB (ex: na própria SRAM) não é o registrador
+        assert!(!board.write8(0x3C0E01, 0xA0));
+        assert!(!board.write8(0x100E01, 0xA0));
+        assert!(!board.write8(0x000D01, 0xA0));
+        assert_eq!(board.sram_mapped(), Some(false));
+
+        assert!(board.write8(0x000E01, 0xA0));
+        assert_eq!(board.sram_mapped(), Some(true));
+        assert_eq!(board.page_offset(1), Some(0x390000));
+
+        // Travado: novas escritas são ignoradas
+        assert!(!board.write8(0x000E01, 0x00));
+        board.reset();
+        assert_eq!(board.sram_mapped(), Some(false));
+    }
+}
@@ -0,0 +1,486 @@
+//! Busca na RAM (localizador de trapaças)
+//!
+//! Fluxo típico: tirar um instantâneo da RAM, jogar um pouco e filtrar os
+//! candidatos comparando o valor atual com o anterior ("diminuiu", "não
+//! mudou") ou com uma constante ("igual a 3"), até sobrar o endereço que
+//! guarda vidas, energia etc. O resultado vira um código Action Replay.
+//!
+//! Funciona sobre a RAM principal do 68000 (64 KB em 0xFF0000) ou a RAM do
+//! Z80 (8 KB em 0xA00000), com valores de 8, 16 ou 32 bits big-endian, com
+//! ou sem sinal.
+//!
+//! Os comandos em texto (`search ...`) ficam em `SearchCommand`, usados
+//! pelo REPL do depurador (ver `debugger`).
+
+use crate::cheats::Z80_RAM_START;
+use crate::memory::Memory;
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Endereço da RAM principal no espaço do 68000
+pub const MAIN_RAM_START: u32 = 0xFF0000;
+/// Máximo de candidatos listados de uma vez
+pub const DEFAULT_LIST_LIMIT: usize = 20;
+
+/// Erros da busca
+#[derive(Debug, Error)]
+pub enum SearchError {
+    #[error("Nenhuma busca em andamento (use \"search start\")")]
+    NotStarted,
+
+    #[error("Argumento inválido: {0}")]
+    InvalidArgument(String),
+
+    #[error("0x{0:06X} não está na área da busca")]
+    OutOfRange(u32),
+}
+
+/// Memória pesquisada
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchRegion {
+    /// RAM principal do 68000
+    MainRam,
+    /// RAM do Z80
+    Z80Ram,
+}
+
+impl SearchRegion {
+    /// Endereço base no espaço do 68000
+    pub fn base(self) -> u32 {
+        match self {
+            Self::MainRam => MAIN_RAM_START,
+            Self::Z80Ram => Z80_RAM_START,
+        }
+    }
+
+    /// Instantâneo atual da memória
+    pub fn snapshot(self, memory: &Memory) -> Vec<u8> {
+        match self {
+            Self::MainRam => memory.dump_ram(),
+            Self::Z80Ram => memory.dump_z80_ram(),
+        }
+    }
+}
+
+impl FromStr for SearchRegion {
+    type Err = SearchError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "main" | "68k" | "ram" => Ok(Self::MainRam),
+            "z80" => Ok(Self::Z80Ram),
+            _ => Err(SearchError::InvalidArgument(format!("região \"{}\" (main, z80)", s))),
+        }
+    }
+}
+
+/// Tamanho do valor pesquisado
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueSize {
+    Byte,
+    Word,
+    Long,
+}
+
+impl ValueSize {
+    pub fn bytes(self) -> usize {
+        match self {
+            Self::Byte => 1,
+            Self::Word => 2,
+            Self::Long => 4,
+        }
+    }
+}
+
+impl FromStr for ValueSize {
+    type Err = SearchError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "8" | "b" => Ok(Self::Byte),
+            "16" | "w" => Ok(Self::Word),
+            "32" | "l" => Ok(Self::Long),
+            _ => Err(SearchError::InvalidArgument(format!("tamanho \"{}\" (8, 16, 32)", s))),
+        }
+    }
+}
+
+/// Comparação usada para filtrar candidatos
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    Greater,
+    LessOrEqual,
+    GreaterOrEqual,
+}
+
+impl Comparison {
+    fn test(self, current: i64, reference: i64) -> bool {
+        match self {
+            Self::Equal => current == reference,
+            Self::NotEqual => current != reference,
+            Self::Less => current < reference,
+            Self::Greater => current > reference,
+            Self::LessOrEqual => current <= reference,
+            Self::GreaterOrEqual => current >= reference,
+        }
+    }
+}
+
+impl FromStr for Comparison {
+    type Err = SearchError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "eq" | "==" => Ok(Self::Equal),
+            "ne" | "!=" => Ok(Self::NotEqual),
+            "lt" | "<" => Ok(Self::Less),
+            "gt" | ">" => Ok(Self::Greater),
+            "le" | "<=" => Ok(Self::LessOrEqual),
+            "ge" | ">=" => Ok(Self::GreaterOrEqual),
+            _ => Err(SearchError::InvalidArgument(format!("comparação \"{}\"", s))),
+        }
+    }
+}
+
+/// Valor de referência da comparação
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operand {
+    /// Valor do instantâneo anterior
+    Previous,
+    /// Constante (interpretada conforme o sinal da busca)
+    Constant(i64),
+}
+
+impl FromStr for Operand {
+    type Err = SearchError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "prev" {
+            return Ok(Self::Previous);
+        }
+        parse_number(s).map(Self::Constant)
+    }
+}
+
+/// Número decimal, hexadecimal (`0x`/`$`) ou negativo
+fn parse_number(s: &str) -> Result<i64, SearchError> {
+    let (negative, digits) = match s.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, s),
+    };
+    let value = match digits.strip_prefix("0x").or_else(|| digits.strip_prefix('$')) {
+        Some(hex) => i64::from_str_radix(hex, 16),
+        None => digits.parse(),
+    }
+    .map_err(|_| SearchError::InvalidArgument(format!("número \"{}\"", s)))?;
+    Ok(if negative { -value } else { value })
+}
+
+/// Endereço que passou em todos os filtros
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Candidate {
+    /// Endereço no espaço do 68000
+    pub addr: u32,
+    pub value: i64,
+    pub previous: i64,
+}
+
+impl fmt::Display for Candidate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:06X}: {} (antes {})", self.addr, self.value, self.previous)
+    }
+}
+
+/// Estado de uma busca em andamento
+#[derive(Debug, Clone)]
+pub struct RamSearch {
+    pub region: SearchRegion,
+    pub size: ValueSize,
+    pub signed: bool,
+    /// Instantâneo usado como "valor anterior"
+    snapshot: Vec<u8>,
+    /// Deslocamentos ainda candidatos
+    offsets: Vec<usize>,
+    /// Valores do instantâneo anterior ao último filtro (para exibição)
+    previous: Vec<u8>,
+}
+
+impl RamSearch {
+    /// Começa uma busca a partir de um instantâneo. Valores de 16/32 bits
+    /// só são procurados em endereços pares na RAM do 68000.
+    pub fn start(region: SearchRegion, size: ValueSize, signed: bool, snapshot: Vec<u8>) -> Self {
+        let step = if region == SearchRegion::MainRam && size != ValueSize::Byte { 2 } else { 1 };
+        let offsets = match snapshot.len().checked_sub(size.bytes()) {
+            Some(last) => (0..=last).step_by(step).collect(),
+            None => Vec::new(),
+        };
+        Self {
+            region,
+            size,
+            signed,
+            previous: snapshot.clone(),
+            snapshot,
+            offsets,
+        }
+    }
+
+    /// Valor big-endian em `offset`, estendido conforme o sinal
+    fn value_at(&self, data: &[u8], offset: usize) -> i64 {
+        let bytes = &data[offset..offset + self.size.bytes()];
+        let raw = bytes.iter().fold(0u64, |acc, &b| (acc << 8) | b as u64);
+        if !self.signed {
+            return raw as i64;
+        }
+        let shift = 64 - 8 * self.size.bytes() as u32;
+        ((raw << shift) as i64) >> shift
+    }
+
+    /// Filtra os candidatos comparando `current` com o operando e passa a
+    /// usar `current` como instantâneo. Retorna quantos sobraram.
+    pub fn filter(&mut self, comparison: Comparison, operand: Operand, current: Vec<u8>) -> usize {
+        let constant = match operand {
+            // Constantes fora da faixa do tipo são reduzidas a ela (ex: 0xFF com sinal = -1)
+            Operand::Constant(value) => Some(self.normalize(value)),
+            Operand::Previous => None,
+        };
+        let offsets = std::mem::take(&mut self.offsets);
+        self.offsets = offsets
+            .into_iter()
+            .filter(|&o| o + self.size.bytes() <= current.len())
+            .filter(|&o| {
+                let reference = constant.unwrap_or_else(|| self.value_at(&self.snapshot, o));
+                comparison.test(self.value_at(&current, o), reference)
+            })
+            .collect();
+        self.previous = std::mem::replace(&mut self.snapshot, current);
+        self.offsets.len()
+    }
+
+    /// Reduz uma constante ao tamanho/sinal da busca
+    fn normalize(&self, value: i64) -> i64 {
+        let bytes = (value as u64).to_be_bytes();
+        self.value_at(&bytes, 8 - self.size.bytes())
+    }
+
+    pub fn len(&self) -> usize {
+        self.offsets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.offsets.is_empty()
+    }
+
+    /// Primeiros `limit` candidatos, com o valor atual e o anterior
+    pub fn candidates(&self, limit: usize) -> Vec<Candidate> {
+        self.offsets
+            .iter()
+            .take(limit)
+            .map(|&o| Candidate {
+                addr: self.region.base() + o as u32,
+                value: self.value_at(&self.snapshot, o),
+                previous: self.value_at(&self.previous, o),
+            })
+            .collect()
+    }
+
+    /// Códigos Action Replay que fixam `value` em `addr`. Valores de 32 bits
+    /// viram dois códigos de 16; na RAM do Z80 os códigos são de 8 bits.
+    pub fn codes_for(&self, addr: u32, value: i64) -> Result<Vec<String>, SearchError> {
+        let base = self.region.base();
+        let size = self.size.bytes();
+        if addr < base || (addr - base) as usize + size > self.snapshot.len() {
+            return Err(SearchError::OutOfRange(addr));
+        }
+        let bytes = (value as u64).to_be_bytes();
+        let bytes = &bytes[8 - size..];
+
+        let chunk = match (self.region, self.size) {
+            (SearchRegion::Z80Ram, _) | (_, ValueSize::Byte) => 1,
+            _ => 2,
+        };
+        Ok(bytes
+            .chunks(chunk)
+            .enumerate()
+            .map(|(i, part)| {
+                let value: String = part.iter().map(|b| format!("{:02X}", b)).collect();
+                format!("{:06X}:{}", addr + (i * chunk) as u32, value)
+            })
+            .collect())
+    }
+
+    /// Códigos que fixam o valor atual do endereço
+    pub fn codes_for_current(&self, addr: u32) -> Result<Vec<String>, SearchError> {
+        let offset = addr
+            .checked_sub(self.region.base())
+            .map(|o| o as usize)
+            .filter(|&o| o + self.size.bytes() <= self.snapshot.len())
+            .ok_or(SearchError::OutOfRange(addr))?;
+        self.codes_for(addr, self.value_at(&self.snapshot, offset))
+    }
+}
+
+// =====================================================
+// COMANDOS EM TEXTO
+// =====================================================
+
+/// Comando de busca já interpretado
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchCommand {
+    /// `search start [main|z80] [8|16|32] [s|u]`
+    Start { region: SearchRegion, size: ValueSize, signed: bool },
+    /// `search <eq|ne|lt|gt|le|ge> [prev|<valor>]`
+    Filter(Comparison, Operand),
+    /// `search list [n]`
+    List(usize),
+    /// `search code <endereço> [valor]`
+    Code { addr: u32, value: Option<i64> },
+    /// `search reset`
+    Reset,
+}
+
+impl SearchCommand {
+    /// Interpreta os argumentos depois de "search"
+    pub fn parse(args: &[&str]) -> Result<Self, SearchError> {
+        match args {
+            ["start", rest @ ..] => {
+                let mut command = (SearchRegion::MainRam, ValueSize::Byte, false);
+                for arg in rest {
+                    match *arg {
+                        "s" | "signed" => command.2 = true,
+                        "u" | "unsigned" => command.2 = false,
+                        arg if arg.parse::<ValueSize>().is_ok() => command.1 = arg.parse()?,
+                        arg => command.0 = arg.parse()?,
+                    }
+                }
+                let (region, size, signed) = command;
+                Ok(Self::Start { region, size, signed })
+            }
+            ["list"] => Ok(Self::List(DEFAULT_LIST_LIMIT)),
+            ["list", n] => Ok(Self::List(parse_number(n)?.max(0) as usize)),
+            ["code", addr, value @ ..] => Ok(Self::Code {
+                addr: parse_address(addr)?,
+                value: value.first().map(|v| parse_number(v)).transpose()?,
+            }),
+            ["reset"] => Ok(Self::Reset),
+            [comparison] => Ok(Self::Filter(comparison.parse()?, Operand::Previous)),
+            [comparison, operand] => Ok(Self::Filter(comparison.parse()?, operand.parse()?)),
+            _ => Err(SearchError::InvalidArgument(args.join(" "))),
+        }
+    }
+
+    /// Executa o comando sobre a busca atual e retorna o texto de resposta
+    pub fn execute(self, search: &mut Option<RamSearch>, memory: &Memory) -> Result<String, SearchError> {
+        match self {
+            Self::Start { region, size, signed } => {
+                let started = RamSearch::start(region, size, signed, region.snapshot(memory));
+                let reply = format!("{} candidatos", started.len());
+                *search = Some(started);
+                Ok(reply)
+            }
+            Self::Reset => {
+                *search = None;
+                Ok("Busca encerrada".to_string())
+            }
+            Self::Filter(comparison, operand) => {
+                let search = search.as_mut().ok_or(SearchError::NotStarted)?;
+                let left = search.filter(comparison, operand, search.region.snapshot(memory));
+                Ok(format!("{} candidatos", left))
+            }
+            Self::List(limit) => {
+                let search = search.as_ref().ok_or(SearchError::NotStarted)?;
+                let mut lines: Vec<String> = search.candidates(limit).iter().map(|c| c.to_string()).collect();
+                if search.len() > limit {
+                    lines.push(format!("... mais {}", search.len() - limit));
+                }
+                Ok(lines.join("\n"))
+            }
+            Self::Code { addr, value } => {
+                let search = search.as_ref().ok_or(SearchError::NotStarted)?;
+                let codes = match value {
+                    Some(value) => search.codes_for(addr, value)?,
+                    None => search.codes_for_current(addr)?,
+                };
+                Ok(codes.join(" "))
+            }
+        }
+    }
+}
+
+/// Endereço em hexadecimal (com ou sem prefixo)
+fn parse_address(s: &str) -> Result<u32, SearchError> {
+    let hex = s.strip_prefix("0x").or_else(|| s.strip_prefix('$')).unwrap_or(s);
+    u32::from_str_radix(hex, 16).map_err(|_| SearchError::InvalidArgument(format!("endereço \"{}\"", s)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_narrow_by_previous_and_constant() {
+        let mut ram = vec![0u8; 64];
+        ram[0x10] = 5;
+        ram[0x20] = 5;
+        let mut search = RamSearch::start(SearchRegion::MainRam, ValueSize::Byte, false, ram.clone());
+        assert_eq!(search.len(), 64);
+
+        // As vidas caem de 5 para 4 só em 0x10
+        ram[0x10] = 4;
+        assert_eq!(search.filter(Comparison::Less, Operand::Previous, ram.clone()), 1);
+        assert_eq!(
+            search.candidates(10),
+            [Candidate { addr: 0xFF0010, value: 4, previous: 5 }]
+        );
+
+        assert_eq!(search.filter(Comparison::Equal, Operand::Constant(4), ram), 1);
+        assert_eq!(search.codes_for(0xFF0010, 9).unwrap(), ["FF0010:09"]);
+    }
+
+    #[test]
+    fn test_signed_words_big_endian() {
+        let mut ram = vec![0u8; 16];
+        ram[4..6].copy_from_slice(&[0xFF, 0xFE]); // -2
+        let mut search = RamSearch::start(SearchRegion::MainRam, ValueSize::Word, true, ram.clone());
+        assert_eq!(search.len(), 8); // apenas endereços pares
+
+        assert_eq!(search.filter(Comparison::Less, Operand::Constant(0), ram.clone()), 1);
+        assert_eq!(search.candidates(1)[0].value, -2);
+        // 0xFFFE é normalizado para -2 em uma busca com sinal
+        assert_eq!(search.filter(Comparison::Equal, Operand::Constant(0xFFFE), ram), 1);
+    }
+
+    #[test]
+    fn test_long_and_z80_codes() {
+        let search = RamSearch::start(SearchRegion::MainRam, ValueSize::Long, false, vec![0; 16]);
+        assert_eq!(search.codes_for(0xFF0004, 0x12345678).unwrap(), ["FF0004:1234", "FF0006:5678"]);
+        assert!(search.codes_for(0xFF000E, 0).is_err());
+
+        let z80 = RamSearch::start(SearchRegion::Z80Ram, ValueSize::Word, false, vec![0; 0x2000]);
+        assert_eq!(z80.codes_for(0xA01F00, 0x0102).unwrap(), ["A01F00:01", "A01F01:02"]);
+    }
+
+    #[test]
+    fn test_command_parsing() {
+        assert_eq!(
+            SearchCommand::parse(&["start", "z80", "16", "s"]).unwrap(),
+            SearchCommand::Start { region: SearchRegion::Z80Ram, size: ValueSize::Word, signed: true }
+        );
+        assert_eq!(
+            SearchCommand::parse(&["gt", "$10"]).unwrap(),
+            SearchCommand::Filter(Comparison::Greater, Operand::Constant(16))
+        );
+        assert_eq!(
+            SearchCommand::parse(&["ne"]).unwrap(),
+            SearchCommand::Filter(Comparison::NotEqual, Operand::Previous)
+        );
+        assert_eq!(
+            SearchCommand::parse(&["code", "FF0010", "-1"]).unwrap(),
+            SearchCommand::Code { addr: 0xFF0010, value: Some(-1) }
+        );
+        assert!(SearchCommand::parse(&["foo", "bar", "baz"]).is_err());
+    }
+}
@@ -0,0 +1,365 @@
+//! Identificação de ROMs por arquivos DAT (No-Intro, formato Logiqx XML)
+//!
+//! Calcula CRC32, MD5 e SHA-1 dos dados da ROM já normalizados (sem
+//! cabeçalho de copiadora e desentrelaçados) e procura os hashes em DATs
+//! guardados localmente. O resultado informa o título canônico, a revisão
+//! e a qualidade do dump (bom, ruim, hack, overdump).
+//!
+//! Os DATs são procurados em `$XDG_CONFIG_HOME/megastrife/dats/`
+//! (ou `~/.config/megastrife/dats/`), além dos passados por `--dat`.
+
+use crate::memory::rom::compute_checksum;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// Erros ao carregar um DAT
+#[derive(Debug, Error)]
+pub enum DatError {
+    #[error("Falha ao ler {0}: {1}")]
+    Io(PathBuf, #[source] std::io::Error),
+
+    #[error("XML inválido em {0}: {1}")]
+    Xml(PathBuf, #[source] roxmltree::Error),
+}
+
+/// Diretório de configuração do usuário: `$XDG_CONFIG_HOME/megastrife`
+/// ou `~/.config/megastrife` (DATs, banco de jogos)
+pub fn config_dir() -> Option<PathBuf> {
+    let config = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config.join("megastrife"))
+}
+
+/// Hashes da ROM
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RomHashes {
+    pub crc32: u32,
+    /// MD5 em hexadecimal minúsculo
+    pub md5: String,
+    /// SHA-1 em hexadecimal minúsculo
+    pub sha1: String,
+    /// Checksum calculado (soma das palavras a partir de 0x200)
+    pub checksum: u16,
+    pub size: usize,
+}
+
+impl RomHashes {
+    /// Calcula os hashes dos dados (já normalizados) da ROM
+    pub fn compute(data: &[u8]) -> Self {
+        Self {
+            crc32: crc32fast::hash(data),
+            md5: format!("{:x}", md5::compute(data)),
+            sha1: sha1_smol::Sha1::from(data).digest().to_string(),
+            checksum: compute_checksum(data),
+            size: data.len(),
+        }
+    }
+}
+
+impl fmt::Display for RomHashes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CRC32 {:08X}  MD5 {}  SHA-1 {}", self.crc32, self.md5, self.sha1)
+    }
+}
+
+/// Qualidade do dump segundo o DAT
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DumpStatus {
+    /// Dump correto (verificado ou não)
+    Good,
+    /// Dump ruim ("baddump" ou marcado com [b])
+    Bad,
+    /// ROM modificada ([h])
+    Hacked,
+    /// Dump com dados além do tamanho real do cartucho ([o])
+    Overdump,
+}
+
+impl fmt::Display for DumpStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            DumpStatus::Good => "bom",
+            DumpStatus::Bad => "ruim",
+            DumpStatus::Hacked => "hack",
+            DumpStatus::Overdump => "overdump",
+        };
+        f.write_str(s)
+    }
+}
+
+/// Resultado da identificação
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RomIdentity {
+    /// Nome canônico completo ("Sonic The Hedgehog (USA, Europe)")
+    pub name: String,
+    /// Título sem as etiquetas entre parênteses/colchetes
+    pub title: String,
+    /// Revisão ("Rev 1", "Rev A"), se houver
+    pub revision: Option<String>,
+    pub status: DumpStatus,
+    /// Dump verificado pelo grupo que mantém o DAT
+    pub verified: bool,
+    /// Nome do DAT onde a ROM foi encontrada
+    pub dat: String,
+}
+
+impl RomIdentity {
+    /// Resumo de uma linha para o título da janela
+    pub fn summary(&self) -> String {
+        match self.status {
+            DumpStatus::Good => self.name.clone(),
+            status => format!("{} [{}]", self.name, status),
+        }
+    }
+}
+
+/// Entrada `<rom>` de um DAT
+#[derive(Debug, Clone)]
+struct DatRom {
+    game: String,
+    size: Option<usize>,
+    md5: Option<String>,
+    sha1: Option<String>,
+    status: Option<String>,
+    dat: usize,
+}
+
+/// Conjunto de DATs carregados, indexados por CRC32
+#[derive(Debug, Default)]
+pub struct DatCollection {
+    /// Nomes dos DATs carregados (cabeçalho `<name>` ou nome do arquivo)
+    dats: Vec<String>,
+    by_crc: HashMap<u32, Vec<DatRom>>,
+}
+
+impl DatCollection {
+    /// Cria uma coleção vazia
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Diretório padrão dos DATs do usuário
+    pub fn default_dir() -> Option<PathBuf> {
+        Some(config_dir()?.join("dats"))
+    }
+
+    /// Carrega todos os `.dat`/`.xml` de um diretório (inexistente = nada a carregar).
+    /// Arquivos ilegíveis ou malformados são ignorados com um aviso.
+    /// Retorna o número de DATs carregados.
+    pub fn load_dir(&mut self, dir: &Path) -> Result<usize, DatError> {
+        let entries = match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(DatError::Io(dir.to_path_buf(), e)),
+        };
+        let mut count = 0;
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let is_dat = path
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| e.eq_ignore_ascii_case("dat") || e.eq_ignore_ascii_case("xml"));
+            if !is_dat {
+                continue;
+            }
+            match self.load_file(&path) {
+                Ok(()) => count += 1,
+                Err(e) => log::warn!("DAT ignorado: {}", e),
+            }
+        }
+        Ok(count)
+    }
+
+    /// Carrega um DAT do disco
+    pub fn load_file(&mut self, path: &Path) -> Result<(), DatError> {
+        let text = std::fs::read_to_string(path).map_err(|e| DatError::Io(path.to_path_buf(), e))?;
+        let fallback = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+        self.load_str(&text, &fallback)
+            .map_err(|e| DatError::Xml(path.to_path_buf(), e))
+    }
+
+    /// Interpreta um DAT Logiqx XML
+    pub fn load_str(&mut self, xml: &str, fallback_name: &str) -> Result<(), roxmltree::Error> {
+        let doc = roxmltree::Document::parse(xml)?;
+        let dat_index = self.dats.len();
+        let dat_name = doc
+            .descendants()
+            .find(|n| n.has_tag_name("header"))
+            .and_then(|h| h.children().find(|n| n.has_tag_name("name")))
+            .and_then(|n| n.text())
+            .unwrap_or(fallback_name);
+        self.dats.push(dat_name.to_string());
+
+        // Logiqx usa <game>; alguns DATs antigos usam <machine>
+        for game in doc.descendants().filter(|n| n.has_tag_name("game") || n.has_tag_name("machine")) {
+            let Some(game_name) = game.attribute("name") else {
+                continue;
+            };
+            for rom in game.children().filter(|n| n.has_tag_name("rom")) {
+                let Some(crc) = rom.attribute("crc").and_then(|c| u32::from_str_radix(c, 16).ok()) else {
+                    continue;
+                };
+                self.by_crc.entry(crc).or_default().push(DatRom {
+                    game: game_name.to_string(),
+                    size: rom.attribute("size").and_then(|s| s.parse().ok()),
+                    md5: rom.attribute("md5").map(str::to_ascii_lowercase),
+                    sha1: rom.attribute("sha1").map(str::to_ascii_lowercase),
+                    status: rom.attribute("status").map(str::to_string),
+                    dat: dat_index,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Número de DATs carregados
+    pub fn dat_count(&self) -> usize {
+        self.dats.len()
+    }
+
+    /// Procura a ROM pelos hashes. O CRC32 seleciona os candidatos e
+    /// tamanho, SHA-1 e MD5 (quando presentes no DAT) precisam conferir.
+    pub fn identify(&self, hashes: &RomHashes) -> Option<RomIdentity> {
+        let candidates = self.by_crc.get(&hashes.crc32)?;
+        let rom = candidates.iter().find(|rom| {
+            rom.size.is_none_or(|s| s == hashes.size)
+                && rom.sha1.as_ref().is_none_or(|s| *s == hashes.sha1)
+                && rom.md5.as_ref().is_none_or(|m| *m == hashes.md5)
+        })?;
+        Some(identity_from_name(&rom.game, rom.status.as_deref(), &self.dats[rom.dat]))
+    }
+}
+
+/// Monta a identificação a partir do nome do jogo e do status do DAT
+fn identity_from_name(name: &str, status: Option<&str>, dat: &str) -> RomIdentity {
+    let tags = name_tags(name);
+    let has_flag = |flag: &str| {
+        tags.iter()
+            .any(|t| t.starts_with('[') && t[1..].trim_end_matches(']').split_whitespace().next() == Some(flag))
+    };
+
+    let status_kind = if status == Some("baddump") || has_flag("b") {
+        DumpStatus::Bad
+    } else if has_flag("h") {
+        DumpStatus::Hacked
+    } else if has_flag("o") {
+        DumpStatus::Overdump
+    } else {
+        DumpStatus::Good
+    };
+
+    let revision = tags
+        .iter()
+        .filter_map(|t| t.strip_prefix('(')?.strip_suffix(')'))
+        .find(|t| t.starts_with("Rev "))
+        .map(str::to_string);
+
+    let title = name.find(['(', '[']).map_or(name, |i| &name[..i]).trim().to_string();
+
+    RomIdentity {
+        name: name.to_string(),
+        title,
+        revision,
+        status: status_kind,
+        verified: status == Some("verified"),
+        dat: dat.to_string(),
+    }
+}
+
+/// Extrai as etiquetas "(...)" e "[...]" de um nome
+fn name_tags(name: &str) -> Vec<&str> {
+    let mut tags = Vec::new();
+    let mut rest = name;
+    while let Some(start) = rest.find(['(', '[']) {
+        let close = if rest.as_bytes()[start] == b'(' { ')' } else { ']' };
+        let Some(len) = rest[start..].find(close) else {
+            break;
+        };
+        tags.push(&rest[start..start + len + 1]);
+        rest = &rest[start + len + 1..];
+    }
+    tags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dat_for(data: &[u8]) -> String {
+        let h = RomHashes::compute(data);
+        format!(
+            r#"<?xml version="1.0"?>
+<datafile>
+  <header><name>Sega - Mega Drive - Genesis</name></header>
+  <game name="Teste (USA) (Rev 1)">
+    <description>Teste (USA) (Rev 1)</description>
+    <rom name="Teste (USA) (Rev 1).md" size="{}" crc="{:08X}" md5="{}" sha1="{}" status="verified"/>
+  </game>
+  <game name="Outro (Europe) [h Tradução]">
+    <rom name="Outro.md" size="4" crc="DEADBEEF"/>
+  </game>
+</datafile>"#,
+            h.size,
+            h.crc32,
+            h.md5.to_uppercase(),
+            h.sha1
+        )
+    }
+
+    #[test]
+    fn test_hashes_of_known_input() {
+        let h = RomHashes::compute(b"abc");
+        assert_eq!(h.crc32, 0x352441C2);
+        assert_eq!(h.md5, "900150983cd24fb0d6963f7d28e17f72");
+        assert_eq!(h.sha1, "a9993e364706816aba3e25717850c26c9cd0d89d");
+    }
+
+    #[test]
+    fn test_identify_from_dat() {
+        let data = vec![0x4E, 0x71, 0x4E, 0x75, 0x00, 0x01];
+        let mut dats = DatCollection::new();
+        dats.load_str(&dat_for(&data), "teste").unwrap();
+
+        let id = dats.identify(&RomHashes::compute(&data)).unwrap();
+        assert_eq!(id.name, "Teste (USA) (Rev 1)");
+        assert_eq!(id.title, "Teste");
+        assert_eq!(id.revision.as_deref(), Some("Rev 1"));
+        assert_eq!(id.status, DumpStatus::Good);
+        assert!(id.verified);
+        assert_eq!(id.dat, "Sega - Mega Drive - Genesis");
+
+        // Mesmo CRC não basta se o SHA-1 não confere
+        let mut other = RomHashes::compute(&data);
+        other.sha1 = "0".repeat(40);
+        assert!(dats.identify(&other).is_none());
+    }
+
+    #[test]
+    fn test_load_dir_skips_malformed_dat() {
+        let dir = std::env::temp_dir().join(format!("megastrife-dats-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let data = vec![0x60, 0xFE];
+        std::fs::write(dir.join("bom.dat"), dat_for(&data)).unwrap();
+        std::fs::write(dir.join("quebrado.dat"), "<datafile><game>").unwrap();
+
+        let mut dats = DatCollection::new();
+        let count = dats.load_dir(&dir).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(count, 1);
+        assert!(dats.identify(&RomHashes::compute(&data)).is_some());
+    }
+
+    #[test]
+    fn test_dump_flags() {
+        let hack = identity_from_name("Outro (Europe) [h Tradução]", None, "dat");
+        assert_eq!(hack.status, DumpStatus::Hacked);
+        assert_eq!(hack.summary(), "Outro (Europe) [h Tradução] [hack]");
+
+        assert_eq!(identity_from_name("Jogo (USA) [b]", None, "dat").status, DumpStatus::Bad);
+        assert_eq!(identity_from_name("Jogo (USA) [o]", None, "dat").status, DumpStatus::Overdump);
+        assert_eq!(identity_from_name("Jogo (USA)", Some("baddump"), "dat").status, DumpStatus::Bad);
+    }
+}
@@ -0,0 +1,114 @@
+//! src/sound/cdda.rs
+//! Áudio CD-DA do Mega-CD.
+//!
+//! Durante a leitura de uma trilha de áudio o drive entrega um setor por
+//! quadro de CD (75 Hz): 2352 bytes com 588 amostras estéreo de 16 bits
+//! (little-endian, esquerda primeiro) a 44,1 kHz. As amostras entram numa
+//! fila e saem no ritmo do CD, contado no clock do sub-68000; a saída é a
+//! última amostra, como no PCM.
+
+use crate::megacd::SUB_CPU_CLOCK;
+use std::collections::VecDeque;
+
+/// Taxa de amostragem do CD
+pub const CDDA_SAMPLE_RATE: u32 = 44_100;
+/// Amostras estéreo por setor
+pub const SAMPLES_PER_SECTOR: usize = 588;
+/// Limite da fila: dois setores, para o atraso não crescer se o drive
+/// adiantar em relação à saída
+const MAX_QUEUED: usize = SAMPLES_PER_SECTOR * 2;
+
+pub struct Cdda {
+    queue: VecDeque<(i16, i16)>,
+    /// Fase da próxima amostra, em ciclos do sub × 44,1 kHz
+    phase: u64,
+    output: (f32, f32),
+}
+
+impl Default for Cdda {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Cdda {
+    pub fn new() -> Self {
+        Self {
+            queue: VecDeque::with_capacity(MAX_QUEUED + SAMPLES_PER_SECTOR),
+            phase: 0,
+            output: (0.0, 0.0),
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.queue.clear();
+        self.phase = 0;
+        self.output = (0.0, 0.0);
+    }
+
+    /// Enfileira as amostras de um setor de áudio bruto
+    pub fn push_sector(&mut self, sector: &[u8]) {
+        for frame in sector.chunks_exact(4) {
+            let left = i16::from_le_bytes([frame[0], frame[1]]);
+            let right = i16::from_le_bytes([frame[2], frame[3]]);
+            self.queue.push_back((left, right));
+        }
+        let excess = self.queue.len().saturating_sub(MAX_QUEUED);
+        self.queue.drain(..excess);
+    }
+
+    /// Avança pelos ciclos do sub-68000; sem amostras na fila (pausa,
+    /// parada, trilha de dados) a saída fica em silêncio
+    pub fn tick(&mut self, cycles: u32) {
+        self.phase += cycles as u64 * CDDA_SAMPLE_RATE as u64;
+        while self.phase >= SUB_CPU_CLOCK as u64 {
+            self.phase -= SUB_CPU_CLOCK as u64;
+            self.output = self.queue.pop_front().map_or((0.0, 0.0), |(left, right)| {
+                (left as f32 / 32768.0, right as f32 / 32768.0)
+            });
+        }
+    }
+
+    /// Retorna a amostra atual (esquerda, direita)
+    pub fn sample(&self) -> (f32, f32) {
+        self.output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::megacd::disc::RAW_SECTOR_SIZE;
+
+    #[test]
+    fn test_sector_plays_at_cd_rate() {
+        let mut cdda = Cdda::new();
+        let mut sector = [0u8; RAW_SECTOR_SIZE];
+        for (i, frame) in sector.chunks_exact_mut(4).enumerate() {
+            frame[..2].copy_from_slice(&(i as i16 * 16).to_le_bytes());
+            frame[2..].copy_from_slice(&(-(i as i16) * 16).to_le_bytes());
+        }
+        cdda.push_sector(&sector);
+
+        // Menos de um período de 44,1 kHz: nada sai ainda
+        cdda.tick(SUB_CPU_CLOCK / CDDA_SAMPLE_RATE - 1);
+        assert_eq!(cdda.sample(), (0.0, 0.0));
+        cdda.tick(2);
+        cdda.tick(SUB_CPU_CLOCK / CDDA_SAMPLE_RATE);
+        assert_eq!(cdda.sample(), (16.0 / 32768.0, -16.0 / 32768.0));
+
+        // Um setor dura 1/75 s; depois dele, silêncio
+        cdda.tick(SUB_CPU_CLOCK / 75);
+        assert_eq!(cdda.sample(), (0.0, 0.0));
+    }
+
+    #[test]
+    fn test_queue_is_bounded() {
+        let mut cdda = Cdda::new();
+        let sector = [0x11u8; RAW_SECTOR_SIZE];
+        for _ in 0..5 {
+            cdda.push_sector(&sector);
+        }
+        assert_eq!(cdda.queue.len(), MAX_QUEUED);
+    }
+}
@@ -2,17 +2,23 @@
 use parking_lot::{Mutex, RwLock};
 use std::sync::Arc;
 
+pub mod cdda;
 pub mod psg;
+pub mod rf5c164;
 pub mod ym2612;
 
+use cdda::Cdda;
 use psg::Psg;
+use rf5c164::Rf5c164;
 use ym2612::Ym2612;
 
 /// Constantes de clock do Mega Drive
-const MASTER_CLOCK: u32 = 53693175; // 53.693175 MHz (NTSC)
-const CPU_CLOCK: u32 = MASTER_CLOCK / 15; // 3.579545 MHz (Z80)
-const PSG_CLOCK: u32 = CPU_CLOCK; // 3.579545 MHz
-const YM2612_CLOCK: u32 = MASTER_CLOCK / 7; // 7.670454 MHz
+pub const NTSC_MASTER_CLOCK: u32 = 53693175; // 53.693175 MHz (NTSC)
+pub const PAL_MASTER_CLOCK: u32 = 53203424; // 53.203424 MHz (PAL)
+const MASTER_CLOCK: u32 = NTSC_MASTER_CLOCK;
+/// Divisores do clock principal
+const Z80_DIVIDER: u32 = 15; // Z80 e PSG: 3.579545 MHz (NTSC)
+const YM2612_DIVIDER: u32 = 7; // YM2612: 7.670454 MHz (NTSC)
 
 /// Representa o sistema de som do Mega Drive (PSG + YM2612)
 pub struct Sound {
@@ -20,8 +26,14 @@ pub struct Sound {
     // Embora Mutex seja suficiente para este caso, RwLock é mais idiomático para acesso a hardware.
     pub psg: Arc<RwLock<Psg>>,
     pub fm: Arc<RwLock<Ym2612>>,
+    // PCM do Mega-CD (RF5C164), presente só com a unidade de expansão
+    pub pcm: Option<Arc<RwLock<Rf5c164>>>,
+    // Áudio CD-DA do Mega-CD
+    pub cdda: Option<Arc<RwLock<Cdda>>>,
     // Taxa de amostragem de saída (e.g., 44100 Hz)
     sample_rate: u32,
+    // Clock principal do console (NTSC ou PAL)
+    master_clock: u32,
     // Ciclos do clock principal por amostra de saída
     cycles_per_sample: f64,
     // Contador de ciclos para sincronização
@@ -30,16 +42,26 @@ pub struct Sound {
 
 impl Sound {
     pub fn new(sample_rate: u32) -> Self {
-        // O Mega Drive tem um clock principal de 53.693175 MHz.
-        // O PSG é clockado a 3.579545 MHz (CPU_CLOCK).
-        // O YM2612 é clockado a 7.670454 MHz (MASTER_CLOCK / 7).
+        Self::with_master_clock(sample_rate, MASTER_CLOCK)
+    }
+
+    /// Cria o sistema de som para um clock principal específico
+    /// (`NTSC_MASTER_CLOCK` ou `PAL_MASTER_CLOCK`).
+    pub fn with_master_clock(sample_rate: u32, master_clock: u32) -> Self {
+        // O Mega Drive tem um clock principal de 53.693175 MHz (NTSC)
+        // ou 53.203424 MHz (PAL).
+        // O PSG é clockado a MASTER_CLOCK / 15.
+        // O YM2612 é clockado a MASTER_CLOCK / 7.
         // A emulação deve avançar os chips com base no clock principal.
-        let cycles_per_sample = MASTER_CLOCK as f64 / sample_rate as f64;
+        let cycles_per_sample = master_clock as f64 / sample_rate as f64;
 
         Self {
             psg: Arc::new(RwLock::new(Psg::new(sample_rate))),
             fm: Arc::new(RwLock::new(Ym2612::new(sample_rate))),
+            pcm: None,
+            cdda: None,
             sample_rate,
+            master_clock,
             cycles_per_sample,
             current_cycles: 0.0,
         }
@@ -55,14 +77,38 @@ impl Sound {
         // mas o `tick` principal deve ser chamado com os ciclos do clock principal.
 
         // O PSG usa o clock do Z80 (3.58MHz).
-        let psg_cycles = (cycles as f64 * (PSG_CLOCK as f64 / MASTER_CLOCK as f64)).round() as u32;
+        let psg_cycles = (cycles as f64 / Z80_DIVIDER as f64).round() as u32;
         // O YM2612 usa o clock de 7.67MHz.
-        let fm_cycles = (cycles as f64 * (YM2612_CLOCK as f64 / MASTER_CLOCK as f64)).round() as u32;
+        let fm_cycles = (cycles as f64 / YM2612_DIVIDER as f64).round() as u32;
 
         self.psg.write().tick(psg_cycles);
         self.fm.write().tick(fm_cycles);
     }
 
+    /// Conecta o PCM do Mega-CD à mixagem. O chip é avançado pelo próprio
+    /// Mega-CD, no clock do sub-68000.
+    pub fn attach_pcm(&mut self, pcm: Arc<RwLock<Rf5c164>>) {
+        self.pcm = Some(pcm);
+    }
+
+    /// Conecta o CD-DA do Mega-CD à mixagem. Como o PCM, é avançado pelo
+    /// Mega-CD.
+    pub fn attach_cdda(&mut self, cdda: Arc<RwLock<Cdda>>) {
+        self.cdda = Some(cdda);
+    }
+
+    /// Amostras estéreo das fontes do Mega-CD conectadas (PCM e CD-DA)
+    fn cd_samples(&self) -> impl Iterator<Item = (f32, f32)> {
+        let pcm = self.pcm.as_ref().map(|pcm| pcm.read().sample());
+        let cdda = self.cdda.as_ref().map(|cdda| cdda.read().sample());
+        pcm.into_iter().chain(cdda)
+    }
+
+    /// Retorna o clock principal usado pelo sistema de som
+    pub fn master_clock(&self) -> u32 {
+        self.master_clock
+    }
+
     /// Gera uma amostra de áudio.
     /// Esta função deve ser chamada na taxa de amostragem de saída (e.g., 44100 Hz).
     pub fn sample(&self) -> f32 {
@@ -78,7 +124,13 @@ impl Sound {
         // Mixagem simples (média)
         // Para um emulador real, a mixagem é mais complexa, envolvendo
         // a atenuação correta dos volumes dos chips.
-        (psg_sample + fm_left + fm_right) / 3.0
+        let mut sum = psg_sample + fm_left + fm_right;
+        let mut sources = 3.0;
+        for (left, right) in self.cd_samples() {
+            sum += left + right;
+            sources += 2.0;
+        }
+        sum / sources
     }
 
     /// Gera uma amostra de áudio estéreo.
@@ -95,6 +147,14 @@ impl Sound {
         let left = (psg_sample + fm_left) / 2.0;
         let right = (psg_sample + fm_right) / 2.0;
 
-        (left, right)
+        // PCM e CD-DA do Mega-CD (estéreo)
+        let (mut sum_left, mut sum_right) = (left * 2.0, right * 2.0);
+        let mut sources = 2.0;
+        for (cd_left, cd_right) in self.cd_samples() {
+            sum_left += cd_left;
+            sum_right += cd_right;
+            sources += 1.0;
+        }
+        (sum_left / sources, sum_right / sources)
     }
 }
@@ -0,0 +1,219 @@
+//! src/sound/rf5c164.rs
+//! Ricoh RF5C164: chip PCM de 8 canais do Mega-CD.
+//!
+//! Cada canal toca amostras de 8 bits (sinal e magnitude: bit 7 = positivo)
+//! de uma RAM de onda de 64 KB, com endereço em ponto fixo 16.11 avançado
+//! pelo passo FD a cada amostra. O byte 0xFF marca o fim do trecho e faz o
+//! canal voltar ao endereço de loop (LS).
+//!
+//! Registradores (escritos pelo sub-68000 nos bytes ímpares de 0xFF0000):
+//! - 0x0: envelope, 0x1: pan (nibble baixo = esquerda, alto = direita)
+//! - 0x2/0x3: FD (passo), 0x4/0x5: LS (loop), 0x6: ST (início, byte alto)
+//! - 0x7: controle — bit 7 liga o chip; com o bit 6, os bits 0–2 escolhem
+//!   o canal dos registradores 0x0–0x6; sem ele, os bits 0–3 escolhem o
+//!   banco de 4 KB da RAM de onda visto em 0xFF2000
+//! - 0x8: canais desligados (bit n = 1 desliga o canal n)
+
+/// Clock do chip (12,5 MHz) dividido por 384: uma amostra a ~32,5 kHz
+pub const PCM_CLOCK_DIVIDER: u32 = 384;
+/// Tamanho da RAM de onda
+pub const PCM_RAM_SIZE: usize = 0x10000;
+/// Tamanho da janela de acesso à RAM de onda
+pub const PCM_BANK_SIZE: usize = 0x1000;
+const NUM_CHANNELS: usize = 8;
+/// Bits fracionários do endereço
+const ADDR_SHIFT: u32 = 11;
+/// Máscara do endereço (64 KB em 16.11)
+const ADDR_MASK: u32 = ((PCM_RAM_SIZE as u32) << ADDR_SHIFT) - 1;
+/// Marcador de loop na RAM de onda
+const LOOP_MARKER: u8 = 0xFF;
+
+#[derive(Clone, Copy, Default)]
+struct PcmChannel {
+    env: u8,
+    pan: u8,
+    fd: u16,
+    ls: u16,
+    st: u8,
+    /// Endereço atual (16.11)
+    addr: u32,
+}
+
+pub struct Rf5c164 {
+    pub ram: Vec<u8>,
+    channels: [PcmChannel; NUM_CHANNELS],
+    enabled: bool,
+    /// Canal selecionado para os registradores 0x0–0x6
+    channel: usize,
+    /// Banco da RAM de onda visto pela CPU
+    bank: usize,
+    /// Máscara de canais desligados (registrador 0x8)
+    off_mask: u8,
+    /// Ciclos do sub-68000 acumulados até a próxima amostra
+    cycles: u32,
+    output: (f32, f32),
+}
+
+impl Default for Rf5c164 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Rf5c164 {
+    pub fn new() -> Self {
+        Self {
+            ram: vec![0; PCM_RAM_SIZE],
+            channels: [PcmChannel::default(); NUM_CHANNELS],
+            enabled: false,
+            channel: 0,
+            bank: 0,
+            off_mask: 0xFF,
+            cycles: 0,
+            output: (0.0, 0.0),
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.channels = [PcmChannel::default(); NUM_CHANNELS];
+        self.enabled = false;
+        self.channel = 0;
+        self.bank = 0;
+        self.off_mask = 0xFF;
+        self.output = (0.0, 0.0);
+    }
+
+    /// Escrita num registrador (0x0–0x8)
+    pub fn write_register(&mut self, reg: u8, value: u8) {
+        let ch = &mut self.channels[self.channel];
+        match reg {
+            0x0 => ch.env = value,
+            0x1 => ch.pan = value,
+            0x2 => ch.fd = (ch.fd & 0xFF00) | value as u16,
+            0x3 => ch.fd = (ch.fd & 0x00FF) | ((value as u16) << 8),
+            0x4 => ch.ls = (ch.ls & 0xFF00) | value as u16,
+            0x5 => ch.ls = (ch.ls & 0x00FF) | ((value as u16) << 8),
+            0x6 => ch.st = value,
+            0x7 => {
+                self.enabled = value & 0x80 != 0;
+                if value & 0x40 != 0 {
+                    self.channel = (value & 0x07) as usize;
+                } else {
+                    self.bank = (value & 0x0F) as usize;
+                }
+            }
+            0x8 => {
+                // Canais que acabam de ser ligados partem do endereço ST
+                let turned_on = self.off_mask & !value;
+                for (i, ch) in self.channels.iter_mut().enumerate() {
+                    if turned_on & (1 << i) != 0 {
+                        ch.addr = (ch.st as u32) << (8 + ADDR_SHIFT);
+                    }
+                }
+                self.off_mask = value;
+            }
+            _ => {}
+        }
+    }
+
+    /// Leitura dos registradores 0x10–0x1F: endereço atual de cada canal
+    /// (byte baixo e alto da parte inteira)
+    pub fn read_register(&self, reg: u8) -> u8 {
+        match reg {
+            0x10..=0x1F => {
+                let addr = self.channels[((reg - 0x10) >> 1) as usize].addr >> ADDR_SHIFT;
+                if reg & 1 == 0 { addr as u8 } else { (addr >> 8) as u8 }
+            }
+            _ => 0xFF,
+        }
+    }
+
+    /// Leitura na janela da RAM de onda (offset 0–0xFFF)
+    pub fn read_ram(&self, offset: usize) -> u8 {
+        self.ram[self.bank * PCM_BANK_SIZE + (offset % PCM_BANK_SIZE)]
+    }
+
+    /// Escrita na janela da RAM de onda (offset 0–0xFFF)
+    pub fn write_ram(&mut self, offset: usize, value: u8) {
+        self.ram[self.bank * PCM_BANK_SIZE + (offset % PCM_BANK_SIZE)] = value;
+    }
+
+    /// Avança o chip por ciclos do sub-68000 (12,5 MHz)
+    pub fn tick(&mut self, cycles: u32) {
+        self.cycles += cycles;
+        while self.cycles >= PCM_CLOCK_DIVIDER {
+            self.cycles -= PCM_CLOCK_DIVIDER;
+            self.step();
+        }
+    }
+
+    /// Gera uma amostra interna de todos os canais ligados
+    fn step(&mut self) {
+        if !self.enabled {
+            self.output = (0.0, 0.0);
+            return;
+        }
+        let (mut left, mut right) = (0i32, 0i32);
+        for (i, ch) in self.channels.iter_mut().enumerate() {
+            if self.off_mask & (1 << i) != 0 {
+                continue;
+            }
+            let mut sample = self.ram[(ch.addr >> ADDR_SHIFT) as usize & (PCM_RAM_SIZE - 1)];
+            if sample == LOOP_MARKER {
+                ch.addr = (ch.ls as u32) << ADDR_SHIFT;
+                sample = self.ram[ch.ls as usize];
+                if sample == LOOP_MARKER {
+                    continue;
+                }
+            }
+            // Sinal e magnitude: bit 7 ligado = positivo
+            let magnitude = (sample & 0x7F) as i32;
+            let value = if sample & 0x80 != 0 { magnitude } else { -magnitude };
+            let scaled = value * ch.env as i32;
+            left += (scaled * (ch.pan & 0x0F) as i32) >> 5;
+            right += (scaled * (ch.pan >> 4) as i32) >> 5;
+            ch.addr = (ch.addr + ch.fd as u32) & ADDR_MASK;
+        }
+        let clamp = |v: i32| v.clamp(i16::MIN as i32, i16::MAX as i32) as f32 / 32768.0;
+        self.output = (clamp(left), clamp(right));
+    }
+
+    /// Última amostra estéreo (-1.0 a 1.0)
+    pub fn sample(&self) -> (f32, f32) {
+        self.output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_channel_plays_and_loops() {
+        let mut pcm = Rf5c164::new();
+        // Banco 0: +64, -64, fim (volta para o endereço 1)
+        pcm.write_register(0x7, 0x00);
+        pcm.write_ram(0, 0x80 | 64);
+        pcm.write_ram(1, 64);
+        pcm.write_ram(2, LOOP_MARKER);
+
+        // Canal 0: envelope máximo, só à esquerda, passo 1.0, loop em 1
+        pcm.write_register(0x7, 0xC0);
+        pcm.write_register(0x0, 0xFF);
+        pcm.write_register(0x1, 0x0F);
+        pcm.write_register(0x3, 0x08);
+        pcm.write_register(0x4, 0x01);
+        pcm.write_register(0x8, 0xFE);
+
+        pcm.tick(PCM_CLOCK_DIVIDER);
+        let (left, right) = pcm.sample();
+        assert!(left > 0.0);
+        assert_eq!(right, 0.0);
+        pcm.tick(PCM_CLOCK_DIVIDER);
+        assert!(pcm.sample().0 < 0.0);
+        // O marcador de fim leva de volta ao endereço 1 (-64)
+        pcm.tick(PCM_CLOCK_DIVIDER);
+        assert!(pcm.sample().0 < 0.0);
+        assert_eq!(pcm.read_register(0x10), 2);
+    }
+}
@@ -0,0 +1,160 @@
+//! Conversão de cores da CRAM para ARGB
+//!
+//! Cada entrada da CRAM tem o formato 0000BBB0GGG0RRR0: três bits por
+//! canal, 512 cores possíveis. A conversão usa uma tabela pré-calculada de
+//! 512 entradas para a curva de saída escolhida, repetida para cada uma das
+//! três escadas de intensidade do modo shadow/highlight (sombra, normal e
+//! realce).
+//!
+//! Curvas disponíveis:
+//! - `Linear`: 0–7 espalhado uniformemente em 0–255; sombra é a metade do
+//!   nível normal e realce é a metade mais 128
+//! - `MegaDrive`: níveis não lineares medidos na saída do DAC do console,
+//!   com escadas próprias para sombra e realce
+
+use std::str::FromStr;
+
+/// Número de cores de 9 bits
+pub const COLOR_COUNT: usize = 512;
+
+/// Níveis medidos do DAC (0–255) para cada valor de 3 bits
+const MD_SHADOW_LEVELS: [u8; 8] = [0, 29, 52, 70, 87, 101, 116, 130];
+const MD_NORMAL_LEVELS: [u8; 8] = [0, 52, 87, 116, 144, 172, 206, 255];
+const MD_HIGHLIGHT_LEVELS: [u8; 8] = [130, 144, 158, 172, 187, 206, 228, 255];
+
+/// Curva de saída de cor
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorCurve {
+    /// Escala linear
+    Linear,
+    /// Níveis medidos do DAC do Mega Drive
+    #[default]
+    MegaDrive,
+}
+
+impl FromStr for ColorCurve {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "linear" => Ok(ColorCurve::Linear),
+            "md" | "megadrive" => Ok(ColorCurve::MegaDrive),
+            _ => Err(format!("Curva de cor desconhecida: {}", s)),
+        }
+    }
+}
+
+/// Escada de intensidade (modo shadow/highlight)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Intensity {
+    Shadow = 0,
+    Normal = 1,
+    Highlight = 2,
+}
+
+impl ColorCurve {
+    /// Nível de 8 bits de um canal de 3 bits (0–7)
+    pub fn level(self, value: u8, intensity: Intensity) -> u8 {
+        let value = (value & 0x07) as usize;
+        match self {
+            ColorCurve::Linear => {
+                let normal = (value * 255 + 3) / 7;
+                match intensity {
+                    Intensity::Shadow => (normal / 2) as u8,
+                    Intensity::Normal => normal as u8,
+                    Intensity::Highlight => (128 + normal / 2) as u8,
+                }
+            }
+            ColorCurve::MegaDrive => match intensity {
+                Intensity::Shadow => MD_SHADOW_LEVELS[value],
+                Intensity::Normal => MD_NORMAL_LEVELS[value],
+                Intensity::Highlight => MD_HIGHLIGHT_LEVELS[value],
+            },
+        }
+    }
+}
+
+/// Índice de 9 bits (BBBGGGRRR) de uma palavra da CRAM (0000BBB0GGG0RRR0)
+pub fn color_index(word: u16) -> usize {
+    let word = word as usize;
+    ((word >> 1) & 0x007) | ((word >> 2) & 0x038) | ((word >> 3) & 0x1C0)
+}
+
+/// Tabela de conversão de cor de 9 bits para ARGB
+#[derive(Debug, Clone)]
+pub struct ColorLut {
+    curve: ColorCurve,
+    /// `COLOR_COUNT` entradas por escada de intensidade
+    table: Vec<u32>,
+}
+
+impl ColorLut {
+    /// Pré-calcula a tabela para a curva
+    pub fn new(curve: ColorCurve) -> Self {
+        let mut table = Vec::with_capacity(COLOR_COUNT * 3);
+        for intensity in [Intensity::Shadow, Intensity::Normal, Intensity::Highlight] {
+            for color in 0..COLOR_COUNT {
+                let channel = |shift: usize| curve.level((color >> shift) as u8, intensity) as u32;
+                table.push(0xFF000000 | (channel(0) << 16) | (channel(3) << 8) | channel(6));
+            }
+        }
+        Self { curve, table }
+    }
+
+    /// Curva usada na tabela
+    pub fn curve(&self) -> ColorCurve {
+        self.curve
+    }
+
+    /// Cor ARGB de uma palavra da CRAM na intensidade dada
+    pub fn argb(&self, word: u16, intensity: Intensity) -> u32 {
+        self.table[intensity as usize * COLOR_COUNT + color_index(word)]
+    }
+}
+
+impl Default for ColorLut {
+    fn default() -> Self {
+        Self::new(ColorCurve::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_color_index_decoding() {
+        assert_eq!(color_index(0x000E), 0x007);
+        assert_eq!(color_index(0x00E0), 0x038);
+        assert_eq!(color_index(0x0E00), 0x1C0);
+        assert_eq!(color_index(0x0EEE), 0x1FF);
+        // Bits fora do formato são ignorados
+        assert_eq!(color_index(0xF111), 0);
+    }
+
+    #[test]
+    fn test_lut_curves_and_intensities() {
+        let linear = ColorLut::new(ColorCurve::Linear);
+        assert_eq!(linear.argb(0x000E, Intensity::Normal), 0xFFFF0000);
+        assert_eq!(linear.argb(0x00E0, Intensity::Normal), 0xFF00FF00);
+        assert_eq!(linear.argb(0x0E00, Intensity::Normal), 0xFF0000FF);
+        assert_eq!(linear.argb(0x0000, Intensity::Highlight), 0xFF808080);
+        assert_eq!(linear.argb(0x0EEE, Intensity::Shadow), 0xFF7F7F7F);
+
+        let md = ColorLut::new(ColorCurve::MegaDrive);
+        assert_eq!(md.curve(), ColorCurve::MegaDrive);
+        // Valor 4 em todos os canais: nível medido, não linear
+        assert_eq!(md.argb(0x0888, Intensity::Normal), 0xFF909090);
+        assert_eq!(md.argb(0x0888, Intensity::Shadow), 0xFF575757);
+        assert_eq!(md.argb(0x0888, Intensity::Highlight), 0xFFBBBBBB);
+        assert_eq!(md.argb(0x0EEE, Intensity::Normal), 0xFFFFFFFF);
+        assert_eq!(ColorLut::default().curve(), ColorCurve::default());
+    }
+
+    #[test]
+    fn test_curve_from_str() {
+        assert_eq!("linear".parse(), Ok(ColorCurve::Linear));
+        assert_eq!("MD".parse(), Ok(ColorCurve::MegaDrive));
+        assert!("srgb".parse::<ColorCurve>().is_err());
+    }
+}
@@ -1,13 +1,69 @@
+use crate::vdp::color::{ColorLut, Intensity};
+
 /// Color RAM (CRAM) — 64 cores de 9 bits (0–511)
-/// Cada cor é armazenada no formato BBBGGGRRR (bits 0–8).
+/// Cada cor é armazenada no formato 0000BBB0GGG0RRR0.
 #[derive(Clone)]
 pub struct Cram {
     pub data: [u16; 64],  // Alterado de `colors` para `data` para consistência com o dump
+    /// Posição X do feixe na linha atual, mantida pelo VDP. `None` quando
+    /// não há feixe (uso fora do `tick`): as escritas não são registradas.
+    beam_x: Option<u16>,
+    /// Escritas desde o início da linha, em ordem cronológica
+    line_writes: Vec<CramWrite>,
+}
+
+/// Escrita na CRAM marcada com a posição do pixel em que caiu
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CramWrite {
+    /// Pixel da linha ativa (0 = antes do início da linha)
+    pub x: u16,
+    pub index: u8,
+    pub value: u16,
+    /// Valor anterior da entrada, para reconstruir a paleta do início da linha
+    pub previous: u16,
 }
 
 impl Cram {
     pub fn new() -> Self {
-        Self { data: [0; 64] }
+        Self { data: [0; 64], beam_x: None, line_writes: Vec::new() }
+    }
+
+    /// Atualiza a posição do feixe usada para marcar as escritas
+    pub fn set_beam_position(&mut self, x: Option<u16>) {
+        self.beam_x = x;
+    }
+
+    /// Descarta as escritas registradas; chamado pelo VDP depois de
+    /// desenhar cada linha
+    pub fn begin_line(&mut self) {
+        self.line_writes.clear();
+    }
+
+    /// Escritas da linha atual, em ordem cronológica
+    pub fn line_writes(&self) -> &[CramWrite] {
+        &self.line_writes
+    }
+
+    /// Paleta como estava no início da linha atual
+    pub fn line_start_palette(&self) -> [u16; 64] {
+        let mut palette = self.data;
+        for write in self.line_writes.iter().rev() {
+            palette[write.index as usize] = write.previous;
+        }
+        palette
+    }
+
+    /// Grava uma entrada, registrando a escrita se houver feixe
+    fn store(&mut self, index: usize, value: u16) {
+        if let Some(x) = self.beam_x {
+            self.line_writes.push(CramWrite {
+                x,
+                index: index as u8,
+                value,
+                previous: self.data[index],
+            });
+        }
+        self.data[index] = value;
     }
 
     /// Escreve um valor de 16 bits na CRAM
@@ -15,7 +71,7 @@ impl Cram {
     pub fn write_word(&mut self, addr: u16, value: u16) {
         let index = (addr as usize) >> 1; // Endereço em bytes, converter para índice de palavra
         if index < 64 {
-            self.data[index] = value & 0x0EEE; // Máscara: BBB0GGG0RRR0 (bits 11-0 válidos)
+            self.store(index, value & 0x0EEE); // Máscara: BBB0GGG0RRR0 (bits 11-0 válidos)
         }
     }
 
@@ -32,7 +88,7 @@ impl Cram {
     /// Escreve diretamente em um índice específico da CRAM
     pub fn write(&mut self, index: usize, value: u16) {
         if index < 64 {
-            self.data[index] = value & 0x0EEE; // Apenas 12 bits válidos (BBB0GGG0RRR0)
+            self.store(index, value & 0x0EEE); // Apenas 12 bits válidos (BBB0GGG0RRR0)
         }
     }
 
@@ -42,29 +98,16 @@ impl Cram {
     }
 
     /// Converte uma cor CRAM em RGB (0–255 por canal)
-    /// Formato original: 0000 BBB0 GGG0 RRR0 (12 bits, mas apenas 9 bits de cor)
-    pub fn to_rgb(&self, index: usize) -> (u8, u8, u8) {
-        let v = self.data[index % 64];
-        
-        // Extrair componentes de 3 bits cada e expandir para 8 bits
-        // BBB está nos bits 8-6, GGG nos bits 4-2, RRR nos bits 0-2
-        let r = ((v >> 0) & 0x7) as u8;  // 3 bits do vermelho
-        let g = ((v >> 4) & 0x7) as u8;  // 3 bits do verde
-        let b = ((v >> 8) & 0x7) as u8;  // 3 bits do azul
-        
-        // Expandir de 3 bits (0-7) para 8 bits (0-255)
-        // Fórmula: valor * 255 / 7, arredondado
-        let r = (r * 36) as u8;  // 36 ≈ 255/7
-        let g = (g * 36) as u8;
-        let b = (b * 36) as u8;
-        
-        (r, g, b)
+    /// Formato original: 0000 BBB0 GGG0 RRR0 (3 bits por canal), convertido
+    /// pela tabela de cores do renderer (curva escolhida)
+    pub fn to_rgb(&self, index: usize, lut: &ColorLut) -> (u8, u8, u8) {
+        let argb = self.to_rgb888(index, lut);
+        ((argb >> 16) as u8, (argb >> 8) as u8, argb as u8)
     }
 
     /// Converte para RGB 888 (32-bit ARGB)
-    pub fn to_rgb888(&self, index: usize) -> u32 {
-        let (r, g, b) = self.to_rgb(index);
-        0xFF000000 | ((r as u32) << 16) | ((g as u32) << 8) | (b as u32)
+    pub fn to_rgb888(&self, index: usize, lut: &ColorLut) -> u32 {
+        lut.argb(self.data[index % 64], Intensity::Normal)
     }
 
     /// Retorna uma cópia de todos os dados da CRAM
@@ -82,6 +125,7 @@ impl Default for Cram {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::vdp::color::ColorCurve;
 
     #[test]
     fn test_cram_write_read() {
@@ -100,47 +144,68 @@ mod tests {
         assert_eq!(cram.read(100), cram.read(100 % 64));
     }
 
+    #[test]
+    fn test_cram_line_writes() {
+        let mut cram = Cram::new();
+        cram.write(3, 0x0222);
+        // Sem feixe as escritas não são registradas
+        assert!(cram.line_writes().is_empty());
+        
+        cram.set_beam_position(Some(40));
+        cram.write(3, 0x0444);
+        cram.write_word(0x06, 0x0666);
+        assert_eq!(cram.read(3), 0x0666);
+        assert_eq!(cram.line_writes().len(), 2);
+        assert_eq!(cram.line_writes()[0], CramWrite { x: 40, index: 3, value: 0x0444, previous: 0x0222 });
+        assert_eq!(cram.line_start_palette()[3], 0x0222);
+        
+        cram.begin_line();
+        assert!(cram.line_writes().is_empty());
+        assert_eq!(cram.line_start_palette()[3], 0x0666);
+    }
+
     #[test]
     fn test_cram_to_rgb() {
         let mut cram = Cram::new();
+        let lut = ColorLut::default();
         
         // Teste: cor branca (todos os bits = 1)
         // BBB=111 (0x7), GGG=111 (0x7), RRR=111 (0x7)
         cram.write(0, 0x0EEE);
-        let (r, g, b) = cram.to_rgb(0);
-        assert_eq!(r, 36 * 7);
-        assert_eq!(g, 36 * 7);
-        assert_eq!(b, 36 * 7);
+        assert_eq!(cram.to_rgb(0, &lut), (255, 255, 255));
         
         // Teste: cor vermelha máxima
         // RRR=111 (0x7), GGG=000 (0x0), BBB=000 (0x0)
         cram.write(1, 0x000E);
-        let (r, g, b) = cram.to_rgb(1);
-        assert_eq!(r, 36 * 7);
-        assert_eq!(g, 0);
-        assert_eq!(b, 0);
+        assert_eq!(cram.to_rgb(1, &lut), (255, 0, 0));
         
-        // Teste: cor verde média
-        // RRR=100 (0x4), GGG=100 (0x4), BBB=100 (0x4)
-        cram.write(2, 0x0444);
-        let (r, g, b) = cram.to_rgb(2);
-        assert_eq!(r, 36 * 4);
-        assert_eq!(g, 36 * 4);
-        assert_eq!(b, 36 * 4);
+        // Teste: cinza médio, RRR=GGG=BBB=100 (0x4): nível medido do DAC
+        cram.write(2, 0x0888);
+        assert_eq!(cram.to_rgb(2, &lut), (144, 144, 144));
+        
+        // Teste: canais em posições distintas
+        cram.write(3, 0x0A42);
+        assert_eq!(cram.to_rgb(3, &lut), (52, 87, 172));
     }
 
     #[test]
     fn test_cram_to_rgb888() {
         let mut cram = Cram::new();
+        let lut = ColorLut::new(ColorCurve::Linear);
         
         // Teste cor vermelha
         cram.write(0, 0x000E); // Vermelho máximo
-        let rgb = cram.to_rgb888(0);
-        assert_eq!(rgb, 0xFF0000FF & 0xFFFF00FF); // Apenas verifica componente vermelho
+        let rgb = cram.to_rgb888(0, &lut);
+        assert_eq!(rgb, 0xFFFF0000);
         
         // Teste cor branca
         cram.write(1, 0x0EEE);
-        let rgb = cram.to_rgb888(1);
+        let rgb = cram.to_rgb888(1, &lut);
         assert_eq!(rgb, 0xFFFFFFFF);
+
+        // A curva da tabela recebida vale: cinza linear, não o nível do DAC
+        cram.write(2, 0x0888);
+        assert_eq!(cram.to_rgb888(2, &lut), 0xFF929292);
+        assert_eq!(cram.to_rgb888(2, &ColorLut::default()), 0xFF909090);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,235 @@
+//! Desentrelaçamento da saída do VDP
+//!
+//! Etapa de pós-processamento sobre o `FrameBuffer` para os modos
+//! entrelaçados (interlace modo 1 e 2). A cada quadro recebe o campo
+//! recém-desenhado e guarda o anterior, gerando a imagem final conforme o
+//! modo escolhido em tempo de execução:
+//! - `Weave`: intercala as linhas dos dois últimos campos (448 linhas)
+//! - `Bob`: dobra as linhas do campo atual, deslocando o campo ímpar em meia
+//!   linha (448 linhas)
+//! - `Blend`: média dos dois últimos campos, com linhas dobradas (448 linhas)
+//! - `NativeField`: entrega cada campo como está, 224 linhas a 60 Hz
+//!
+//! No interlace modo 2 o renderer desenha os campos em linhas alternadas de
+//! um quadro de 448 linhas; no modo 1 cada campo ocupa o quadro inteiro.
+
+use crate::vdp::framebuffer::FrameBuffer;
+use std::str::FromStr;
+
+/// Modo de desentrelaçamento
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DeinterlaceMode {
+    /// Intercala os dois últimos campos
+    #[default]
+    Weave,
+    /// Dobra as linhas do campo atual
+    Bob,
+    /// Média dos dois últimos campos
+    Blend,
+    /// Um campo de 224 linhas por quadro
+    NativeField,
+}
+
+impl FromStr for DeinterlaceMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "weave" => Ok(DeinterlaceMode::Weave),
+            "bob" => Ok(DeinterlaceMode::Bob),
+            "blend" => Ok(DeinterlaceMode::Blend),
+            "native" | "field" => Ok(DeinterlaceMode::NativeField),
+            _ => Err(format!("Modo de desentrelaçamento desconhecido: {}", s)),
+        }
+    }
+}
+
+/// Estado do desentrelaçamento: últimos campos e quadro de saída
+#[derive(Clone)]
+pub struct Deinterlacer {
+    pub mode: DeinterlaceMode,
+    /// Campos par e ímpar mais recentes
+    fields: [FrameBuffer; 2],
+    output: FrameBuffer,
+}
+
+impl Deinterlacer {
+    pub fn new(mode: DeinterlaceMode) -> Self {
+        Self {
+            mode,
+            fields: [FrameBuffer::new(0, 0), FrameBuffer::new(0, 0)],
+            output: FrameBuffer::new(0, 0),
+        }
+    }
+
+    /// Troca o modo; os campos guardados continuam valendo
+    pub fn set_mode(&mut self, mode: DeinterlaceMode) {
+        self.mode = mode;
+    }
+
+    /// Quadro de saída do último `process`
+    pub fn output(&self) -> &FrameBuffer {
+        &self.output
+    }
+
+    /// Descarta os campos guardados (fim do modo entrelaçado)
+    pub fn reset(&mut self) {
+        self.fields = [FrameBuffer::new(0, 0), FrameBuffer::new(0, 0)];
+    }
+
+    /// Processa o quadro desenhado pelo renderer. `odd` indica o campo
+    /// recém-desenhado; `double_resolution` indica o interlace modo 2, em
+    /// que o campo está nas linhas de mesma paridade do quadro.
+    ///
+    /// Campos e saída são buffers reaproveitados entre quadros: só há
+    /// alocação quando a resolução muda.
+    pub fn process(&mut self, frame: &FrameBuffer, odd: bool, double_resolution: bool) -> &FrameBuffer {
+        let width = frame.width;
+        let height = if double_resolution { frame.height / 2 } else { frame.height };
+        let slot = odd as usize;
+
+        let field = &mut self.fields[slot];
+        Self::fit(field, width, height);
+        for line in 0..height {
+            let source = if double_resolution { line * 2 + slot } else { line };
+            Self::row_mut(field, line).copy_from_slice(Self::row(frame, source));
+        }
+
+        // Campo anterior de outro tamanho (troca de modo): repetir o atual
+        let [even_field, odd_field] = &mut self.fields;
+        let (current, other) = if odd { (odd_field, even_field) } else { (even_field, odd_field) };
+        if other.width != width || other.height != height {
+            Self::fit(other, width, height);
+            other.pixels.copy_from_slice(&current.pixels);
+        }
+
+        let output_height = if self.mode == DeinterlaceMode::NativeField { height } else { height * 2 };
+        let output = &mut self.output;
+        Self::fit(output, width, output_height);
+        let [even_field, odd_field] = &self.fields;
+        let current = &self.fields[slot];
+
+        match self.mode {
+            DeinterlaceMode::NativeField => output.pixels.copy_from_slice(&current.pixels),
+            DeinterlaceMode::Weave => {
+                for y in 0..output_height {
+                    let source = if y % 2 == 0 { even_field } else { odd_field };
+                    Self::row_mut(output, y).copy_from_slice(Self::row(source, y / 2));
+                }
+            }
+            DeinterlaceMode::Bob => {
+                for y in 0..output_height {
+                    // Campo ímpar meia linha abaixo
+                    let line = if odd { y.saturating_sub(1) / 2 } else { y / 2 };
+                    Self::row_mut(output, y).copy_from_slice(Self::row(current, line));
+                }
+            }
+            DeinterlaceMode::Blend => {
+                for y in 0..output_height {
+                    let pixels = Self::row(even_field, y / 2).iter().zip(Self::row(odd_field, y / 2));
+                    for (out, (&a, &b)) in Self::row_mut(output, y).iter_mut().zip(pixels) {
+                        *out = blend_argb(a, b);
+                    }
+                }
+            }
+        }
+        output.dirty = true;
+        &self.output
+    }
+
+    /// Ajusta um buffer reaproveitado às dimensões pedidas (realoca só se mudarem)
+    fn fit(buffer: &mut FrameBuffer, width: usize, height: usize) {
+        if buffer.width != width || buffer.height != height {
+            *buffer = FrameBuffer::new(width, height);
+        }
+    }
+
+    fn row(frame: &FrameBuffer, line: usize) -> &[u32] {
+        &frame.pixels[line * frame.width..(line + 1) * frame.width]
+    }
+
+    fn row_mut(frame: &mut FrameBuffer, line: usize) -> &mut [u32] {
+        let width = frame.width;
+        &mut frame.pixels[line * width..(line + 1) * width]
+    }
+}
+
+impl Default for Deinterlacer {
+    fn default() -> Self {
+        Self::new(DeinterlaceMode::default())
+    }
+}
+
+/// Média por canal de duas cores ARGB
+fn blend_argb(a: u32, b: u32) -> u32 {
+    // Soma sem transbordar entre canais: metade de cada mais o bit comum
+    ((a >> 1) & 0x7F7F7F7F) + ((b >> 1) & 0x7F7F7F7F) + (a & b & 0x01010101)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Quadro 2x4 de resolução dupla: linhas pares `even`, ímpares `odd`
+    fn interlaced_frame(even: u32, odd: u32) -> FrameBuffer {
+        let mut frame = FrameBuffer::new(2, 4);
+        for y in 0..4 {
+            let color = if y % 2 == 0 { even } else { odd };
+            frame.draw_horizontal_line(0, 1, y, color);
+        }
+        frame
+    }
+
+    #[test]
+    fn test_mode_from_str() {
+        assert_eq!("bob".parse(), Ok(DeinterlaceMode::Bob));
+        assert_eq!("Weave".parse(), Ok(DeinterlaceMode::Weave));
+        assert_eq!("blend".parse(), Ok(DeinterlaceMode::Blend));
+        assert_eq!("native".parse(), Ok(DeinterlaceMode::NativeField));
+        assert!("scanlines".parse::<DeinterlaceMode>().is_err());
+    }
+
+    #[test]
+    fn test_weave_and_native_field() {
+        let mut deinterlacer = Deinterlacer::new(DeinterlaceMode::Weave);
+        deinterlacer.process(&interlaced_frame(0xFF111111, 0), false, true);
+        let output = deinterlacer.process(&interlaced_frame(0xFF111111, 0xFF222222), true, true);
+        assert_eq!((output.width, output.height), (2, 4));
+        assert_eq!(output.get_pixel(0, 0), Some(0xFF111111));
+        assert_eq!(output.get_pixel(0, 1), Some(0xFF222222));
+
+        deinterlacer.set_mode(DeinterlaceMode::NativeField);
+        let output = deinterlacer.process(&interlaced_frame(0xFF333333, 0xFF222222), false, true);
+        assert_eq!((output.width, output.height), (2, 2));
+        assert_eq!(output.get_pixel(1, 1), Some(0xFF333333));
+    }
+
+    #[test]
+    fn test_bob_and_blend() {
+        let mut deinterlacer = Deinterlacer::new(DeinterlaceMode::Bob);
+        // Interlace modo 1: cada campo é o quadro inteiro
+        let mut field = FrameBuffer::new(2, 2);
+        field.draw_horizontal_line(0, 1, 0, 0xFF000000);
+        field.draw_horizontal_line(0, 1, 1, 0xFF202020);
+        let output = deinterlacer.process(&field, true, false);
+        assert_eq!(output.height, 4);
+        // Campo ímpar deslocado meia linha
+        assert_eq!(output.get_pixel(0, 2), Some(0xFF000000));
+        assert_eq!(output.get_pixel(0, 3), Some(0xFF202020));
+
+        deinterlacer.set_mode(DeinterlaceMode::Blend);
+        field.clear(0xFF404040);
+        let output = deinterlacer.process(&field, false, false);
+        assert_eq!(output.get_pixel(0, 0), Some(0xFF202020));
+        assert_eq!(output.get_pixel(0, 3), Some(0xFF303030));
+    }
+
+    #[test]
+    fn test_buffers_are_reused_between_frames() {
+        let mut deinterlacer = Deinterlacer::new(DeinterlaceMode::Blend);
+        let first = deinterlacer.process(&interlaced_frame(0xFF101010, 0xFF303030), false, true).pixels.as_ptr();
+        let second = deinterlacer.process(&interlaced_frame(0xFF101010, 0xFF303030), true, true);
+        assert_eq!(second.pixels.as_ptr(), first);
+        assert_eq!(second.get_pixel(0, 0), Some(0xFF202020));
+    }
+}
@@ -397,28 +397,31 @@ mod tests {
         let mut vram = Vram::new();
         let mut cram = Cram::new();
         let mut vsram = Vsram::new();
+        let sound = std::sync::Arc::new(std::sync::Mutex::new(crate::sound::Sound::new(44100)));
         let mut bus = Bus::new(
-            std::sync::Arc::new(std::sync::Mutex::new(crate::memory::ram::Ram::new(0x10000))),
-            std::sync::Arc::new(std::sync::Mutex::new(crate::vdp::vdp::Vdp::new())),
-            std::sync::Arc::new(std::sync::Mutex::new(crate::io::io::Io::new())),
-            std::sync::Arc::new(std::sync::Mutex::new(crate::memory::rom::Rom::new(Vec::new()))),
-            std::sync::Arc::new(std::sync::Mutex::new(crate::sound::ym2612::Ym2612::new())),
-            std::sync::Arc::new(std::sync::Mutex::new(crate::sound::psg::Psg::new())),
+            std::sync::Arc::new(std::sync::Mutex::new(crate::cpu::z80::Z80::new(sound.clone()))),
+            std::sync::Arc::new(std::sync::Mutex::new(crate::vdp::Vdp::new(false))),
+            sound,
+            crate::memory::ram::Ram::new(0x10000),
+            std::sync::Arc::new(std::sync::Mutex::new(crate::memory::mapper::Mapper::new(
+                crate::memory::rom::Rom::new(vec![0; 0x10000]),
+                crate::memory::mapper::MapperType::Standard,
+            ))),
         );
         
         // Setup DMA
         dma.mode = DmaMode::MemoryToVdp;
-        dma.source_addr = 0x1000;
+        dma.source_addr = 0xFF1000;
         dma.dest_addr = 0x0000;  // VRAM
         dma.length = 4;
         dma.words_remaining = 4;
         dma.active = true;
         
         // Write test data to bus memory
-        bus.write16(0x1000, 0x1234);
-        bus.write16(0x1002, 0x5678);
-        bus.write16(0x1004, 0x9ABC);
-        bus.write16(0x1006, 0xDEF0);
+        bus.write16(0xFF1000, 0x1234);
+        bus.write16(0xFF1002, 0x5678);
+        bus.write16(0xFF1004, 0x9ABC);
+        bus.write16(0xFF1006, 0xDEF0);
         
         // Executar transferências
         for _ in 0..4 {
@@ -7,14 +7,17 @@
 //! - Controlador de interrupções
 //! - Planos (A, B, Window)
 //! - Sprites
-//! - Modos de vídeo
+//! - Modos de vídeo (incluindo o modo 4 do Master System)
 //! - Renderizador
 //! - Framebuffer
 
+pub mod color;
 pub mod cram;
+pub mod deinterlace;
 pub mod dma;
 pub mod framebuffer;
 pub mod interrupts;
+pub mod mode4;
 pub mod video_modes;
 pub mod planes;
 pub mod registers;
@@ -24,10 +27,13 @@ pub mod vram;
 pub mod vsram;
 
 // Re-export de tipos importantes para uso externo
+pub use color::{ColorCurve, ColorLut, Intensity};
 pub use cram::Cram;
+pub use deinterlace::{DeinterlaceMode, Deinterlacer};
 pub use dma::{VdpDma, DmaMode};
 pub use framebuffer::FrameBuffer;
 pub use interrupts::{VdpInterruptController, VdpInterruptType, VdpStatus};
+pub use mode4::Mode4Vdp;
 pub use video_modes::{VdpVideoMode, VdpResolution, VdpRenderMode};
 pub use planes::{Plane, PlaneType, PlaneManager, TileEntry};
 pub use registers::VdpRegisters;
@@ -158,12 +164,23 @@ impl Vdp {
         // Atualizar contadores de linha/quadro
         self.update_counters();
         
-        // Verificar se é hora de renderizar uma linha
-        if self.interrupts.current_hpos() == 0 {
+        // Posição do feixe para marcar escritas na CRAM
+        let hpos = self.interrupts.current_hpos();
+        self.cram.set_beam_position(Some(hpos));
+        
+        // Fim da parte ativa: desenhar a linha e começar a registrar as
+        // escritas na CRAM da próxima
+        if hpos == self.active_width() {
             self.render_scanline();
+            self.cram.begin_line();
         }
     }
     
+    /// Largura da parte ativa da linha em pixels (H40 ou H32)
+    fn active_width(&self) -> u16 {
+        if self.regs.get(0x0C) & 0x01 != 0 { 320 } else { 256 }
+    }
+    
     /// Atualiza contadores de linha e quadro
     fn update_counters(&mut self) {
         // Verificar VBlank
@@ -191,26 +208,18 @@ impl Vdp {
         // Incrementar contador de frames
         self.frame_counter += 1;
         
-        // Carregar sprites para o próximo quadro
+        // Tabela de sprites do quadro (debug); colisão e excesso de sprites
+        // são detectados linha a linha em `render_scanline`
         self.sprite_table.load_from_vram(&self.vram);
         
-        // Calcular sprites ativos por linha (para detecção de overflow)
-        self.sprite_table.calculate_active_sprites(self.video_mode.visible_height);
-        
-        // Detectar colisões de sprites
-        self.sprite_table.detect_collisions(
-            self.video_mode.visible_width as usize,
-            self.video_mode.visible_height as usize,
-        );
-        
-        // Sinalizar interrupção de colisão/overflow se necessário
-        if self.sprite_table.collision_detected {
-            self.interrupts.signal_sprite_collision();
-        }
+        // O quadro montado linha a linha está completo
+        let timestamp = self.cycles_elapsed as f64 / 7_670_000.0; // Clock do VDP
+        self.renderer.finish_frame(&self.regs, &self.sprite_table, timestamp);
         
-        if self.sprite_table.overflow_line.is_some() {
-            self.interrupts.signal_sprite_overflow(0); // Linha será atualizada
-        }
+        // Entrelaçado: o próximo quadro é o outro campo
+        let interlaced = (self.regs.get(0x0C) >> 1) & 0x03 != 0;
+        let odd = interlaced && !self.regs.odd_frame();
+        self.regs.set_odd_frame(odd);
     }
     
     /// Chamado no final do VBlank
@@ -223,20 +232,29 @@ impl Vdp {
         // Processamento específico do HBlank
     }
     
-    /// Renderiza a linha atual do scanline
+    /// Renderiza a linha atual. Chamado ao fim da parte ativa, antes da
+    /// interrupção H ser atendida: a linha usa VRAM, VSRAM e registradores
+    /// como estão neste instante, e as escritas na CRAM feitas durante ela
+    /// entram na posição X em que caíram.
     fn render_scanline(&mut self) {
-        // Atualizar planos com scroll atual
-        if let Some(plane_manager) = &mut self.plane_manager {
-            plane_manager.update(&self.regs, &self.video_mode);
+        if !self.display_enabled {
+            return;
         }
         
-        // Renderizar linha atual se display estiver habilitado
-        if self.display_enabled && self.regs.display_enabled() {
-            let current_line = self.interrupts.current_scanline();
-            
-            // Em um renderizador completo, renderizaríamos a linha aqui
-            // Para simplificar, apenas marcamos que o framebuffer está sujo
-            self.renderer.frame_buffer.mark_clean(); // Remover após implementação completa
+        let line = self.interrupts.current_scanline();
+        let status = self.renderer.render_line(
+            line as usize,
+            &self.regs,
+            &self.cram,
+            &self.vram,
+            &self.vsram,
+        );
+        
+        if status.contains(VdpStatus::SPRITE_COLLISION) {
+            self.interrupts.signal_sprite_collision();
+        }
+        if status.contains(VdpStatus::SPRITE_OVERFLOW) {
+            self.interrupts.signal_sprite_overflow(line);
         }
     }
     
@@ -253,8 +271,9 @@ impl Vdp {
             0x02 => {  // Porta de dados (0xC00002) - byte alto
                 (self.regs.read_data_port() >> 8) as u8
             }
-            0x04 => {  // Porta de controle (0xC00004) - leitura de status
-                self.regs.read_control_port()
+            0x04 => {  // Porta de controle (0xC00004) - status: byte alto no endereço par, baixo no ímpar
+                let status = self.regs.read_control_port_word();
+                if addr & 1 == 0 { (status >> 8) as u8 } else { status as u8 }
             }
             0x06 => {  // Porta de controle (0xC00006) - sempre retorna 0
                 0
@@ -293,7 +312,27 @@ impl Vdp {
         // Processar escrita se necessário
         self.process_pending_write();
     }
-    
+
+    /// Leitura de palavra pelo 68K (acesso nativo de 16 bits às portas)
+    pub fn bus_read16(&mut self, addr: u32) -> u16 {
+        match addr & 0x1F {
+            0x00..=0x03 => self.regs.read_data_port(),
+            0x04..=0x07 => self.regs.read_control_port_word(),
+            _ => 0,
+        }
+    }
+
+    /// Escrita de palavra pelo 68K (acesso nativo de 16 bits às portas)
+    pub fn bus_write16(&mut self, addr: u32, value: u16) {
+        match addr & 0x1F {
+            0x00..=0x03 => self.regs.write_data_port(value),
+            0x04..=0x07 => self.regs.write_control_port(value),
+            _ => {}
+        }
+
+        self.process_pending_write();
+    }
+
     /// Processa escrita pendente após configuração de endereço
     fn process_pending_write(&mut self) {
         // Se temos um endereço configurado e não estamos em modo de leitura
@@ -324,8 +363,14 @@ impl Vdp {
     // RENDERIZAÇÃO
     // =====================================================
     
-    /// Renderiza um quadro completo
+    /// Retorna o quadro atual. Com o VDP avançado por `tick` o quadro já foi
+    /// desenhado linha a linha; sem isso, desenha todas as linhas com o
+    /// estado atual.
     pub fn render_frame(&mut self) -> &FrameBuffer {
+        if self.frame_counter > 0 {
+            return self.renderer.presented_frame();
+        }
+        
         // Atualizar modo de vídeo
         self.update_video_mode();
         
@@ -348,12 +393,12 @@ impl Vdp {
     
     /// Retorna uma referência ao framebuffer atual
     pub fn get_framebuffer(&self) -> &FrameBuffer {
-        &self.renderer.frame_buffer
+        self.renderer.presented_frame()
     }
     
     /// Retorna uma cópia do framebuffer atual
     pub fn copy_framebuffer(&self) -> FrameBuffer {
-        self.renderer.frame_buffer.clone()
+        self.renderer.presented_frame().clone()
     }
     
     // =====================================================
@@ -469,6 +514,21 @@ impl Vdp {
         self.renderer.set_render_flags(flags);
     }
     
+    /// Seleciona o modo de desentrelaçamento da saída
+    pub fn set_deinterlace_mode(&mut self, mode: DeinterlaceMode) {
+        self.renderer.set_deinterlace_mode(mode);
+    }
+    
+    /// Seleciona a curva de saída de cor do renderer
+    pub fn set_color_curve(&mut self, curve: ColorCurve) {
+        self.renderer.set_color_curve(curve);
+    }
+    
+    /// Habilita/desabilita a emulação dos pontos de CRAM
+    pub fn set_cram_dots(&mut self, enabled: bool) {
+        self.renderer.set_cram_dots(enabled);
+    }
+    
     /// Habilita/desabilita renderização
     pub fn set_render_enabled(&mut self, enabled: bool) {
         self.renderer.set_render_enabled(enabled);
@@ -525,6 +585,18 @@ mod tests {
         assert!(!vdp.vblank_active);
     }
     
+    #[test]
+    fn test_status_byte_reads_split_the_word() {
+        let mut vdp = Vdp::new(false);
+        vdp.regs.set_odd_frame(true);
+        let word = vdp.bus_read16(0xC00004);
+        assert_ne!(word & registers::STATUS_ODD_FRAME, 0);
+
+        assert_eq!(vdp.bus_read(0xC00004), (word >> 8) as u8);
+        assert_eq!(vdp.bus_read(0xC00005), word as u8);
+        assert_eq!(vdp.bus_read(0xC00007), word as u8);
+    }
+    
     #[test]
     fn test_vdp_tick() {
         let mut vdp = Vdp::new(false);
@@ -539,6 +611,49 @@ mod tests {
         assert!(vdp.cycles_elapsed > 0);
     }
     
+    #[test]
+    fn test_vdp_scanline_keeps_mid_frame_changes() {
+        let mut vdp = Vdp::new(false);
+        vdp.initialize();
+        vdp.regs.set(1, 0x44); // display ligado, modo 5
+        vdp.cram.write(0, 0x000E);
+        let cycles_per_line = vdp.interrupts.cycles_per_line();
+        
+        // Linha 0 desenhada ao fim da sua parte ativa
+        for _ in 0..cycles_per_line {
+            vdp.tick();
+        }
+        // Troca da cor de fundo no início da linha 1
+        vdp.cram.write(0, 0x0E00);
+        for _ in 0..cycles_per_line + 100 {
+            vdp.tick();
+        }
+        // Troca no meio da linha 2, no pixel 100
+        vdp.cram.write(0, 0x00E0);
+        for _ in 0..cycles_per_line {
+            vdp.tick();
+        }
+        
+        let framebuffer = vdp.get_framebuffer();
+        let line0 = framebuffer.get_pixel(0, 0);
+        let line1 = framebuffer.get_pixel(0, 1);
+        assert!(line0.is_some());
+        assert_ne!(line0, line1);
+        assert_eq!(framebuffer.get_pixel(99, 2), line1);
+        assert_ne!(framebuffer.get_pixel(100, 2), line1);
+        
+        // Escrita em uma cor fora de uso no pixel 100 da linha 3: só aparece
+        // com os pontos de CRAM
+        vdp.set_cram_dots(true);
+        vdp.cram.write(5, 0x0EEE);
+        for _ in 0..cycles_per_line {
+            vdp.tick();
+        }
+        let framebuffer = vdp.get_framebuffer();
+        assert_ne!(framebuffer.get_pixel(100, 3), framebuffer.get_pixel(99, 3));
+        assert_eq!(framebuffer.get_pixel(101, 3), framebuffer.get_pixel(99, 3));
+    }
+    
     #[test]
     fn test_vdp_render_frame() {
         let mut vdp = Vdp::new(false);
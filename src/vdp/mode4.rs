@@ -0,0 +1,368 @@
+//! Modo 4 do VDP (compatibilidade com o Master System)
+//!
+//! Com o Power Base Converter o VDP do Mega Drive volta ao modo 4 do
+//! VDP do Master System (TMS9918 estendido):
+//! - 16 KB de VRAM, 32 bytes de CRAM (cores --BBGGRR) e 11 registradores;
+//! - porta de dados (0xBE) e de controle (0xBF) de 8 bits: a escrita de
+//!   controle tem dois bytes (endereço baixo, depois endereço alto + código);
+//! - contadores V/H legíveis pela CPU (0x7E/0x7F);
+//! - interrupção de quadro (registrador 1, bit 5) e de linha (registrador 0,
+//!   bit 4, com o contador recarregado do registrador 10).
+//!
+//! Aqui fica só o estado das portas e da temporização; a composição da
+//! linha (tiles e sprites) é feita em `VdpRenderer::render_mode4_line`.
+
+use crate::vdp::vram::Vram;
+
+/// Resolução do modo 4 (192 linhas ativas)
+pub const MODE4_WIDTH: usize = 256;
+pub const MODE4_HEIGHT: usize = 192;
+/// Tamanho da VRAM do modo 4
+pub const MODE4_VRAM_SIZE: usize = 0x4000;
+/// Entradas da CRAM (16 cores de fundo + 16 de sprites)
+pub const MODE4_CRAM_SIZE: usize = 32;
+
+// Bits do registrador de status
+pub const STATUS_FRAME_IRQ: u8 = 0x80;
+pub const STATUS_OVERFLOW: u8 = 0x40;
+pub const STATUS_COLLISION: u8 = 0x20;
+
+/// Código da última escrita de controle (bits 6–7 do segundo byte)
+const CODE_VRAM_READ: u8 = 0;
+const CODE_REGISTER: u8 = 2;
+const CODE_CRAM_WRITE: u8 = 3;
+
+/// Estado do VDP em modo 4
+#[derive(Clone)]
+pub struct Mode4Vdp {
+    pub vram: Vram,
+    pub cram: [u8; MODE4_CRAM_SIZE],
+    pub regs: [u8; 11],
+    pub status: u8,
+    /// Linha atual (0 até linhas por quadro - 1)
+    pub line: u16,
+    /// Rolagem vertical, travada no início de cada quadro
+    pub vscroll: u8,
+    pub is_pal: bool,
+    addr: u16,
+    code: u8,
+    /// Primeiro byte de uma escrita de controle pendente
+    latch: Option<u8>,
+    /// Buffer de leitura antecipada da VRAM
+    buffer: u8,
+    line_counter: u8,
+    line_irq: bool,
+}
+
+impl Mode4Vdp {
+    pub fn new(is_pal: bool) -> Self {
+        Self {
+            vram: Vram::with_size(MODE4_VRAM_SIZE),
+            cram: [0; MODE4_CRAM_SIZE],
+            regs: [0; 11],
+            status: 0,
+            line: 0,
+            vscroll: 0,
+            is_pal,
+            addr: 0,
+            code: 0,
+            latch: None,
+            buffer: 0,
+            line_counter: 0,
+            line_irq: false,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new(self.is_pal);
+    }
+
+    /// Linhas por quadro (262 NTSC, 313 PAL)
+    pub fn lines_per_frame(&self) -> u16 {
+        if self.is_pal {
+            313
+        } else {
+            262
+        }
+    }
+
+    // =====================================================
+    // PORTAS
+    // =====================================================
+
+    /// Leitura da porta de dados (0xBE): devolve o buffer e antecipa o
+    /// próximo byte da VRAM
+    pub fn read_data(&mut self) -> u8 {
+        self.latch = None;
+        let value = self.buffer;
+        self.buffer = self.vram.read8(self.addr as u32);
+        self.addr = (self.addr + 1) & 0x3FFF;
+        value
+    }
+
+    /// Escrita na porta de dados (0xBE): VRAM ou CRAM, conforme o código
+    pub fn write_data(&mut self, value: u8) {
+        self.latch = None;
+        if self.code == CODE_CRAM_WRITE {
+            self.cram[self.addr as usize % MODE4_CRAM_SIZE] = value & 0x3F;
+        } else {
+            self.vram.write8(self.addr as u32, value);
+        }
+        self.buffer = value;
+        self.addr = (self.addr + 1) & 0x3FFF;
+    }
+
+    /// Leitura da porta de controle (0xBF): status. Limpa os flags, a
+    /// interrupção de linha e a escrita de controle pela metade.
+    pub fn read_control(&mut self) -> u8 {
+        let value = self.status | 0x1F;
+        self.status = 0;
+        self.line_irq = false;
+        self.latch = None;
+        value
+    }
+
+    /// Escrita na porta de controle (0xBF)
+    pub fn write_control(&mut self, value: u8) {
+        let Some(low) = self.latch.take() else {
+            self.latch = Some(value);
+            self.addr = (self.addr & 0x3F00) | value as u16;
+            return;
+        };
+        self.code = value >> 6;
+        self.addr = (((value & 0x3F) as u16) << 8) | low as u16;
+        match self.code {
+            CODE_VRAM_READ => {
+                self.buffer = self.vram.read8(self.addr as u32);
+                self.addr = (self.addr + 1) & 0x3FFF;
+            }
+            CODE_REGISTER => {
+                if let Some(reg) = self.regs.get_mut((value & 0x0F) as usize) {
+                    *reg = low;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Contador vertical (0x7E). As linhas depois da área ativa saltam para
+    /// trás (0xDA → 0xD5 em NTSC, 0xF2 → 0xBA em PAL).
+    pub fn v_counter(&self) -> u8 {
+        match (self.is_pal, self.line) {
+            (false, line @ 0..=0xDA) | (true, line @ 0..=0xF2) => line as u8,
+            (false, line) => (line - 6) as u8,
+            (true, line) => (line - 57) as u8,
+        }
+    }
+
+    /// Contador horizontal (0x7F) a partir dos ciclos da CPU na linha
+    /// (228 por linha, 342 pixels contados de dois em dois)
+    pub fn h_counter(&self, line_cycles: u32) -> u8 {
+        ((line_cycles.min(227) * 342 / 228) / 2) as u8
+    }
+
+    // =====================================================
+    // INTERRUPÇÕES E TEMPORIZAÇÃO
+    // =====================================================
+
+    /// Linha /INT da CPU
+    pub fn irq(&self) -> bool {
+        let frame = self.status & STATUS_FRAME_IRQ != 0 && self.regs[1] & 0x20 != 0;
+        let line = self.line_irq && self.regs[0] & 0x10 != 0;
+        frame || line
+    }
+
+    /// Fim da linha atual: contador de linha, flag de quadro no início da
+    /// linha 192 e rolagem vertical travada no início do quadro
+    pub fn end_line(&mut self) {
+        if self.line <= MODE4_HEIGHT as u16 {
+            match self.line_counter.checked_sub(1) {
+                Some(counter) => self.line_counter = counter,
+                None => {
+                    self.line_counter = self.regs[10];
+                    self.line_irq = true;
+                }
+            }
+        } else {
+            self.line_counter = self.regs[10];
+        }
+
+        self.line += 1;
+        if self.line == MODE4_HEIGHT as u16 {
+            self.status |= STATUS_FRAME_IRQ;
+        }
+        if self.line == self.lines_per_frame() {
+            self.line = 0;
+            self.vscroll = self.regs[9];
+        }
+    }
+
+    // =====================================================
+    // REGISTRADORES
+    // =====================================================
+
+    pub fn display_enabled(&self) -> bool {
+        self.regs[1] & 0x40 != 0
+    }
+
+    /// Sprites de 8x16 (dois padrões consecutivos)
+    pub fn tall_sprites(&self) -> bool {
+        self.regs[1] & 0x02 != 0
+    }
+
+    /// Sprites com pixels dobrados
+    pub fn zoomed_sprites(&self) -> bool {
+        self.regs[1] & 0x01 != 0
+    }
+
+    /// Sprites deslocados 8 pixels para a esquerda
+    pub fn shift_sprites(&self) -> bool {
+        self.regs[0] & 0x08 != 0
+    }
+
+    /// Primeira coluna de 8 pixels pintada com a cor de fundo
+    pub fn mask_left_column(&self) -> bool {
+        self.regs[0] & 0x20 != 0
+    }
+
+    /// Sem rolagem horizontal nas duas primeiras linhas de tiles
+    pub fn lock_top_rows(&self) -> bool {
+        self.regs[0] & 0x40 != 0
+    }
+
+    /// Sem rolagem vertical nas oito colunas da direita
+    pub fn lock_right_columns(&self) -> bool {
+        self.regs[0] & 0x80 != 0
+    }
+
+    pub fn hscroll(&self) -> u8 {
+        self.regs[8]
+    }
+
+    pub fn name_table_base(&self) -> usize {
+        ((self.regs[2] & 0x0E) as usize) << 10
+    }
+
+    pub fn sprite_table_base(&self) -> usize {
+        ((self.regs[5] & 0x7E) as usize) << 7
+    }
+
+    /// Padrões dos sprites na segunda metade da VRAM (índice + 256)
+    pub fn sprite_pattern_offset(&self) -> usize {
+        if self.regs[6] & 0x04 != 0 {
+            0x100
+        } else {
+            0
+        }
+    }
+
+    /// Índice na CRAM da cor de fundo (paleta dos sprites)
+    pub fn backdrop_index(&self) -> usize {
+        16 + (self.regs[7] & 0x0F) as usize
+    }
+
+    // =====================================================
+    // PADRÕES E CORES
+    // =====================================================
+
+    /// Índice de cor (0–15) de um pixel de padrão: 4 planos de bits, 4
+    /// bytes por linha. `row` pode passar de 7 para ler o padrão seguinte
+    /// (sprites de 8x16).
+    pub fn pattern_pixel(&self, pattern: usize, row: usize, col: usize) -> u8 {
+        let base = (pattern * 32 + row * 4) % MODE4_VRAM_SIZE;
+        let bit = 7 - col;
+        (0..4).fold(0, |color, plane| {
+            color | (((self.vram.data[base + plane] >> bit) & 1) << plane)
+        })
+    }
+
+    /// Cor da CRAM em ARGB (2 bits por canal)
+    pub fn color(&self, index: usize) -> u32 {
+        let value = self.cram[index % MODE4_CRAM_SIZE] as u32;
+        let r = (value & 0x03) * 85;
+        let g = ((value >> 2) & 0x03) * 85;
+        let b = ((value >> 4) & 0x03) * 85;
+        0xFF000000 | (r << 16) | (g << 8) | b
+    }
+}
+
+impl Default for Mode4Vdp {
+    fn default() -> Self {
+        Self::new(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_control_port_registers_and_vram() {
+        let mut vdp = Mode4Vdp::new(false);
+        // Registrador 1 = 0x60
+        vdp.write_control(0x60);
+        vdp.write_control(0x81);
+        assert_eq!(vdp.regs[1], 0x60);
+        assert!(vdp.display_enabled());
+
+        // Escrita na VRAM em 0x3FFF com incremento circular
+        vdp.write_control(0xFF);
+        vdp.write_control(0x7F);
+        vdp.write_data(0xAB);
+        vdp.write_data(0xCD);
+        assert_eq!(vdp.vram.data[0x3FFF], 0xAB);
+        assert_eq!(vdp.vram.data[0x0000], 0xCD);
+
+        // Leitura com buffer antecipado
+        vdp.write_control(0xFF);
+        vdp.write_control(0x3F);
+        assert_eq!(vdp.read_data(), 0xAB);
+        assert_eq!(vdp.read_data(), 0xCD);
+
+        // CRAM
+        vdp.write_control(0x11);
+        vdp.write_control(0xC0);
+        vdp.write_data(0x3F);
+        assert_eq!(vdp.color(0x11), 0xFFFFFFFF);
+    }
+
+    #[test]
+    fn test_frame_and_line_interrupts() {
+        let mut vdp = Mode4Vdp::new(false);
+        vdp.regs[0] = 0x10;
+        vdp.regs[1] = 0x20;
+        vdp.regs[10] = 9;
+        vdp.line = 200;
+        vdp.end_line();
+        vdp.line = 0;
+
+        // O contador recarregado com 9 dispara depois de 10 linhas
+        for _ in 0..9 {
+            vdp.end_line();
+        }
+        assert!(!vdp.irq());
+        vdp.end_line();
+        assert!(vdp.irq());
+        vdp.read_control();
+        assert!(!vdp.irq());
+
+        while vdp.line != MODE4_HEIGHT as u16 - 1 {
+            vdp.end_line();
+            vdp.read_control();
+        }
+        vdp.end_line();
+        assert!(vdp.irq());
+        assert_eq!(vdp.read_control() & STATUS_FRAME_IRQ, STATUS_FRAME_IRQ);
+    }
+
+    #[test]
+    fn test_v_counter_jumps_back() {
+        let mut vdp = Mode4Vdp::new(false);
+        vdp.line = 0xDA;
+        assert_eq!(vdp.v_counter(), 0xDA);
+        vdp.line = 0xDB;
+        assert_eq!(vdp.v_counter(), 0xD5);
+        vdp.line = 261;
+        assert_eq!(vdp.v_counter(), 0xFF);
+    }
+}
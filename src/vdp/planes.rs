@@ -13,6 +13,7 @@
 //! Bits 10-0: Índice do tile (0-2047)
 
 use crate::vdp::{
+    color::ColorLut,
     cram::Cram,
     framebuffer::FrameBuffer,
     video_modes::{VdpVideoMode, VdpRenderMode},
@@ -222,6 +223,7 @@ impl Plane {
         framebuffer: &mut FrameBuffer,
         vram: &Vram,
         cram: &Cram,
+        lut: &ColorLut,
         vsram: &Vsram,
         regs: &VdpRegisters,
         mode: &VdpVideoMode,
@@ -298,6 +300,7 @@ impl Plane {
                     let palette_offset = (tile_entry.palette as usize) * 16;
                     let color = self.get_final_color(
                         cram, 
+                        lut,
                         color_index as usize + palette_offset,
                         mode,
                         regs,
@@ -364,22 +367,13 @@ impl Plane {
     fn get_final_color(
         &self,
         cram: &Cram,
+        lut: &ColorLut,
         color_index: usize,
         mode: &VdpVideoMode,
         regs: &VdpRegisters,
     ) -> u32 {
-        let color_9bit = cram.read(color_index % 64);
-        
-        // Extrair componentes RGB (cada um 3 bits: 0-7)
-        let r = ((color_9bit >> 0) & 0x07) as u32;
-        let g = ((color_9bit >> 4) & 0x07) as u32;
-        let b = ((color_9bit >> 8) & 0x07) as u32;
-        
-        // Converter para 8 bits por canal (0-255)
-        // Fórmula: valor * 255 / 7, mas otimizado
-        let r8 = (r * 36) as u32;  // 36 ≈ 255/7
-        let g8 = (g * 36) as u32;
-        let b8 = (b * 36) as u32;
+        // Converter para RGB pela tabela de cores
+        let rgb = cram.to_rgb888(color_index, lut) & 0x00FFFFFF;
         
         // Alpha: transparente se índice 0, senão opaco
         let alpha = if color_index == 0 { 0x00 } else { 0xFF };
@@ -387,7 +381,7 @@ impl Plane {
         // Prioridade no alpha channel (bits 28-31)
         let priority_alpha = (self.priority as u32) << 28;
         
-        (alpha << 24) | (r8 << 16) | (g8 << 8) | b8 | priority_alpha
+        (alpha << 24) | rgb | priority_alpha
     }
 
     /// Renderiza apenas uma região do plano (para otimização)
@@ -492,20 +486,14 @@ impl PlaneManager {
         framebuffer: &mut FrameBuffer,
         vram: &Vram,
         cram: &Cram,
+        lut: &ColorLut,
         vsram: &Vsram,
         regs: &VdpRegisters,
         mode: &VdpVideoMode,
     ) {
         // Primeiro limpar o framebuffer com a cor de fundo
         let bg_color_index = regs.get_background_color() as usize;
-        let bg_color_9bit = cram.read(bg_color_index % 64);
-        let (r, g, b) = {
-            let r = ((bg_color_9bit >> 0) & 0x07) as u32 * 36;
-            let g = ((bg_color_9bit >> 4) & 0x07) as u32 * 36;
-            let b = ((bg_color_9bit >> 8) & 0x07) as u32 * 36;
-            (r, g, b)
-        };
-        let bg_color = 0xFF000000 | (r << 16) | (g << 8) | b;
+        let bg_color = cram.to_rgb888(bg_color_index, lut);
         framebuffer.clear(bg_color);
 
         // Renderizar planos na ordem especificada
@@ -518,10 +506,10 @@ impl PlaneManager {
             };
 
             // Renderizar tiles sem prioridade primeiro
-            plane.render(framebuffer, vram, cram, vsram, regs, mode, false);
+            plane.render(framebuffer, vram, cram, lut, vsram, regs, mode, false);
             
             // Renderizar tiles com prioridade depois
-            plane.render(framebuffer, vram, cram, vsram, regs, mode, true);
+            plane.render(framebuffer, vram, cram, lut, vsram, regs, mode, true);
         }
     }
 
@@ -33,6 +33,17 @@ bitflags! {
     }
 }
 
+// Bits da palavra de status de 16 bits, no layout do hardware
+pub const STATUS_FIFO_EMPTY: u16 = 0x0200;
+pub const STATUS_FIFO_FULL: u16 = 0x0100;
+pub const STATUS_SPRITE_OVERFLOW: u16 = 0x0040;
+pub const STATUS_SPRITE_COLLISION: u16 = 0x0020;
+/// Campo ímpar do quadro entrelaçado
+pub const STATUS_ODD_FRAME: u16 = 0x0010;
+pub const STATUS_VBLANK: u16 = 0x0008;
+pub const STATUS_HBLANK: u16 = 0x0004;
+pub const STATUS_DMA_ACTIVE: u16 = 0x0002;
+
 /// Modo de DMA do VDP
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum DmaMode {
@@ -105,6 +116,7 @@ pub struct VdpRegisters {
     pub mode_40_cell: bool,     // H40 (320 pixels) vs H32 (256 pixels)
     pub interlace_mode: u8,     // 0=off, 1=interlace, 2=interlace+30Hz
     pub shadow_highlight: bool, // Modo shadow/highlight (12-bit)
+    pub odd_frame: bool,        // Campo ímpar do quadro entrelaçado
 
     // Debug
     pub write_count: u64,
@@ -144,6 +156,7 @@ impl VdpRegisters {
             mode_40_cell: false,
             interlace_mode: 0,
             shadow_highlight: false,
+            odd_frame: false,
             write_count: 0,
             read_count: 0,
         };
@@ -391,6 +404,30 @@ impl VdpRegisters {
         status
     }
 
+    /// Lê a palavra de status de 16 bits no layout do hardware
+    /// (FIFO vazio/cheio, overflow, colisão, campo ímpar, VBlank, HBlank, DMA)
+    pub fn read_control_port_word(&mut self) -> u16 {
+        let status = VdpStatus::from_bits_truncate(self.read_control_port());
+        let bits = [
+            (VdpStatus::FIFO_EMPTY, STATUS_FIFO_EMPTY),
+            (VdpStatus::FIFO_FULL, STATUS_FIFO_FULL),
+            (VdpStatus::SPRITE_OVERFLOW, STATUS_SPRITE_OVERFLOW),
+            (VdpStatus::SPRITE_COLLISION, STATUS_SPRITE_COLLISION),
+            (VdpStatus::VBLANK, STATUS_VBLANK),
+            (VdpStatus::HBLANK, STATUS_HBLANK),
+            (VdpStatus::DMA_ACTIVE, STATUS_DMA_ACTIVE),
+        ];
+        let word = bits
+            .iter()
+            .filter(|(flag, _)| status.contains(*flag))
+            .fold(0, |word, (_, bit)| word | bit);
+        if self.odd_frame {
+            word | STATUS_ODD_FRAME
+        } else {
+            word
+        }
+    }
+
     /// Processa palavra de controle completa
     fn process_control_word(&mut self) {
         let code = self.code_buffer;
@@ -682,6 +719,16 @@ impl VdpRegisters {
         }
     }
 
+    /// Define o campo atual do quadro entrelaçado
+    pub fn set_odd_frame(&mut self, odd: bool) {
+        self.odd_frame = odd;
+    }
+
+    /// Retorna true se o campo atual é o ímpar
+    pub fn odd_frame(&self) -> bool {
+        self.odd_frame
+    }
+
     /// Atualiza flags de sprite
     pub fn update_sprite_flags(&mut self, overflow: bool, collision: bool) {
         if overflow {
@@ -723,6 +770,7 @@ impl VdpRegisters {
         self.mode_40_cell = false;
         self.interlace_mode = 0;
         self.shadow_highlight = false;
+        self.odd_frame = false;
         self.write_count = 0;
         self.read_count = 0;
 
@@ -982,11 +1030,32 @@ mod tests {
         // Verificar que flags foram limpas após leitura
         assert!(!regs.status.contains(VdpStatus::VBLANK));
         assert!(!regs.status.contains(VdpStatus::LINE_IRQ));
+
         // Flags de sprite não são limpas
         assert!(regs.status.contains(VdpStatus::SPRITE_OVERFLOW));
         assert!(regs.status.contains(VdpStatus::SPRITE_COLLISION));
     }
 
+    #[test]
+    fn test_status_word_hardware_layout() {
+        let mut regs = VdpRegisters::new();
+        regs.update_interrupt_flags(true, true, false);
+        regs.update_sprite_flags(true, false);
+        regs.set_odd_frame(true);
+
+        let word = regs.read_control_port_word();
+        assert_eq!(word & STATUS_ODD_FRAME, 0x0010);
+        assert_eq!(word & STATUS_VBLANK, STATUS_VBLANK);
+        assert_eq!(word & STATUS_HBLANK, STATUS_HBLANK);
+        assert_eq!(word & STATUS_SPRITE_OVERFLOW, STATUS_SPRITE_OVERFLOW);
+        assert_eq!(word & (STATUS_SPRITE_COLLISION | STATUS_FIFO_FULL), 0);
+
+        // VBlank/HBlank limpos pela leitura; campo ímpar continua
+        regs.set_odd_frame(false);
+        let word = regs.read_control_port_word();
+        assert_eq!(word & (STATUS_VBLANK | STATUS_HBLANK | STATUS_ODD_FRAME), 0);
+    }
+
     #[test]
     fn test_reset() {
         let mut regs = VdpRegisters::new();
@@ -1,22 +1,43 @@
 //! Renderer principal do VDP
 //!
-//! Responsável por coordenar a renderização completa de um frame:
+//! O modo 5 é desenhado linha a linha por `render_line`, chamado pelo VDP
+//! ao fim da parte ativa de cada linha com o estado daquele instante (VRAM,
+//! VSRAM e registradores). Assim trocas de paleta, de scroll e de
+//! registradores feitas no meio do quadro aparecem na tela. Escritas na
+//! CRAM durante a linha entram a partir do pixel em que caíram, com os
+//! "pontos de CRAM" opcionais (`cram_dots`).
+//!
+//! Camadas de uma linha:
 //! 1. Plano B (background)
-//! 2. Plano A (foreground)
-//! 3. Plano Window (sobreposição)
-//! 4. Sprites (com prioridade dupla)
+//! 2. Plano A (foreground), substituído pelo plano Window onde ativo
+//! 3. Sprites
+//!
+//! Ordem de composição, da frente para trás:
+//! 1. Sprites, plano A e plano B com prioridade
+//! 2. Sprites, plano A e plano B sem prioridade
+//! 3. Cor de fundo (registrador 7)
+//!
+//! Com o modo shadow/highlight (registrador 12, bit 3) cada pixel recebe
+//! também uma intensidade (sombra, normal ou realce), definida pelas
+//! prioridades e pelos sprites operadores da paleta 3.
+//!
+//! No interlace modo 2 os tiles têm 8x16 pixels e o quadro de saída tem o
+//! dobro de linhas: cada campo (par ou ímpar, conforme o status) preenche
+//! as linhas correspondentes. Ao fechar o quadro, os modos entrelaçados
+//! passam pelo desentrelaçamento (`deinterlace`), e `presented_frame`
+//! retorna a imagem a exibir.
 //!
-//! Ordem de renderização do VDP:
-//! 1. Planos (tiles sem prioridade)
-//! 2. Sprites (sem prioridade)
-//! 3. Planos (tiles com prioridade)
-//! 4. Sprites (com prioridade)
+//! No modo 4 (Master System) a composição é feita linha a linha por
+//! `render_mode4_line`: um único plano de fundo e até 8 sprites por linha.
 
 use crate::vdp::{
+    color::{ColorCurve, ColorLut, Intensity},
     cram::Cram,
+    deinterlace::{DeinterlaceMode, Deinterlacer},
     framebuffer::FrameBuffer,
+    interrupts::VdpStatus,
+    mode4::{self, Mode4Vdp, MODE4_HEIGHT, MODE4_WIDTH},
     video_modes::{VdpVideoMode, VdpRenderMode},
-    planes::{Plane, PlaneManager, PlaneType},
     registers::VdpRegisters,
     sprite::SpriteTable,
     vram::Vram,
@@ -36,6 +57,15 @@ pub struct VdpRenderer {
     pub show_window: bool,
     pub show_sprites: bool,
     pub debug_overlay: bool,
+    /// Emula os pontos de CRAM: escrita durante a linha ativa aparece
+    /// como um pixel com a cor escrita
+    pub cram_dots: bool,
+    /// Tabela de conversão de cores da curva selecionada
+    pub color_lut: ColorLut,
+    /// Pós-processamento dos modos entrelaçados
+    pub deinterlacer: Deinterlacer,
+    /// Último quadro fechado estava em modo entrelaçado
+    pub interlaced: bool,
 }
 
 impl VdpRenderer {
@@ -55,10 +85,16 @@ impl VdpRenderer {
             show_window: true,
             show_sprites: true,
             debug_overlay: false,
+            cram_dots: false,
+            color_lut: ColorLut::default(),
+            deinterlacer: Deinterlacer::default(),
+            interlaced: false,
         }
     }
     
-    /// Renderiza um frame completo
+    /// Renderiza um frame completo com o estado atual, linha a linha.
+    /// Usado quando o VDP não está sendo avançado por `tick`; nesse caso
+    /// não há efeitos de raster, pois todas as linhas veem o mesmo estado.
     pub fn render_frame(
         &mut self,
         regs: &VdpRegisters,
@@ -68,266 +104,312 @@ impl VdpRenderer {
         sprite_table: &SpriteTable,
         timestamp: f64,
     ) -> &FrameBuffer {
-        if !self.render_enabled || !self.video_mode.display_enabled() {
+        if !self.render_enabled {
             return &self.frame_buffer;
         }
         
         // Atualizar modo de vídeo baseado nos registradores
         self.update_video_mode(regs);
         
-        // Renderizar o frame
-        self.render_internal(regs, cram, vram, vsram, sprite_table);
-        
-        // Aplicar efeitos pós-processamento
-        self.apply_post_processing(regs);
-        
-        // Atualizar estatísticas
-        self.last_frame_time = timestamp;
-        self.frames_rendered += 1;
+        let height = LineRegisters::new(regs).height;
+        for line in 0..height {
+            self.render_line(line, regs, cram, vram, vsram);
+        }
         
-        &self.frame_buffer
+        self.finish_frame(regs, sprite_table, timestamp);
+        self.presented_frame()
     }
     
-    /// Renderiza internamente o frame (sem pós-processamento)
-    fn render_internal(
-        &mut self,
-        regs: &VdpRegisters,
-        cram: &Cram,
-        vram: &Vram,
-        vsram: &Vsram,
-        sprite_table: &SpriteTable,
-    ) {
-        let (width, height) = self.video_mode.resolution();
-        let screen_width = width as usize;
-        let screen_height = height as usize;
-        
-        // Verificar se o framebuffer tem o tamanho correto
-        if self.frame_buffer.width != screen_width || self.frame_buffer.height != screen_height {
-            self.frame_buffer.resize(screen_width, screen_height);
-        }
-        
-        // Obter cor de fundo do registrador
-        let bg_color_index = regs.get_background_color() as usize;
-        let bg_color = self.get_color_from_cram(cram, bg_color_index);
-        
-        // Limpar com cor de fundo se habilitado
-        if self.show_background {
-            self.frame_buffer.clear(bg_color);
+    /// Quadro a exibir: a saída do desentrelaçamento nos modos
+    /// entrelaçados, ou o próprio framebuffer
+    pub fn presented_frame(&self) -> &FrameBuffer {
+        if self.interlaced {
+            self.deinterlacer.output()
         } else {
-            self.frame_buffer.clear(0x00000000); // Transparente
-        }
-        
-        // Criar gerenciador de planos
-        let mut plane_manager = PlaneManager::new(regs, &self.video_mode);
-        plane_manager.update(regs, &self.video_mode);
-        
-        // 1. Renderizar tiles sem prioridade de todos os planos
-        if self.show_planes || self.show_window {
-            self.render_low_priority_tiles(&plane_manager, regs, cram, vram, vsram);
+            &self.frame_buffer
         }
-        
-        // 2. Renderizar sprites sem prioridade
-        if self.show_sprites {
-            self.render_low_priority_sprites(sprite_table, vram, cram);
-        }
-        
-        // 3. Renderizar tiles com prioridade de todos os planos
-        if self.show_planes || self.show_window {
-            self.render_high_priority_tiles(&plane_manager, regs, cram, vram, vsram);
+    }
+    
+    /// Seleciona o modo de desentrelaçamento
+    pub fn set_deinterlace_mode(&mut self, mode: DeinterlaceMode) {
+        self.deinterlacer.set_mode(mode);
+    }
+    
+    /// Fecha o quadro montado linha a linha: overlay de debug e estatísticas
+    pub fn finish_frame(&mut self, regs: &VdpRegisters, sprite_table: &SpriteTable, timestamp: f64) {
+        if self.debug_overlay {
+            self.render_debug_overlay(regs, sprite_table);
         }
         
-        // 4. Renderizar sprites com prioridade
-        if self.show_sprites {
-            self.render_high_priority_sprites(sprite_table, vram, cram);
+        // Modos entrelaçados passam pelo desentrelaçamento
+        let interlace_mode = (regs.get(0x0C) >> 1) & 0x03;
+        self.interlaced = interlace_mode != 0;
+        if self.interlaced {
+            self.deinterlacer.process(&self.frame_buffer, regs.odd_frame(), interlace_mode == 0x03);
+        } else {
+            self.deinterlacer.reset();
         }
         
-        // 5. Renderizar overlay de debug (se habilitado)
-        if self.debug_overlay {
-            self.render_debug_overlay(regs, &plane_manager, sprite_table);
-        }
+        self.last_frame_time = timestamp;
+        self.frames_rendered += 1;
     }
     
-    /// Renderiza tiles sem prioridade
-    fn render_low_priority_tiles(
+    // =====================================================
+    // LINHA A LINHA (MODO 5)
+    // =====================================================
+
+    /// Renderiza uma linha ativa do modo 5 no framebuffer com o estado de
+    /// VRAM, CRAM, VSRAM e registradores deste instante. Chamado pelo VDP a
+    /// cada linha, o que preserva trocas de paleta, scroll e registradores
+    /// feitas no meio do quadro (HBlank, interrupção de linha).
+    /// Retorna os bits de excesso de sprites e colisão gerados na linha.
+    pub fn render_line(
         &mut self,
-        plane_manager: &PlaneManager,
+        line: usize,
         regs: &VdpRegisters,
         cram: &Cram,
         vram: &Vram,
         vsram: &Vsram,
-    ) {
-        // Criar um framebuffer temporário para composição de planos
-        let mut temp_buffer = FrameBuffer::new(self.frame_buffer.width, self.frame_buffer.height);
-        
-        // Renderizar planos na ordem correta (B, A, Window)
-        for &plane_type in &plane_manager.plane_order {
-            match plane_type {
-                PlaneType::A if self.show_planes => {
-                    plane_manager.plane_a.render(
-                        &mut temp_buffer,
-                        vram,
-                        cram,
-                        vsram,
-                        regs,
-                        &self.video_mode,
-                        false, // Sem prioridade
-                    );
-                }
-                PlaneType::B if self.show_planes => {
-                    plane_manager.plane_b.render(
-                        &mut temp_buffer,
-                        vram,
-                        cram,
-                        vsram,
-                        regs,
-                        &self.video_mode,
-                        false, // Sem prioridade
-                    );
-                }
-                PlaneType::Window if self.show_window => {
-                    plane_manager.plane_window.render(
-                        &mut temp_buffer,
-                        vram,
-                        cram,
-                        vsram,
-                        regs,
-                        &self.video_mode,
-                        false, // Sem prioridade
-                    );
+    ) -> VdpStatus {
+        let lr = LineRegisters::new(regs);
+        if line >= lr.height {
+            return VdpStatus::empty();
+        }
+        // Interlace modo 2: cada campo preenche linhas alternadas do quadro
+        let output_height = lr.height * lr.fields();
+        if self.frame_buffer.width != lr.width || self.frame_buffer.height != output_height {
+            self.frame_buffer.resize(lr.width, output_height);
+        }
+        let y = lr.pixel_line(line);
+        
+        let mut plane_b = [LayerPixel::default(); MAX_LINE_WIDTH];
+        let mut plane_a = [LayerPixel::default(); MAX_LINE_WIDTH];
+        let mut sprites = [LayerPixel::default(); MAX_LINE_WIDTH];
+        let width = lr.width;
+        let mut status = VdpStatus::empty();
+        
+        // Display desligado: as camadas ficam transparentes e a linha
+        // inteira mostra a cor de fundo
+        if self.render_enabled && lr.display {
+            if self.show_planes {
+                Self::scroll_plane_line(&lr, vram, vsram, 1, line, y, &mut plane_b[..width]);
+                Self::scroll_plane_line(&lr, vram, vsram, 0, line, y, &mut plane_a[..width]);
+            }
+            if self.show_window {
+                // A janela substitui o plano A onde estiver ativa
+                for (x, pixel) in plane_a[..width].iter_mut().enumerate() {
+                    if lr.window_covers(x, line) {
+                        *pixel = Self::window_pixel(&lr, vram, x, y);
+                    }
                 }
-                _ => {}
+            }
+            status = Self::sprite_line(&lr, vram, y, &mut sprites[..width]);
+            if !self.show_sprites {
+                sprites = [LayerPixel::default(); MAX_LINE_WIDTH];
             }
         }
         
-        // Mesclar com framebuffer principal (somente pixels não transparentes)
-        self.merge_framebuffers(&temp_buffer);
-    }
-    
-    /// Renderiza tiles com prioridade
-    fn render_high_priority_tiles(
-        &mut self,
-        plane_manager: &PlaneManager,
-        regs: &VdpRegisters,
-        cram: &Cram,
-        vram: &Vram,
-        vsram: &Vsram,
-    ) {
-        // Criar um framebuffer temporário
-        let mut temp_buffer = FrameBuffer::new(self.frame_buffer.width, self.frame_buffer.height);
-        
-        // Renderizar planos na ordem correta
-        for &plane_type in &plane_manager.plane_order {
-            match plane_type {
-                PlaneType::A if self.show_planes => {
-                    plane_manager.plane_a.render(
-                        &mut temp_buffer,
-                        vram,
-                        cram,
-                        vsram,
-                        regs,
-                        &self.video_mode,
-                        true, // Com prioridade
-                    );
-                }
-                PlaneType::B if self.show_planes => {
-                    plane_manager.plane_b.render(
-                        &mut temp_buffer,
-                        vram,
-                        cram,
-                        vsram,
-                        regs,
-                        &self.video_mode,
-                        true, // Com prioridade
-                    );
-                }
-                PlaneType::Window if self.show_window => {
-                    plane_manager.plane_window.render(
-                        &mut temp_buffer,
-                        vram,
-                        cram,
-                        vsram,
-                        regs,
-                        &self.video_mode,
-                        true, // Com prioridade
-                    );
+        // Paleta do início da linha; as escritas feitas durante a linha
+        // entram a partir do pixel em que caíram. As do HBlank anterior
+        // (X fora da área ativa) valem desde o pixel 0.
+        let writes = cram.line_writes();
+        let mut palette = cram.line_start_palette();
+        let mut next_write = 0;
+        
+        for x in 0..width {
+            let mut dot = None;
+            while let Some(write) = writes
+                .get(next_write)
+                .filter(|write| write.x as usize >= width || write.x as usize <= x)
+            {
+                palette[write.index as usize] = write.value;
+                if write.x as usize == x {
+                    dot = Some(write.value);
                 }
-                _ => {}
+                next_write += 1;
             }
+            
+            let (index, intensity) = if lr.shadow_highlight {
+                compose_shadow_highlight(plane_b[x], plane_a[x], sprites[x])
+            } else {
+                (compose_pixel(plane_b[x], plane_a[x], sprites[x]), Intensity::Normal)
+            };
+            let color = match (dot, index) {
+                // Ponto de CRAM: o pixel mostra o valor sendo escrito
+                (Some(value), _) if self.cram_dots => self.color_from_word(value),
+                (_, Some(index)) => self.color_lut.argb(palette[index as usize], intensity),
+                (_, None) if self.show_background => {
+                    self.color_lut.argb(palette[lr.backdrop as usize], intensity)
+                }
+                (_, None) => 0xFF000000,
+            };
+            self.frame_buffer.set_pixel(x, y, color);
         }
         
-        // Mesclar com framebuffer principal
-        self.merge_framebuffers(&temp_buffer);
+        status
     }
     
-    /// Renderiza sprites sem prioridade
-    fn render_low_priority_sprites(
-        &mut self,
-        sprite_table: &SpriteTable,
+    /// Pixels de um plano de scroll (0 = A, 1 = B) na linha. `y` é a linha
+    /// em pixels do plano (dobrada no interlace modo 2); o hscroll segue a
+    /// linha da tela.
+    fn scroll_plane_line(
+        lr: &LineRegisters,
         vram: &Vram,
-        cram: &Cram,
+        vsram: &Vsram,
+        plane: usize,
+        line: usize,
+        y: usize,
+        out: &mut [LayerPixel],
     ) {
-        // Criar um framebuffer temporário para sprites
-        let mut temp_buffer = FrameBuffer::new(self.frame_buffer.width, self.frame_buffer.height);
+        let base = if plane == 0 { lr.plane_a } else { lr.plane_b };
+        let width_px = lr.plane_width * 8;
+        let height_px = lr.plane_height * lr.tile_height;
+        // No interlace modo 2 o vscroll tem um bit a mais (meias linhas)
+        let vscroll_mask = if lr.interlace2 { 0x7FF } else { 0x3FF };
         
-        // Renderizar sprites sem prioridade
-        for sprite in &sprite_table.sprites {
-            if sprite.valid && sprite.visible && !sprite.priority {
-                sprite.render(&mut temp_buffer, vram, cram, None);
-            }
-        }
+        // Tabela de hscroll: 4 bytes por linha (A, B)
+        let hscroll_row = match lr.hscroll_mode {
+            0 => 0,
+            1 => line & 7,
+            2 => line & !7,
+            _ => line,
+        };
+        let hscroll_addr = lr.hscroll_table + hscroll_row * 4 + plane * 2;
+        let hscroll = (vram.read16((hscroll_addr & 0xFFFF) as u32) & 0x3FF) as usize;
         
-        // Mesclar com framebuffer principal
-        self.merge_framebuffers(&temp_buffer);
+        for (x, pixel) in out.iter_mut().enumerate() {
+            // VSRAM: um par de words (A, B) por coluna de 16 pixels
+            let column = if lr.column_vscroll { x / 16 } else { 0 };
+            let vscroll = (vsram.read16(((column * 2 + plane) * 2) as u32) & vscroll_mask) as usize;
+            
+            let px = (x + width_px * 2 - hscroll % width_px) % width_px;
+            let py = (y + vscroll) % height_px;
+            let entry_addr = base + ((py / lr.tile_height) * lr.plane_width + px / 8) * 2;
+            let entry = vram.read16((entry_addr & 0xFFFF) as u32);
+            *pixel = Self::name_pixel(vram, entry, px, py, lr.tile_height);
+        }
     }
     
-    /// Renderiza sprites com prioridade
-    fn render_high_priority_sprites(
-        &mut self,
-        sprite_table: &SpriteTable,
-        vram: &Vram,
-        cram: &Cram,
-    ) {
-        // Criar um framebuffer temporário para sprites
-        let mut temp_buffer = FrameBuffer::new(self.frame_buffer.width, self.frame_buffer.height);
-        
-        // Renderizar sprites com prioridade
-        for sprite in &sprite_table.sprites {
-            if sprite.valid && sprite.visible && sprite.priority {
-                sprite.render(&mut temp_buffer, vram, cram, None);
-            }
+    /// Pixel do plano window na coluna `x` da linha `y` (sem scroll)
+    fn window_pixel(lr: &LineRegisters, vram: &Vram, x: usize, y: usize) -> LayerPixel {
+        let cells = if lr.h40 { 64 } else { 32 };
+        let entry_addr = lr.window + ((y / lr.tile_height) * cells + x / 8) * 2;
+        let entry = vram.read16((entry_addr & 0xFFFF) as u32);
+        Self::name_pixel(vram, entry, x, y, lr.tile_height)
+    }
+    
+    /// Pixel de uma entrada da tabela de nomes na posição (x, y) do plano,
+    /// com tiles de 8 ou 16 linhas
+    fn name_pixel(vram: &Vram, entry: u16, x: usize, y: usize, tile_height: usize) -> LayerPixel {
+        let row = y % tile_height;
+        let row = if entry & 0x1000 != 0 { tile_height - 1 - row } else { row };
+        let col = if entry & 0x0800 != 0 { 7 - x % 8 } else { x % 8 };
+        let color = Self::pattern_pixel(vram, (entry & 0x07FF) as usize, row, col, tile_height);
+        LayerPixel {
+            color: (((entry >> 13) & 0x03) as u8) << 4 | color,
+            priority: entry & 0x8000 != 0,
         }
+    }
+    
+    /// Índice de cor (0-15) de um pixel de padrão 4bpp. No interlace modo
+    /// 2 os padrões têm 16 linhas (64 bytes).
+    fn pattern_pixel(vram: &Vram, pattern: usize, row: usize, col: usize, tile_height: usize) -> u8 {
+        let addr = pattern * tile_height * 4 + row * 4 + (col / 4) * 2;
+        let word = vram.read16((addr & 0xFFFF) as u32);
+        ((word >> (12 - 4 * (col % 4))) & 0x0F) as u8
+    }
+    
+    /// Pixels dos sprites na linha, seguindo a lista encadeada da SAT.
+    /// Aplica os limites por linha (sprites e pixels), o mascaramento por
+    /// sprite com X = 0 e a detecção de colisão. `line` é a linha em pixels
+    /// (dobrada no interlace modo 2, onde Y tem um bit a mais e origem 256).
+    fn sprite_line(lr: &LineRegisters, vram: &Vram, line: usize, out: &mut [LayerPixel]) -> VdpStatus {
+        let tile_height = lr.tile_height;
+        let (max_sprites, max_per_line) = if lr.h40 { (80, 20) } else { (64, 16) };
+        let mut status = VdpStatus::empty();
+        let mut dots_left = lr.width;
+        let mut on_line = 0;
+        let mut masked = false;
+        let mut index = 0;
         
-        // Mesclar com framebuffer principal
-        self.merge_framebuffers(&temp_buffer);
-    }
-    
-    /// Mescla dois framebuffers (destino = this, fonte = other)
-    fn merge_framebuffers(&mut self, other: &FrameBuffer) {
-        for y in 0..self.frame_buffer.height {
-            for x in 0..self.frame_buffer.width {
-                if let Some(src_color) = other.get_pixel(x, y) {
-                    // Verificar se pixel fonte não é transparente (alpha > 0)
-                    let alpha = (src_color >> 24) & 0xFF;
-                    if alpha > 0 {
-                        // Obter cor atual no destino
-                        let dst_color = self.frame_buffer.get_pixel(x, y).unwrap_or(0);
-                        
-                        // Verificar prioridade (bits 28-31 do alpha channel)
-                        let src_priority = (src_color >> 28) & 0x0F;
-                        let dst_priority = (dst_color >> 28) & 0x0F;
-                        
-                        // Se pixel fonte tem prioridade maior ou destino é transparente, substituir
-                        if src_priority > dst_priority || ((dst_color >> 24) & 0xFF) == 0 {
-                            self.frame_buffer.set_pixel(x, y, src_color);
-                        } else if src_priority == dst_priority && alpha == 0xFF {
-                            // Mesma prioridade e totalmente opaco: usar fonte
-                            self.frame_buffer.set_pixel(x, y, src_color);
+        for _ in 0..max_sprites {
+            let attr = lr.sprite_table + index * 8;
+            let read = |offset: usize| vram.read16(((attr + offset) & 0xFFFF) as u32);
+            let (w0, w1, w2, w3) = (read(0), read(2), read(4), read(6));
+            
+            let y = if lr.interlace2 {
+                (w0 & 0x3FF) as i32 - 256
+            } else {
+                (w0 & 0x1FF) as i32 - 128
+            };
+            let cells_w = ((w1 >> 10) & 0x03) as usize + 1;
+            let cells_h = ((w1 >> 8) & 0x03) as usize + 1;
+            let row = line as i32 - y;
+            
+            if (0..(cells_h * tile_height) as i32).contains(&row) {
+                on_line += 1;
+                if on_line > max_per_line {
+                    status |= VdpStatus::SPRITE_OVERFLOW;
+                    break;
+                }
+                
+                let raw_x = w3 & 0x1FF;
+                if raw_x == 0 && on_line > 1 {
+                    masked = true;
+                }
+                
+                let mut dots = cells_w * 8;
+                if dots > dots_left {
+                    dots = dots_left;
+                    status |= VdpStatus::SPRITE_OVERFLOW;
+                }
+                dots_left -= dots;
+                
+                if !masked {
+                    let x = raw_x as i32 - 128;
+                    let row = row as usize;
+                    let sprite_row = if w2 & 0x1000 != 0 { cells_h * tile_height - 1 - row } else { row };
+                    let palette = (((w2 >> 13) & 0x03) as u8) << 4;
+                    
+                    for px in 0..dots {
+                        let sx = x + px as i32;
+                        if !(0..out.len() as i32).contains(&sx) {
+                            continue;
+                        }
+                        let sprite_col = if w2 & 0x0800 != 0 { cells_w * 8 - 1 - px } else { px };
+                        // Tiles do sprite em ordem de coluna
+                        let pattern = (w2 & 0x07FF) as usize + (sprite_col / 8) * cells_h + sprite_row / tile_height;
+                        let color = Self::pattern_pixel(
+                            vram,
+                            pattern,
+                            sprite_row % tile_height,
+                            sprite_col % 8,
+                            tile_height,
+                        );
+                        if color == 0 {
+                            continue;
+                        }
+                        let pixel = &mut out[sx as usize];
+                        if pixel.is_opaque() {
+                            status |= VdpStatus::SPRITE_COLLISION;
+                        } else {
+                            *pixel = LayerPixel { color: palette | color, priority: w2 & 0x8000 != 0 };
                         }
-                        // Caso contrário, manter destino
                     }
                 }
+                
+                if dots_left == 0 {
+                    break;
+                }
+            }
+            
+            index = (w1 & 0x7F) as usize;
+            if index == 0 || index >= max_sprites {
+                break;
             }
         }
+        
+        status
     }
     
     /// Atualiza o modo de vídeo baseado nos registradores
@@ -338,109 +420,14 @@ impl VdpRenderer {
         // Criar novo modo de vídeo baseado nos registradores
         let new_mode = VdpVideoMode::from_registers(regs, is_pal);
         
-        // Se o modo mudou, redimensionar framebuffer
-        if new_mode.resolution() != self.video_mode.resolution() {
-            let (width, height) = new_mode.resolution();
-            self.frame_buffer.resize(width as usize, height as usize);
-        }
-        
+        // O tamanho do framebuffer é ajustado por `render_line`
         self.video_mode = new_mode;
     }
     
-    /// Aplica pós-processamento baseado nos registradores
-    fn apply_post_processing(&mut self, regs: &VdpRegisters) {
-        // Aplicar modo shadow/highlight se habilitado
-        if self.video_mode.has_shadow_highlight() {
-            self.apply_shadow_highlight_effect(regs);
-        }
-        
-        // Aplicar efeito de interlace se necessário
-        if self.video_mode.is_interlace() {
-            self.apply_interlace_effect();
-        }
-        
-        // Ajustar brilho/contraste baseado em configurações (se houver)
-        let brightness = 0.0; // Deveria vir dos registradores
-        let contrast = 0.0;   // Deveria vir dos registradores
-        if brightness != 0.0 || contrast != 0.0 {
-            self.frame_buffer.adjust_brightness_contrast(brightness, contrast);
-        }
-    }
-    
-    /// Aplica efeito shadow/highlight (modo de 12 bits)
-    fn apply_shadow_highlight_effect(&mut self, regs: &VdpRegisters) {
-        // Em modo shadow/highlight, cada canal de cor tem 4 bits (0-15)
-        // O VDP aplica uma curva de correção específica
-        
-        for y in 0..self.frame_buffer.height {
-            for x in 0..self.frame_buffer.width {
-                if let Some(color) = self.frame_buffer.get_pixel(x, y) {
-                    let alpha = (color >> 24) & 0xFF;
-                    
-                    // Extrair componentes (originalmente 0-7, mas expandidos para 0-255)
-                    let r = ((color >> 16) & 0xFF) as f32;
-                    let g = ((color >> 8) & 0xFF) as f32;
-                    let b = (color & 0xFF) as f32;
-                    
-                    // Aplicar curva shadow/highlight do VDP
-                    // Simplificação: converter de 9-bit (0-511) para 12-bit (0-4095)
-                    // e depois para 8-bit (0-255)
-                    
-                    // Na prática, o VDP tem registradores específicos para shadow/highlight
-                    // que ajustam a intensidade. Aqui fazemos uma simulação simples.
-                    
-                    let r_new = (r * 1.2).min(255.0) as u32; // Aumentar brilho
-                    let g_new = (g * 1.2).min(255.0) as u32;
-                    let b_new = (b * 1.2).min(255.0) as u32;
-                    
-                    let new_color = (alpha << 24) | (r_new << 16) | (g_new << 8) | b_new;
-                    self.frame_buffer.set_pixel(x, y, new_color);
-                }
-            }
-        }
-    }
-    
-    /// Aplica efeito de interlace (para modos de alta resolução)
-    fn apply_interlace_effect(&mut self) {
-        // Em modo interlace, alternamos entre campos pares e ímpares
-        // Para simulação, podemos aplicar um leve blur vertical
-        
-        let mut temp_buffer = self.frame_buffer.clone();
-        
-        for y in 1..self.frame_buffer.height - 1 {
-            for x in 0..self.frame_buffer.width {
-                if let Some(color1) = self.frame_buffer.get_pixel(x, y - 1) {
-                    if let Some(color2) = self.frame_buffer.get_pixel(x, y + 1) {
-                        // Misturar com linhas adjacentes
-                        let r1 = (color1 >> 16) & 0xFF;
-                        let g1 = (color1 >> 8) & 0xFF;
-                        let b1 = color1 & 0xFF;
-                        
-                        let r2 = (color2 >> 16) & 0xFF;
-                        let g2 = (color2 >> 8) & 0xFF;
-                        let b2 = color2 & 0xFF;
-                        
-                        let r_avg = (r1 + r2) / 2;
-                        let g_avg = (g1 + g2) / 2;
-                        let b_avg = (b1 + b2) / 2;
-                        
-                        let alpha = (color1 >> 24) & 0xFF; // Manter alpha original
-                        let new_color = (alpha << 24) | (r_avg << 16) | (g_avg << 8) | b_avg;
-                        
-                        temp_buffer.set_pixel(x, y, new_color);
-                    }
-                }
-            }
-        }
-        
-        self.frame_buffer = temp_buffer;
-    }
-    
     /// Renderiza overlay de debug
     fn render_debug_overlay(
         &mut self,
         regs: &VdpRegisters,
-        plane_manager: &PlaneManager,
         sprite_table: &SpriteTable,
     ) {
         let width = self.frame_buffer.width;
@@ -460,14 +447,13 @@ impl VdpRenderer {
         }
         
         // Desenhar informações de debug
-        self.render_debug_text(regs, plane_manager, sprite_table);
+        self.render_debug_text(regs, sprite_table);
     }
     
     /// Renderiza texto de debug
     fn render_debug_text(
         &mut self,
         regs: &VdpRegisters,
-        plane_manager: &PlaneManager,
         sprite_table: &SpriteTable,
     ) {
         // Esta é uma implementação simplificada de renderização de texto
@@ -503,22 +489,134 @@ impl VdpRenderer {
         }
     }
     
+    // =====================================================
+    // MODO 4 (MASTER SYSTEM)
+    // =====================================================
+
+    /// Renderiza uma linha ativa do modo 4 no framebuffer (256x192).
+    /// Retorna os bits de status gerados pelos sprites (excesso de sprites
+    /// na linha e colisão), a serem acumulados no status do VDP.
+    pub fn render_mode4_line(&mut self, vdp: &Mode4Vdp, line: usize) -> u8 {
+        if self.frame_buffer.width != MODE4_WIDTH || self.frame_buffer.height != MODE4_HEIGHT {
+            self.frame_buffer.resize(MODE4_WIDTH, MODE4_HEIGHT);
+        }
+        let backdrop = vdp.color(vdp.backdrop_index());
+        if !self.render_enabled || !vdp.display_enabled() {
+            self.frame_buffer.draw_horizontal_line(0, MODE4_WIDTH - 1, line, backdrop);
+            return 0;
+        }
+
+        let mut sprites = [0u8; MODE4_WIDTH];
+        let status = if self.show_sprites { Self::mode4_sprite_line(vdp, line, &mut sprites) } else { 0 };
+
+        for (x, &sprite) in sprites.iter().enumerate() {
+            let color = if x < 8 && vdp.mask_left_column() {
+                None
+            } else {
+                let (tile, priority) = Self::mode4_background_pixel(vdp, x, line);
+                // Tile com prioridade cobre o sprite, exceto na cor 0
+                let tile_on_top = priority && tile & 0x0F != 0;
+                match (sprite, self.show_planes) {
+                    (0, true) => Some(tile),
+                    (0, false) => None,
+                    (_, true) if tile_on_top => Some(tile),
+                    _ => Some(sprite),
+                }
+            };
+            let argb = color.map_or(backdrop, |index| vdp.color(index as usize));
+            self.frame_buffer.set_pixel(x, line, argb);
+        }
+        status
+    }
+
+    /// Pixel do plano de fundo: índice na CRAM (0–31) e bit de prioridade
+    fn mode4_background_pixel(vdp: &Mode4Vdp, x: usize, line: usize) -> (u8, bool) {
+        let hscroll = if vdp.lock_top_rows() && line < 16 { 0 } else { vdp.hscroll() };
+        let vscroll = if vdp.lock_right_columns() && x >= 192 { 0 } else { vdp.vscroll };
+        let bg_x = (x as u8).wrapping_sub(hscroll) as usize;
+        let bg_y = (line + vscroll as usize) % 224;
+
+        // Entrada da tabela de nomes (32x28, little-endian):
+        // bits 0–8 padrão, 9 espelho H, 10 espelho V, 11 paleta, 12 prioridade
+        let entry_addr = vdp.name_table_base() + ((bg_y / 8) * 32 + bg_x / 8) * 2;
+        let entry = vdp.vram.read16(entry_addr as u32);
+        let row = if entry & 0x0400 != 0 { 7 - bg_y % 8 } else { bg_y % 8 };
+        let col = if entry & 0x0200 != 0 { 7 - bg_x % 8 } else { bg_x % 8 };
+        let color = vdp.pattern_pixel((entry & 0x01FF) as usize, row, col);
+        let palette = if entry & 0x0800 != 0 { 16 } else { 0 };
+        (palette + color, entry & 0x1000 != 0)
+    }
+
+    /// Sprites da linha: preenche `out` com índices da CRAM (16–31, 0 =
+    /// transparente). O primeiro sprite da tabela fica na frente; só os 8
+    /// primeiros da linha são desenhados.
+    fn mode4_sprite_line(vdp: &Mode4Vdp, line: usize, out: &mut [u8; MODE4_WIDTH]) -> u8 {
+        let table = vdp.sprite_table_base();
+        let zoom = if vdp.zoomed_sprites() { 2 } else { 1 };
+        let height = if vdp.tall_sprites() { 16 } else { 8 };
+        let shift = if vdp.shift_sprites() { 8 } else { 0 };
+        let mut status = 0;
+        let mut count = 0;
+
+        for i in 0..64 {
+            let y = vdp.vram.read8((table + i) as u32) as i32;
+            // Y = 0xD0 encerra a lista no modo de 192 linhas
+            if y == 0xD0 {
+                break;
+            }
+            // Sprites perto do fim da faixa aparecem no topo da tela
+            let top = if y >= 0xF0 { y - 255 } else { y + 1 };
+            let row = line as i32 - top;
+            if row < 0 || row >= height * zoom {
+                continue;
+            }
+            count += 1;
+            if count > 8 {
+                status |= mode4::STATUS_OVERFLOW;
+                break;
+            }
+
+            let x = vdp.vram.read8((table + 0x80 + i * 2) as u32) as i32 - shift;
+            let mut pattern = vdp.vram.read8((table + 0x81 + i * 2) as u32) as usize | vdp.sprite_pattern_offset();
+            if height == 16 {
+                pattern &= !1;
+            }
+            for px in 0..8 * zoom {
+                let sx = x + px;
+                if !(0..MODE4_WIDTH as i32).contains(&sx) {
+                    continue;
+                }
+                let color = vdp.pattern_pixel(pattern, (row / zoom) as usize, (px / zoom) as usize);
+                if color == 0 {
+                    continue;
+                }
+                let pixel = &mut out[sx as usize];
+                if *pixel == 0 {
+                    *pixel = 16 + color;
+                } else {
+                    status |= mode4::STATUS_COLLISION;
+                }
+            }
+        }
+        status
+    }
+
     /// Obtém uma cor da CRAM e converte para ARGB
-    fn get_color_from_cram(&self, cram: &Cram, color_index: usize) -> u32 {
-        let color_9bit = cram.read(color_index % 64);
-        
-        // Extrair componentes (3 bits cada)
-        let r = ((color_9bit >> 0) & 0x07) as u32;
-        let g = ((color_9bit >> 4) & 0x07) as u32;
-        let b = ((color_9bit >> 8) & 0x07) as u32;
-        
-        // Converter para 8 bits por canal
-        let r8 = (r * 36) as u32;  // 36 ≈ 255/7
-        let g8 = (g * 36) as u32;
-        let b8 = (b * 36) as u32;
-        
-        // Alpha máximo (opaco)
-        (0xFF << 24) | (r8 << 16) | (g8 << 8) | b8
+    pub fn get_color_from_cram(&self, cram: &Cram, color_index: usize) -> u32 {
+        self.color_from_word(cram.read(color_index % 64))
+    }
+    
+    /// Converte uma palavra de cor da CRAM para ARGB pela tabela da curva
+    /// selecionada
+    fn color_from_word(&self, word: u16) -> u32 {
+        self.color_lut.argb(word, Intensity::Normal)
+    }
+    
+    /// Seleciona a curva de saída de cor, recalculando a tabela
+    pub fn set_color_curve(&mut self, curve: ColorCurve) {
+        if self.color_lut.curve() != curve {
+            self.color_lut = ColorLut::new(curve);
+        }
     }
     
     /// Configura quais elementos renderizar
@@ -530,6 +628,11 @@ impl VdpRenderer {
         self.debug_overlay = flags.debug_overlay;
     }
     
+    /// Habilita/desabilita a emulação dos pontos de CRAM
+    pub fn set_cram_dots(&mut self, enabled: bool) {
+        self.cram_dots = enabled;
+    }
+    
     /// Habilita/desabilita renderização
     pub fn set_render_enabled(&mut self, enabled: bool) {
         self.render_enabled = enabled;
@@ -592,15 +695,187 @@ pub struct RenderStats {
     pub video_mode: VdpVideoMode,
 }
 
+/// Largura máxima de uma linha ativa (H40)
+pub const MAX_LINE_WIDTH: usize = 320;
+
+/// Pixel de uma camada antes da composição
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct LayerPixel {
+    /// Índice na CRAM (paleta × 16 + cor); cor 0 da paleta é transparente
+    color: u8,
+    priority: bool,
+}
+
+impl LayerPixel {
+    fn is_opaque(self) -> bool {
+        self.color & 0x0F != 0
+    }
+}
+
+/// Compõe as camadas de um pixel. Ordem do VDP, da frente para trás:
+/// sprite, plano A, plano B com prioridade; depois sprite, A, B sem
+/// prioridade. `None` indica que vale a cor de fundo (registrador 7).
+fn compose_pixel(plane_b: LayerPixel, plane_a: LayerPixel, sprite: LayerPixel) -> Option<u8> {
+    [true, false].into_iter().find_map(|priority| {
+        [sprite, plane_a, plane_b]
+            .into_iter()
+            .find(|pixel| pixel.priority == priority && pixel.is_opaque())
+            .map(|pixel| pixel.color)
+    })
+}
+
+/// Índices da paleta 3 usados pelos sprites como operadores no modo
+/// shadow/highlight
+const SPRITE_HIGHLIGHT_OPERATOR: u8 = 0x3E;
+const SPRITE_SHADOW_OPERATOR: u8 = 0x3F;
+
+/// Compõe um pixel no modo shadow/highlight, retornando também a
+/// intensidade:
+/// - planos A e B sem prioridade deixam o pixel (inclusive o fundo) em
+///   sombra; basta um deles ter prioridade, mesmo transparente, para ficar
+///   normal
+/// - sprite com prioridade fica sempre normal; sem prioridade, segue a
+///   intensidade dos planos
+/// - sprites nas cores 14 e 15 da paleta 3 não são desenhados: realçam
+///   (sombra → normal → realce) ou sombreiam o que está embaixo, desde que
+///   não estejam atrás de um plano com prioridade
+fn compose_shadow_highlight(
+    plane_b: LayerPixel,
+    plane_a: LayerPixel,
+    sprite: LayerPixel,
+) -> (Option<u8>, Intensity) {
+    let planes_intensity = if plane_a.priority || plane_b.priority {
+        Intensity::Normal
+    } else {
+        Intensity::Shadow
+    };
+    let planes = compose_pixel(plane_b, plane_a, LayerPixel::default());
+    
+    let plane_in_front = !sprite.priority
+        && [plane_a, plane_b].iter().any(|pixel| pixel.priority && pixel.is_opaque());
+    if !sprite.is_opaque() || plane_in_front {
+        return (planes, planes_intensity);
+    }
+    
+    match sprite.color {
+        SPRITE_HIGHLIGHT_OPERATOR => {
+            let intensity = match planes_intensity {
+                Intensity::Shadow => Intensity::Normal,
+                _ => Intensity::Highlight,
+            };
+            (planes, intensity)
+        }
+        SPRITE_SHADOW_OPERATOR => (planes, Intensity::Shadow),
+        // Cor 14 das paletas 0-2 nunca é sombreada (peculiaridade do VDP)
+        0x0E | 0x1E | 0x2E => (Some(sprite.color), Intensity::Normal),
+        _ if sprite.priority => (Some(sprite.color), Intensity::Normal),
+        _ => (Some(sprite.color), planes_intensity),
+    }
+}
+
+/// Registradores do modo 5 como valem para a linha sendo desenhada.
+/// Lidos dos valores brutos a cada linha, já que podem mudar no meio do
+/// quadro.
+#[derive(Debug, Clone, Copy)]
+struct LineRegisters {
+    h40: bool,
+    width: usize,
+    height: usize,
+    display: bool,
+    plane_a: usize,
+    plane_b: usize,
+    window: usize,
+    sprite_table: usize,
+    hscroll_table: usize,
+    hscroll_mode: u8,
+    column_vscroll: bool,
+    plane_width: usize,
+    plane_height: usize,
+    window_h: u8,
+    window_v: u8,
+    backdrop: u8,
+    shadow_highlight: bool,
+    /// Interlace modo 2 (registrador 12, bits 1-2 = 3)
+    interlace2: bool,
+    /// Campo ímpar do quadro entrelaçado (flag do status)
+    odd_field: bool,
+    /// Altura dos tiles em pixels: 8, ou 16 no interlace modo 2
+    tile_height: usize,
+}
+
+impl LineRegisters {
+    fn new(regs: &VdpRegisters) -> Self {
+        let r = &regs.regs;
+        let h40 = r[12] & 0x01 != 0;
+        let interlace2 = (r[12] >> 1) & 0x03 == 0x03;
+        let cells = |bits: u8| match bits & 0x03 {
+            1 => 64,
+            3 => 128,
+            _ => 32,
+        };
+        
+        Self {
+            h40,
+            width: if h40 { 320 } else { 256 },
+            height: if r[1] & 0x08 != 0 { 240 } else { 224 },
+            display: r[1] & 0x40 != 0,
+            plane_a: ((r[2] & 0x38) as usize) << 10,
+            plane_b: ((r[4] & 0x07) as usize) << 13,
+            window: ((r[3] & if h40 { 0x3C } else { 0x3E }) as usize) << 10,
+            sprite_table: ((r[5] & if h40 { 0x7E } else { 0x7F }) as usize) << 9,
+            hscroll_table: ((r[13] & 0x3F) as usize) << 10,
+            hscroll_mode: r[11] & 0x03,
+            column_vscroll: r[11] & 0x04 != 0,
+            plane_width: cells(r[16]),
+            plane_height: cells(r[16] >> 4),
+            window_h: r[17],
+            window_v: r[18],
+            backdrop: r[7] & 0x3F,
+            shadow_highlight: r[12] & 0x08 != 0,
+            interlace2,
+            odd_field: regs.odd_frame(),
+            tile_height: if interlace2 { 16 } else { 8 },
+        }
+    }
+    
+    /// Campos por quadro na saída: 2 no interlace modo 2 (448/480 linhas)
+    fn fields(&self) -> usize {
+        if self.interlace2 { 2 } else { 1 }
+    }
+    
+    /// Linha em pixels dos planos e sprites para a linha da tela. No
+    /// interlace modo 2 o campo par desenha as linhas pares e o ímpar as
+    /// ímpares.
+    fn pixel_line(&self, line: usize) -> usize {
+        if self.interlace2 {
+            line * 2 + self.odd_field as usize
+        } else {
+            line
+        }
+    }
+    
+    /// Se o plano window cobre o pixel: faixa vertical (R18, em linhas de
+    /// 8 pixels) ou horizontal (R17, em colunas de 16 pixels)
+    fn window_covers(&self, x: usize, line: usize) -> bool {
+        let v = (self.window_v & 0x1F) as usize * 8;
+        let in_v = if self.window_v & 0x80 != 0 { line >= v } else { line < v };
+        let h = (self.window_h & 0x1F) as usize * 16;
+        let in_h = if self.window_h & 0x80 != 0 { x >= h } else { x < h };
+        in_v || in_h
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::vdp::video_modes::VdpVideoMode;
     use crate::vdp::registers::VdpRegisters;
     use crate::vdp::sprite::{Sprite, SpriteSize, SpriteTable};
+    use crate::vdp::mode4::Mode4Vdp;
     
     fn create_test_components() -> (VdpRegisters, Cram, Vram, Vsram, SpriteTable) {
-        let regs = VdpRegisters::new();
+        let mut regs = VdpRegisters::new();
+        regs.set(12, 0x81); // H40: 320x224
         let cram = Cram::new();
         let vram = Vram::new();
         let vsram = Vsram::new();
@@ -701,30 +976,316 @@ mod tests {
         assert_eq!(renderer.frame_buffer.height, 480);
     }
     
+    /// Registradores de modo 5 em H40: plano A em 0xC000, B em 0xE000,
+    /// sprites em 0xF800, hscroll em 0xFC00, planos de 64x32 células
+    fn mode5_regs() -> VdpRegisters {
+        let mut regs = VdpRegisters::new();
+        for (index, value) in [(1, 0x44), (2, 0x30), (4, 0x07), (5, 0x7C), (12, 0x81), (13, 0x3F), (16, 0x01)] {
+            regs.set(index, value);
+        }
+        regs
+    }
+
+    /// Preenche o padrão `pattern` inteiro com a cor `color`
+    fn fill_pattern(vram: &mut Vram, pattern: u32, color: u16) {
+        for offset in (0..32).step_by(2) {
+            vram.write16(pattern * 32 + offset, color * 0x1111);
+        }
+    }
+
     #[test]
-    fn test_merge_framebuffers() {
-        let video_mode = VdpVideoMode::new_default();
-        let mut renderer = VdpRenderer::new(video_mode);
-        
-        // Criar framebuffers de teste
-        let mut src = FrameBuffer::new(320, 224);
-        let dst = FrameBuffer::new(320, 224);
-        
-        // Preencher src com alguns pixels
-        src.set_pixel(10, 10, 0xFFFFFFFF); // Pixel opaco branco
-        src.set_pixel(20, 20, 0x80FF0000); // Pixel semi-transparente vermelho
+    fn test_line_planes_and_backdrop() {
+        let mut renderer = VdpRenderer::new(VdpVideoMode::new_default());
+        let (_, mut cram, mut vram, vsram, _) = create_test_components();
+        let mut regs = mode5_regs();
+        cram.write(1, 0x000E);
+        cram.write(0x21, 0x0E00);
+        fill_pattern(&mut vram, 1, 1);
+        vram.write16(0xC000, 0x0001); // plano A, célula (0, 0)
+        vram.write16(0xE002, 0x4001); // plano B, célula (1, 0), paleta 2
+
+        let status = renderer.render_line(0, &regs, &cram, &vram, &vsram);
+        assert!(status.is_empty());
+        assert_eq!((renderer.frame_buffer.width, renderer.frame_buffer.height), (320, 224));
+        let color1 = renderer.get_color_from_cram(&cram, 1);
+        let backdrop = renderer.get_color_from_cram(&cram, 0);
+        assert_eq!(renderer.frame_buffer.get_pixel(0, 0), Some(color1));
+        // Plano B visível onde o plano A é transparente, com a paleta 2
+        assert_eq!(renderer.frame_buffer.get_pixel(8, 0), Some(renderer.get_color_from_cram(&cram, 0x21)));
+        assert_eq!(renderer.frame_buffer.get_pixel(16, 0), Some(backdrop));
+
+        // Display desligado: linha inteira com a cor de fundo
+        regs.set(1, 0x04);
+        renderer.render_line(1, &regs, &cram, &vram, &vsram);
+        assert_eq!(renderer.frame_buffer.get_pixel(0, 1), Some(backdrop));
+    }
+
+    #[test]
+    fn test_line_uses_state_of_each_line() {
+        let mut renderer = VdpRenderer::new(VdpVideoMode::new_default());
+        let (_, mut cram, mut vram, vsram, _) = create_test_components();
+        let regs = mode5_regs();
+        cram.write(1, 0x000E);
+        fill_pattern(&mut vram, 1, 1);
+        vram.write16(0xC000, 0x0001);
+
+        renderer.render_line(0, &regs, &cram, &vram, &vsram);
+        let before = renderer.get_color_from_cram(&cram, 1);
+
+        // Troca de paleta e de scroll horizontal entre as linhas (HBlank)
+        cram.write(1, 0x0E00);
+        vram.write16(0xFC00, 8);
+        renderer.render_line(1, &regs, &cram, &vram, &vsram);
+        let after = renderer.get_color_from_cram(&cram, 1);
+
+        assert_ne!(before, after);
+        assert_eq!(renderer.frame_buffer.get_pixel(0, 0), Some(before));
+        assert_eq!(renderer.frame_buffer.get_pixel(8, 0), Some(renderer.get_color_from_cram(&cram, 0)));
+        // A linha 1 vê a nova cor e o plano deslocado 8 pixels
+        assert_eq!(renderer.frame_buffer.get_pixel(0, 1), Some(renderer.get_color_from_cram(&cram, 0)));
+        assert_eq!(renderer.frame_buffer.get_pixel(8, 1), Some(after));
+    }
+
+    #[test]
+    fn test_line_applies_cram_writes_at_x() {
+        let mut renderer = VdpRenderer::new(VdpVideoMode::new_default());
+        let (_, mut cram, vram, vsram, _) = create_test_components();
+        let regs = mode5_regs();
+        cram.write(0, 0x000E);
         
-        // Copiar dst para renderer
-        renderer.frame_buffer = dst;
+        // Escritas no HBlank anterior (X fora da área ativa) e no pixel 100
+        cram.set_beam_position(Some(330));
+        cram.write(0, 0x00E0);
+        cram.set_beam_position(Some(100));
+        cram.write(0, 0x0E00);
+        cram.write(5, 0x0EEE);
         
-        // Mesclar
-        renderer.merge_framebuffers(&src);
+        renderer.render_line(0, &regs, &cram, &vram, &vsram);
+        let green = renderer.color_from_word(0x00E0);
+        let blue = renderer.color_from_word(0x0E00);
+        assert_eq!(renderer.frame_buffer.get_pixel(0, 0), Some(green));
+        assert_eq!(renderer.frame_buffer.get_pixel(99, 0), Some(green));
+        assert_eq!(renderer.frame_buffer.get_pixel(100, 0), Some(blue));
         
-        // Verificar que os pixels foram mesclados
-        assert_eq!(renderer.frame_buffer.get_pixel(10, 10), Some(0xFFFFFFFF));
-        assert_eq!(renderer.frame_buffer.get_pixel(20, 20), Some(0x80FF0000));
+        // Pontos de CRAM: o pixel 100 mostra a última cor escrita nele
+        renderer.set_cram_dots(true);
+        renderer.render_line(0, &regs, &cram, &vram, &vsram);
+        assert_eq!(renderer.frame_buffer.get_pixel(100, 0), Some(renderer.color_from_word(0x0EEE)));
+        assert_eq!(renderer.frame_buffer.get_pixel(101, 0), Some(blue));
         
-        // Pixel transparente não deve afetar
-        assert_eq!(renderer.frame_buffer.get_pixel(30, 30), None);
+        // Nova linha: a paleta final vale desde o pixel 0
+        cram.begin_line();
+        renderer.render_line(1, &regs, &cram, &vram, &vsram);
+        assert_eq!(renderer.frame_buffer.get_pixel(0, 1), Some(blue));
+    }
+
+    #[test]
+    fn test_color_curve_selection() {
+        let mut renderer = VdpRenderer::new(VdpVideoMode::new_default());
+        let (_, mut cram, vram, vsram, _) = create_test_components();
+        let regs = mode5_regs();
+        cram.write(0, 0x0888);
+
+        renderer.render_line(0, &regs, &cram, &vram, &vsram);
+        assert_eq!(renderer.frame_buffer.get_pixel(0, 0), Some(0xFF909090));
+
+        renderer.set_color_curve(ColorCurve::Linear);
+        renderer.render_line(0, &regs, &cram, &vram, &vsram);
+        assert_eq!(renderer.frame_buffer.get_pixel(0, 0), Some(0xFF929292));
+        assert_eq!(renderer.get_color_from_cram(&cram, 0), 0xFF929292);
+    }
+
+    #[test]
+    fn test_line_shadow_highlight() {
+        let mut renderer = VdpRenderer::new(VdpVideoMode::new_default());
+        let (_, mut cram, mut vram, vsram, _) = create_test_components();
+        let mut regs = mode5_regs();
+        regs.set(12, 0x89); // H40 com shadow/highlight
+        cram.write(1, 0x0888);
+        cram.write(0x11, 0x0888);
+        cram.write(0x0E, 0x0888);
+        cram.write(0x3E, 0x000E);
+        fill_pattern(&mut vram, 1, 1);
+        fill_pattern(&mut vram, 2, 14);
+        fill_pattern(&mut vram, 3, 15);
+        let lut = renderer.color_lut.clone();
+        let shadow = lut.argb(0x0888, Intensity::Shadow);
+        let normal = lut.argb(0x0888, Intensity::Normal);
+        let pixel = |renderer: &VdpRenderer, x| renderer.frame_buffer.get_pixel(x, 0);
+
+        // Células 0-1: plano B sem prioridade (sombra); célula 2: com prioridade
+        vram.write16(0xE000, 0x0001);
+        vram.write16(0xE002, 0x0001);
+        vram.write16(0xE004, 0x8001);
+        // Sprite normal sem prioridade na célula 1, operador de realce na
+        // célula 0 e operador de sombra na célula 2
+        vram.write_sprite_attributes(0xF800, 0, &[128, 0x0001, 0x2001, 136]);
+        vram.write_sprite_attributes(0xF800, 1, &[128, 0x0002, 0x6002, 128]);
+        vram.write_sprite_attributes(0xF800, 2, &[128, 0x0003, 0x6003, 144]);
+        // Cor 14 da paleta 0 sem prioridade na célula 3
+        vram.write_sprite_attributes(0xF800, 3, &[128, 0x0000, 0x0002, 152]);
+
+        renderer.render_line(0, &regs, &cram, &vram, &vsram);
+        // Realce sobre um plano em sombra volta ao normal
+        assert_eq!(pixel(&renderer, 0), Some(normal));
+        // Sprite sem prioridade segue a sombra dos planos
+        assert_eq!(pixel(&renderer, 8), Some(shadow));
+        // Operador de sombra sobre plano com prioridade, fora da frente dele
+        assert_eq!(pixel(&renderer, 16), Some(normal));
+        // Cor 14 das paletas 0-2 nunca fica em sombra
+        assert_eq!(pixel(&renderer, 24), Some(normal));
+        // Fundo sem planos com prioridade fica em sombra
+        assert_eq!(pixel(&renderer, 40), Some(lut.argb(0, Intensity::Shadow)));
+
+        // Plano B com prioridade (transparente) na célula 0: o realce leva o
+        // fundo de normal a realce; sprites passam a ter prioridade
+        vram.write16(0xE000, 0x8000);
+        vram.write_sprite_attributes(0xF800, 1, &[128, 0x0002, 0xE002, 128]);
+        vram.write_sprite_attributes(0xF800, 2, &[128, 0x0000, 0xE003, 144]);
+        vram.write_sprite_attributes(0xF800, 0, &[128, 0x0001, 0xA001, 136]);
+        renderer.render_line(0, &regs, &cram, &vram, &vsram);
+        assert_eq!(pixel(&renderer, 0), Some(lut.argb(0, Intensity::Highlight)));
+        // Sprite com prioridade fica normal
+        assert_eq!(pixel(&renderer, 8), Some(normal));
+        // Operador de sombra com prioridade escurece o plano
+        assert_eq!(pixel(&renderer, 16), Some(shadow));
+
+        // Sem o modo, os operadores são desenhados como cores comuns
+        regs.set(12, 0x81);
+        renderer.render_line(0, &regs, &cram, &vram, &vsram);
+        assert_eq!(pixel(&renderer, 0), Some(lut.argb(0x000E, Intensity::Normal)));
+    }
+
+    #[test]
+    fn test_line_interlace_mode2() {
+        let mut renderer = VdpRenderer::new(VdpVideoMode::new_default());
+        let (_, mut cram, mut vram, vsram, _) = create_test_components();
+        let mut regs = mode5_regs();
+        regs.set(12, 0x87); // H40, interlace modo 2
+        cram.write(1, 0x000E);
+        cram.write(2, 0x00E0);
+        cram.write(3, 0x0E00);
+        // Padrão 1 (8x16, 64 bytes): linha 0 na cor 1, linha 1 na cor 2
+        vram.write16(64, 0x1111);
+        vram.write16(66, 0x1111);
+        vram.write16(68, 0x2222);
+        vram.write16(70, 0x2222);
+        // Padrão 2 inteiro na cor 3
+        for offset in (0..64).step_by(2) {
+            vram.write16(128 + offset, 0x3333);
+        }
+        vram.write16(0xC000, 0x0001);
+        // Sprite 1x1 (8x16) com Y = 264: linhas 8 a 23 do quadro, X = 100
+        vram.write_sprite_attributes(0xF800, 0, &[264, 0x0000, 0x0002, 228]);
+
+        // Campo par: linhas pares do quadro de 448 linhas
+        renderer.render_line(0, &regs, &cram, &vram, &vsram);
+        renderer.render_line(3, &regs, &cram, &vram, &vsram);
+        renderer.render_line(4, &regs, &cram, &vram, &vsram);
+        assert_eq!((renderer.frame_buffer.width, renderer.frame_buffer.height), (320, 448));
+        assert_eq!(renderer.frame_buffer.get_pixel(0, 0), Some(renderer.get_color_from_cram(&cram, 1)));
+        assert_eq!(renderer.frame_buffer.get_pixel(100, 6), Some(renderer.get_color_from_cram(&cram, 0)));
+        assert_eq!(renderer.frame_buffer.get_pixel(100, 8), Some(renderer.get_color_from_cram(&cram, 3)));
+
+        // Campo ímpar: mesma linha da tela vai para a linha ímpar do quadro
+        regs.set_odd_frame(true);
+        renderer.render_line(0, &regs, &cram, &vram, &vsram);
+        assert_eq!(renderer.frame_buffer.get_pixel(0, 1), Some(renderer.get_color_from_cram(&cram, 2)));
+        assert_eq!(renderer.frame_buffer.get_pixel(0, 0), Some(renderer.get_color_from_cram(&cram, 1)));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_interlaced_frame_is_deinterlaced() {
+        let mut renderer = VdpRenderer::new(VdpVideoMode::new_default());
+        let (_, mut cram, vram, vsram, sprite_table) = create_test_components();
+        let mut regs = mode5_regs();
+        regs.set(12, 0x87);
+        let red = renderer.color_from_word(0x000E);
+        let blue = renderer.color_from_word(0x0E00);
+
+        cram.write(0, 0x000E);
+        renderer.render_frame(&regs, &cram, &vram, &vsram, &sprite_table, 0.0);
+        regs.set_odd_frame(true);
+        cram.write(0, 0x0E00);
+        let frame = renderer.render_frame(&regs, &cram, &vram, &vsram, &sprite_table, 1.0);
+        // Weave: os dois campos intercalados
+        assert_eq!(frame.height, 448);
+        assert_eq!(frame.get_pixel(0, 0), Some(red));
+        assert_eq!(frame.get_pixel(0, 1), Some(blue));
+
+        renderer.set_deinterlace_mode(DeinterlaceMode::NativeField);
+        let frame = renderer.render_frame(&regs, &cram, &vram, &vsram, &sprite_table, 2.0);
+        assert_eq!(frame.height, 224);
+
+        // Sem entrelaçamento o framebuffer é exibido direto
+        regs.set(12, 0x81);
+        renderer.render_frame(&regs, &cram, &vram, &vsram, &sprite_table, 3.0);
+        assert!(!renderer.interlaced);
+        assert_eq!(renderer.presented_frame().height, 224);
+    }
+
+    #[test]
+    fn test_line_sprite_priority_and_collision() {
+        let mut renderer = VdpRenderer::new(VdpVideoMode::new_default());
+        let (_, mut cram, mut vram, vsram, _) = create_test_components();
+        let regs = mode5_regs();
+        cram.write(1, 0x000E);
+        cram.write(2, 0x00E0);
+        fill_pattern(&mut vram, 1, 1);
+        fill_pattern(&mut vram, 2, 2);
+        vram.write16(0xE000, 0x0001); // plano B sem prioridade
+        // Sprite 0 em (0, 0), 1x1 célula, padrão 2, fim da lista
+        vram.write_sprite_attributes(0xF800, 0, &[128, 0x0000, 0x0002, 128]);
+
+        let status = renderer.render_line(0, &regs, &cram, &vram, &vsram);
+        assert!(status.is_empty());
+        assert_eq!(renderer.frame_buffer.get_pixel(0, 0), Some(renderer.get_color_from_cram(&cram, 2)));
+
+        // Plano B com prioridade passa à frente do sprite sem prioridade
+        vram.write16(0xE000, 0x8001);
+        renderer.render_line(0, &regs, &cram, &vram, &vsram);
+        assert_eq!(renderer.frame_buffer.get_pixel(0, 0), Some(renderer.get_color_from_cram(&cram, 1)));
+
+        // Um segundo sprite sobreposto gera colisão
+        vram.write_sprite_attributes(0xF800, 0, &[128, 0x0001, 0x0002, 128]);
+        vram.write_sprite_attributes(0xF800, 1, &[128, 0x0000, 0x0002, 132]);
+        let status = renderer.render_line(0, &regs, &cram, &vram, &vsram);
+        assert_eq!(status, VdpStatus::SPRITE_COLLISION);
+    }
+
+    #[test]
+    fn test_mode4_line_background_and_sprites() {
+        let mut renderer = VdpRenderer::new(VdpVideoMode::new_default());
+        let mut vdp = Mode4Vdp::new(false);
+        vdp.regs[1] = 0x40; // display ligado
+        vdp.regs[2] = 0x0E; // tabela de nomes em 0x3800
+        vdp.regs[5] = 0x7E; // sprites em 0x3F00
+        vdp.regs[7] = 0x00; // fundo: cor 16
+        vdp.cram[1] = 0x03; // vermelho
+        vdp.cram[17] = 0x30; // azul (sprites)
+        vdp.cram[16] = 0x0C; // verde (cor de fundo)
+
+        // Padrão 1: linha 0 inteira na cor 1 (plano 0)
+        vdp.vram.data[32] = 0xFF;
+        // Tile (0,0) usa o padrão 1
+        vdp.vram.data[0x3800] = 0x01;
+        // Sprite 0 em (8, 0) com o padrão 1; lista termina no sprite 1
+        vdp.vram.data[0x3F00] = 0xFF;
+        vdp.vram.data[0x3F01] = 0xD0;
+        vdp.vram.data[0x3F80] = 8;
+        vdp.vram.data[0x3F81] = 1;
+
+        let status = renderer.render_mode4_line(&vdp, 0);
+        assert_eq!(status, 0);
+        assert_eq!((renderer.frame_buffer.width, renderer.frame_buffer.height), (256, 192));
+        assert_eq!(renderer.frame_buffer.get_pixel(0, 0), Some(0xFFFF0000));
+        assert_eq!(renderer.frame_buffer.get_pixel(8, 0), Some(0xFF0000FF));
+        // Sem tile nem sprite: cor 0 do plano
+        assert_eq!(renderer.frame_buffer.get_pixel(20, 0), Some(vdp.color(0)));
+
+        // Com a coluna esquerda mascarada aparece a cor de fundo
+        vdp.regs[0] = 0x20;
+        renderer.render_mode4_line(&vdp, 0);
+        assert_eq!(renderer.frame_buffer.get_pixel(0, 0), Some(vdp.color(16)));
+    }
+}
@@ -9,6 +9,7 @@
 //! - Detecção de overflow e colisão de sprites
 
 use crate::vdp::{
+    color::ColorLut,
     cram::Cram,
     framebuffer::FrameBuffer,
     video_modes::VdpVideoMode,
@@ -384,6 +385,7 @@ impl Sprite {
         framebuffer: &mut FrameBuffer,
         vram: &Vram,
         cram: &Cram,
+        lut: &ColorLut,
         line: Option<u16>, // Se especificado, renderiza apenas esta linha
     ) {
         if !self.valid || !self.visible || self.is_offscreen(framebuffer.width, framebuffer.height) {
@@ -439,14 +441,8 @@ impl Sprite {
                         (palette as usize * 16) + color_index as usize
                     };
                     
-                    // Obter cor da CRAM
-                    let color_9bit = cram.read(cram_index % 64);
-                    
-                    // Converter para RGB888
-                    let r = ((color_9bit >> 0) & 0x07) as u8 * 36;  // 36 ≈ 255/7
-                    let g = ((color_9bit >> 4) & 0x07) as u8 * 36;
-                    let b = ((color_9bit >> 8) & 0x07) as u8 * 36;
-                    let color = (0xFF << 24) | ((r as u32) << 16) | ((g as u32) << 8) | (b as u32);
+                    // Obter cor da CRAM em RGB888
+                    let color = cram.to_rgb888(cram_index, lut);
                     
                     // Desenhar pixel
                     framebuffer.set_pixel(screen_x as usize, screen_y as usize, color);
@@ -571,6 +567,7 @@ impl SpriteTable {
         framebuffer: &mut FrameBuffer,
         vram: &Vram,
         cram: &Cram,
+        lut: &ColorLut,
         high_priority_only: bool,
     ) {
         let mut sprites_rendered = 0;
@@ -586,7 +583,7 @@ impl SpriteTable {
             if sprite.is_on_line(line as i16) &&
                (!high_priority_only || sprite.priority) {
                 
-                sprite.render(framebuffer, vram, cram, Some(line));
+                sprite.render(framebuffer, vram, cram, lut, Some(line));
                 sprites_rendered += 1;
             }
         }
@@ -598,18 +595,19 @@ impl SpriteTable {
         framebuffer: &mut FrameBuffer,
         vram: &Vram,
         cram: &Cram,
+        lut: &ColorLut,
     ) {
         // Primeiro renderizar sprites sem prioridade
         for sprite in &self.sprites {
             if sprite.valid && sprite.visible && !sprite.priority {
-                sprite.render(framebuffer, vram, cram, None);
+                sprite.render(framebuffer, vram, cram, lut, None);
             }
         }
         
         // Depois renderizar sprites com prioridade
         for sprite in &self.sprites {
             if sprite.valid && sprite.visible && sprite.priority {
-                sprite.render(framebuffer, vram, cram, None);
+                sprite.render(framebuffer, vram, cram, lut, None);
             }
         }
     }
@@ -1040,7 +1038,7 @@ mod tests {
         let mut framebuffer = FrameBuffer::new(64, 64);
         
         // Renderizar sprite
-        sprite_table.render_all(&mut framebuffer, &vram, &cram);
+        sprite_table.render_all(&mut framebuffer, &vram, &cram, &ColorLut::default());
         
         // Verificar que alguns pixels foram desenhados
         let has_pixels = framebuffer.pixels().iter().any(|&c| c != 0);